@@ -7,6 +7,7 @@
 
 use std::process::ExitCode;
 
+use seoyu::cloud::client::start_configured_sync;
 use seoyu::ipc::{default_socket_path, handlers::SidecarState, serve};
 use seoyu::{logging, store};
 
@@ -35,6 +36,7 @@ fn main() -> ExitCode {
 
     let state = SidecarState::new(store_handle);
     let socket_path = default_socket_path();
+    let cloud_sync = start_configured_sync(&state.store);
 
     let runtime = match tokio::runtime::Builder::new_multi_thread()
         .worker_threads(2)
@@ -48,7 +50,12 @@ fn main() -> ExitCode {
         }
     };
 
-    if let Err(e) = runtime.block_on(serve(state, socket_path)) {
+    let served = runtime.block_on(serve(state, socket_path));
+    if let Some(sync) = cloud_sync {
+        sync.stop();
+        sync.join();
+    }
+    if let Err(e) = served {
         log::error!("sidecar server exited with error: {e}");
         return ExitCode::from(1);
     }
@@ -0,0 +1,292 @@
+//! Sync client: drains `cloud_outbox` to the configured endpoint.
+//!
+//! Runs on its own OS thread with its own tokio runtime, like the wiki
+//! worker. Each round peeks a batch under a short store lock, pushes it
+//! with the lock released, then acks `applied`/`stale` results and
+//! records failures on the rest. A chat with failed ops goes behind
+//! the others in the next peek, its ops still in queue order, so a
+//! rejected op holds up only its own chat. Failed rounds back off
+//! exponentially; an idle round first asks the store to requeue
+//! watermark gaps ([`Store::requeue_outbox_gaps`]) before sleeping.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::push::{Endpoint, PushStatus, Pusher, SyncError};
+use crate::store::Store;
+
+#[derive(Debug, Clone)]
+pub struct SyncConfig {
+    /// Ops per push.
+    pub batch_size: usize,
+    /// Sleep between rounds once the outbox is empty.
+    pub idle_interval: Duration,
+    /// Delay after the first failed round; doubles per failure.
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Limit on one push, connect to last byte.
+    pub timeout: Duration,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            idle_interval: Duration::from_secs(5),
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Delay before the next round after `failures` failed rounds in a
+/// row.
+pub fn backoff_delay(config: &SyncConfig, failures: u32) -> Duration {
+    if failures == 0 {
+        return Duration::ZERO;
+    }
+    let factor = 1u32 << (failures - 1).min(16);
+    config
+        .base_backoff
+        .saturating_mul(factor)
+        .min(config.max_backoff)
+}
+
+/// Outcome of one push round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PushReport {
+    pub sent: usize,
+    pub acked: usize,
+    pub failed: usize,
+}
+
+pub struct SyncClient {
+    store: Arc<Mutex<Store>>,
+    pusher: Pusher,
+    config: SyncConfig,
+}
+
+impl SyncClient {
+    pub fn new(store: Arc<Mutex<Store>>, endpoint: Endpoint, config: SyncConfig) -> Self {
+        let pusher = Pusher::new(endpoint, config.timeout);
+        Self {
+            store,
+            pusher,
+            config,
+        }
+    }
+
+    /// Push one batch from the head of the outbox. A transport failure
+    /// is recorded on every op of the batch and returned; per-op
+    /// `error` results and ops the server did not answer are recorded
+    /// and counted in [`PushReport::failed`].
+    pub async fn push_once(&self) -> Result<PushReport, SyncError> {
+        let entries = lock(&self.store).peek_outbox(self.config.batch_size)?;
+        if entries.is_empty() {
+            return Ok(PushReport::default());
+        }
+        let payloads: Vec<String> = entries.iter().map(|e| e.payload.clone()).collect();
+
+        let results = match self.pusher.push(&payloads).await {
+            Ok(results) => results,
+            Err(e) => {
+                let store = lock(&self.store);
+                let error = e.to_string();
+                for entry in &entries {
+                    store.fail_outbox(&entry.client_op_id, &error)?;
+                }
+                return Err(e);
+            }
+        };
+
+        let mut report = PushReport {
+            sent: entries.len(),
+            ..PushReport::default()
+        };
+        let mut acked = Vec::new();
+        let store = lock(&self.store);
+        for entry in &entries {
+            let status = results
+                .iter()
+                .find(|r| r.client_op_id == entry.client_op_id)
+                .map(|r| &r.status);
+            match status {
+                Some(PushStatus::Applied { .. } | PushStatus::Stale { .. }) => {
+                    acked.push(entry.client_op_id.clone());
+                }
+                Some(PushStatus::Error { error }) => {
+                    store.fail_outbox(&entry.client_op_id, error)?;
+                    report.failed += 1;
+                }
+                None => {
+                    store.fail_outbox(&entry.client_op_id, "no result from server")?;
+                    report.failed += 1;
+                }
+            }
+        }
+        store.ack_outbox(&acked)?;
+        report.acked = acked.len();
+        Ok(report)
+    }
+
+    /// Loop until `shutdown` is set.
+    pub async fn run(&self, shutdown: &AtomicBool) {
+        let mut failures: u32 = 0;
+        while !shutdown.load(Ordering::Relaxed) {
+            if !lock(&self.store).cloud_sync_enabled().unwrap_or(false) {
+                sleep_unless(shutdown, self.config.idle_interval).await;
+                continue;
+            }
+            let delay = match self.push_once().await {
+                Ok(report) if report.sent > 0 && report.acked == 0 => {
+                    failures = failures.saturating_add(1);
+                    log::warn!(
+                        "cloud sync: server rejected all {} ops, retrying",
+                        report.failed
+                    );
+                    backoff_delay(&self.config, failures)
+                }
+                Ok(report) if report.sent > 0 => {
+                    failures = 0;
+                    Duration::ZERO
+                }
+                Ok(_) => {
+                    failures = 0;
+                    match lock(&self.store).requeue_outbox_gaps() {
+                        Ok(0) => self.config.idle_interval,
+                        Ok(n) => {
+                            log::info!("cloud sync: requeued {n} unacked messages");
+                            Duration::ZERO
+                        }
+                        Err(e) => {
+                            log::warn!("cloud sync: gap requeue failed: {e}");
+                            self.config.idle_interval
+                        }
+                    }
+                }
+                Err(SyncError::UpgradeRequired) => {
+                    log::error!("cloud sync: cloud needs a newer protocol version, waiting");
+                    self.config.max_backoff
+                }
+                Err(e) => {
+                    failures = failures.saturating_add(1);
+                    log::warn!("cloud sync: push failed ({failures} in a row): {e}");
+                    backoff_delay(&self.config, failures)
+                }
+            };
+            sleep_unless(shutdown, delay).await;
+        }
+        log::info!("cloud sync stopped");
+    }
+}
+
+/// Handle used to stop a running sync client.
+pub struct SyncHandle {
+    pub shutdown: Arc<AtomicBool>,
+    pub thread: std::thread::JoinHandle<()>,
+}
+
+impl SyncHandle {
+    /// Request shutdown. Returns immediately; call
+    /// [`SyncHandle::join`] to block until the thread finishes.
+    pub fn stop(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+
+    pub fn join(self) {
+        let _ = self.thread.join();
+    }
+}
+
+/// Start a sync client pushing to `endpoint` in a dedicated OS thread.
+pub fn start_sync(
+    store: Arc<Mutex<Store>>,
+    endpoint: Endpoint,
+    config: SyncConfig,
+) -> std::io::Result<SyncHandle> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_clone = Arc::clone(&shutdown);
+
+    let thread = std::thread::Builder::new()
+        .name("seoyu-cloud-sync".into())
+        .spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::error!("cloud sync: failed to build runtime: {e}");
+                    return;
+                }
+            };
+            let client = SyncClient::new(store, endpoint, config);
+            rt.block_on(client.run(&shutdown_clone));
+        })?;
+
+    Ok(SyncHandle { shutdown, thread })
+}
+
+/// Start a sync client from the stored settings, or return `None` when
+/// sync is off or no valid endpoint is set.
+pub fn start_configured_sync(store: &Arc<Mutex<Store>>) -> Option<SyncHandle> {
+    let (enabled, endpoint) = {
+        let s = lock(store);
+        (
+            s.cloud_sync_enabled().unwrap_or(false),
+            s.cloud_sync_endpoint().ok().flatten(),
+        )
+    };
+    if !enabled {
+        return None;
+    }
+    let endpoint = match Endpoint::parse(endpoint.as_deref()?) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            log::warn!("cloud sync: {e}");
+            return None;
+        }
+    };
+    match start_sync(Arc::clone(store), endpoint, SyncConfig::default()) {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            log::error!("cloud sync: failed to spawn: {e}");
+            None
+        }
+    }
+}
+
+async fn sleep_unless(shutdown: &AtomicBool, delay: Duration) {
+    let step = Duration::from_millis(100);
+    let mut left = delay;
+    while !left.is_zero() && !shutdown.load(Ordering::Relaxed) {
+        let nap = left.min(step);
+        tokio::time::sleep(nap).await;
+        left -= nap;
+    }
+}
+
+fn lock(store: &Arc<Mutex<Store>>) -> std::sync::MutexGuard<'_, Store> {
+    store.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let config = SyncConfig {
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            ..SyncConfig::default()
+        };
+        let delays: Vec<u64> = (0..6)
+            .map(|n| backoff_delay(&config, n).as_secs())
+            .collect();
+        assert_eq!(delays, vec![0, 1, 2, 4, 8, 10]);
+        assert_eq!(backoff_delay(&config, u32::MAX), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,10 @@
+//! Cloud sync: pushes the local `cloud_outbox` to the cloud wiki
+//! service (docs/specs/2026-04-27-cloud-wiki-architecture.md).
+//!
+//! Off unless the user turns it on and sets an endpoint; see
+//! [`crate::store::Store::set_cloud_sync_enabled`] and
+//! [`crate::store::Store::set_cloud_sync_endpoint`].
+
+pub mod client;
+pub mod push;
+pub mod stand_in;
@@ -0,0 +1,413 @@
+//! Push transport: `POST /v1/wiki/jobs` with one outbox payload per
+//! NDJSON line, answered with one [`PushResult`] line per op (spec
+//! "Sync protocol → Push").
+//!
+//! The crate has no HTTP client dependency, so this speaks just enough
+//! HTTP/1.1 to POST one body per connection: `Connection: close`,
+//! `Content-Length` out, `Content-Length` or chunked in. There is no
+//! TLS; a remote cloud is reached through a local proxy or tunnel.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+
+/// Request path used when an endpoint URL does not name one.
+pub const DEFAULT_PUSH_PATH: &str = "/v1/wiki/jobs";
+
+/// Cap on a response body; a batch answer is one short line per op.
+const MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Where ops are pushed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    /// `http://host[:port][/path]`, with an IPv6 host in brackets
+    /// (`http://[::1]:8080`). `host` is kept without them.
+    Tcp {
+        host: String,
+        port: u16,
+        path: String,
+    },
+    /// `unix:///absolute/socket/path`; requests go to
+    /// [`DEFAULT_PUSH_PATH`].
+    Unix {
+        socket: std::path::PathBuf,
+        path: String,
+    },
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> Result<Self, SyncError> {
+        let bad = |why: &str| SyncError::BadEndpoint(format!("{url}: {why}"));
+        if let Some(socket) = url.strip_prefix("unix://") {
+            if !socket.starts_with('/') {
+                return Err(bad("socket path must be absolute"));
+            }
+            return Ok(Endpoint::Unix {
+                socket: socket.into(),
+                path: DEFAULT_PUSH_PATH.into(),
+            });
+        }
+        let Some(rest) = url.strip_prefix("http://") else {
+            return Err(bad("expected http:// or unix://"));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| bad("unclosed '[' in host"))?;
+                match after {
+                    "" => (host, None),
+                    _ => (
+                        host,
+                        Some(after.strip_prefix(':').ok_or_else(|| bad("invalid port"))?),
+                    ),
+                }
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, _)) if host.contains(':') => {
+                    return Err(bad("IPv6 host must be in brackets"));
+                }
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse::<u16>().map_err(|_| bad("invalid port"))?,
+            None => 80,
+        };
+        if host.is_empty() {
+            return Err(bad("missing host"));
+        }
+        Ok(Endpoint::Tcp {
+            host: host.to_string(),
+            port,
+            path: if path.is_empty() || path == "/" {
+                DEFAULT_PUSH_PATH.into()
+            } else {
+                path.to_string()
+            },
+        })
+    }
+}
+
+/// Server verdict on one op, keyed by its `client_op_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushResult {
+    pub client_op_id: String,
+    #[serde(flatten)]
+    pub status: PushStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PushStatus {
+    Applied {
+        seq: i64,
+    },
+    /// The cloud already holds a newer version; acked like `applied`.
+    Stale {
+        current_version: i64,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("bad sync endpoint {0}")]
+    BadEndpoint(String),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("push timed out")]
+    Timeout,
+    /// HTTP 426: the cloud does not speak our `protocol_version` yet.
+    #[error("cloud rejected protocol version (426)")]
+    UpgradeRequired,
+    #[error("http {status}: {body}")]
+    Http { status: u16, body: String },
+    #[error("malformed response: {0}")]
+    Protocol(String),
+    #[error("store: {0}")]
+    Store(#[from] sqlite::Error),
+}
+
+/// Sends a batch of outbox payloads to one [`Endpoint`].
+#[derive(Debug, Clone)]
+pub struct Pusher {
+    endpoint: Endpoint,
+    timeout: Duration,
+}
+
+impl Pusher {
+    pub fn new(endpoint: Endpoint, timeout: Duration) -> Self {
+        Self { endpoint, timeout }
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// POST `payloads` as one NDJSON body and parse the per-op
+    /// results. Any non-2xx answer fails the whole batch.
+    pub async fn push(&self, payloads: &[String]) -> Result<Vec<PushResult>, SyncError> {
+        let mut body = String::new();
+        for payload in payloads {
+            body.push_str(payload);
+            body.push('\n');
+        }
+        let (host, path) = match &self.endpoint {
+            Endpoint::Tcp { host, port, path } if host.contains(':') => {
+                (format!("[{host}]:{port}"), path.as_str())
+            }
+            Endpoint::Tcp { host, port, path } => (format!("{host}:{port}"), path.as_str()),
+            Endpoint::Unix { path, .. } => ("localhost".to_string(), path.as_str()),
+        };
+        let request = format!(
+            "POST {path} HTTP/1.1\r\n\
+             Host: {host}\r\n\
+             Content-Type: application/x-ndjson\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let round_trip = async {
+            match &self.endpoint {
+                Endpoint::Tcp { host, port, .. } => {
+                    let stream = TcpStream::connect((host.as_str(), *port)).await?;
+                    exchange(stream, request.as_bytes()).await
+                }
+                Endpoint::Unix { socket, .. } => {
+                    let stream = UnixStream::connect(socket).await?;
+                    exchange(stream, request.as_bytes()).await
+                }
+            }
+        };
+        let raw = tokio::time::timeout(self.timeout, round_trip)
+            .await
+            .map_err(|_| SyncError::Timeout)??;
+        let (status, body) = parse_response(&raw)?;
+        match status {
+            200..=299 => parse_results(&body),
+            426 => Err(SyncError::UpgradeRequired),
+            _ => Err(SyncError::Http {
+                status,
+                body: String::from_utf8_lossy(&body).trim().to_string(),
+            }),
+        }
+    }
+}
+
+async fn exchange<S>(mut stream: S, request: &[u8]) -> std::io::Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request).await?;
+    stream.flush().await?;
+    let mut raw = Vec::new();
+    (&mut stream)
+        .take(MAX_RESPONSE_BYTES as u64 + 64 * 1024)
+        .read_to_end(&mut raw)
+        .await?;
+    Ok(raw)
+}
+
+/// Split a raw HTTP/1.1 response into its status code and decoded
+/// body.
+fn parse_response(raw: &[u8]) -> Result<(u16, Vec<u8>), SyncError> {
+    let head_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| SyncError::Protocol("no end of headers".into()))?;
+    let head = std::str::from_utf8(&raw[..head_end])
+        .map_err(|_| SyncError::Protocol("non-utf8 headers".into()))?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| SyncError::Protocol("bad status line".into()))?;
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+    let body = &raw[head_end + 4..];
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match content_length {
+            Some(len) if len <= body.len() => body[..len].to_vec(),
+            Some(_) => return Err(SyncError::Protocol("truncated body".into())),
+            None => body.to_vec(),
+        }
+    };
+    if body.len() > MAX_RESPONSE_BYTES {
+        return Err(SyncError::Protocol("response too large".into()));
+    }
+    Ok((status, body))
+}
+
+fn decode_chunked(mut raw: &[u8]) -> Result<Vec<u8>, SyncError> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = raw
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| SyncError::Protocol("truncated chunk size".into()))?;
+        let size_line = std::str::from_utf8(&raw[..line_end])
+            .map_err(|_| SyncError::Protocol("bad chunk size".into()))?;
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| SyncError::Protocol(format!("bad chunk size {size_hex:?}")))?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        let framed = size
+            .checked_add(2)
+            .ok_or_else(|| SyncError::Protocol(format!("bad chunk size {size_hex:?}")))?;
+        if raw.len() < framed {
+            return Err(SyncError::Protocol("truncated chunk".into()));
+        }
+        out.extend_from_slice(&raw[..size]);
+        raw = &raw[framed..];
+    }
+}
+
+fn parse_results(body: &[u8]) -> Result<Vec<PushResult>, SyncError> {
+    let text =
+        std::str::from_utf8(body).map_err(|_| SyncError::Protocol("non-utf8 body".into()))?;
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| SyncError::Protocol(format!("bad result line {line:?}: {e}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_parse() {
+        assert_eq!(
+            Endpoint::parse("http://127.0.0.1:8080").unwrap(),
+            Endpoint::Tcp {
+                host: "127.0.0.1".into(),
+                port: 8080,
+                path: DEFAULT_PUSH_PATH.into(),
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("http://cloud.local/custom/jobs").unwrap(),
+            Endpoint::Tcp {
+                host: "cloud.local".into(),
+                port: 80,
+                path: "/custom/jobs".into(),
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("http://[::1]:8080/jobs").unwrap(),
+            Endpoint::Tcp {
+                host: "::1".into(),
+                port: 8080,
+                path: "/jobs".into(),
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("http://[fe80::1]").unwrap(),
+            Endpoint::Tcp {
+                host: "fe80::1".into(),
+                port: 80,
+                path: DEFAULT_PUSH_PATH.into(),
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("unix:///tmp/seoyu-cloud.sock").unwrap(),
+            Endpoint::Unix {
+                socket: "/tmp/seoyu-cloud.sock".into(),
+                path: DEFAULT_PUSH_PATH.into(),
+            }
+        );
+        for bad in [
+            "https://cloud",
+            "unix://relative.sock",
+            "http://:80",
+            "http://h:x",
+            "http://::1:8080",
+            "http://[::1",
+            "http://[::1]8080",
+            "http://[]:80",
+        ] {
+            assert!(Endpoint::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn responses_decode_with_length_or_chunks() {
+        let line = "{\"client_op_id\":\"a\",\"status\":\"applied\",\"seq\":1}\n";
+        // Bytes past Content-Length are not part of the body.
+        let plain = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{line}garbage",
+            line.len()
+        );
+        let (status, body) = parse_response(plain.as_bytes()).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(
+            parse_results(&body).unwrap(),
+            vec![PushResult {
+                client_op_id: "a".into(),
+                status: PushStatus::Applied { seq: 1 },
+            }]
+        );
+
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            3\r\n{\"c\r\n5;x=1\r\nlient\r\n0\r\n\r\n";
+        let (_, body) = parse_response(chunked).unwrap();
+        assert_eq!(body, b"{\"client");
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+        // A chunk size at usize::MAX must not overflow the framing.
+        let huge = format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\nx\r\n",
+            usize::MAX
+        );
+        assert!(matches!(
+            parse_response(huge.as_bytes()),
+            Err(SyncError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn result_statuses_parse() {
+        let results = parse_results(
+            b"{\"client_op_id\":\"a\",\"status\":\"stale\",\"current_version\":4}\n\n\
+              {\"client_op_id\":\"b\",\"status\":\"error\",\"error\":\"bad op\"}\n",
+        )
+        .unwrap();
+        assert_eq!(results[0].status, PushStatus::Stale { current_version: 4 });
+        assert_eq!(
+            results[1].status,
+            PushStatus::Error {
+                error: "bad op".into()
+            }
+        );
+        assert!(parse_results(b"{\"status\":\"applied\"}").is_err());
+    }
+}
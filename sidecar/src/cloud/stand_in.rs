@@ -0,0 +1,312 @@
+//! In-process stand-in for the cloud push endpoint.
+//!
+//! Applies ops to an in-memory copy with the server rules from the
+//! spec: results are idempotent per `client_op_id`, an op at or below
+//! the version the cloud holds is `stale`, `chat_purge` drops the chat,
+//! and a `protocol_version` above [`PROTOCOL_VERSION`] gets HTTP 426.
+//! Failures can be injected so tests can drive the client's retry path
+//! with no network. Serves HTTP over a Unix socket or loopback TCP on
+//! the runtime it was bound on, until dropped.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::task::JoinHandle;
+
+use super::push::{PushResult, PushStatus};
+use crate::store::outbox::PROTOCOL_VERSION;
+
+/// What the stand-in holds for one message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudMessage {
+    pub msg_version: i64,
+    /// `None` once deleted.
+    pub text: Option<String>,
+}
+
+#[derive(Default)]
+struct CloudState {
+    seq: i64,
+    /// client_op_id → the result first returned for it.
+    results: HashMap<String, PushResult>,
+    /// (account_id, chat_id, message_id) → message.
+    messages: BTreeMap<(i64, i64, i64), CloudMessage>,
+    /// (account_id, chat_id) → title.
+    chats: BTreeMap<(i64, i64), String>,
+    ops: Vec<String>,
+    requests: u64,
+    fail_next: u32,
+    /// (chat_id, message_id) pairs whose ops are answered with `error`.
+    rejected: HashSet<(i64, i64)>,
+}
+
+pub struct StandInServer {
+    state: Arc<Mutex<CloudState>>,
+    endpoint: String,
+    socket: Option<PathBuf>,
+    task: JoinHandle<()>,
+}
+
+impl StandInServer {
+    /// Listen on a Unix socket at `path`. Must be called inside a
+    /// tokio runtime.
+    pub fn bind_unix(path: &Path) -> std::io::Result<Self> {
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+        let state = Arc::new(Mutex::new(CloudState::default()));
+        let shared = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(stream, Arc::clone(&shared)));
+            }
+        });
+        Ok(Self {
+            state,
+            endpoint: format!("unix://{}", path.display()),
+            socket: Some(path.to_path_buf()),
+            task,
+        })
+    }
+
+    /// Listen on an ephemeral loopback TCP port.
+    pub async fn bind_tcp() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(CloudState::default()));
+        let shared = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(stream, Arc::clone(&shared)));
+            }
+        });
+        Ok(Self {
+            state,
+            endpoint: format!("http://{addr}"),
+            socket: None,
+            task,
+        })
+    }
+
+    /// URL to point the sync client at.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Answer the next `n` requests with HTTP 503.
+    pub fn fail_next(&self, n: u32) {
+        self.lock().fail_next = n;
+    }
+
+    /// Answer ops on a message with `status: error` until
+    /// [`StandInServer::accept_message`].
+    pub fn reject_message(&self, chat_id: i64, message_id: i64) {
+        self.lock().rejected.insert((chat_id, message_id));
+    }
+
+    pub fn accept_message(&self, chat_id: i64, message_id: i64) {
+        self.lock().rejected.remove(&(chat_id, message_id));
+    }
+
+    pub fn message(&self, account_id: i64, chat_id: i64, message_id: i64) -> Option<CloudMessage> {
+        self.lock()
+            .messages
+            .get(&(account_id, chat_id, message_id))
+            .cloned()
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.lock().messages.len()
+    }
+
+    pub fn chat_title(&self, account_id: i64, chat_id: i64) -> Option<String> {
+        self.lock().chats.get(&(account_id, chat_id)).cloned()
+    }
+
+    /// HTTP requests received, including failed ones.
+    pub fn requests(&self) -> u64 {
+        self.lock().requests
+    }
+
+    /// `client_op_id` of every op applied, in order. Replays of an
+    /// already applied op are not repeated here.
+    pub fn ops(&self) -> Vec<String> {
+        self.lock().ops.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CloudState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for StandInServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        if let Some(socket) = &self.socket {
+            let _ = std::fs::remove_file(socket);
+        }
+    }
+}
+
+async fn serve_connection<S>(mut stream: S, state: Arc<Mutex<CloudState>>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Some(body) = read_request(&mut stream).await else {
+        return;
+    };
+    let (status, reply) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        handle_batch(&mut state, &body)
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        426 => "Upgrade Required",
+        _ => "Service Unavailable",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\n\
+         Content-Type: application/x-ndjson\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{reply}",
+        reply.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Read one request and return its body.
+async fn read_request<S>(stream: &mut S) -> Option<String>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 8192];
+    let head_end = loop {
+        if let Some(i) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        raw.extend_from_slice(&buf[..n]);
+    };
+    let head = std::str::from_utf8(&raw[..head_end]).ok()?;
+    let content_length = head
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    let body_start = head_end + 4;
+    while raw.len() < body_start + content_length {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8(raw[body_start..body_start + content_length].to_vec()).ok()
+}
+
+fn handle_batch(state: &mut CloudState, body: &str) -> (u16, String) {
+    state.requests += 1;
+    if state.fail_next > 0 {
+        state.fail_next -= 1;
+        return (503, "unavailable\n".into());
+    }
+    let mut ops = Vec::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<Value>(line) {
+            Ok(op) => ops.push(op),
+            Err(e) => return (400, format!("bad line: {e}\n")),
+        }
+    }
+    if ops
+        .iter()
+        .any(|op| op["protocol_version"].as_i64().unwrap_or(0) > PROTOCOL_VERSION)
+    {
+        return (426, "protocol_version too new\n".into());
+    }
+
+    let mut reply = String::new();
+    for op in &ops {
+        let result = apply(state, op);
+        reply.push_str(&serde_json::to_string(&result).unwrap_or_default());
+        reply.push('\n');
+    }
+    (200, reply)
+}
+
+fn apply(state: &mut CloudState, op: &Value) -> PushResult {
+    let client_op_id = op["client_op_id"].as_str().unwrap_or_default().to_string();
+    if let Some(done) = state.results.get(&client_op_id) {
+        return done.clone();
+    }
+    let account_id = op["account_id"].as_i64().unwrap_or(0);
+    let chat_id = op["chat_id"].as_i64().unwrap_or(0);
+    let message_id = op["message_id"].as_i64().unwrap_or(0);
+    let error = |error: &str| PushResult {
+        client_op_id: client_op_id.clone(),
+        status: PushStatus::Error {
+            error: error.to_string(),
+        },
+    };
+    if state.rejected.contains(&(chat_id, message_id)) {
+        return error("rejected");
+    }
+
+    let kind = op["op"].as_str().unwrap_or_default();
+    match kind {
+        "msg_upsert" | "msg_delete" => {
+            let version = op["msg_version"].as_i64().unwrap_or(0);
+            let key = (account_id, chat_id, message_id);
+            if let Some(current) = state.messages.get(&key) {
+                if current.msg_version >= version {
+                    return PushResult {
+                        client_op_id,
+                        status: PushStatus::Stale {
+                            current_version: current.msg_version,
+                        },
+                    };
+                }
+            }
+            let text = match kind {
+                "msg_upsert" => op["text"].as_str().map(str::to_string),
+                _ => None,
+            };
+            state.messages.insert(
+                key,
+                CloudMessage {
+                    msg_version: version,
+                    text,
+                },
+            );
+        }
+        "chat_meta" => {
+            let title = op["title"].as_str().unwrap_or_default().to_string();
+            state.chats.insert((account_id, chat_id), title);
+        }
+        "chat_purge" => {
+            state.chats.remove(&(account_id, chat_id));
+            state
+                .messages
+                .retain(|&(a, c, _), _| (a, c) != (account_id, chat_id));
+        }
+        _ => return error("unknown op"),
+    }
+
+    state.seq += 1;
+    state.ops.push(client_op_id.clone());
+    let result = PushResult {
+        client_op_id: client_op_id.clone(),
+        status: PushStatus::Applied { seq: state.seq },
+    };
+    state.results.insert(client_op_id, result.clone());
+    result
+}
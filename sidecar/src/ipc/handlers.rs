@@ -10,13 +10,25 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 use crate::ipc::protocol::{
-    DeleteMessageParams, IndexBatchParams, IndexBatchResult, IndexMessageInput, Method,
-    Notification, NotifyIn, Outcome, PongResult, Request, Response, ResponsePayload, RpcError,
-    SearchParams, SearchScopeInput, WikiSearchParams, WikiTopicDetail, WikiTopicDetailParams,
-    WikiTopicSummary, WikiTrendingParams,
+    BackfillProgressParams, ChatParams, DeleteMessageParams, IndexBatchParams, IndexBatchResult,
+    IndexMessageInput, ListChatsParams, MessageContextParams, MessageRevisionsParams, Method,
+    Notification, NotifyIn, Outcome, PongResult, ReconcileChatParams, ReplyChainParams, Request,
+    Response, ResponsePayload, RpcError, SearchParams, SearchScopeInput, ServerEvent,
+    SetChatExcludedParams, SetSearchHistoryEnabledParams, SuggestParams, SyncStateParams,
+    ThreadMessagesParams, TopEntitiesParams, UndeleteResult, UpsertChatParams, WikiSearchParams,
+    WikiTopicDetail, WikiTopicDetailParams, WikiTopicSummary, WikiTrendingParams,
 };
+use crate::ipc::server::EventSender;
+use crate::search::suggest::{self, Suggestion};
 use crate::search::{engine, SearchResult};
-use crate::store::message::{strip_whitespace, IndexOutcome, MessageRef, MessageRow};
+use crate::store::chat::{ChatOverview, ChatRow};
+use crate::store::entity::EntityCount;
+use crate::store::message::{
+    strip_whitespace, IndexOutcome, MessageRef, MessageRow, MessageWithChat,
+};
+use crate::store::recon::ReconReport;
+use crate::store::revision::MessageRevision;
+use crate::store::sync_state::{BackfillProgress, SyncStateRow};
 use crate::store::wiki_topic::WikiTopic;
 use crate::store::Store;
 
@@ -24,18 +36,39 @@ use crate::store::Store;
 #[derive(Clone)]
 pub struct SidecarState {
     pub store: Arc<Mutex<Store>>,
+    /// Push channel to the connected shell, attached by
+    /// [`crate::ipc::SidecarServer::bind`].
+    pub(crate) events: Option<EventSender>,
 }
 
 impl SidecarState {
     pub fn new(store: Store) -> Self {
         Self {
             store: Arc::new(Mutex::new(store)),
+            events: None,
+        }
+    }
+
+    fn emit(&self, event: ServerEvent) {
+        if let Some(events) = &self.events {
+            events.send(event);
         }
     }
 
     fn lock_store(&self) -> std::sync::MutexGuard<'_, Store> {
         self.store.lock().unwrap_or_else(|e| e.into_inner())
     }
+
+    /// Purge tombstones past the retention period. Run periodically by
+    /// [`crate::ipc::SidecarServer::run`].
+    pub(crate) fn sweep_tombstones(&self) {
+        let now = crate::wiki::norm::unix_now();
+        match self.lock_store().purge_tombstones(now) {
+            Ok(0) => {}
+            Ok(purged) => log::info!("sidecar: purged {purged} deleted messages"),
+            Err(e) => log::warn!("sidecar: tombstone sweep failed: {e}"),
+        }
+    }
 }
 
 /// Outcome of dispatching one message. `Shutdown` tells the server
@@ -57,6 +90,38 @@ pub fn dispatch_request(state: &SidecarState, req: Request) -> Dispatch {
         Method::Shutdown => {
             return Dispatch::Shutdown;
         }
+        Method::UpsertChat(params) => match upsert_chat(state, params) {
+            Ok(chat) => Outcome::Ok {
+                result: ResponsePayload::Chat(chat),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::GetChat(params) => match get_chat(state, params) {
+            Ok(chat) => Outcome::Ok {
+                result: ResponsePayload::Chat(chat),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::SetChatExcluded(params) => match set_chat_excluded(state, params) {
+            Ok(chat) => Outcome::Ok {
+                result: ResponsePayload::Chat(chat),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::ListChats(params) => match list_chats(state, params) {
+            Ok(chats) => Outcome::Ok {
+                result: ResponsePayload::ListChats(chats),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
         Method::IndexMessagesBatch(params) => match index_messages_batch(state, params) {
             Ok(outcome) => Outcome::Ok {
                 result: ResponsePayload::IndexBatch(IndexBatchResult {
@@ -76,7 +141,64 @@ pub fn dispatch_request(state: &SidecarState, req: Request) -> Dispatch {
                 error: RpcError::internal(e.to_string()),
             },
         },
-        Method::Search(params) => match run_search(state, params) {
+        Method::UndeleteMessage(params) => match undelete_message(state, params) {
+            Ok(restored) => Outcome::Ok {
+                result: ResponsePayload::Undelete(UndeleteResult {
+                    restored: restored > 0,
+                }),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::ReconcileChat(params) => match reconcile_chat(state, params) {
+            Ok(report) => Outcome::Ok {
+                result: ResponsePayload::ReconcileChat(report),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::GetSyncState(params) => match get_sync_state(state, params) {
+            Ok(row) => Outcome::Ok {
+                result: ResponsePayload::SyncState(row),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::UpdateLastMessageId(params) => {
+            let (account_id, chat_id) = (params.account_id, params.chat_id);
+            sync_state_outcome(state, account_id, chat_id, |store| {
+                store.update_last_message_id(
+                    account_id,
+                    chat_id,
+                    params.last_message_id,
+                    &params.last_sync_at,
+                )
+            })
+        }
+        Method::UpdateOldestMessageId(params) => {
+            let (account_id, chat_id) = (params.account_id, params.chat_id);
+            sync_state_outcome(state, account_id, chat_id, |store| {
+                store.update_oldest_message_id(account_id, chat_id, params.oldest_message_id)
+            })
+        }
+        Method::MarkInitialDone(params) => {
+            let (account_id, chat_id) = (params.account_id, params.chat_id);
+            sync_state_outcome(state, account_id, chat_id, |store| {
+                store.mark_initial_done(account_id, chat_id)
+            })
+        }
+        Method::BackfillProgress(params) => match backfill_progress(state, params) {
+            Ok(list) => Outcome::Ok {
+                result: ResponsePayload::BackfillProgress(list),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::Search(params) => match run_search(state, *params) {
             Ok(result) => Outcome::Ok {
                 result: ResponsePayload::Search(result),
             },
@@ -84,6 +206,64 @@ pub fn dispatch_request(state: &SidecarState, req: Request) -> Dispatch {
                 error: RpcError::internal(e.to_string()),
             },
         },
+        Method::MessageContext(params) => match message_context(state, params) {
+            Ok(rows) => Outcome::Ok {
+                result: ResponsePayload::MessageContext(rows),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::ReplyChain(params) => match reply_chain(state, params) {
+            Ok(rows) => Outcome::Ok {
+                result: ResponsePayload::ReplyChain(rows),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::MessageRevisions(params) => match message_revisions(state, params) {
+            Ok(rows) => Outcome::Ok {
+                result: ResponsePayload::MessageRevisions(rows),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::ThreadMessages(params) => match thread_messages(state, params) {
+            Ok(rows) => Outcome::Ok {
+                result: ResponsePayload::ThreadMessages(rows),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::Suggest(params) => match suggest(state, params) {
+            Ok(list) => Outcome::Ok {
+                result: ResponsePayload::Suggest(list),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
+        Method::SetSearchHistoryEnabled(params) => {
+            match set_search_history_enabled(state, params) {
+                Ok(enabled) => Outcome::Ok {
+                    result: ResponsePayload::SearchHistoryEnabled(enabled),
+                },
+                Err(e) => Outcome::Err {
+                    error: RpcError::internal(e.to_string()),
+                },
+            }
+        }
+        Method::TopEntities(params) => match top_entities(state, params) {
+            Ok(list) => Outcome::Ok {
+                result: ResponsePayload::TopEntities(list),
+            },
+            Err(e) => Outcome::Err {
+                error: RpcError::internal(e.to_string()),
+            },
+        },
         Method::WikiTrending(params) => match wiki_trending(state, params) {
             Ok(list) => Outcome::Ok {
                 result: ResponsePayload::WikiTrending(list),
@@ -134,28 +314,231 @@ fn index_messages_batch(
     if rows.is_empty() {
         return Ok(IndexOutcome::default());
     }
+    let outcome = state.lock_store().insert_messages_batch(&rows)?;
+    if !outcome.saved_search_matches.is_empty() {
+        state.emit(ServerEvent::SavedSearchMatches {
+            matches: outcome.saved_search_matches.clone(),
+        });
+    }
+    Ok(outcome)
+}
+
+fn upsert_chat(
+    state: &SidecarState,
+    params: UpsertChatParams,
+) -> Result<Option<ChatRow>, sqlite::Error> {
     let store = state.lock_store();
-    store.insert_messages_batch(&rows)
+    store.upsert_chat(&ChatRow {
+        account_id: params.account_id,
+        chat_id: params.chat_id,
+        title: params.title,
+        chat_type: params.chat_type,
+        username: params.username,
+        access_hash: params.access_hash,
+        is_excluded: params.is_excluded,
+    })?;
+    store.get_chat(params.account_id, params.chat_id)
+}
+
+fn get_chat(state: &SidecarState, params: ChatParams) -> Result<Option<ChatRow>, sqlite::Error> {
+    state
+        .lock_store()
+        .get_chat(params.account_id, params.chat_id)
+}
+
+fn set_chat_excluded(
+    state: &SidecarState,
+    params: SetChatExcludedParams,
+) -> Result<Option<ChatRow>, sqlite::Error> {
+    let store = state.lock_store();
+    store.set_chat_excluded(params.account_id, params.chat_id, params.excluded)?;
+    store.get_chat(params.account_id, params.chat_id)
+}
+
+fn list_chats(
+    state: &SidecarState,
+    params: ListChatsParams,
+) -> Result<Vec<ChatOverview>, sqlite::Error> {
+    state
+        .lock_store()
+        .list_chats(params.account_id, params.include_excluded)
 }
 
 fn delete_message(state: &SidecarState, params: DeleteMessageParams) -> Result<u64, sqlite::Error> {
     let store = state.lock_store();
     store.delete_messages(&[MessageRef {
+        account_id: params.account_id,
+        chat_id: params.chat_id,
+        message_id: params.message_id,
+    }])
+}
+
+fn undelete_message(
+    state: &SidecarState,
+    params: DeleteMessageParams,
+) -> Result<u64, sqlite::Error> {
+    let store = state.lock_store();
+    store.undelete_messages(&[MessageRef {
+        account_id: params.account_id,
         chat_id: params.chat_id,
         message_id: params.message_id,
     }])
 }
 
+fn reconcile_chat(
+    state: &SidecarState,
+    params: ReconcileChatParams,
+) -> Result<ReconReport, sqlite::Error> {
+    let report = state.lock_store().reconcile_chat(
+        params.account_id,
+        params.chat_id,
+        &params.summary,
+        params.full,
+        crate::wiki::norm::unix_now(),
+    )?;
+    let ranges = report.backfill_ranges();
+    if !ranges.is_empty() {
+        state.emit(ServerEvent::BackfillRequested {
+            account_id: report.account_id,
+            chat_id: report.chat_id,
+            ranges,
+        });
+    }
+    Ok(report)
+}
+
+fn get_sync_state(
+    state: &SidecarState,
+    params: SyncStateParams,
+) -> Result<Option<SyncStateRow>, sqlite::Error> {
+    state
+        .lock_store()
+        .get_sync_state(params.account_id, params.chat_id)
+}
+
+/// Apply a sync-state update, then answer with (and push) the chat's
+/// backfill progress.
+fn sync_state_outcome(
+    state: &SidecarState,
+    account_id: i64,
+    chat_id: i64,
+    update: impl FnOnce(&Store) -> Result<(), sqlite::Error>,
+) -> Outcome {
+    let progress = {
+        let store = state.lock_store();
+        update(&store).and_then(|()| store.backfill_progress(account_id, chat_id))
+    };
+    match progress {
+        Ok(Some(progress)) => {
+            state.emit(ServerEvent::BackfillProgress(progress.clone()));
+            Outcome::Ok {
+                result: ResponsePayload::ChatBackfillProgress(progress),
+            }
+        }
+        Ok(None) => Outcome::Err {
+            error: RpcError::internal("sync state missing after update"),
+        },
+        Err(e) => Outcome::Err {
+            error: RpcError::internal(e.to_string()),
+        },
+    }
+}
+
+fn backfill_progress(
+    state: &SidecarState,
+    params: BackfillProgressParams,
+) -> Result<Vec<BackfillProgress>, sqlite::Error> {
+    state.lock_store().list_backfill_progress(params.account_id)
+}
+
+fn message_context(
+    state: &SidecarState,
+    params: MessageContextParams,
+) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+    let store = state.lock_store();
+    store.message_context(
+        params.account_id,
+        params.chat_id,
+        params.message_id,
+        params.before,
+        params.after,
+    )
+}
+
+fn reply_chain(
+    state: &SidecarState,
+    params: ReplyChainParams,
+) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+    let store = state.lock_store();
+    store.reply_chain(params.account_id, params.chat_id, params.message_id)
+}
+
+fn message_revisions(
+    state: &SidecarState,
+    params: MessageRevisionsParams,
+) -> Result<Vec<MessageRevision>, sqlite::Error> {
+    let store = state.lock_store();
+    store.message_revisions(params.account_id, params.chat_id, params.message_id)
+}
+
+fn thread_messages(
+    state: &SidecarState,
+    params: ThreadMessagesParams,
+) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+    let store = state.lock_store();
+    store.thread_messages(
+        params.account_id,
+        params.chat_id,
+        params.thread_id,
+        params.after_message_id,
+        params.limit,
+    )
+}
+
+fn suggest(state: &SidecarState, params: SuggestParams) -> Result<Vec<Suggestion>, sqlite::Error> {
+    let store = state.lock_store();
+    suggest::suggest(&store, &params.prefix, params.limit)
+}
+
+fn set_search_history_enabled(
+    state: &SidecarState,
+    params: SetSearchHistoryEnabledParams,
+) -> Result<bool, sqlite::Error> {
+    let store = state.lock_store();
+    store.set_search_history_enabled(params.enabled)?;
+    store.search_history_enabled()
+}
+
+fn top_entities(
+    state: &SidecarState,
+    params: TopEntitiesParams,
+) -> Result<Vec<EntityCount>, sqlite::Error> {
+    let store = state.lock_store();
+    store.top_entities(
+        params.kind,
+        params.chat_id.map(|chat_id| (params.account_id, chat_id)),
+        params.since,
+        params.until,
+        params.limit,
+    )
+}
+
 fn to_message_row(msg: IndexMessageInput) -> MessageRow {
     let stripped = strip_whitespace(&msg.text);
     MessageRow {
         message_id: msg.message_id,
+        account_id: msg.account_id,
         chat_id: msg.chat_id,
         timestamp: msg.timestamp,
         text_plain: msg.text,
         text_stripped: stripped,
         link: None,
         sender_id: msg.sender_id.unwrap_or(0),
+        media: msg.media,
+        reply_to_message_id: msg.reply_to_message_id,
+        thread_id: msg.thread_id,
+        sender_name: msg.sender_name,
+        sender_username: msg.sender_username,
     }
 }
 
@@ -231,14 +614,47 @@ fn wiki_search(
 fn run_search(state: &SidecarState, params: SearchParams) -> Result<SearchResult, sqlite::Error> {
     let scope = match params.scope {
         SearchScopeInput::All => engine::SearchScope::All,
-        SearchScopeInput::Chat(id) => engine::SearchScope::Chat(id),
+        SearchScopeInput::Chat {
+            account_id,
+            chat_id,
+        } => engine::SearchScope::Chat {
+            account_id,
+            chat_id,
+        },
+        SearchScopeInput::Thread {
+            account_id,
+            chat_id,
+            thread_id,
+        } => engine::SearchScope::Thread {
+            account_id,
+            chat_id,
+            thread_id,
+        },
     };
     let store = state.lock_store();
-    engine::search(
+    let mut result = engine::search(
         &store,
         &params.query,
         &scope,
+        &params.filters,
+        params.sort,
         params.cursor.as_ref(),
         params.limit,
-    )
+    )?;
+    engine::record_history(&store, &params.query, params.cursor.as_ref(), &result);
+    if params.facets {
+        let query = result.did_you_mean.as_deref().unwrap_or(&params.query);
+        result.facets = Some(engine::search_facets(
+            &store,
+            query,
+            &scope,
+            &params.filters,
+        )?);
+    }
+    if let Some(options) = &params.snippet {
+        for item in &mut result.items {
+            item.apply_snippet(options);
+        }
+    }
+    Ok(result)
 }
@@ -13,8 +13,19 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::search::highlight::SnippetOptions;
+use crate::search::suggest::{Suggestion, DEFAULT_SUGGEST_LIMIT};
 use crate::search::SearchResult;
-use crate::store::message::Cursor;
+use crate::store::chat::{ChatOverview, ChatRow};
+use crate::store::entity::{EntityCount, EntityKind, DEFAULT_TOP_ENTITIES_LIMIT};
+use crate::store::media::MessageMedia;
+use crate::store::message::{
+    Cursor, MessageWithChat, SearchFilters, SearchSort, DEFAULT_THREAD_PAGE_SIZE,
+};
+use crate::store::recon::{ChatSummary, IdRange, ReconReport};
+use crate::store::revision::MessageRevision;
+use crate::store::saved_search::SavedSearchMatch;
+use crate::store::sync_state::{BackfillProgress, SyncStateRow};
 
 /// Incoming message from the Swift client.
 #[derive(Debug, Deserialize)]
@@ -45,10 +56,42 @@ pub enum Method {
     Ping,
     Shutdown,
 
+    /// Create a chat or update its title, type and handle. Chats
+    /// first seen through indexing exist only as untitled stubs until
+    /// this is sent.
+    UpsertChat(UpsertChatParams),
+    GetChat(ChatParams),
+    /// Leave a chat out of search and cloud sync, or bring it back.
+    SetChatExcluded(SetChatExcludedParams),
+    ListChats(ListChatsParams),
+
     IndexMessagesBatch(IndexBatchParams),
     DeleteMessage(DeleteMessageParams),
-
-    Search(SearchParams),
+    /// Restore a deleted message while its tombstone is kept.
+    UndeleteMessage(DeleteMessageParams),
+    /// Diff a chat against the shell's copy and get the ranges to
+    /// index again.
+    ReconcileChat(ReconcileChatParams),
+
+    /// Where fetching a chat left off, `null` before anything was
+    /// recorded.
+    GetSyncState(SyncStateParams),
+    UpdateLastMessageId(UpdateLastMessageIdParams),
+    /// Record how far back the initial backfill has reached.
+    UpdateOldestMessageId(UpdateOldestMessageIdParams),
+    MarkInitialDone(SyncStateParams),
+    BackfillProgress(BackfillProgressParams),
+
+    Search(Box<SearchParams>),
+    MessageContext(MessageContextParams),
+    ReplyChain(ReplyChainParams),
+    MessageRevisions(MessageRevisionsParams),
+    ThreadMessages(ThreadMessagesParams),
+    Suggest(SuggestParams),
+    /// Turn search history on or off. Turning it off keeps what was
+    /// recorded.
+    SetSearchHistoryEnabled(SetSearchHistoryEnabledParams),
+    TopEntities(TopEntitiesParams),
 
     WikiTrending(WikiTrendingParams),
     WikiTopicDetail(WikiTopicDetailParams),
@@ -78,6 +121,19 @@ pub enum ServerEvent {
         message: String,
         recoverable: bool,
     },
+    /// Saved searches picked up new matches from an indexed batch.
+    SavedSearchMatches {
+        matches: Vec<SavedSearchMatch>,
+    },
+    /// A chat's sync state moved; sent after every sync-state update.
+    BackfillProgress(BackfillProgress),
+    /// Reconciliation found messages the index lacks or holds stale;
+    /// the shell should index these ranges of the chat again.
+    BackfillRequested {
+        account_id: i64,
+        chat_id: i64,
+        ranges: Vec<IdRange>,
+    },
 }
 
 /// What the sidecar writes back on the wire.
@@ -140,9 +196,26 @@ impl RpcError {
 pub enum ResponsePayload {
     Pong(PongResult),
     ShutdownAck,
+    /// The chat as stored after the call; `null` for an unknown chat.
+    Chat(Option<ChatRow>),
+    ListChats(Vec<ChatOverview>),
     IndexBatch(IndexBatchResult),
     DeleteAck,
+    Undelete(UndeleteResult),
+    ReconcileChat(ReconReport),
+    SyncState(Option<SyncStateRow>),
+    /// Answer to the sync-state updates: the chat's progress after it.
+    ChatBackfillProgress(BackfillProgress),
+    BackfillProgress(Vec<BackfillProgress>),
     Search(SearchResult),
+    MessageContext(Vec<MessageWithChat>),
+    ReplyChain(Vec<MessageWithChat>),
+    MessageRevisions(Vec<MessageRevision>),
+    ThreadMessages(Vec<MessageWithChat>),
+    Suggest(Vec<Suggestion>),
+    /// The history setting as stored after the call.
+    SearchHistoryEnabled(bool),
+    TopEntities(Vec<EntityCount>),
     WikiTrending(Vec<WikiTopicSummary>),
     WikiTopicDetail(WikiTopicDetail),
     WikiSearch(Vec<WikiTopicSummary>),
@@ -155,6 +228,49 @@ pub struct PongResult {
 
 // ---------- method-specific params and payloads ----------
 
+/// `is_excluded` only applies when the chat is new; use
+/// [`Method::SetChatExcluded`] to change it.
+#[derive(Debug, Deserialize)]
+pub struct UpsertChatParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub title: String,
+    /// `dm`, `group`, `supergroup` or `channel`.
+    pub chat_type: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub access_hash: Option<i64>,
+    #[serde(default)]
+    pub is_excluded: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetChatExcludedParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub excluded: bool,
+}
+
+/// Chats by title with message counts. `account_id` narrows to one
+/// account; excluded chats are left out unless `include_excluded`.
+#[derive(Debug, Deserialize)]
+pub struct ListChatsParams {
+    #[serde(default)]
+    pub account_id: Option<i64>,
+    #[serde(default)]
+    pub include_excluded: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IndexBatchParams {
     pub messages: Vec<IndexMessageInput>,
@@ -165,12 +281,25 @@ pub struct IndexBatchParams {
 /// but stays decoupled so the wire format can evolve independently.
 #[derive(Debug, Deserialize, Clone)]
 pub struct IndexMessageInput {
+    /// Telegram account the shell read this message with; 0 when only
+    /// one account is signed in.
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
     pub sender_id: Option<i64>,
     pub sender_name: Option<String>,
+    #[serde(default)]
+    pub sender_username: Option<String>,
     pub timestamp: i64,
     pub text: String,
+    #[serde(default)]
+    pub media: Option<MessageMedia>,
+    #[serde(default)]
+    pub reply_to_message_id: Option<i64>,
+    /// Forum topic or discussion thread, by the id of its root message.
+    #[serde(default)]
+    pub thread_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -181,25 +310,208 @@ pub struct IndexBatchResult {
 
 #[derive(Debug, Deserialize)]
 pub struct DeleteMessageParams {
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
 }
 
+/// `restored` is false when the message was never deleted or its
+/// retention window has passed.
+#[derive(Debug, Serialize)]
+pub struct UndeleteResult {
+    pub restored: bool,
+}
+
+/// `{"chat_id": 1, "mode": "ranges", "ranges": [{"from": 1, "to": 40}]}`
+/// or `{"chat_id": 1, "mode": "digest", "buckets": [{"from": 1,
+/// "to": 1000, "count": 812, "digest": "…"}]}`. `full` says the
+/// summary covers the whole chat.
+#[derive(Debug, Deserialize)]
+pub struct ReconcileChatParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    #[serde(flatten)]
+    pub summary: ChatSummary,
+    #[serde(default)]
+    pub full: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncStateParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+}
+
+/// Newest message id fetched; `last_sync_at` is stored as given.
+#[derive(Debug, Deserialize)]
+pub struct UpdateLastMessageIdParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub last_message_id: i64,
+    pub last_sync_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOldestMessageIdParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub oldest_message_id: i64,
+}
+
+/// Progress of every chat with sync state, least complete first.
+/// `account_id` narrows to one account.
+#[derive(Debug, Deserialize)]
+pub struct BackfillProgressParams {
+    #[serde(default)]
+    pub account_id: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
     pub query: String,
     #[serde(default)]
     pub scope: SearchScopeInput,
+    /// Date range, sender, chat, chat-type and account narrowing,
+    /// ANDed with `scope`. Omitted fields do not restrict.
+    /// `"include_revisions": true` also matches text edits replaced.
+    #[serde(default)]
+    pub filters: SearchFilters,
+    /// `{"mode": "newest"}`, `{"mode": "relevance_with_decay",
+    /// "half_life_days": 7}`, ...; relevance when omitted. A cursor is
+    /// only valid with the sort it came from.
+    #[serde(default)]
+    pub sort: SearchSort,
     pub limit: Option<usize>,
     pub cursor: Option<Cursor>,
+    /// Also return total, per-chat and per-day hit counts.
+    #[serde(default)]
+    pub facets: bool,
+    /// Return snippets around the highlights instead of whole
+    /// messages. `{}` takes the default lengths.
+    #[serde(default)]
+    pub snippet: Option<SnippetOptions>,
 }
 
+/// `{"kind": "all"}`, `{"kind": "chat", "account_id": 0, "chat_id": 1}`
+/// or `{"kind": "thread", "account_id": 0, "chat_id": 1, "thread_id": 40}`.
+/// `account_id` defaults to 0.
 #[derive(Debug, Deserialize, Default)]
-#[serde(tag = "kind", content = "chat_id", rename_all = "snake_case")]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum SearchScopeInput {
     #[default]
     All,
-    Chat(i64),
+    Chat {
+        #[serde(default)]
+        account_id: i64,
+        chat_id: i64,
+    },
+    /// One reply thread or forum topic, its root message included.
+    Thread {
+        #[serde(default)]
+        account_id: i64,
+        chat_id: i64,
+        thread_id: i64,
+    },
+}
+
+/// Messages around a search hit, so the shell can show the
+/// conversation without asking Telegram again.
+#[derive(Debug, Deserialize)]
+pub struct MessageContextParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub message_id: i64,
+    #[serde(default = "default_context_window")]
+    pub before: usize,
+    #[serde(default = "default_context_window")]
+    pub after: usize,
+}
+
+fn default_context_window() -> usize {
+    5
+}
+
+/// The replies leading to a message, root first.
+#[derive(Debug, Deserialize)]
+pub struct ReplyChainParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub message_id: i64,
+}
+
+/// Earlier versions of an edited message, oldest first.
+#[derive(Debug, Deserialize)]
+pub struct MessageRevisionsParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub message_id: i64,
+}
+
+/// A page of a reply thread or forum topic, oldest first. Pass the
+/// last `message_id` of a page as `after_message_id` for the next.
+#[derive(Debug, Deserialize)]
+pub struct ThreadMessagesParams {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub thread_id: i64,
+    #[serde(default)]
+    pub after_message_id: Option<i64>,
+    #[serde(default = "default_thread_limit")]
+    pub limit: usize,
+}
+
+fn default_thread_limit() -> usize {
+    DEFAULT_THREAD_PAGE_SIZE
+}
+
+/// Autocomplete for the search box. An empty `prefix` lists recent
+/// searches.
+#[derive(Debug, Deserialize)]
+pub struct SuggestParams {
+    pub prefix: String,
+    #[serde(default = "default_suggest_limit")]
+    pub limit: usize,
+}
+
+fn default_suggest_limit() -> usize {
+    DEFAULT_SUGGEST_LIMIT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetSearchHistoryEnabledParams {
+    pub enabled: bool,
+}
+
+/// Most frequent entities, optionally of one kind, in one chat and
+/// within `since..=until` (unix seconds). `account_id` is the account
+/// of `chat_id` and unused without it.
+#[derive(Debug, Deserialize)]
+pub struct TopEntitiesParams {
+    #[serde(default)]
+    pub kind: Option<EntityKind>,
+    #[serde(default)]
+    pub account_id: i64,
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    #[serde(default)]
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default = "default_top_entities_limit")]
+    pub limit: usize,
+}
+
+fn default_top_entities_limit() -> usize {
+    DEFAULT_TOP_ENTITIES_LIMIT
 }
 
 #[derive(Debug, Deserialize)]
@@ -10,6 +10,7 @@
 
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use tokio::io::AsyncWriteExt;
 use tokio::net::{UnixListener, UnixStream};
@@ -30,6 +31,9 @@ pub fn default_socket_path() -> PathBuf {
     base.join("telegram-seoyu-sidecar.sock")
 }
 
+/// How often deleted messages past their retention are purged.
+const TOMBSTONE_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 /// Handle used by the server owner to push events to the currently
 /// connected shell. Dropping the handle simply stops pushes; the
 /// accept loop keeps running until you await [`SidecarServer::run`]
@@ -64,6 +68,10 @@ impl SidecarServer {
         }
         let listener = UnixListener::bind(&path)?;
         let (tx, rx) = mpsc::unbounded_channel();
+        let state = SidecarState {
+            events: Some(EventSender { tx: tx.clone() }),
+            ..state
+        };
         Ok((
             Self {
                 listener,
@@ -82,8 +90,24 @@ impl SidecarServer {
     /// Serve one connection at a time. When a client disconnects, the
     /// next incoming connection is accepted. Exits only when the
     /// listener returns a fatal error or a handler returns
-    /// [`Dispatch::Shutdown`].
+    /// [`Dispatch::Shutdown`]. Tombstones are swept on a timer for as
+    /// long as the server runs, connected or not.
     pub async fn run(mut self) -> io::Result<()> {
+        let sweep_state = self.state.clone();
+        let sweeper = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(TOMBSTONE_SWEEP_INTERVAL);
+            loop {
+                ticks.tick().await;
+                let state = sweep_state.clone();
+                let _ = tokio::task::spawn_blocking(move || state.sweep_tombstones()).await;
+            }
+        });
+        let result = self.serve().await;
+        sweeper.abort();
+        result
+    }
+
+    async fn serve(&mut self) -> io::Result<()> {
         loop {
             tokio::select! {
                 accept = self.listener.accept() => {
@@ -181,7 +205,12 @@ async fn handle_conn(
                     Some(event) => {
                         let body = serde_json::to_vec(&OutgoingFrame::Event(Event { body: event }))
                             .expect("ServerEvent must serialize");
-                        write_frame(&mut writer, &body).await?;
+                        // A client that hung up right after its last
+                        // response is gone, not a server failure.
+                        if let Err(e) = write_frame(&mut writer, &body).await {
+                            log::info!("sidecar: dropping event, client went away: {e}");
+                            return Ok(true);
+                        }
                     }
                     None => {
                         // Channel closed: the server is shutting down.
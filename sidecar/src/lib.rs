@@ -5,6 +5,7 @@
 //! `src/bin/main.rs`, which serves requests over a Unix-domain
 //! socket.
 
+pub mod cloud;
 pub mod error;
 pub mod ipc;
 pub mod logging;
@@ -1,105 +1,218 @@
-use crate::store::message::{strip_whitespace, Cursor, MessageWithChat};
+use crate::store::message::{
+    Cursor, FacetSource, MessageWithChat, SearchFacets, SearchFilters, SearchSort,
+};
 use crate::store::Store;
 
-use super::hangul::decompose_jamo;
+use super::fuzzy::suggest_correction;
+use super::hangul::{layout_mistype, stem_korean};
 use super::highlight::find_highlights;
+use super::query::{ignored_filters, parse_filters, parse_query, QueryNode};
 use super::{SearchItem, SearchResult};
 
 const DEFAULT_PAGE_SIZE: usize = 30;
 
-/// Search scope: all chats or a specific chat.
+/// Search scope: all chats, a specific chat of an account, or one
+/// reply thread or forum topic of such a chat (its root message
+/// included).
 #[derive(Debug, Clone)]
 pub enum SearchScope {
     All,
-    Chat(i64),
+    Chat {
+        account_id: i64,
+        chat_id: i64,
+    },
+    Thread {
+        account_id: i64,
+        chat_id: i64,
+        thread_id: i64,
+    },
 }
 
-/// Build an FTS5 `MATCH` argument from user input. Each whitespace
-/// separated term is quoted so FTS5 treats it as a phrase (exact
-/// substring in trigram mode); multiple terms are AND'd implicitly.
-fn build_fts_query(query: &str) -> String {
-    query
-        .split_whitespace()
-        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
-        .collect::<Vec<_>>()
-        .join(" ")
-}
-
-/// Build one MATCH expression over the combined v8 FTS table. The
-/// variants preserve the old plain, nospace, and jamo match behavior
-/// while BM25 ranks the single result set.
-fn build_match_query(raw_query: &str) -> Option<String> {
-    let trimmed = raw_query.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-
-    let stripped = strip_whitespace(trimmed);
-    let jamo = decompose_jamo(trimmed);
-    let mut variants = Vec::new();
-
-    for variant in [trimmed.to_string(), stripped, jamo] {
-        if variant.is_empty() {
-            continue;
-        }
-        let query = build_fts_query(&variant);
-        if trigram_ready(&query) && !variants.contains(&query) {
-            variants.push(query);
+impl SearchScope {
+    /// `(account_id, chat_id)` of a chat or thread scope.
+    fn chat(&self) -> Option<(i64, i64)> {
+        match *self {
+            Self::All => None,
+            Self::Chat {
+                account_id,
+                chat_id,
+            }
+            | Self::Thread {
+                account_id,
+                chat_id,
+                ..
+            } => Some((account_id, chat_id)),
         }
     }
+}
 
-    if variants.is_empty() {
-        None
-    } else {
-        Some(variants.join(" OR "))
-    }
+/// Build one MATCH expression over the combined v8 FTS table from the
+/// user's query. See [`super::query`] for the accepted syntax; `None`
+/// means MATCH cannot express it faithfully and the caller should use
+/// LIKE.
+pub(crate) fn build_match_query(raw_query: &str) -> Option<String> {
+    parse_query(raw_query)?.to_fts_match()
 }
 
-/// FTS5 trigram refuses queries whose longest term is < 3 chars.
-/// Check against the already-quoted query string the planner built.
-fn trigram_ready(fts_query: &str) -> bool {
-    fts_query
-        .split_whitespace()
-        .any(|term| term.trim_matches('"').chars().count() >= 3)
+/// `filters` combined with the entity, media and sender filters written
+/// into the query itself (`tag:airdrop`, `type:photo`, `from:김철수`)
+/// and the thread of a
+/// [`SearchScope::Thread`].
+fn with_query_filters(filters: &SearchFilters, query: &str, scope: &SearchScope) -> SearchFilters {
+    let typed = parse_filters(query);
+    let mut merged = filters.clone();
+    if let SearchScope::Thread { thread_id, .. } = scope {
+        merged.thread_id = Some(*thread_id);
+    }
+    merged.has.extend(typed.has);
+    merged.domains.extend(typed.domains);
+    merged.tags.extend(typed.tags);
+    merged.mentions.extend(typed.mentions);
+    merged.cashtags.extend(typed.cashtags);
+    merged.media_kinds.extend(typed.media_kinds);
+    merged.from.extend(typed.from);
+    merged
 }
 
 pub fn search(
     store: &Store,
     query: &str,
     scope: &SearchScope,
+    filters: &SearchFilters,
+    sort: SearchSort,
     cursor: Option<&Cursor>,
     limit: Option<usize>,
 ) -> Result<SearchResult, sqlite::Error> {
     let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
     let query_trimmed = query.trim();
 
-    if query_trimmed.is_empty() {
+    let result = run_search(store, query_trimmed, scope, filters, sort, cursor, limit)?;
+    // Typo fallback only on a first page that found nothing; later pages
+    // are requested with the corrected query itself.
+    if !result.items.is_empty() || cursor.is_some() {
+        return Ok(result);
+    }
+    let Some(corrected) = suggest_correction(store, query_trimmed)? else {
+        return Ok(result);
+    };
+    let mut fallback = run_search(store, &corrected, scope, filters, sort, None, limit)?;
+    if fallback.items.is_empty() {
+        return Ok(result);
+    }
+    fallback.did_you_mean = Some(corrected);
+    Ok(fallback)
+}
+
+/// Add a first page that found something to search history, under
+/// the query that actually ran (the correction, if one was used). Later
+/// pages and misses are not recorded, so typos do not become
+/// suggestions. Best-effort: a failed write is logged, never fails the
+/// search.
+pub fn record_history(store: &Store, query: &str, cursor: Option<&Cursor>, result: &SearchResult) {
+    if cursor.is_some() || result.items.is_empty() {
+        return;
+    }
+    if let Err(e) = store.record_search(result.did_you_mean.as_deref().unwrap_or(query)) {
+        log::warn!("search history: {e}");
+    }
+}
+
+/// Hit counts for `query` across all pages, planned the same way as
+/// [`search`]. Pass the `did_you_mean` query when the page fell back
+/// to it.
+pub fn search_facets(
+    store: &Store,
+    query: &str,
+    scope: &SearchScope,
+    filters: &SearchFilters,
+) -> Result<SearchFacets, sqlite::Error> {
+    let query_trimmed = query.trim();
+    let filters = &with_query_filters(filters, query_trimmed, scope);
+    let parsed = parse_query(query_trimmed);
+    if parsed.is_none() && !filters.has_content_filters() {
+        return Ok(SearchFacets::default());
+    }
+    let scope_chat = scope.chat();
+    match build_match_query(query_trimmed) {
+        Some(fts_query) => store.search_facets(FacetSource::Fts(&fts_query), scope_chat, filters),
+        None => {
+            let like = parsed.as_ref().map(QueryNode::to_like_match);
+            store.search_facets(FacetSource::Like(like.as_ref()), scope_chat, filters)
+        }
+    }
+}
+
+fn run_search(
+    store: &Store,
+    query_trimmed: &str,
+    scope: &SearchScope,
+    filters: &SearchFilters,
+    sort: SearchSort,
+    cursor: Option<&Cursor>,
+    limit: usize,
+) -> Result<SearchResult, sqlite::Error> {
+    let filters = &with_query_filters(filters, query_trimmed, scope);
+    let parsed = parse_query(query_trimmed);
+    let ignored = ignored_filters(query_trimmed);
+    // Entity filters alone still select messages: `has:link` lists
+    // every message with a link, newest first.
+    if parsed.is_none() && !filters.has_content_filters() {
         return Ok(SearchResult {
-            items: vec![],
-            next_cursor: None,
+            ignored_filters: ignored,
+            ..Default::default()
         });
     }
-
-    let tokens: Vec<String> = query_trimmed
-        .split_whitespace()
-        .map(|s| s.to_string())
-        .collect();
+    // Negated terms never highlight; they only filter.
+    let tokens = parsed
+        .as_ref()
+        .map(|p| p.positive_terms())
+        .unwrap_or_default();
+    // `삼성전자가` matches through its stem and `tkatjdwjswk` through
+    // its keyboard-layout conversion; highlight those forms too.
+    let mut highlight_terms = tokens.clone();
+    for term in &tokens {
+        let stem = stem_korean(term);
+        for extra in [Some(stem), layout_mistype(term)].into_iter().flatten() {
+            if extra != *term && !highlight_terms.contains(&extra) {
+                highlight_terms.push(extra);
+            }
+        }
+    }
 
     let fts_query = build_match_query(query_trimmed);
-    let scope_chat = match scope {
-        SearchScope::All => None,
-        SearchScope::Chat(id) => Some(*id),
-    };
+    let scope_chat = scope.chat();
+
+    // Decay ranks are measured from the first page's clock so every
+    // page of one search agrees on them.
+    let decay_anchor = cursor
+        .and_then(|c| c.decay_anchor)
+        .unwrap_or_else(crate::wiki::norm::unix_now);
 
     let messages = if let Some(fts_query) = fts_query {
-        store.search_messages_bm25(&fts_query, scope_chat, cursor, limit + 1)?
+        store.search_messages_bm25(
+            &fts_query,
+            scope_chat,
+            filters,
+            sort,
+            decay_anchor,
+            cursor,
+            limit + 1,
+        )?
     } else {
-        // Trigram needs >=3 chars; fall back to LIKE.
-        match scope {
-            SearchScope::All => store.search_messages_like(&tokens, cursor, limit + 1)?,
-            SearchScope::Chat(chat_id) => {
-                store.search_messages_like_in_chat(&tokens, *chat_id, cursor, limit + 1)?
-            }
+        // Trigram needs >=3 chars; fall back to LIKE, which evaluates
+        // the same query tree.
+        let like = parsed.as_ref().map(QueryNode::to_like_match);
+        match scope_chat {
+            None => store.search_messages_like(like.as_ref(), filters, sort, cursor, limit + 1)?,
+            Some((account_id, chat_id)) => store.search_messages_like_in_chat(
+                like.as_ref(),
+                account_id,
+                chat_id,
+                filters,
+                sort,
+                cursor,
+                limit + 1,
+            )?,
         }
     };
 
@@ -114,8 +227,11 @@ pub fn search(
         results.last().map(|last| Cursor {
             rank: last.rank,
             timestamp: last.timestamp,
+            account_id: last.account_id,
             chat_id: last.chat_id,
             message_id: last.message_id,
+            decay_anchor: matches!(sort, SearchSort::RelevanceWithDecay { .. })
+                .then_some(decay_anchor),
         })
     } else {
         None
@@ -124,20 +240,29 @@ pub fn search(
     let items: Vec<SearchItem> = results
         .into_iter()
         .map(|msg| {
-            let highlights = find_highlights(&msg.text_plain, &tokens);
+            let highlights = find_highlights(&msg.text_plain, &highlight_terms);
             SearchItem {
                 message_id: msg.message_id,
+                account_id: msg.account_id,
                 chat_id: msg.chat_id,
                 timestamp: msg.timestamp,
                 text: msg.text_plain,
                 link: msg.link,
                 chat_title: msg.chat_title,
+                sender_name: msg.sender_name,
+                matched_revision: msg.matched_revision,
                 highlights,
+                is_snippet: false,
             }
         })
         .collect();
 
-    Ok(SearchResult { items, next_cursor })
+    Ok(SearchResult {
+        items,
+        next_cursor,
+        ignored_filters: ignored,
+        ..Default::default()
+    })
 }
 
 #[cfg(test)]
@@ -153,6 +278,7 @@ mod tests {
     fn setup(store: &Store) {
         store
             .upsert_chat(&ChatRow {
+                account_id: 0,
                 chat_id: 1,
                 title: "Korean Chat".to_string(),
                 chat_type: "supergroup".to_string(),
@@ -163,6 +289,7 @@ mod tests {
             .unwrap();
         store
             .upsert_chat(&ChatRow {
+                account_id: 0,
                 chat_id: 2,
                 title: "English Chat".to_string(),
                 chat_type: "supergroup".to_string(),
@@ -178,12 +305,18 @@ mod tests {
         store
             .insert_messages_batch(&[MessageRow {
                 message_id: msg_id,
+                account_id: 0,
                 chat_id,
                 timestamp: ts,
                 text_plain: text.to_string(),
                 text_stripped: stripped,
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             }])
             .unwrap();
     }
@@ -195,7 +328,16 @@ mod tests {
         insert_msg(&store, 1, 1, 1000, "Hello world test message");
         insert_msg(&store, 1, 2, 1001, "Another message here");
 
-        let result = search(&store, "Hello", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "Hello",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.items.len(), 1);
         assert_eq!(result.items[0].message_id, 1);
         assert!(!result.items[0].highlights.is_empty());
@@ -208,7 +350,16 @@ mod tests {
         insert_msg(&store, 1, 1, 1000, "삼성전자 주가가 상승했다");
         insert_msg(&store, 1, 2, 1001, "오늘 날씨가 좋습니다");
 
-        let result = search(&store, "삼성", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "삼성",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert!(!result.items.is_empty());
         assert_eq!(result.items[0].chat_id, 1);
     }
@@ -216,7 +367,16 @@ mod tests {
     #[test]
     fn test_search_empty_query() {
         let store = test_store();
-        let result = search(&store, "", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert!(result.items.is_empty());
         assert!(result.next_cursor.is_none());
     }
@@ -227,7 +387,16 @@ mod tests {
         setup(&store);
         insert_msg(&store, 1, 1, 1000, "Hello world");
 
-        let result = search(&store, "zzzznonexistent", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "zzzznonexistent",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert!(result.items.is_empty());
     }
 
@@ -238,7 +407,19 @@ mod tests {
         insert_msg(&store, 1, 1, 1000, "Hello from chat 1");
         insert_msg(&store, 2, 2, 1001, "Hello from chat 2");
 
-        let result = search(&store, "Hello", &SearchScope::Chat(1), None, None).unwrap();
+        let result = search(
+            &store,
+            "Hello",
+            &SearchScope::Chat {
+                account_id: 0,
+                chat_id: 1,
+            },
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.items.len(), 1);
         assert_eq!(result.items[0].chat_id, 1);
     }
@@ -251,7 +432,16 @@ mod tests {
             insert_msg(&store, 1, i + 1, 1000 + i, &format!("test message {}", i));
         }
 
-        let page1 = search(&store, "test", &SearchScope::All, None, Some(2)).unwrap();
+        let page1 = search(
+            &store,
+            "test",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            Some(2),
+        )
+        .unwrap();
         assert_eq!(page1.items.len(), 2);
         assert!(page1.next_cursor.is_some());
 
@@ -259,6 +449,8 @@ mod tests {
             &store,
             "test",
             &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
             page1.next_cursor.as_ref(),
             Some(2),
         )
@@ -270,6 +462,8 @@ mod tests {
             &store,
             "test",
             &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
             page2.next_cursor.as_ref(),
             Some(2),
         )
@@ -284,7 +478,16 @@ mod tests {
         setup(&store);
         insert_msg(&store, 1, 1, 1000, "Hello world test");
 
-        let result = search(&store, "Hello", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "Hello",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.items.len(), 1);
         let item = &result.items[0];
         assert!(!item.highlights.is_empty());
@@ -300,7 +503,16 @@ mod tests {
         insert_msg(&store, 1, 2, 2000, "test new message");
         insert_msg(&store, 1, 3, 1500, "test middle message");
 
-        let result = search(&store, "test", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "test",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.items.len(), 3);
         assert_eq!(result.items[0].timestamp, 2000);
         assert_eq!(result.items[1].timestamp, 1500);
@@ -320,16 +532,323 @@ mod tests {
         );
         insert_msg(&store, 1, 2, 1001, "bitcoin etf mentioned once");
 
-        let result = search(&store, "bitcoin etf", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "bitcoin etf",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.items.len(), 2);
         assert_eq!(result.items[0].message_id, 1);
     }
 
+    fn filter_store() -> Store {
+        let store = test_store();
+        setup(&store);
+        store
+            .upsert_chat(&ChatRow {
+                account_id: 0,
+                chat_id: 3,
+                title: "News Channel".to_string(),
+                chat_type: "channel".to_string(),
+                username: None,
+                access_hash: None,
+                is_excluded: false,
+            })
+            .unwrap();
+        let rows: Vec<MessageRow> = [
+            (1, 1, 1000, 10, "bitcoin etf 승인"),
+            (1, 2, 2000, 11, "bitcoin etf 루머"),
+            (2, 3, 3000, 10, "bitcoin etf flows"),
+            (3, 4, 4000, 10, "bitcoin etf 속보"),
+        ]
+        .into_iter()
+        .map(
+            |(chat_id, message_id, timestamp, sender_id, text)| MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id,
+                timestamp,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            },
+        )
+        .collect();
+        store.insert_messages_batch(&rows).unwrap();
+        store
+    }
+
+    fn filtered_ids(
+        store: &Store,
+        query: &str,
+        scope: &SearchScope,
+        filters: &SearchFilters,
+    ) -> Vec<i64> {
+        let mut ids: Vec<i64> = search(
+            store,
+            query,
+            scope,
+            filters,
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap()
+        .items
+        .iter()
+        .map(|i| i.message_id)
+        .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn filters_narrow_bm25_search() {
+        let store = filter_store();
+        let all = SearchScope::All;
+
+        let range = SearchFilters {
+            since: Some(2000),
+            until: Some(3000),
+            ..Default::default()
+        };
+        assert_eq!(filtered_ids(&store, "bitcoin", &all, &range), vec![2, 3]);
+
+        let sender = SearchFilters {
+            sender_ids: vec![10],
+            ..Default::default()
+        };
+        assert_eq!(
+            filtered_ids(&store, "bitcoin", &all, &sender),
+            vec![1, 3, 4]
+        );
+
+        let chats = SearchFilters {
+            chat_ids: vec![2, 3],
+            ..Default::default()
+        };
+        assert_eq!(filtered_ids(&store, "bitcoin", &all, &chats), vec![3, 4]);
+
+        let channels = SearchFilters {
+            chat_types: vec!["channel".to_string()],
+            sender_ids: vec![10],
+            ..Default::default()
+        };
+        assert_eq!(filtered_ids(&store, "bitcoin", &all, &channels), vec![4]);
+
+        // Filters AND with the chat scope.
+        assert_eq!(
+            filtered_ids(
+                &store,
+                "bitcoin",
+                &SearchScope::Chat {
+                    account_id: 0,
+                    chat_id: 1
+                },
+                &sender
+            ),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn filters_narrow_like_fallback() {
+        let store = filter_store();
+        let filters = SearchFilters {
+            since: Some(1500),
+            sender_ids: vec![10, 11],
+            chat_types: vec!["supergroup".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            filtered_ids(&store, "et", &SearchScope::All, &filters),
+            vec![2, 3]
+        );
+        assert_eq!(
+            filtered_ids(
+                &store,
+                "et",
+                &SearchScope::Chat {
+                    account_id: 0,
+                    chat_id: 1
+                },
+                &filters
+            ),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn test_build_match_query() {
+        assert_eq!(
+            build_match_query("hello world").as_deref(),
+            Some("(\"hello\" AND \"world\") OR \"helloworld\"")
+        );
+        assert_eq!(
+            build_match_query("  spaces  ").as_deref(),
+            Some("\"spaces\"")
+        );
+    }
+
+    #[test]
+    fn query_or_matches_either_term() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "비트코인 급등");
+        insert_msg(&store, 1, 2, 1001, "이더리움 급등");
+        insert_msg(&store, 1, 3, 1002, "솔라나 급등");
+
+        let result = search(
+            &store,
+            "비트코인 OR 이더리움",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn query_exclusion_drops_matches_and_skips_highlight() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "bitcoin etf approved");
+        insert_msg(&store, 1, 2, 1001, "bitcoin etf rumor again");
+
+        let result = search(
+            &store,
+            "bitcoin -rumor",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].message_id, 1);
+        assert_eq!(result.items[0].highlights.len(), 1);
+
+        // Same exclusion on the short-term LIKE path.
+        let result = search(
+            &store,
+            "et -ru",
+            &SearchScope::Chat {
+                account_id: 0,
+                chat_id: 1,
+            },
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    fn all_ids(store: &Store, query: &str) -> Vec<i64> {
+        let result = search(
+            store,
+            query,
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        ids.sort();
+        ids
+    }
+
     #[test]
-    fn test_build_fts_query() {
-        assert_eq!(build_fts_query("hello world"), "\"hello\" \"world\"");
-        assert_eq!(build_fts_query("삼성전자"), "\"삼성전자\"");
-        assert_eq!(build_fts_query("  spaces  "), "\"spaces\"");
+    fn short_terms_keep_their_meaning() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "ab bitcoin cdef");
+        insert_msg(&store, 1, 2, 1001, "bitcoin efgh");
+        insert_msg(&store, 1, 3, 1002, "cd only");
+
+        // A short required term still has to be present.
+        assert_eq!(all_ids(&store, "ab bitcoin"), vec![1]);
+        // A short exclusion still excludes.
+        assert_eq!(all_ids(&store, "bitcoin -ab"), vec![2]);
+        // `OR` on the LIKE path is an OR.
+        assert_eq!(all_ids(&store, "ab OR cd"), vec![1, 3]);
+        assert_eq!(all_ids(&store, "(ab OR cd) -bitcoin"), vec![3]);
+    }
+
+    #[test]
+    fn negation_excludes_only_the_literal_text() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "삼성 bitcoin 매수");
+        insert_msg(&store, 1, 2, 1001, "sam bitcoin buy");
+        insert_msg(&store, 1, 3, 1002, "bitcoin 매수");
+
+        // 삼성's romanization holds `sam`; the exclusion must not see it.
+        assert_eq!(all_ids(&store, "bitcoin -sam"), vec![1, 3]);
+        assert_eq!(all_ids(&store, "bit -sa"), vec![1, 3]);
+    }
+
+    #[test]
+    fn query_phrase_requires_adjacent_words() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "the bitcoin etf launched");
+        insert_msg(&store, 1, 2, 1001, "etf flows and bitcoin price");
+
+        let result = search(
+            &store,
+            "\"bitcoin etf\"",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].message_id, 1);
+        let h = &result.items[0].highlights[0];
+        assert_eq!(&result.items[0].text[h.start..h.end], "bitcoin etf");
+    }
+
+    #[test]
+    fn query_with_only_exclusions_returns_nothing() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "hello world");
+
+        let result = search(
+            &store,
+            "-hello",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(result.items.is_empty());
     }
 
     // -------- Korean-specific integration tests (schema v6) --------
@@ -350,7 +869,16 @@ mod tests {
         // `삼성` should hit both `삼성전자` and `삼성 전자` via the
         // plain trigram path.
         let store = korean_store();
-        let result = search(&store, "삼성", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "삼성",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
         assert!(ids.contains(&1), "expected 삼성전자 row, got {ids:?}");
         assert!(ids.contains(&2), "expected 삼성 전자 row, got {ids:?}");
@@ -360,7 +888,16 @@ mod tests {
     fn korean_whitespace_insensitive_match() {
         // `삼성전자` should match the row that has a space inserted.
         let store = korean_store();
-        let result = search(&store, "삼성전자", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "삼성전자",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
         assert!(ids.contains(&1), "expected exact 삼성전자 row, got {ids:?}");
         assert!(
@@ -373,7 +910,16 @@ mod tests {
     fn korean_bare_jamo_query() {
         // `ㅅㅏㅁ` should match `삼` via the jamo index.
         let store = korean_store();
-        let result = search(&store, "ㅅㅏㅁ", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "ㅅㅏㅁ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
         assert!(
             ids.contains(&1) || ids.contains(&2),
@@ -384,7 +930,16 @@ mod tests {
     #[test]
     fn korean_short_jamo_like_fallback() {
         let store = korean_store();
-        let result = search(&store, "ㅅㅏ", &SearchScope::All, None, None).unwrap();
+        let result = search(
+            &store,
+            "ㅅㅏ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
         assert!(
             ids.contains(&1) || ids.contains(&2),
@@ -392,10 +947,758 @@ mod tests {
         );
     }
 
+    #[test]
+    fn korean_choseong_query() {
+        let store = korean_store();
+        let result = search(
+            &store,
+            "ㅅㅅㅈㅈ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+        let item = result.items.iter().find(|i| i.message_id == 2).unwrap();
+        let h = &item.highlights[0];
+        assert_eq!(&item.text[h.start..h.end], "삼성 전자");
+    }
+
+    #[test]
+    fn korean_mixed_syllable_choseong_query() {
+        let store = korean_store();
+        insert_msg(&store, 1, 6, 1005, "상속 제재 논의");
+
+        // `삼성ㅈㅈ` keeps the syllables: 상속 제재 shares the
+        // consonants but not 삼성.
+        let result = search(
+            &store,
+            "삼성ㅈㅈ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let mut ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+
+        // Pure consonants cannot tell them apart.
+        let result = search(
+            &store,
+            "ㅅㅅㅈㅈ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.items.len(), 3);
+    }
+
+    #[test]
+    fn korean_short_choseong_like_fallback() {
+        let store = korean_store();
+        let result = search(
+            &store,
+            "ㄷㅎ",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        assert_eq!(
+            ids,
+            vec![3],
+            "expected 대한민국 via choseong LIKE, got {ids:?}"
+        );
+    }
+
+    #[test]
+    fn korean_particle_query_matches_bare_noun() {
+        let store = korean_store();
+        insert_msg(&store, 1, 6, 1005, "삼성전자를 매수했다");
+        insert_msg(&store, 1, 7, 1006, "삼성전자는 어제 하락");
+
+        let ids = |q: &str| {
+            let mut ids: Vec<i64> = search(
+                &store,
+                q,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .iter()
+            .map(|i| i.message_id)
+            .collect();
+            ids.sort();
+            ids
+        };
+        let bare = ids("삼성전자");
+        assert_eq!(bare, vec![1, 2, 6, 7]);
+        // 삼성 전자 (id 2) has no stem that contains 삼성전자, so the
+        // particle forms find the rows that contain the noun itself.
+        assert_eq!(ids("삼성전자가"), vec![1, 6, 7]);
+        assert_eq!(ids("삼성전자를"), vec![1, 6, 7]);
+
+        let result = search(
+            &store,
+            "삼성전자가",
+            &SearchScope::Chat {
+                account_id: 0,
+                chat_id: 1,
+            },
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let item = result.items.iter().find(|i| i.message_id == 6).unwrap();
+        let h = &item.highlights[0];
+        assert_eq!(&item.text[h.start..h.end], "삼성전자");
+    }
+
+    #[test]
+    fn korean_short_stem_like_fallback() {
+        let store = korean_store();
+        // 주가가 → 주가 is too short for trigram; LIKE uses the stem.
+        let result = search(
+            &store,
+            "주가가",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let ids: Vec<i64> = result.items.iter().map(|i| i.message_id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
     #[test]
     fn plan_branches_falls_back_to_like_for_short_queries() {
         // One-char query should skip FTS so the LIKE path can handle it.
         assert!(build_match_query("a").is_none());
         assert!(build_match_query("ㅅ").is_none());
     }
+
+    #[test]
+    fn typo_falls_back_to_corrected_query() {
+        let store = korean_store();
+        let result = search(
+            &store,
+            "삼송전자",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(!result.items.is_empty());
+        assert_eq!(result.did_you_mean.as_deref(), Some("삼성전자"));
+        assert!(result.items.iter().any(|i| i.message_id == 1));
+
+        let exact = search(
+            &store,
+            "삼성전자",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(exact.did_you_mean.is_none());
+    }
+
+    #[test]
+    fn english_mode_keystrokes_find_hangul() {
+        let store = korean_store();
+        let result = search(
+            &store,
+            "tkatjdwjswk",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
+        let item = result.items.iter().find(|i| i.message_id == 1).unwrap();
+        let h = &item.highlights[0];
+        assert_eq!(&item.text[h.start..h.end], "삼성전자");
+        assert!(result.did_you_mean.is_none());
+    }
+
+    #[test]
+    fn romanized_and_hangul_spellings_find_each_other() {
+        let store = korean_store();
+        insert_msg(&store, 2, 10, 2000, "Samseong Electronics earnings");
+        let ids = |query: &str| -> Vec<i64> {
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .iter()
+            .map(|i| i.message_id)
+            .collect()
+        };
+        let latin = ids("samseong");
+        assert!(latin.contains(&1) && latin.contains(&10));
+        // The literal Latin hit outranks the transliterated ones.
+        assert_eq!(latin[0], 10);
+        assert!(ids("삼성").contains(&10));
+    }
+
+    #[test]
+    fn facets_count_every_page() {
+        let store = test_store();
+        setup(&store);
+        for i in 0..5 {
+            insert_msg(&store, 1, i + 1, 86400 * 3 + i, "삼성전자 실적");
+        }
+        insert_msg(&store, 2, 10, 86400 * 5, "삼성전자 주가");
+        insert_msg(&store, 2, 11, 86400 * 5 + 1, "애플 주가");
+
+        let facets = search_facets(
+            &store,
+            "삼성전자",
+            &SearchScope::All,
+            &SearchFilters::default(),
+        )
+        .unwrap();
+        assert_eq!(facets.total, 6);
+        let chats: Vec<(i64, u64)> = facets
+            .by_chat
+            .iter()
+            .map(|c| (c.chat_id, c.count))
+            .collect();
+        assert_eq!(chats, vec![(1, 5), (2, 1)]);
+        assert_eq!(facets.by_chat[0].chat_title, "Korean Chat");
+        let days: Vec<(i64, u64)> = facets
+            .by_day
+            .iter()
+            .map(|d| (d.day_start, d.count))
+            .collect();
+        assert_eq!(days, vec![(86400 * 3, 5), (86400 * 5, 1)]);
+
+        // Scope and the LIKE fallback go through the same counts.
+        let scoped = search_facets(
+            &store,
+            "주가",
+            &SearchScope::Chat {
+                account_id: 0,
+                chat_id: 2,
+            },
+            &SearchFilters::default(),
+        )
+        .unwrap();
+        assert_eq!(scoped.total, 2);
+        let none = search_facets(&store, "", &SearchScope::All, &SearchFilters::default()).unwrap();
+        assert_eq!(none, SearchFacets::default());
+    }
+
+    #[test]
+    fn entity_filters_narrow_and_browse() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(
+            &store,
+            1,
+            1,
+            1000,
+            "삼성전자 실적 #공시 https://dart.fss.or.kr/x",
+        );
+        insert_msg(&store, 1, 2, 1001, "삼성전자 루머 #공시");
+        insert_msg(&store, 2, 3, 1002, "삼성전자 주가 $SSNLF");
+
+        let ids = |query: &str| -> Vec<i64> {
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::Newest,
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .iter()
+            .map(|i| i.message_id)
+            .collect()
+        };
+        assert_eq!(ids("삼성전자 tag:공시"), vec![2, 1]);
+        assert_eq!(ids("삼성전자 domain:fss.or.kr"), vec![1]);
+        assert_eq!(ids("tag:공시 -루머"), vec![1]);
+        assert_eq!(ids("has:cashtag"), vec![3]);
+        assert!(ids("tag:없는태그").is_empty());
+
+        let facets = search_facets(
+            &store,
+            "tag:공시",
+            &SearchScope::All,
+            &SearchFilters::default(),
+        )
+        .unwrap();
+        assert_eq!(facets.total, 2);
+    }
+
+    #[test]
+    fn negated_and_or_filters_are_ignored_and_reported() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "삼성전자 실적 https://dart.fss.or.kr/x");
+        insert_msg(&store, 1, 2, 1001, "삼성전자 루머");
+        insert_msg(&store, 2, 3, 1002, "비트코인 급등");
+
+        let run = |query: &str| -> SearchResult {
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::Newest,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        let ids = |result: &SearchResult| -> Vec<i64> {
+            result.items.iter().map(|i| i.message_id).collect()
+        };
+
+        // Never the opposite of what was typed.
+        let result = run("삼성전자 -has:link");
+        assert_eq!(ids(&result), vec![2, 1]);
+        assert_eq!(result.ignored_filters, vec!["has:link"]);
+
+        // Nor an `OR` quietly turned into an AND.
+        let result = run("비트코인 OR domain:fss.or.kr");
+        assert_eq!(ids(&result), vec![3]);
+        assert_eq!(result.ignored_filters, vec!["domain:fss.or.kr"]);
+
+        let result = run("-has:link");
+        assert!(result.items.is_empty());
+        assert_eq!(result.ignored_filters, vec!["has:link"]);
+
+        let result = run("삼성전자 has:link");
+        assert_eq!(ids(&result), vec![1]);
+        assert!(result.ignored_filters.is_empty());
+    }
+
+    #[test]
+    fn media_captions_file_names_and_type_filters() {
+        use crate::store::media::{MediaKind, MessageMedia};
+
+        let store = test_store();
+        setup(&store);
+        let with_media = |message_id: i64, text: &str, media: MessageMedia| {
+            store
+                .insert_messages_batch(&[MessageRow {
+                    message_id,
+                    account_id: 0,
+                    chat_id: 1,
+                    timestamp: 1000 + message_id,
+                    text_plain: text.to_string(),
+                    text_stripped: strip_whitespace(text),
+                    link: None,
+                    sender_id: 0,
+                    media: Some(media),
+                    reply_to_message_id: None,
+                    thread_id: None,
+                    sender_name: None,
+                    sender_username: None,
+                }])
+                .unwrap();
+        };
+        with_media(
+            1,
+            "",
+            MessageMedia {
+                kind: Some(MediaKind::Document),
+                file_name: Some("삼성전자_3분기_보고서.pdf".into()),
+                mime_type: Some("application/pdf".into()),
+                ..Default::default()
+            },
+        );
+        with_media(
+            2,
+            "",
+            MessageMedia {
+                kind: Some(MediaKind::Photo),
+                caption: Some("공장 준공식 사진".into()),
+                ..Default::default()
+            },
+        );
+        insert_msg(&store, 1, 3, 1003, "보고서 나왔나요");
+
+        let ids = |query: &str| -> Vec<i64> {
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::Newest,
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .iter()
+            .map(|i| i.message_id)
+            .collect()
+        };
+        assert_eq!(ids("보고서"), vec![3, 1]);
+        assert_eq!(ids("준공식"), vec![2]);
+        // Two syllables take the LIKE path, which reads text_media too.
+        assert_eq!(ids("공장"), vec![2]);
+        assert_eq!(ids("보고서 type:document"), vec![1]);
+        assert_eq!(ids("has:photo"), vec![2]);
+        assert_eq!(ids("pdf"), vec![1]);
+
+        let filters = SearchFilters {
+            media_kinds: vec![MediaKind::Photo, MediaKind::Document],
+            ..Default::default()
+        };
+        let browsed = search(
+            &store,
+            "",
+            &SearchScope::All,
+            &filters,
+            SearchSort::Newest,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(browsed.items.len(), 2);
+    }
+
+    #[test]
+    fn from_filter_matches_current_and_former_names() {
+        let store = test_store();
+        setup(&store);
+        let by =
+            |message_id: i64, sender_id: i64, name: &str, username: Option<&str>, text: &str| {
+                store
+                    .insert_messages_batch(&[MessageRow {
+                        message_id,
+                        account_id: 0,
+                        chat_id: 1,
+                        timestamp: 1000 + message_id,
+                        text_plain: text.to_string(),
+                        text_stripped: strip_whitespace(text),
+                        link: None,
+                        sender_id,
+                        media: None,
+                        reply_to_message_id: None,
+                        thread_id: None,
+                        sender_name: Some(name.into()),
+                        sender_username: username.map(Into::into),
+                    }])
+                    .unwrap();
+            };
+        by(1, 7, "김철수", None, "배당 공지 올립니다");
+        by(2, 7, "철수 (운영자)", Some("cheolsu_kim"), "배당 일정 변경");
+        by(3, 8, "박영희", None, "배당 언제 나오나요");
+
+        let hits = |query: &str| -> Vec<(i64, Option<String>)> {
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                SearchSort::Oldest,
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| (i.message_id, i.sender_name))
+            .collect()
+        };
+        let ids = |query: &str| -> Vec<i64> { hits(query).into_iter().map(|h| h.0).collect() };
+
+        // The former name still finds both messages; hits carry the
+        // current one.
+        assert_eq!(
+            hits("배당 from:김철수"),
+            vec![
+                (1, Some("철수 (운영자)".to_string())),
+                (2, Some("철수 (운영자)".to_string())),
+            ]
+        );
+        assert_eq!(ids("배당 from:김철ㅅ"), vec![1, 2]);
+        assert_eq!(ids("from:@CheolSu_Kim"), vec![1, 2]);
+        assert_eq!(ids("from:\"철수 (운영\""), vec![1, 2]);
+        assert_eq!(ids("배당 from:영희 OR from:운영자"), vec![1, 2, 3]);
+        assert!(ids("from:이순신").is_empty());
+    }
+
+    #[test]
+    fn accounts_keep_same_ids_apart_and_filter() {
+        let store = test_store();
+        setup(&store);
+        store
+            .upsert_chat(&ChatRow {
+                account_id: 1,
+                chat_id: 1,
+                title: "Korean Chat (work)".to_string(),
+                chat_type: "supergroup".to_string(),
+                username: None,
+                access_hash: None,
+                is_excluded: false,
+            })
+            .unwrap();
+        insert_msg(&store, 1, 1, 1000, "비트코인 상장 공지");
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id: 1,
+                account_id: 1,
+                chat_id: 1,
+                timestamp: 1000,
+                text_plain: "비트코인 상장 공지".to_string(),
+                text_stripped: strip_whitespace("비트코인 상장 공지"),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+
+        let hits = |scope: &SearchScope, account_ids: Vec<i64>| -> Vec<(i64, String)> {
+            let filters = SearchFilters {
+                account_ids,
+                ..Default::default()
+            };
+            search(
+                &store,
+                "비트코인",
+                scope,
+                &filters,
+                SearchSort::Newest,
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| (i.account_id, i.chat_title))
+            .collect()
+        };
+        assert_eq!(
+            hits(&SearchScope::All, vec![]),
+            vec![
+                (0, "Korean Chat".to_string()),
+                (1, "Korean Chat (work)".to_string()),
+            ]
+        );
+        assert_eq!(
+            hits(&SearchScope::All, vec![1]),
+            vec![(1, "Korean Chat (work)".to_string())]
+        );
+        // A chat scope names the account too: the same chat id in
+        // another account is another chat.
+        let work = SearchScope::Chat {
+            account_id: 1,
+            chat_id: 1,
+        };
+        assert_eq!(
+            hits(&work, vec![]),
+            vec![(1, "Korean Chat (work)".to_string())]
+        );
+        assert_eq!(hits(&work, vec![0]), vec![]);
+
+        // Rows equal in everything but the chat or account still page
+        // apart: (0, 1, 1), (0, 2, 1) | (1, 1, 1).
+        insert_msg(&store, 2, 1, 1000, "비트코인 상장 공지");
+        assert_eq!(
+            paged_ids(&store, "비트코인", SearchSort::Newest),
+            vec![1, 1, 1]
+        );
+        assert_eq!(paged_ids(&store, "비트", SearchSort::Oldest), vec![1, 1, 1]);
+
+        // Excluding one account's copy of the chat leaves the other.
+        store.set_chat_excluded(0, 1, true).unwrap();
+        assert_eq!(
+            hits(&SearchScope::All, vec![]),
+            vec![
+                (0, "English Chat".to_string()),
+                (1, "Korean Chat (work)".to_string()),
+            ]
+        );
+    }
+
+    /// Page through `query` two at a time under `sort`.
+    fn paged_ids(store: &Store, query: &str, sort: SearchSort) -> Vec<i64> {
+        let mut ids = Vec::new();
+        let mut cursor: Option<Cursor> = None;
+        loop {
+            let page = search(
+                store,
+                query,
+                &SearchScope::All,
+                &SearchFilters::default(),
+                sort,
+                cursor.as_ref(),
+                Some(2),
+            )
+            .unwrap();
+            ids.extend(page.items.iter().map(|i| i.message_id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return ids,
+            }
+        }
+    }
+
+    #[test]
+    fn time_sorts_paginate_in_order() {
+        let store = test_store();
+        setup(&store);
+        for (id, ts) in [(1, 3000), (2, 1000), (3, 2000), (4, 2000), (5, 4000)] {
+            insert_msg(&store, 1, id, ts, "bitcoin etf news");
+        }
+        // Same timestamp ties break on message id in both directions.
+        assert_eq!(
+            paged_ids(&store, "bitcoin", SearchSort::Newest),
+            vec![5, 1, 3, 4, 2]
+        );
+        assert_eq!(
+            paged_ids(&store, "bitcoin", SearchSort::Oldest),
+            vec![2, 3, 4, 1, 5]
+        );
+        // The LIKE fallback honours the time sorts too.
+        assert_eq!(
+            paged_ids(&store, "et", SearchSort::Oldest),
+            vec![2, 3, 4, 1, 5]
+        );
+    }
+
+    #[test]
+    fn decay_prefers_recent_matches_and_paginates() {
+        let store = test_store();
+        setup(&store);
+        let now = crate::wiki::norm::unix_now();
+        let day = 86400;
+        // A strong but year-old match against a weak fresh one.
+        insert_msg(
+            &store,
+            1,
+            1,
+            now - 365 * day,
+            "bitcoin etf bitcoin etf bitcoin etf confirmed inflows",
+        );
+        insert_msg(&store, 1, 2, now - day, "bitcoin etf mentioned once");
+        for id in 3..=6 {
+            insert_msg(&store, 1, id, now - id * 30 * day, "bitcoin etf update");
+        }
+
+        // With a very long half-life the stronger match still wins.
+        let slow = paged_ids(
+            &store,
+            "bitcoin etf",
+            SearchSort::RelevanceWithDecay {
+                half_life_days: 100_000.0,
+            },
+        );
+        assert_eq!(slow[0], 1);
+
+        let decayed = paged_ids(
+            &store,
+            "bitcoin etf",
+            SearchSort::RelevanceWithDecay {
+                half_life_days: 7.0,
+            },
+        );
+        assert_eq!(decayed[0], 2);
+        let mut sorted = decayed.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![1, 2, 3, 4, 5, 6]);
+
+        let first = search(
+            &store,
+            "bitcoin etf",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::RelevanceWithDecay {
+                half_life_days: 7.0,
+            },
+            None,
+            Some(2),
+        )
+        .unwrap();
+        assert!(first.next_cursor.unwrap().decay_anchor.is_some());
+    }
+
+    #[test]
+    fn include_revisions_matches_edited_text() {
+        let store = test_store();
+        setup(&store);
+        insert_msg(&store, 1, 1, 1000, "비트코인 상장 공지");
+        insert_msg(&store, 1, 1, 1000, "상장 취소");
+        insert_msg(&store, 1, 2, 2000, "비트코인 급등");
+        insert_msg(&store, 1, 2, 2000, "비트코인 급락");
+        insert_msg(&store, 1, 3, 3000, "비트코인 시세");
+
+        let hits = |query: &str, include_revisions: bool| -> Vec<(i64, Option<i64>)> {
+            let filters = SearchFilters {
+                include_revisions,
+                ..Default::default()
+            };
+            search(
+                &store,
+                query,
+                &SearchScope::All,
+                &filters,
+                SearchSort::Newest,
+                None,
+                None,
+            )
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| (i.message_id, i.matched_revision))
+            .collect()
+        };
+        // FTS path: only the edited-away text of message 1 matches.
+        assert_eq!(hits("비트코인", false), vec![(3, None), (2, None)]);
+        assert_eq!(
+            hits("비트코인", true),
+            vec![(3, None), (2, None), (1, Some(1))]
+        );
+        // LIKE path, the same split.
+        assert_eq!(hits("코인", false), vec![(3, None), (2, None)]);
+        assert_eq!(hits("코인", true), vec![(3, None), (2, None), (1, Some(1))]);
+        assert_eq!(hits("급등", true), vec![(2, Some(1))]);
+        assert_eq!(hits("공지 -상장", true), vec![]);
+    }
 }
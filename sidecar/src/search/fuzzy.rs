@@ -0,0 +1,190 @@
+//! Typo-tolerant fallback for searches that match nothing.
+//!
+//! Korean typos are usually one wrong jamo (`삼송` for `삼성`), which
+//! is a whole different syllable and shares no trigram with the
+//! intended word. Comparing in the compat-jamo alphabet turns that
+//! into an edit distance of one. Candidates come from the vocabulary
+//! table harvested at index time (`store::vocab`).
+
+use crate::store::Store;
+
+use super::hangul::{decompose_jamo, stem_korean};
+use super::query::parse_query;
+
+/// How many vocabulary words to compare per query term. Ordered by
+/// frequency, so a typo of a rare word may not be corrected.
+const CANDIDATE_LIMIT: usize = 5000;
+
+/// Levenshtein distance between the jamo decompositions of `a` and `b`.
+pub fn jamo_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = decompose_jamo(a).chars().collect();
+    let b: Vec<char> = decompose_jamo(b).chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Typos allowed for a word of `jamo_len` jamo. Very short words are
+/// never corrected: everything is one edit away from them.
+fn max_typos(jamo_len: usize) -> usize {
+    match jamo_len {
+        0..=4 => 0,
+        5..=9 => 1,
+        _ => 2,
+    }
+}
+
+/// Closest vocabulary word to `word`, or `None` when `word` is itself
+/// known or nothing is close enough. Ties go to the more frequent word.
+fn correct_word(store: &Store, word: &str) -> Result<Option<String>, sqlite::Error> {
+    let word = word.to_lowercase();
+    if store.vocab_contains(&word)? {
+        return Ok(None);
+    }
+    let jamo_len = decompose_jamo(&word).chars().count();
+    let budget = max_typos(jamo_len);
+    if budget == 0 {
+        return Ok(None);
+    }
+    let candidates = store.vocab_candidates(
+        jamo_len.saturating_sub(budget),
+        jamo_len + budget,
+        CANDIDATE_LIMIT,
+    )?;
+    // Candidates arrive most frequent first; strict `<` keeps the
+    // first (most frequent) of equally close words.
+    let mut best: Option<(usize, String)> = None;
+    for entry in candidates {
+        let distance = jamo_distance(&word, &entry.term);
+        if distance <= budget && best.as_ref().is_none_or(|(d, _)| distance < *d) {
+            best = Some((distance, entry.term));
+        }
+    }
+    Ok(best.map(|(_, term)| term))
+}
+
+/// Propose a corrected query by replacing misspelled positive terms
+/// with their closest vocabulary word. Particles survive the swap:
+/// `삼송전자를` becomes `삼성전자를`. Phrases, column-pinned terms and
+/// exclusions are left as typed. Returns `None` if nothing changed.
+pub fn suggest_correction(store: &Store, query: &str) -> Result<Option<String>, sqlite::Error> {
+    let Some(parsed) = parse_query(query) else {
+        return Ok(None);
+    };
+    let mut corrected = query.to_string();
+    let mut changed = false;
+    for term in parsed.correctable_terms() {
+        let stem = stem_korean(&term);
+        let Some(fixed_stem) = correct_word(store, &stem)? else {
+            continue;
+        };
+        let suffix = &term[stem.len()..];
+        let fixed = format!("{fixed_stem}{suffix}");
+        corrected = replace_word(&corrected, &term, &fixed);
+        changed = true;
+    }
+    Ok(changed.then_some(corrected))
+}
+
+/// Replace the first whitespace-delimited occurrence of `word` (it may
+/// be wrapped in parentheses) in `query`.
+fn replace_word(query: &str, word: &str, replacement: &str) -> String {
+    let mut done = false;
+    query
+        .split(' ')
+        .map(|token| {
+            if !done && token.trim_matches(|c| c == '(' || c == ')') == word {
+                done = true;
+                token.replacen(word, replacement, 1)
+            } else {
+                token.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRow};
+
+    fn store_with(texts: &[&str]) -> Store {
+        let store = Store::open_in_memory().unwrap();
+        let rows: Vec<MessageRow> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| MessageRow {
+                message_id: i as i64 + 1,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1000 + i as i64,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            })
+            .collect();
+        store.insert_messages_batch(&rows).unwrap();
+        store
+    }
+
+    #[test]
+    fn jamo_distance_counts_single_jamo_typos() {
+        assert_eq!(jamo_distance("삼성", "삼성"), 0);
+        assert_eq!(jamo_distance("삼송", "삼성"), 1);
+        assert_eq!(jamo_distance("삼성전자", "삼성전재"), 1);
+        assert_eq!(jamo_distance("bitcoin", "bitcon"), 1);
+        assert_eq!(jamo_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggests_closest_known_word() {
+        let store = store_with(&["삼성전자 실적 발표", "삼성전자가 상승", "bitcoin etf"]);
+        assert_eq!(
+            suggest_correction(&store, "삼송전자").unwrap().as_deref(),
+            Some("삼성전자")
+        );
+        assert_eq!(
+            suggest_correction(&store, "삼송전자를 bitcon")
+                .unwrap()
+                .as_deref(),
+            Some("삼성전자를 bitcoin")
+        );
+        assert_eq!(
+            suggest_correction(&store, "실젹").unwrap().as_deref(),
+            Some("실적")
+        );
+    }
+
+    #[test]
+    fn leaves_known_short_and_excluded_terms_alone() {
+        let store = store_with(&["삼성전자 실적 발표"]);
+        assert_eq!(suggest_correction(&store, "삼성전자").unwrap(), None);
+        // Too short to correct safely: everything is one edit away.
+        assert_eq!(suggest_correction(&store, "eft").unwrap(), None);
+        assert_eq!(suggest_correction(&store, "-삼송전자").unwrap(), None);
+        assert_eq!(suggest_correction(&store, "\"삼송전자\"").unwrap(), None);
+    }
+
+    #[test]
+    fn replace_word_handles_groups() {
+        assert_eq!(
+            replace_word("(삼송 OR x) 삼송", "삼송", "삼성"),
+            "(삼성 OR x) 삼송"
+        );
+    }
+}
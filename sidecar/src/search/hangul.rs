@@ -20,6 +20,17 @@
 //! is needed — all of this is simple codepoint math off the Unicode
 //! Hangul Syllables block at U+AC00–U+D7A3.
 //!
+//! A second form, `text_choseong`, keeps only the initial consonant
+//! of each syllable so phone-keyboard shorthand like `ㅅㅅㅈㅈ` finds
+//! `삼성전자`.
+//!
+//! A third, `text_roman`, is the Revised Romanization of the text
+//! so `samseong` finds `삼성` and `삼성` finds `Samseong`.
+//!
+//! Queries are also checked for keyboard-layout mistakes: the 2-set
+//! (두벌식) mapper turns `tkatjdwjswk`, typed with the IME in English
+//! mode, back into `삼성전자`, and the reverse.
+//!
 //! ```text
 //!   syllable = 0xAC00 + (cho * 588) + (jung * 28) + jong
 //! ```
@@ -103,6 +114,494 @@ pub fn contains_bare_jamo(query: &str) -> bool {
     })
 }
 
+/// Choseong (initial-consonant) form of `text`: every syllable is
+/// replaced by its leading consonant and whitespace is dropped, so
+/// `삼성 전자` becomes `ㅅㅅㅈㅈ`. Other code points pass through
+/// unchanged. Indexed as `text_choseong` for phone-keyboard shorthand.
+pub fn to_choseong(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            if is_hangul_syllable(c) {
+                split_syllable(c).0
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Particles (josa) and verb endings (eomi) peeled off the end of a
+/// word by [`stem_korean`], longest first so `에서는` wins over `는`.
+/// No dictionary: a suffix only needs to end the word and leave a stem
+/// of at least two characters behind.
+const JOSA_EOMI_SUFFIXES: &[&str] = &[
+    // Verb and adjective endings, mostly on 하다/되다 predicates.
+    "했습니다",
+    "됐습니다",
+    "하겠습니다",
+    "되었습니다",
+    "합니다",
+    "됩니다",
+    "습니다",
+    "했었다",
+    "되었다",
+    "했다",
+    "됐다",
+    "한다",
+    "된다",
+    "하다",
+    "되다",
+    "하는",
+    "되는",
+    "하고",
+    "되고",
+    "해서",
+    "돼서",
+    "하며",
+    "하여",
+    "되어",
+    "했고",
+    "됐고",
+    "었다",
+    "았다",
+    "였다",
+    "겠다",
+    "는다",
+    "이다",
+    "었고",
+    "았고",
+    // Stacked and multi-syllable particles.
+    "에서부터",
+    "으로부터",
+    "에게서",
+    "한테서",
+    "으로서",
+    "으로써",
+    "이라고",
+    "에서는",
+    "에서도",
+    "에게는",
+    "까지",
+    "부터",
+    "에서",
+    "에게",
+    "한테",
+    "으로",
+    "이나",
+    "이랑",
+    "처럼",
+    "보다",
+    "마다",
+    "조차",
+    "밖에",
+    "라고",
+    "로서",
+    "로써",
+    "과는",
+    "와는",
+    "에는",
+    "에도",
+    "이고",
+    // Single-syllable particles.
+    "은",
+    "는",
+    "이",
+    "가",
+    "을",
+    "를",
+    "의",
+    "에",
+    "와",
+    "과",
+    "도",
+    "로",
+    "만",
+];
+
+/// True when `suffix` may follow a stem ending in `last`. Particles
+/// come in batchim/no-batchim pairs (`이`/`가`, `을`/`를`, ...); picking
+/// the wrong half usually means the syllable belongs to the word, as
+/// in `아이` or `오를`.
+fn suffix_fits(last: char, suffix: &str) -> bool {
+    let batchim = is_hangul_syllable(last).then(|| split_syllable(last).2);
+    match (suffix, batchim) {
+        // Not a syllable (Latin, digits): either form is plausible.
+        (_, None) => true,
+        ("이" | "을" | "은" | "과" | "으로" | "이나" | "이랑" | "이라고" | "이고", Some(jong)) => {
+            !jong.is_empty()
+        }
+        ("가" | "를" | "는" | "와" | "라고", Some(jong)) => jong.is_empty(),
+        // `로` follows a vowel or ㄹ batchim (`서울로`).
+        ("로" | "로서" | "로써", Some(jong)) => jong.is_empty() || jong == "ㄹ",
+        _ => true,
+    }
+}
+
+fn stem_word(word: &str) -> &str {
+    for suffix in JOSA_EOMI_SUFFIXES {
+        let Some(stem) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        let Some(last) = stem.chars().last() else {
+            continue;
+        };
+        if stem.chars().count() >= 2 && suffix_fits(last, suffix) {
+            return stem;
+        }
+    }
+    word
+}
+
+/// Strip one trailing particle or verb ending from every word, so
+/// `삼성전자가 상승했다` becomes `삼성전자 상승`. Dictionary-free and
+/// deliberately conservative: stems keep at least two characters and
+/// batchim-sensitive particles must agree with the preceding
+/// syllable. Indexed as `text_stem`; queries go through the same
+/// function so `삼성전자를` and `삼성전자` meet in that column.
+pub fn stem_korean(text: &str) -> String {
+    text.split_whitespace()
+        .map(stem_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// True for a standalone compat-jamo initial consonant (`ㄱ`..`ㅎ`,
+/// doubles included).
+pub fn is_choseong(c: char) -> bool {
+    CHOSEONG_COMPAT.contains(&c)
+}
+
+/// True for input typed as initial consonants, alone or mixed with
+/// whole syllables and Latin: `ㅅㅅㅈㅈ`, `삼성ㅈㅈ`. Every bare jamo
+/// must be a choseong consonant; a bare vowel means the user is
+/// spelling jamo out (`ㅅㅏㅁ`), which the jamo column already covers.
+pub fn is_choseong_query(query: &str) -> bool {
+    let mut has_choseong = false;
+    for c in query.chars() {
+        if is_choseong(c) {
+            has_choseong = true;
+        } else if contains_bare_jamo(c.encode_utf8(&mut [0; 4])) {
+            return false;
+        }
+    }
+    has_choseong
+}
+
+/// Byte ranges in `text` matched by a choseong `pattern`. A bare
+/// choseong in the pattern matches any syllable starting with that
+/// consonant; every other character must match exactly (ignoring
+/// case). Whitespace inside `text` is skipped so `ㅅㅅㅈㅈ` spans
+/// `삼성 전자`.
+pub fn find_choseong_matches(text: &str, pattern: &str) -> Vec<(usize, usize)> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let char_matches = |t: char, p: char| {
+        t.to_lowercase().eq(std::iter::once(p))
+            || (is_choseong(p) && is_hangul_syllable(t) && split_syllable(t).0 == p)
+    };
+
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut j = i;
+        let mut matched = 0;
+        while j < chars.len() && matched < pattern.len() {
+            let (_, c) = chars[j];
+            if c.is_whitespace() && matched > 0 {
+                j += 1;
+                continue;
+            }
+            if !char_matches(c, pattern[matched]) {
+                break;
+            }
+            matched += 1;
+            j += 1;
+        }
+        if matched == pattern.len() {
+            let (last_start, last) = chars[j - 1];
+            out.push((chars[i].0, last_start + last.len_utf8()));
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Revised Romanization of the 19 initial consonants; ㅇ is silent.
+const CHOSEONG_ROMAN: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+
+/// Revised Romanization of the 21 vowels.
+const JUNGSEONG_ROMAN: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+
+/// Revised Romanization of the 28 finals, as pronounced at the end of
+/// a syllable: clusters collapse to one sound, ㅅ/ㅈ/ㅊ/ㅌ/ㅎ to `t`.
+const JONGSEONG_ROMAN: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// Revised Romanization of `text`, lowercase: `삼성전자` becomes
+/// `samseongjeonja`. Each syllable is romanized on its own, without
+/// the liaison and assimilation rules of official RR (`한국어` gives
+/// `hangukeo`, not `hangugeo`), so a romanized word is always a
+/// substring of the romanized sentence around it. Indexed as
+/// `text_roman`; non-Hangul code points pass through lowercased.
+pub fn romanize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        if is_hangul_syllable(c) {
+            let offset = (c as u32 - HANGUL_SYLLABLE_START) as usize;
+            out.push_str(CHOSEONG_ROMAN[offset / 588]);
+            out.push_str(JUNGSEONG_ROMAN[(offset % 588) / 28]);
+            out.push_str(JONGSEONG_ROMAN[offset % 28]);
+        } else {
+            out.extend(c.to_lowercase());
+        }
+    }
+    out
+}
+
+/// True if `text` contains at least one Hangul syllable.
+pub fn contains_hangul_syllable(text: &str) -> bool {
+    text.chars().any(is_hangul_syllable)
+}
+
+/// Keys of the standard 2-set (두벌식) layout and the jamo they type.
+/// Shift only changes the five doubled consonants and ㅒ/ㅖ; every
+/// other capital types the same jamo as its lowercase key.
+const DUBEOLSIK_KEYS: [(char, char); 33] = [
+    ('q', 'ㅂ'),
+    ('w', 'ㅈ'),
+    ('e', 'ㄷ'),
+    ('r', 'ㄱ'),
+    ('t', 'ㅅ'),
+    ('y', 'ㅛ'),
+    ('u', 'ㅕ'),
+    ('i', 'ㅑ'),
+    ('o', 'ㅐ'),
+    ('p', 'ㅔ'),
+    ('a', 'ㅁ'),
+    ('s', 'ㄴ'),
+    ('d', 'ㅇ'),
+    ('f', 'ㄹ'),
+    ('g', 'ㅎ'),
+    ('h', 'ㅗ'),
+    ('j', 'ㅓ'),
+    ('k', 'ㅏ'),
+    ('l', 'ㅣ'),
+    ('z', 'ㅋ'),
+    ('x', 'ㅌ'),
+    ('c', 'ㅊ'),
+    ('v', 'ㅍ'),
+    ('b', 'ㅠ'),
+    ('n', 'ㅜ'),
+    ('m', 'ㅡ'),
+    ('Q', 'ㅃ'),
+    ('W', 'ㅉ'),
+    ('E', 'ㄸ'),
+    ('R', 'ㄲ'),
+    ('T', 'ㅆ'),
+    ('O', 'ㅒ'),
+    ('P', 'ㅖ'),
+];
+
+/// Compound vowels and final-consonant clusters typed as two keys,
+/// as `(first, second, compound)`.
+const DUBEOLSIK_COMPOUNDS: [(char, char, char); 18] = [
+    ('ㅗ', 'ㅏ', 'ㅘ'),
+    ('ㅗ', 'ㅐ', 'ㅙ'),
+    ('ㅗ', 'ㅣ', 'ㅚ'),
+    ('ㅜ', 'ㅓ', 'ㅝ'),
+    ('ㅜ', 'ㅔ', 'ㅞ'),
+    ('ㅜ', 'ㅣ', 'ㅟ'),
+    ('ㅡ', 'ㅣ', 'ㅢ'),
+    ('ㄱ', 'ㅅ', 'ㄳ'),
+    ('ㄴ', 'ㅈ', 'ㄵ'),
+    ('ㄴ', 'ㅎ', 'ㄶ'),
+    ('ㄹ', 'ㄱ', 'ㄺ'),
+    ('ㄹ', 'ㅁ', 'ㄻ'),
+    ('ㄹ', 'ㅂ', 'ㄼ'),
+    ('ㄹ', 'ㅅ', 'ㄽ'),
+    ('ㄹ', 'ㅌ', 'ㄾ'),
+    ('ㄹ', 'ㅍ', 'ㄿ'),
+    ('ㄹ', 'ㅎ', 'ㅀ'),
+    ('ㅂ', 'ㅅ', 'ㅄ'),
+];
+
+fn dubeolsik_jamo(key: char) -> Option<char> {
+    let lookup = |k: char| {
+        DUBEOLSIK_KEYS
+            .iter()
+            .find(|(key, _)| *key == k)
+            .map(|(_, jamo)| *jamo)
+    };
+    lookup(key).or_else(|| lookup(key.to_ascii_lowercase()))
+}
+
+fn combine_jamo(first: char, second: char) -> Option<char> {
+    DUBEOLSIK_COMPOUNDS
+        .iter()
+        .find(|(a, b, _)| *a == first && *b == second)
+        .map(|(_, _, compound)| *compound)
+}
+
+fn split_compound(jamo: char) -> Option<(char, char)> {
+    DUBEOLSIK_COMPOUNDS
+        .iter()
+        .find(|(_, _, compound)| *compound == jamo)
+        .map(|(a, b, _)| (*a, *b))
+}
+
+fn jongseong_index(jamo: char) -> Option<usize> {
+    JONGSEONG_COMPAT.iter().position(|j| j.starts_with(jamo))
+}
+
+/// Syllable being assembled by [`latin_to_hangul`].
+#[derive(Default)]
+struct Composer {
+    cho: Option<char>,
+    jung: Option<char>,
+    jong: Option<char>,
+}
+
+impl Composer {
+    fn flush(&mut self, out: &mut String) {
+        match (self.cho.take(), self.jung.take(), self.jong.take()) {
+            (Some(cho), Some(jung), jong) => {
+                let cho = CHOSEONG_COMPAT.iter().position(|c| *c == cho).unwrap_or(0);
+                let jung = JUNGSEONG_COMPAT
+                    .iter()
+                    .position(|c| *c == jung)
+                    .unwrap_or(0);
+                let jong = jong.and_then(jongseong_index).unwrap_or(0);
+                let cp = HANGUL_SYLLABLE_START + (cho * 588 + jung * 28 + jong) as u32;
+                out.extend(char::from_u32(cp));
+            }
+            (cho, jung, _) => out.extend(cho.into_iter().chain(jung)),
+        }
+    }
+
+    fn push_consonant(&mut self, c: char, out: &mut String) {
+        match (self.cho, self.jung, self.jong) {
+            (Some(_), Some(_), None) if jongseong_index(c).is_some() => self.jong = Some(c),
+            (Some(_), Some(_), Some(jong)) if combine_jamo(jong, c).is_some() => {
+                self.jong = combine_jamo(jong, c);
+            }
+            _ => {
+                self.flush(out);
+                self.cho = Some(c);
+            }
+        }
+    }
+
+    fn push_vowel(&mut self, v: char, out: &mut String) {
+        match (self.cho, self.jung, self.jong) {
+            // The final consonant moves over to start the next
+            // syllable; a cluster gives up only its second half.
+            (Some(_), Some(_), Some(jong)) => {
+                let next_cho = match split_compound(jong) {
+                    Some((kept, moved)) => {
+                        self.jong = Some(kept);
+                        moved
+                    }
+                    None => {
+                        self.jong = None;
+                        jong
+                    }
+                };
+                self.flush(out);
+                self.cho = Some(next_cho);
+                self.jung = Some(v);
+            }
+            (_, Some(jung), None) if combine_jamo(jung, v).is_some() => {
+                self.jung = combine_jamo(jung, v);
+            }
+            (Some(_), None, _) => self.jung = Some(v),
+            _ => {
+                self.flush(out);
+                self.jung = Some(v);
+            }
+        }
+    }
+}
+
+/// Hangul a 2-set keyboard types for the Latin key sequence `keys`:
+/// `tkatjdwjswk` becomes `삼성전자`. Keys that type nothing on the
+/// layout (digits, punctuation, spaces) pass through unchanged.
+pub fn latin_to_hangul(keys: &str) -> String {
+    let mut out = String::with_capacity(keys.len() * 2);
+    let mut composer = Composer::default();
+    for key in keys.chars() {
+        match dubeolsik_jamo(key) {
+            Some(jamo) if JUNGSEONG_COMPAT.contains(&jamo) => composer.push_vowel(jamo, &mut out),
+            Some(jamo) => composer.push_consonant(jamo, &mut out),
+            None => {
+                composer.flush(&mut out);
+                out.push(key);
+            }
+        }
+    }
+    composer.flush(&mut out);
+    out
+}
+
+/// Latin keys that type `text` on a 2-set keyboard, the inverse of
+/// [`latin_to_hangul`]: `삼성전자` becomes `tkatjdwjswk`. Non-Hangul
+/// code points pass through unchanged.
+pub fn hangul_to_latin(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let key_for = |jamo: char| {
+        DUBEOLSIK_KEYS
+            .iter()
+            .find(|(_, j)| *j == jamo)
+            .map(|(k, _)| *k)
+    };
+    for jamo in decompose_jamo(text).chars() {
+        let parts = split_compound(jamo).map_or([Some(jamo), None], |(a, b)| [Some(a), Some(b)]);
+        for part in parts.into_iter().flatten() {
+            out.push(key_for(part).unwrap_or(part));
+        }
+    }
+    out
+}
+
+/// The query `term` was probably meant to be typed with the other
+/// input mode. Returns the term as the user intended it, or `None`.
+///
+/// Latin keys are a mistake when every key is a letter and they
+/// compose into whole syllables only; English words almost always
+/// leave a stray jamo behind (`test` → `ㅅㄷㄴㅅ`). Hangul is a mistake
+/// when whole syllables sit next to a bare vowel (`ㅗ디ㅣㅐ` for
+/// `hello`), which a Korean IME never produces on purpose.
+pub fn layout_mistype(term: &str) -> Option<String> {
+    let chars = term.chars().count();
+    if chars >= 2 && term.chars().all(|c| c.is_ascii_alphabetic()) {
+        let hangul = latin_to_hangul(term);
+        return hangul.chars().all(is_hangul_syllable).then_some(hangul);
+    }
+    let all_hangul = term
+        .chars()
+        .all(|c| is_hangul_syllable(c) || contains_bare_jamo(c.encode_utf8(&mut [0; 4])));
+    let has_syllable = term.chars().any(is_hangul_syllable);
+    let has_bare_vowel = term.chars().any(|c| JUNGSEONG_COMPAT.contains(&c));
+    (all_hangul && has_syllable && has_bare_vowel).then(|| hangul_to_latin(term))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,4 +630,112 @@ mod tests {
         assert!(!contains_bare_jamo("삼성전자"));
         assert!(!contains_bare_jamo("samsung"));
     }
+
+    #[test]
+    fn choseong_form_drops_vowels_finals_and_spaces() {
+        assert_eq!(to_choseong("삼성전자"), "ㅅㅅㅈㅈ");
+        assert_eq!(to_choseong("삼성 전자 galaxy"), "ㅅㅅㅈㅈgalaxy");
+        assert_eq!(to_choseong("ㅋㅋ 까치"), "ㅋㅋㄲㅊ");
+    }
+
+    #[test]
+    fn detects_choseong_queries() {
+        assert!(is_choseong_query("ㅅㅅㅈㅈ"));
+        assert!(is_choseong_query("삼성ㅈㅈ"));
+        assert!(is_choseong_query("ㅅㅅ galaxy"));
+        assert!(!is_choseong_query("ㅅㅏㅁ"));
+        assert!(!is_choseong_query("삼성전자"));
+        assert!(!is_choseong_query("ㄳ"));
+    }
+
+    #[test]
+    fn strips_common_particles() {
+        assert_eq!(stem_korean("삼성전자가"), "삼성전자");
+        assert_eq!(stem_korean("삼성전자를"), "삼성전자");
+        assert_eq!(stem_korean("삼성전자는"), "삼성전자");
+        assert_eq!(stem_korean("실적이"), "실적");
+        assert_eq!(stem_korean("서울에서는"), "서울");
+        assert_eq!(stem_korean("서울로"), "서울");
+        assert_eq!(stem_korean("미국으로"), "미국");
+        assert_eq!(stem_korean("시장의"), "시장");
+        assert_eq!(stem_korean("BTC가"), "BTC");
+    }
+
+    #[test]
+    fn strips_verb_endings() {
+        assert_eq!(stem_korean("주가가 상승했다"), "주가 상승");
+        assert_eq!(stem_korean("발표합니다"), "발표");
+        assert_eq!(stem_korean("하락했습니다"), "하락");
+        assert_eq!(stem_korean("검토되었다"), "검토");
+    }
+
+    #[test]
+    fn stemming_is_conservative() {
+        // One-character stems are left alone.
+        assert_eq!(stem_korean("나는"), "나는");
+        assert_eq!(stem_korean("주가"), "주가");
+        // Particle must agree with the batchim of the stem.
+        assert_eq!(stem_korean("아이"), "아이");
+        assert_eq!(stem_korean("사과를"), "사과");
+        assert_eq!(stem_korean("사람를"), "사람를");
+        assert_eq!(stem_korean("galaxy"), "galaxy");
+        assert_eq!(stem_korean("  삼성전자가   상승  "), "삼성전자 상승");
+    }
+
+    #[test]
+    fn finds_choseong_matches() {
+        let text = "오늘 삼성 전자 실적";
+        let ranges = find_choseong_matches(text, "ㅅㅅㅈㅈ");
+        assert_eq!(ranges.len(), 1);
+        let (start, end) = ranges[0];
+        assert_eq!(&text[start..end], "삼성 전자");
+
+        let ranges = find_choseong_matches(text, "삼ㅅ");
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(&text[ranges[0].0..ranges[0].1], "삼성");
+
+        assert!(find_choseong_matches(text, "ㅎㅎ").is_empty());
+    }
+
+    #[test]
+    fn converts_latin_keys_to_hangul() {
+        assert_eq!(latin_to_hangul("tkatjdwjswk"), "삼성전자");
+        assert_eq!(latin_to_hangul("dkssudgktpdy"), "안녕하세요");
+        // Compound vowels, clusters and the shifted doubles.
+        assert_eq!(latin_to_hangul("ghkdlfld"), "화이링");
+        assert_eq!(latin_to_hangul("ekfr"), "닭");
+        assert_eq!(latin_to_hangul("ekfrdl"), "닭이");
+        assert_eq!(latin_to_hangul("Tkd"), "쌍");
+        assert_eq!(latin_to_hangul("gksrmf 2024"), "한글 2024");
+        assert_eq!(latin_to_hangul("test"), "ㅅㄷㄴㅅ");
+    }
+
+    #[test]
+    fn converts_hangul_back_to_latin_keys() {
+        assert_eq!(hangul_to_latin("삼성전자"), "tkatjdwjswk");
+        assert_eq!(hangul_to_latin("닭이"), "ekfrdl");
+        assert_eq!(hangul_to_latin("ㅗ디ㅣㅐ"), "hello");
+        assert_eq!(hangul_to_latin("쌍 BTC"), "Tkd BTC");
+    }
+
+    #[test]
+    fn detects_layout_mistypes() {
+        assert_eq!(layout_mistype("tkatjdwjswk").as_deref(), Some("삼성전자"));
+        assert_eq!(layout_mistype("ㅗ디ㅣㅐ").as_deref(), Some("hello"));
+        assert_eq!(layout_mistype("test"), None);
+        assert_eq!(layout_mistype("galaxy"), None);
+        assert_eq!(layout_mistype("삼성전자"), None);
+        assert_eq!(layout_mistype("ㅅㅏㅁ"), None);
+        assert_eq!(layout_mistype("btc2024"), None);
+    }
+
+    #[test]
+    fn romanizes_syllables_independently() {
+        assert_eq!(romanize("삼성"), "samseong");
+        assert_eq!(romanize("삼성전자"), "samseongjeonja");
+        assert_eq!(romanize("한국어"), "hangukeo");
+        assert_eq!(romanize("값 닭 꽃"), "gap dak kkot");
+        assert_eq!(romanize("의사 쉬워"), "uisa swiwo");
+        assert_eq!(romanize("Samsung 갤럭시"), "samsung gaelreoksi");
+    }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::hangul::{find_choseong_matches, is_choseong_query};
+
 /// A highlight range representing a match in the text.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct HighlightRange {
@@ -10,7 +12,9 @@ pub struct HighlightRange {
 }
 
 /// Find all occurrences of query tokens in the text (case-insensitive).
-/// Returns non-overlapping highlight ranges sorted by start position.
+/// Choseong tokens (`ㅅㅅㅈㅈ`) also highlight the syllables they
+/// abbreviate. Returns non-overlapping highlight ranges sorted by start
+/// position.
 pub fn find_highlights(text: &str, tokens: &[String]) -> Vec<HighlightRange> {
     let text_lower = text.to_lowercase();
     let mut ranges: Vec<HighlightRange> = Vec::new();
@@ -30,6 +34,13 @@ pub fn find_highlights(text: &str, tokens: &[String]) -> Vec<HighlightRange> {
             });
             search_from = byte_end;
         }
+        if is_choseong_query(token) {
+            ranges.extend(
+                find_choseong_matches(text, token)
+                    .into_iter()
+                    .map(|(start, end)| HighlightRange { start, end }),
+            );
+        }
     }
 
     // Sort by start position
@@ -58,6 +69,235 @@ fn merge_overlapping(ranges: &mut Vec<HighlightRange>) {
     *ranges = merged;
 }
 
+/// Joins fragments of a snippet and marks text cut off at either end.
+pub const ELLIPSIS: &str = "…";
+
+/// How [`make_snippet`] trims a message. Lengths count grapheme
+/// clusters, so a Hangul syllable or a flag emoji is one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetOptions {
+    /// Total length across all fragments, ellipses excluded.
+    pub max_len: usize,
+    pub max_fragments: usize,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            max_len: 160,
+            max_fragments: 3,
+        }
+    }
+}
+
+/// A shortened message with its highlights re-based onto `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<HighlightRange>,
+    /// False when the whole message fit and `text` is unchanged.
+    pub truncated: bool,
+}
+
+/// Cut `text` down to the window(s) around its `highlights`, densest
+/// first, joined with [`ELLIPSIS`]. Cuts only fall on grapheme
+/// boundaries. Without highlights the start of the message is kept.
+pub fn make_snippet(
+    text: &str,
+    highlights: &[HighlightRange],
+    options: &SnippetOptions,
+) -> Snippet {
+    let mut starts = grapheme_starts(text);
+    let count = starts.len();
+    let max_len = options.max_len.max(1);
+    if count <= max_len {
+        return Snippet {
+            text: text.to_string(),
+            highlights: highlights.to_vec(),
+            truncated: false,
+        };
+    }
+    starts.push(text.len());
+
+    // Grapheme index containing byte `offset`.
+    let grapheme_at = |offset: usize| starts.partition_point(|&s| s <= offset) - 1;
+    let spans: Vec<(usize, usize)> = highlights
+        .iter()
+        .filter(|h| h.start < h.end && h.end <= text.len())
+        .map(|h| (grapheme_at(h.start), grapheme_at(h.end - 1) + 1))
+        .collect();
+    let windows = pick_windows(&spans, count, max_len, options.max_fragments.max(1));
+
+    let mut out = String::new();
+    let mut rebased = Vec::new();
+    for (i, &(first, last)) in windows.iter().enumerate() {
+        if i > 0 || first > 0 {
+            out.push_str(ELLIPSIS);
+        }
+        let fragment = &text[starts[first]..starts[last]];
+        let base = starts[first] + (fragment.len() - fragment.trim_start().len());
+        let fragment = fragment.trim();
+        let offset = out.len();
+        out.push_str(fragment);
+        for h in highlights {
+            let start = h.start.max(base);
+            let end = h.end.min(base + fragment.len());
+            if start < end {
+                rebased.push(HighlightRange {
+                    start: offset + start - base,
+                    end: offset + end - base,
+                });
+            }
+        }
+    }
+    if windows.last().is_some_and(|&(_, last)| last < count) {
+        out.push_str(ELLIPSIS);
+    }
+    Snippet {
+        text: out,
+        highlights: rebased,
+        truncated: true,
+    }
+}
+
+/// Grapheme windows `[first, last)` to keep, in text order. Nearby
+/// highlights share a window; when there are more groups than
+/// `max_fragments`, the ones with the most highlights win.
+fn pick_windows(
+    spans: &[(usize, usize)],
+    count: usize,
+    max_len: usize,
+    max_fragments: usize,
+) -> Vec<(usize, usize)> {
+    if spans.is_empty() {
+        return vec![(0, max_len)];
+    }
+    let per_fragment = (max_len / max_fragments.min(spans.len())).max(1);
+    // (first, last, highlight count)
+    let mut groups: Vec<(usize, usize, usize)> = Vec::new();
+    for &(first, last) in spans {
+        match groups.last_mut() {
+            Some(group) if last - group.0 <= per_fragment => {
+                group.1 = group.1.max(last);
+                group.2 += 1;
+            }
+            _ => groups.push((first, last, 1)),
+        }
+    }
+    let mut chosen: Vec<usize> = (0..groups.len()).collect();
+    chosen.sort_by(|&a, &b| groups[b].2.cmp(&groups[a].2).then(a.cmp(&b)));
+    chosen.truncate(max_fragments);
+    chosen.sort_unstable();
+
+    let budget = max_len / chosen.len();
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for i in chosen {
+        let (first, last, _) = groups[i];
+        let (first, last) = if last - first >= budget {
+            (first, first + budget)
+        } else {
+            // Centre the group, sliding back from the end of the text.
+            let end = (first.saturating_sub((budget - (last - first)) / 2) + budget).min(count);
+            (end.saturating_sub(budget), end)
+        };
+        match windows.last_mut() {
+            Some(prev) if first <= prev.1 => prev.1 = prev.1.max(last),
+            _ => windows.push((first, last)),
+        }
+    }
+    windows
+}
+
+/// Byte offsets where grapheme clusters start. Covers what chat text
+/// actually contains — combining marks, variation selectors, emoji
+/// modifier/ZWJ/flag sequences, conjoining Hangul jamo and CRLF —
+/// rather than all of UAX #29.
+fn grapheme_starts(text: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut prev: Option<char> = None;
+    let mut regional_run = 0;
+    for (i, c) in text.char_indices() {
+        if !prev.is_some_and(|p| continues_grapheme(p, c, regional_run)) {
+            starts.push(i);
+        }
+        regional_run = if is_regional_indicator(c) {
+            regional_run + 1
+        } else {
+            0
+        };
+        prev = Some(c);
+    }
+    starts
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JamoKind {
+    Leading,
+    Vowel,
+    Trailing,
+    SyllableLv,
+    SyllableLvt,
+}
+
+fn jamo_kind(c: char) -> Option<JamoKind> {
+    match c as u32 {
+        0x1100..=0x115F | 0xA960..=0xA97C => Some(JamoKind::Leading),
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => Some(JamoKind::Vowel),
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => Some(JamoKind::Trailing),
+        cp @ 0xAC00..=0xD7A3 if (cp - 0xAC00) % 28 == 0 => Some(JamoKind::SyllableLv),
+        0xAC00..=0xD7A3 => Some(JamoKind::SyllableLvt),
+        _ => None,
+    }
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Marks that attach to the preceding character.
+fn is_extend(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036F
+            | 0x0483..=0x0489
+            | 0x0591..=0x05BD
+            | 0x0610..=0x061A
+            | 0x064B..=0x065F
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x200C..=0x200D
+            | 0x20D0..=0x20FF
+            | 0x302A..=0x302F
+            | 0x3099..=0x309A
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0xE0020..=0xE007F
+            | 0xE0100..=0xE01EF
+    )
+}
+
+/// No grapheme break between `prev` and `c`. `regional_run` counts the
+/// regional indicators ending at `prev`; flags pair them up.
+fn continues_grapheme(prev: char, c: char, regional_run: usize) -> bool {
+    use JamoKind::*;
+    if (prev == '\r' && c == '\n') || prev == '\u{200D}' || is_extend(c) {
+        return true;
+    }
+    if is_regional_indicator(prev) && is_regional_indicator(c) {
+        return regional_run % 2 == 1;
+    }
+    matches!(
+        (jamo_kind(prev), jamo_kind(c)),
+        (
+            Some(Leading),
+            Some(Leading | Vowel | SyllableLv | SyllableLvt)
+        ) | (Some(Vowel | SyllableLv), Some(Vowel | Trailing))
+            | (Some(Trailing | SyllableLvt), Some(Trailing))
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,9 +363,107 @@ mod tests {
         assert_eq!(ranges.len(), 3);
     }
 
+    #[test]
+    fn test_choseong_highlight() {
+        let text = "삼성 전자 실적";
+        let ranges = find_highlights(text, &["ㅅㅅㅈㅈ".to_string()]);
+        assert_eq!(ranges, vec![HighlightRange { start: 0, end: 13 }]);
+    }
+
     #[test]
     fn test_case_insensitive() {
         let ranges = find_highlights("HELLO hello Hello", &["hello".to_string()]);
         assert_eq!(ranges.len(), 3);
     }
+
+    fn snippet(text: &str, tokens: &[&str], max_len: usize, max_fragments: usize) -> Snippet {
+        let tokens: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+        let highlights = find_highlights(text, &tokens);
+        make_snippet(
+            text,
+            &highlights,
+            &SnippetOptions {
+                max_len,
+                max_fragments,
+            },
+        )
+    }
+
+    fn highlighted(s: &Snippet) -> Vec<&str> {
+        s.highlights
+            .iter()
+            .map(|h| &s.text[h.start..h.end])
+            .collect()
+    }
+
+    #[test]
+    fn short_text_is_returned_whole() {
+        let s = snippet("삼성전자 실적", &["실적"], 20, 2);
+        assert!(!s.truncated);
+        assert_eq!(s.text, "삼성전자 실적");
+        assert_eq!(highlighted(&s), vec!["실적"]);
+    }
+
+    #[test]
+    fn window_is_centred_and_rebased() {
+        let text = format!(
+            "{} 삼성전자 실적 발표 {}",
+            "가".repeat(100),
+            "나".repeat(100)
+        );
+        let s = snippet(&text, &["실적"], 20, 1);
+        assert!(s.truncated);
+        assert!(s.text.starts_with(ELLIPSIS) && s.text.ends_with(ELLIPSIS));
+        assert!(s.text.contains("삼성전자 실적 발표"));
+        assert_eq!(highlighted(&s), vec!["실적"]);
+    }
+
+    #[test]
+    fn distant_hits_become_separate_fragments() {
+        let text = format!(
+            "btc up {} filler {} btc down",
+            "x".repeat(200),
+            "y".repeat(200)
+        );
+        let s = snippet(&text, &["btc"], 40, 3);
+        assert_eq!(s.text.matches(ELLIPSIS).count(), 1);
+        assert_eq!(highlighted(&s), vec!["btc", "btc"]);
+        // The densest group wins when fragments run out.
+        let text = format!("eth {} btc btc btc {}", "z".repeat(200), "w".repeat(200));
+        let s = snippet(&text, &["btc", "eth"], 30, 1);
+        assert_eq!(highlighted(&s), vec!["btc", "btc", "btc"]);
+    }
+
+    #[test]
+    fn no_highlights_keeps_the_start() {
+        let s = snippet(&"가".repeat(50), &[], 10, 2);
+        assert_eq!(s.text, format!("{}{ELLIPSIS}", "가".repeat(10)));
+    }
+
+    #[test]
+    fn cuts_never_split_graphemes() {
+        // Conjoining jamo, a ZWJ family, a skin-tone modifier and a flag.
+        let clusters = [
+            "\u{1112}\u{1161}\u{11AB}",
+            "👨\u{200D}👩\u{200D}👧",
+            "👍🏽",
+            "🇰🇷",
+            "e\u{0301}",
+        ];
+        let text: String = clusters.iter().cycle().take(60).copied().collect();
+        assert_eq!(grapheme_starts(&text).len(), 60);
+        for max_len in 1..12 {
+            let s = make_snippet(
+                &text,
+                &[],
+                &SnippetOptions {
+                    max_len,
+                    max_fragments: 1,
+                },
+            );
+            let kept = s.text.trim_end_matches(ELLIPSIS);
+            assert_eq!(grapheme_starts(kept).len(), max_len);
+            assert!(text.starts_with(kept));
+        }
+    }
 }
@@ -1,27 +1,65 @@
 pub mod engine;
+pub mod fuzzy;
 pub mod hangul;
 pub mod highlight;
+pub mod query;
+pub mod suggest;
 
 use serde::{Deserialize, Serialize};
 
-use crate::store::message::Cursor;
-use highlight::HighlightRange;
+use crate::store::message::{Cursor, SearchFacets};
+use highlight::{make_snippet, HighlightRange, SnippetOptions};
 
 /// A single search result item.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchItem {
     pub message_id: i64,
+    /// Account whose copy of the chat this hit came from.
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub timestamp: i64,
     pub text: String,
     pub link: Option<String>,
     pub chat_title: String,
+    /// Current display name of the sender, when known.
+    #[serde(default)]
+    pub sender_name: Option<String>,
+    /// Set when the query matched only an earlier version of the
+    /// message: that version's number. `text` is the current text.
+    #[serde(default)]
+    pub matched_revision: Option<i64>,
     pub highlights: Vec<HighlightRange>,
+    /// `text` was cut down to a snippet around the highlights.
+    #[serde(default)]
+    pub is_snippet: bool,
+}
+
+impl SearchItem {
+    /// Replace `text` with a snippet and re-base `highlights` onto it.
+    pub fn apply_snippet(&mut self, options: &SnippetOptions) {
+        let snippet = make_snippet(&self.text, &self.highlights, options);
+        self.text = snippet.text;
+        self.highlights = snippet.highlights;
+        self.is_snippet = snippet.truncated;
+    }
 }
 
 /// Paginated search results.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchResult {
     pub items: Vec<SearchItem>,
     pub next_cursor: Option<Cursor>,
+    /// Set when the query as typed matched nothing and `items` are the
+    /// results for this corrected query instead. Request further pages
+    /// with the corrected query.
+    #[serde(default)]
+    pub did_you_mean: Option<String>,
+    /// Hit counts over all pages; only filled in when requested.
+    #[serde(default)]
+    pub facets: Option<SearchFacets>,
+    /// Filters in the query that were not applied because they were
+    /// negated or inside an `OR` (`-has:link`), as written.
+    #[serde(default)]
+    pub ignored_filters: Vec<String>,
 }
@@ -0,0 +1,1092 @@
+//! Structured query language for message search.
+//!
+//! The search box accepts a small, forgiving query language:
+//!
+//! ```text
+//!   삼성 전자            both terms (implicit AND)
+//!   "삼성 전자 실적"      exact phrase, spaces included
+//!   -루머                exclude messages containing the term
+//!   비트코인 OR 이더리움   either term
+//!   (btc OR eth) etf     parenthesised groups
+//!   jamo:ㅅㅏㅁ           restrict a term to one index column
+//!   ㅅㅅㅈㅈ              initial consonants (choseong) → 삼성전자
+//!   삼성전자를            particles and endings are stripped → 삼성전자
+//!   samseong             romanized Hangul → 삼성, and the reverse
+//!   has:link             messages carrying a link (hashtag, mention, cashtag)
+//!   domain:x.com         a link to x.com or any subdomain of it
+//!   tag:airdrop          hashtag #airdrop; mention:durov, cashtag:btc alike
+//!   has:photo            an attachment of that kind; type:document alike
+//!   from:김철수           sent by someone named so, now or before; from:"김 철수"
+//! ```
+//!
+//! Entity, media and sender filters are not text terms: [`parse_filters`] collects them
+//! into [`SearchFilters`] and [`parse_query`] skips them, so a query of
+//! nothing but filters lists every message they select. They narrow the
+//! whole query, so one written after `-` or inside an `OR` is not
+//! applied; [`ignored_filters`] lists those for the caller to report.
+//!
+//! Parsing never fails. Unbalanced parentheses and unterminated
+//! quotes are closed at end of input, a stray `)` is ignored, and an
+//! unknown `prefix:` is kept as part of a literal term so URLs and
+//! times like `10:30` still search as typed. `OR` is only an operator
+//! when written in upper case; `or` is an ordinary term.
+//!
+//! [`QueryNode::to_fts_match`] compiles the tree into an FTS5 `MATCH`
+//! expression over the plain/nospace/jamo/choseong/stem/roman columns
+//! of `messages_fts`. A query with a term under the trigram minimum
+//! runs whole through [`QueryNode::to_like_match`] instead, so a short
+//! term is never silently dropped.
+
+use crate::store::entity::EntityKind;
+use crate::store::media::MediaKind;
+use crate::store::message::{strip_whitespace, LikeMatch, SearchFilters};
+
+use super::hangul::{
+    contains_bare_jamo, contains_hangul_syllable, decompose_jamo, is_choseong, is_choseong_query,
+    layout_mistype, romanize, stem_korean, to_choseong,
+};
+
+/// Column prefix a term can be pinned to with `prefix:term`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryField {
+    /// `plain:` — the message text as sent.
+    Plain,
+    /// `nospace:` — whitespace-stripped text, so `삼성전자` finds
+    /// `삼성 전자`.
+    NoSpace,
+    /// `jamo:` — compat-jamo decomposition, for `ㅅㅏㅁ` → `삼`.
+    Jamo,
+    /// `choseong:` — initial consonants only, for `ㅅㅅㅈㅈ` → `삼성전자`.
+    Choseong,
+    /// `stem:` — josa/eomi stripped, for `삼성전자를` → `삼성전자`.
+    Stem,
+    /// `roman:` — Revised Romanization, for `samseong` → `삼성`.
+    Roman,
+}
+
+impl QueryField {
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_ascii_lowercase().as_str() {
+            "plain" | "text" => Some(Self::Plain),
+            "nospace" | "stripped" => Some(Self::NoSpace),
+            "jamo" => Some(Self::Jamo),
+            "choseong" | "chosung" => Some(Self::Choseong),
+            "stem" => Some(Self::Stem),
+            "roman" | "romaja" => Some(Self::Roman),
+            _ => None,
+        }
+    }
+
+    fn fts_column(self) -> &'static str {
+        match self {
+            Self::Plain => "text_plain",
+            Self::NoSpace => "text_stripped",
+            Self::Jamo => "text_jamo",
+            Self::Choseong => "text_choseong",
+            Self::Stem => "text_stem",
+            Self::Roman => "text_roman",
+        }
+    }
+
+    /// Normalize `text` into the alphabet the column was indexed in.
+    fn normalize(self, text: &str) -> String {
+        match self {
+            Self::Plain => text.to_string(),
+            Self::NoSpace => strip_whitespace(text),
+            Self::Jamo => decompose_jamo(text),
+            Self::Choseong => to_choseong(text),
+            Self::Stem => stem_korean(text),
+            Self::Roman => romanize(text),
+        }
+    }
+}
+
+/// One searchable unit: a bare word or a quoted phrase, optionally
+/// pinned to a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryTerm {
+    pub text: String,
+    pub phrase: bool,
+    pub field: Option<QueryField>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    Term(QueryTerm),
+    Not(Box<QueryNode>),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+/// A `prefix:value` word that narrows by extracted entities or the
+/// attachment rather than searching the text. See
+/// [`crate::store::entity`] and [`crate::store::media`].
+#[derive(Debug, Clone, PartialEq)]
+enum ContentFilter {
+    Has(EntityKind),
+    Domain(String),
+    Value(EntityKind, String),
+    Media(MediaKind),
+    From(String),
+}
+
+impl ContentFilter {
+    fn from_word(prefix: &str, value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return None;
+        }
+        match prefix.to_ascii_lowercase().as_str() {
+            "has" => EntityKind::parse(value)
+                .map(Self::Has)
+                .or_else(|| MediaKind::parse(value).map(Self::Media)),
+            "type" => MediaKind::parse(value).map(Self::Media),
+            "domain" | "site" => Some(Self::Domain(value.to_string())),
+            "tag" | "hashtag" => Some(Self::Value(EntityKind::Hashtag, value.to_string())),
+            "mention" => Some(Self::Value(EntityKind::Mention, value.to_string())),
+            "cashtag" => Some(Self::Value(EntityKind::Cashtag, value.to_string())),
+            "from" => Some(Self::From(value.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Or,
+    Minus,
+    Word {
+        field: Option<QueryField>,
+        text: String,
+        phrase: bool,
+    },
+    /// The filter and the word it was written as.
+    Filter(ContentFilter, String),
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // `-` only negates at the start of a token; inside a word
+    // (`e-mail`) the word loop below consumes it.
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '-' => {
+                tokens.push(Token::Minus);
+                i += 1;
+            }
+            '"' => {
+                let (text, next) = read_quoted(&chars, i + 1);
+                tokens.push(Token::Word {
+                    field: None,
+                    text,
+                    phrase: true,
+                });
+                i = next;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')' | '"')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word == "OR" {
+                    tokens.push(Token::Or);
+                    continue;
+                }
+                if word.eq_ignore_ascii_case("from:") && chars.get(i) == Some(&'"') {
+                    let (name, next) = read_quoted(&chars, i + 1);
+                    let written: String = chars[start..next].iter().collect();
+                    i = next;
+                    if !name.trim().is_empty() {
+                        tokens.push(Token::Filter(ContentFilter::From(name), written));
+                    }
+                    continue;
+                }
+                if let Some(filter) = word
+                    .split_once(':')
+                    .and_then(|(prefix, rest)| ContentFilter::from_word(prefix, rest))
+                {
+                    tokens.push(Token::Filter(filter, word));
+                    continue;
+                }
+                let prefixed = word
+                    .split_once(':')
+                    .and_then(|(prefix, rest)| QueryField::from_prefix(prefix).map(|f| (f, rest)));
+                match prefixed {
+                    Some((field, "")) if i < chars.len() && chars[i] == '"' => {
+                        let (text, next) = read_quoted(&chars, i + 1);
+                        tokens.push(Token::Word {
+                            field: Some(field),
+                            text,
+                            phrase: true,
+                        });
+                        i = next;
+                    }
+                    Some((field, rest)) if !rest.is_empty() => tokens.push(Token::Word {
+                        field: Some(field),
+                        text: rest.to_string(),
+                        phrase: false,
+                    }),
+                    _ => tokens.push(Token::Word {
+                        field: None,
+                        text: word,
+                        phrase: false,
+                    }),
+                }
+            }
+        }
+    }
+    tokens
+}
+
+/// Read a quoted run starting after the opening quote. Returns the
+/// inner text and the index after the closing quote (or end of input
+/// for an unterminated quote).
+fn read_quoted(chars: &[char], start: usize) -> (String, usize) {
+    let mut end = start;
+    while end < chars.len() && chars[end] != '"' {
+        end += 1;
+    }
+    let text: String = chars[start..end].iter().collect();
+    (text.trim().to_string(), (end + 1).min(chars.len()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Filters that narrow the whole query, with how they were written.
+    filters: Vec<(ContentFilter, String)>,
+    /// Filters written after `-` or inside an `OR`, as written.
+    ignored: Vec<String>,
+    /// How many `-` enclose the current position.
+    negations: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Option<QueryNode> {
+        let first_filter = self.filters.len();
+        let mut branches = Vec::new();
+        if let Some(node) = self.parse_and() {
+            branches.push(node);
+        }
+        let mut alternatives = false;
+        while let Some(Token::Or) = self.peek() {
+            self.pos += 1;
+            alternatives = true;
+            if let Some(node) = self.parse_and() {
+                branches.push(node);
+            }
+        }
+        // A filter can only narrow the whole result; as one side of an
+        // `OR` it would turn the `OR` into an AND.
+        if alternatives {
+            let dropped = self.filters.drain(first_filter..).map(|(_, word)| word);
+            self.ignored.extend(dropped);
+        }
+        collapse(branches, QueryNode::Or)
+    }
+
+    fn parse_and(&mut self) -> Option<QueryNode> {
+        let mut parts = Vec::new();
+        while let Some(tok) = self.peek() {
+            if matches!(tok, Token::Or | Token::Close) {
+                break;
+            }
+            if let Some(node) = self.parse_unary() {
+                parts.push(node);
+            }
+        }
+        collapse(parts, QueryNode::And)
+    }
+
+    fn parse_unary(&mut self) -> Option<QueryNode> {
+        match self.peek()? {
+            Token::Minus => {
+                self.pos += 1;
+                match self.peek() {
+                    // A dangling `-` (end of input, before `OR` or `)`)
+                    // negates nothing.
+                    None | Some(Token::Or) | Some(Token::Close) => None,
+                    _ => {
+                        self.negations += 1;
+                        let inner = self.parse_unary();
+                        self.negations -= 1;
+                        inner.map(|n| QueryNode::Not(Box::new(n)))
+                    }
+                }
+            }
+            Token::Open => {
+                self.pos += 1;
+                let inner = self.parse_or();
+                if let Some(Token::Close) = self.peek() {
+                    self.pos += 1;
+                }
+                inner
+            }
+            Token::Word {
+                field,
+                text,
+                phrase,
+            } => {
+                let term = QueryTerm {
+                    text: text.clone(),
+                    phrase: *phrase,
+                    field: *field,
+                };
+                self.pos += 1;
+                (!term.text.is_empty()).then_some(QueryNode::Term(term))
+            }
+            Token::Filter(filter, word) => {
+                // Filters cannot be negated; `-has:link` narrowing to
+                // messages with links would invert what was asked.
+                if self.negations > 0 {
+                    self.ignored.push(word.clone());
+                } else {
+                    self.filters.push((filter.clone(), word.clone()));
+                }
+                self.pos += 1;
+                None
+            }
+            Token::Or | Token::Close => {
+                self.pos += 1;
+                None
+            }
+        }
+    }
+}
+
+fn collapse(mut nodes: Vec<QueryNode>, wrap: fn(Vec<QueryNode>) -> QueryNode) -> Option<QueryNode> {
+    match nodes.len() {
+        0 => None,
+        1 => nodes.pop(),
+        _ => Some(wrap(nodes)),
+    }
+}
+
+/// Parse all of `input`, returning the query tree alongside the
+/// parser's collected filters.
+fn parse(input: &str) -> (Option<QueryNode>, Parser) {
+    let mut parser = Parser {
+        tokens: tokenize(input),
+        pos: 0,
+        filters: Vec::new(),
+        ignored: Vec::new(),
+        negations: 0,
+    };
+    let mut groups = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        if let Some(node) = parser.parse_or() {
+            groups.push(node);
+        }
+        // Only a stray `)` stops parse_or before the end; skip it and
+        // AND whatever follows onto the query.
+        if let Some(Token::Close) = parser.peek() {
+            parser.pos += 1;
+        }
+    }
+    (collapse(groups, QueryNode::And), parser)
+}
+
+/// Parse user input into a query tree. Returns `None` when the input
+/// holds no searchable term at all (empty, only operators, ...).
+pub fn parse_query(input: &str) -> Option<QueryNode> {
+    parse(input).0
+}
+
+/// The entity, media and sender filters written into `input`
+/// (`has:link tag:airdrop type:photo from:김철수`),
+/// as [`SearchFilters`] to combine with the caller's own. Filters
+/// under `-` or inside an `OR` are left out; see [`ignored_filters`].
+pub fn parse_filters(input: &str) -> SearchFilters {
+    let mut filters = SearchFilters::default();
+    for (filter, _) in parse(input).1.filters {
+        match filter {
+            ContentFilter::Has(kind) => filters.has.push(kind),
+            ContentFilter::Domain(domain) => filters.domains.push(domain),
+            ContentFilter::Media(kind) => filters.media_kinds.push(kind),
+            ContentFilter::From(name) => filters.from.push(name),
+            ContentFilter::Value(kind, value) => match kind {
+                EntityKind::Hashtag => filters.tags.push(value),
+                EntityKind::Mention => filters.mentions.push(value),
+                _ => filters.cashtags.push(value),
+            },
+        }
+    }
+    filters
+}
+
+/// Filters in `input` that search does not apply, as written: those
+/// after `-` (`-has:link`) or on one side of an `OR`
+/// (`foo OR domain:x.com`). Filters only narrow the whole query, so
+/// honouring these would invert or tighten what was asked.
+pub fn ignored_filters(input: &str) -> Vec<String> {
+    parse(input).1.ignored
+}
+
+/// FTS5 trigram only matches phrases of at least three characters.
+/// Shorter phrases match nothing on their own and are ignored inside
+/// an AND, so a query holding one cannot be compiled faithfully.
+fn trigram_ready(text: &str) -> bool {
+    text.chars().count() >= 3
+}
+
+fn fts_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// A compiled sub-expression. `atomic` expressions can be combined
+/// with other operators as-is; the rest are parenthesised first so
+/// FTS5 operator precedence cannot regroup them.
+struct Compiled {
+    expr: String,
+    atomic: bool,
+}
+
+impl Compiled {
+    fn atom(expr: String) -> Self {
+        Self { expr, atomic: true }
+    }
+
+    fn compound(expr: String) -> Self {
+        Self {
+            expr,
+            atomic: false,
+        }
+    }
+
+    fn grouped(self) -> String {
+        if self.atomic {
+            self.expr
+        } else {
+            format!("({})", self.expr)
+        }
+    }
+}
+
+/// Literal text columns a negated unpinned term is tested against
+/// as written. `text_jamo` is not among them: the term's jamo form is
+/// tested there separately, or through [`LITERAL_COLUMNS_AND_JAMO`].
+const LITERAL_COLUMNS: &str = "{text_plain text_stripped text_media}";
+/// [`LITERAL_COLUMNS`] plus `text_jamo`, for a term that is its own
+/// jamo form (Latin, digits, bare jamo).
+const LITERAL_COLUMNS_AND_JAMO: &str = "{text_plain text_stripped text_jamo text_media}";
+
+impl QueryTerm {
+    /// `None` when neither the term nor its jamo form is long enough
+    /// for the trigram index; the recall branches alone would let
+    /// messages without the term through.
+    fn compile(&self, negated: bool) -> Option<Compiled> {
+        if let Some(field) = self.field {
+            let text = field.normalize(&self.text);
+            if !trigram_ready(&text) {
+                return None;
+            }
+            return Some(Compiled::atom(format!(
+                "{}:{}",
+                field.fts_column(),
+                fts_quote(&text)
+            )));
+        }
+        // A negation excludes what was typed and nothing more: no
+        // stem, romanization or layout guesses, and the literal stays
+        // out of the derived columns (`-sam` must not drop `삼성`).
+        if negated {
+            let jamo = decompose_jamo(&self.text);
+            if jamo == self.text {
+                return trigram_ready(&jamo).then(|| {
+                    Compiled::atom(format!("{LITERAL_COLUMNS_AND_JAMO}:{}", fts_quote(&jamo)))
+                });
+            }
+            let mut variants = Vec::new();
+            if trigram_ready(&self.text) {
+                variants.push(format!("{LITERAL_COLUMNS}:{}", fts_quote(&self.text)));
+            }
+            if trigram_ready(&jamo) {
+                variants.push(format!(
+                    "{}:{}",
+                    QueryField::Jamo.fts_column(),
+                    fts_quote(&jamo)
+                ));
+            }
+            return match variants.len() {
+                0 => None,
+                1 => variants.pop().map(Compiled::atom),
+                _ => Some(Compiled::atom(format!("({})", variants.join(" OR ")))),
+            };
+        }
+        // Unpinned terms search every column; the jamo variant lets
+        // bare-jamo and mixed input hit the decomposed index.
+        let mut variants: Vec<String> = Vec::new();
+        for variant in [self.text.clone(), decompose_jamo(&self.text)] {
+            let quoted = fts_quote(&variant);
+            if trigram_ready(&variant) && !variants.contains(&quoted) {
+                variants.push(quoted);
+            }
+        }
+        if variants.is_empty() {
+            return None;
+        }
+        variants.extend(self.choseong_branch());
+        variants.extend(self.stem_branch());
+        variants.extend(self.roman_branch());
+        variants.extend(self.layout_branch());
+        match variants.len() {
+            1 => variants.pop().map(Compiled::atom),
+            _ => Some(Compiled::atom(format!("({})", variants.join(" OR ")))),
+        }
+    }
+
+    /// `삼성전자가` never occurs verbatim in `삼성전자 주가`; its stem
+    /// does, in the stem column where the message's own particles are
+    /// gone too.
+    fn stem_branch(&self) -> Option<String> {
+        let stem = stem_korean(&self.text);
+        (stem != self.text && trigram_ready(&stem))
+            .then(|| format!("{}:{}", QueryField::Stem.fts_column(), fts_quote(&stem)))
+    }
+
+    /// Hangul terms also look for their romanization, so `삼성` finds
+    /// `Samseong`. The stem is romanized: a Latin spelling never
+    /// carries the particle. Latin terms need no branch: unpinned, they
+    /// already search the romanized column of Hangul messages.
+    fn roman_branch(&self) -> Option<String> {
+        if !contains_hangul_syllable(&self.text) || contains_bare_jamo(&self.text) {
+            return None;
+        }
+        let roman = romanize(&stem_korean(&self.text));
+        trigram_ready(&roman)
+            .then(|| format!("{}:{}", QueryField::Roman.fts_column(), fts_quote(&roman)))
+    }
+
+    /// `tkatjdwjswk` is `삼성전자` typed with the IME in English mode
+    /// (and `ㅗ디ㅣㅐ` is `hello` the other way round); search the
+    /// intended text as well.
+    fn layout_branch(&self) -> Option<String> {
+        let intended = layout_mistype(&self.text)?;
+        trigram_ready(&intended).then(|| fts_quote(&intended))
+    }
+
+    /// Route `ㅅㅅㅈㅈ`-style input to the choseong column. Syllables
+    /// in a mixed term (`삼성ㅈㅈ`) are reduced to consonants for that
+    /// match, so each run of them is also required in the jamo column
+    /// to keep `삼성ㅈㅈ` from matching `상속제재`. A pure-consonant
+    /// term needs no extra branch: its plain variant already searches
+    /// every column, including choseong.
+    fn choseong_branch(&self) -> Option<String> {
+        if !is_choseong_query(&self.text) {
+            return None;
+        }
+        let choseong = to_choseong(&self.text);
+        if !trigram_ready(&choseong) || choseong == self.text {
+            return None;
+        }
+        let mut parts = vec![format!(
+            "{}:{}",
+            QueryField::Choseong.fts_column(),
+            fts_quote(&choseong)
+        )];
+        for run in self
+            .text
+            .split(|c: char| is_choseong(c) || c.is_whitespace())
+            .filter(|run| !run.is_empty())
+        {
+            let jamo = decompose_jamo(run);
+            if trigram_ready(&jamo) {
+                parts.push(format!(
+                    "{}:{}",
+                    QueryField::Jamo.fts_column(),
+                    fts_quote(&jamo)
+                ));
+            }
+        }
+        Some(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            format!("({})", parts.join(" AND "))
+        })
+    }
+}
+
+impl QueryNode {
+    /// Compile into an FTS5 `MATCH` expression. Returns `None` when
+    /// some term is too short for the trigram index or a negation has
+    /// nothing to subtract from (`-a`, `a OR -b`); MATCH cannot express
+    /// either, so the caller falls back to LIKE with
+    /// [`QueryNode::to_like_match`].
+    pub fn to_fts_match(&self) -> Option<String> {
+        self.compile(false).map(|c| c.expr)
+    }
+
+    fn compile(&self, negated: bool) -> Option<Compiled> {
+        match self {
+            QueryNode::Term(term) => term.compile(negated),
+            // A bare negation has nothing to subtract from.
+            QueryNode::Not(_) => None,
+            QueryNode::Or(branches) => {
+                let mut compiled: Vec<Compiled> = branches
+                    .iter()
+                    .map(|b| b.compile(negated))
+                    .collect::<Option<_>>()?;
+                match compiled.len() {
+                    0 => None,
+                    1 => compiled.pop(),
+                    _ => Some(Compiled::compound(
+                        compiled
+                            .into_iter()
+                            .map(Compiled::grouped)
+                            .collect::<Vec<_>>()
+                            .join(" OR "),
+                    )),
+                }
+            }
+            QueryNode::And(parts) => {
+                let mut positive = Vec::new();
+                let mut negative = Vec::new();
+                for part in parts {
+                    match part {
+                        QueryNode::Not(inner) => negative.push(inner.compile(!negated)?),
+                        other => positive.push(other.compile(negated)?),
+                    }
+                }
+                let mut expr = match positive.len() {
+                    0 => return None,
+                    1 => positive.pop()?,
+                    _ => Compiled::compound(
+                        positive
+                            .into_iter()
+                            .map(Compiled::grouped)
+                            .collect::<Vec<_>>()
+                            .join(" AND "),
+                    ),
+                };
+                if !negative.is_empty() {
+                    let negated = if negative.len() == 1 {
+                        negative.pop()?.grouped()
+                    } else {
+                        format!(
+                            "({})",
+                            negative
+                                .into_iter()
+                                .map(Compiled::grouped)
+                                .collect::<Vec<_>>()
+                                .join(" OR ")
+                        )
+                    };
+                    expr = Compiled::compound(format!("{} NOT {negated}", expr.grouped()));
+                }
+                // `삼성 전자` should also find `삼성전자`: a run of plain
+                // words gets one extra branch matching them glued
+                // together, which the nospace column can satisfy.
+                if let Some(glued) = self.glued_words().filter(|_| !negated) {
+                    if trigram_ready(&glued) {
+                        expr = Compiled::compound(format!(
+                            "{} OR {}",
+                            expr.grouped(),
+                            fts_quote(&glued)
+                        ));
+                    }
+                }
+                Some(expr)
+            }
+        }
+    }
+
+    /// The same tree as a LIKE match, for queries
+    /// [`QueryNode::to_fts_match`] cannot compile. Pinned terms are
+    /// normalized into their column's alphabet here.
+    pub fn to_like_match(&self) -> LikeMatch {
+        match self {
+            QueryNode::Term(QueryTerm {
+                text,
+                field: Some(field),
+                ..
+            }) => LikeMatch::Term {
+                text: field.normalize(text),
+                column: Some(field.fts_column()),
+            },
+            QueryNode::Term(term) => LikeMatch::term(&term.text),
+            QueryNode::Not(inner) => LikeMatch::Not(Box::new(inner.to_like_match())),
+            QueryNode::And(nodes) => {
+                LikeMatch::And(nodes.iter().map(QueryNode::to_like_match).collect())
+            }
+            QueryNode::Or(nodes) => {
+                LikeMatch::Or(nodes.iter().map(QueryNode::to_like_match).collect())
+            }
+        }
+    }
+
+    /// For an AND of two or more unpinned, positive terms, the terms
+    /// concatenated without whitespace.
+    fn glued_words(&self) -> Option<String> {
+        let QueryNode::And(parts) = self else {
+            return None;
+        };
+        let mut glued = String::new();
+        for part in parts {
+            match part {
+                QueryNode::Term(t) if t.field.is_none() => glued.push_str(&t.text),
+                _ => return None,
+            }
+        }
+        (parts.len() >= 2).then(|| strip_whitespace(&glued))
+    }
+
+    /// Terms the user wants to see, in query order. Negated terms are
+    /// skipped; these drive highlighting and the LIKE fallback.
+    pub fn positive_terms(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        self.collect_terms(false, &mut out);
+        out
+    }
+
+    /// Terms under a negation, in query order.
+    pub fn negative_terms(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        self.collect_terms(true, &mut out);
+        out
+    }
+
+    /// Positive, unpinned bare words: the terms a spelling correction
+    /// may rewrite. Phrases are taken as deliberate.
+    pub fn correctable_terms(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        self.walk(false, &mut |term, under_not| {
+            if !under_not && !term.phrase && term.field.is_none() && !out.contains(&term.text) {
+                out.push(term.text.clone());
+            }
+        });
+        out
+    }
+
+    fn collect_terms(&self, negated: bool, out: &mut Vec<String>) {
+        self.walk(false, &mut |term, under_not| {
+            if under_not == negated && !out.contains(&term.text) {
+                out.push(term.text.clone());
+            }
+        });
+    }
+
+    fn walk(&self, under_not: bool, visit: &mut dyn FnMut(&QueryTerm, bool)) {
+        match self {
+            QueryNode::Term(term) => visit(term, under_not),
+            QueryNode::Not(inner) => inner.walk(!under_not, visit),
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+                for node in nodes {
+                    node.walk(under_not, visit);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str) -> QueryNode {
+        QueryNode::Term(QueryTerm {
+            text: text.to_string(),
+            phrase: false,
+            field: None,
+        })
+    }
+
+    fn compile(q: &str) -> Option<String> {
+        parse_query(q).and_then(|n| n.to_fts_match())
+    }
+
+    #[test]
+    fn parses_implicit_and() {
+        assert_eq!(
+            parse_query("hello world"),
+            Some(QueryNode::And(vec![term("hello"), term("world")]))
+        );
+    }
+
+    #[test]
+    fn parses_phrase_negation_and_or() {
+        let parsed = parse_query("\"bitcoin etf\" -rumor OR 이더리움").unwrap();
+        assert_eq!(
+            parsed,
+            QueryNode::Or(vec![
+                QueryNode::And(vec![
+                    QueryNode::Term(QueryTerm {
+                        text: "bitcoin etf".into(),
+                        phrase: true,
+                        field: None,
+                    }),
+                    QueryNode::Not(Box::new(term("rumor"))),
+                ]),
+                term("이더리움"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_groups_and_fields() {
+        let parsed = parse_query("(btc OR eth) jamo:ㅅㅏㅁ").unwrap();
+        assert_eq!(
+            parsed,
+            QueryNode::And(vec![
+                QueryNode::Or(vec![term("btc"), term("eth")]),
+                QueryNode::Term(QueryTerm {
+                    text: "ㅅㅏㅁ".into(),
+                    phrase: false,
+                    field: Some(QueryField::Jamo),
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn lowercase_or_and_inner_dash_are_literal() {
+        assert_eq!(
+            parse_query("this or e-mail"),
+            Some(QueryNode::And(vec![
+                term("this"),
+                term("or"),
+                term("e-mail")
+            ]))
+        );
+    }
+
+    #[test]
+    fn unknown_prefix_stays_literal() {
+        assert_eq!(parse_query("https://x.com"), Some(term("https://x.com")));
+        assert_eq!(parse_query("10:30"), Some(term("10:30")));
+    }
+
+    #[test]
+    fn entity_filters_are_collected_not_searched() {
+        let q = "삼성 has:link tag:#Airdrop domain:x.com mention:@durov cashtag:btc";
+        assert_eq!(parse_query(q), Some(term("삼성")));
+        let filters = parse_filters(q);
+        assert_eq!(filters.has, vec![EntityKind::Link]);
+        assert_eq!(filters.tags, vec!["#Airdrop"]);
+        assert_eq!(filters.domains, vec!["x.com"]);
+        assert_eq!(filters.mentions, vec!["@durov"]);
+        assert_eq!(filters.cashtags, vec!["btc"]);
+
+        let filters = parse_filters("from:김철수 FROM:\"김 영희\" from:\"\" 보고서");
+        assert_eq!(filters.from, vec!["김철수", "김 영희"]);
+        assert_eq!(
+            parse_query("from:\"김 영희\" 보고서")
+                .unwrap()
+                .positive_terms(),
+            vec!["보고서"]
+        );
+
+        let filters = parse_filters("has:photo type:doc 보고서");
+        assert_eq!(
+            filters.media_kinds,
+            vec![MediaKind::Photo, MediaKind::Document]
+        );
+
+        assert_eq!(parse_query("tag:airdrop"), None);
+        // An unknown kind or an empty value stays a literal term.
+        assert_eq!(parse_query("has:cats"), Some(term("has:cats")));
+        assert_eq!(parse_query("tag:"), Some(term("tag:")));
+        assert!(parse_filters("has:cats tag:").is_empty());
+    }
+
+    #[test]
+    fn filters_under_negation_or_or_are_ignored() {
+        let q = "삼성 -has:link (tag:airdrop OR 전자) -(from:\"김 영희\" 루머) type:photo";
+        let filters = parse_filters(q);
+        assert!(filters.has.is_empty());
+        assert!(filters.tags.is_empty());
+        assert!(filters.from.is_empty());
+        assert_eq!(filters.media_kinds, vec![MediaKind::Photo]);
+        assert_eq!(
+            ignored_filters(q),
+            vec!["has:link", "tag:airdrop", "from:\"김 영희\""]
+        );
+        // Grouping alone does not drop a filter.
+        assert_eq!(parse_filters("(삼성 has:link)").has, vec![EntityKind::Link]);
+        assert!(ignored_filters("삼성 has:link").is_empty());
+    }
+
+    #[test]
+    fn tolerates_unbalanced_input() {
+        assert_eq!(parse_query("(hello world"), parse_query("hello world"));
+        assert_eq!(parse_query("hello) world"), parse_query("hello world"));
+        assert_eq!(
+            parse_query("\"open phrase"),
+            Some(QueryNode::Term(QueryTerm {
+                text: "open phrase".into(),
+                phrase: true,
+                field: None,
+            }))
+        );
+        assert_eq!(parse_query("- OR ()"), None);
+        assert_eq!(parse_query(""), None);
+    }
+
+    #[test]
+    fn compiles_single_and_multi_term_queries() {
+        assert_eq!(compile("hello").as_deref(), Some("\"hello\""));
+        assert_eq!(
+            compile("hello world").as_deref(),
+            Some("(\"hello\" AND \"world\") OR \"helloworld\"")
+        );
+        assert_eq!(
+            compile("삼성전자").as_deref(),
+            Some("(\"삼성전자\" OR \"ㅅㅏㅁㅅㅓㅇㅈㅓㄴㅈㅏ\" OR text_roman:\"samseongjeonja\")")
+        );
+    }
+
+    #[test]
+    fn compiles_phrase_exclusion_and_fields() {
+        assert_eq!(
+            compile("\"bitcoin etf\" -rumor").as_deref(),
+            Some("\"bitcoin etf\" NOT {text_plain text_stripped text_jamo text_media}:\"rumor\"")
+        );
+        assert_eq!(
+            compile("nospace:\"삼성 전자\"").as_deref(),
+            Some("text_stripped:\"삼성전자\"")
+        );
+        assert_eq!(
+            compile("plain:hello OR jamo:삼성").as_deref(),
+            Some("text_plain:\"hello\" OR text_jamo:\"ㅅㅏㅁㅅㅓㅇ\"")
+        );
+    }
+
+    #[test]
+    fn compile_drops_short_and_purely_negative_queries() {
+        assert!(compile("a").is_none());
+        assert!(compile("ㅅ").is_none());
+        assert!(compile("-hello").is_none());
+        assert!(compile("hello OR -world").is_none());
+        // A short term, required, alternative or excluded, sends the
+        // whole query to LIKE rather than being dropped from it.
+        assert!(compile("ab cdef").is_none());
+        assert!(compile("ab OR cdef").is_none());
+        assert!(compile("bitcoin -ab").is_none());
+    }
+
+    #[test]
+    fn negations_compile_literal_and_jamo_only() {
+        assert_eq!(
+            compile("bitcoin -sam").as_deref(),
+            Some("\"bitcoin\" NOT {text_plain text_stripped text_jamo text_media}:\"sam\"")
+        );
+        assert_eq!(
+            compile("bitcoin -삼성전자를").as_deref(),
+            Some(
+                "\"bitcoin\" NOT ({text_plain text_stripped text_media}:\"삼성전자를\" \
+                 OR text_jamo:\"ㅅㅏㅁㅅㅓㅇㅈㅓㄴㅈㅏㄹㅡㄹ\")"
+            )
+        );
+        // Pinned negations keep their column.
+        assert_eq!(
+            compile("bitcoin -roman:삼성").as_deref(),
+            Some("\"bitcoin\" NOT text_roman:\"samseong\"")
+        );
+    }
+
+    #[test]
+    fn like_match_mirrors_the_tree() {
+        let q = parse_query("(ab OR cd) -ef stem:상승했다").unwrap();
+        assert_eq!(
+            q.to_like_match(),
+            LikeMatch::And(vec![
+                LikeMatch::Or(vec![LikeMatch::term("ab"), LikeMatch::term("cd")]),
+                LikeMatch::Not(Box::new(LikeMatch::term("ef"))),
+                LikeMatch::Term {
+                    text: "상승".into(),
+                    column: Some("text_stem"),
+                },
+            ])
+        );
+        assert!(q.to_like_match().has_positive());
+        assert!(!parse_query("-ab").unwrap().to_like_match().has_positive());
+    }
+
+    #[test]
+    fn compiles_choseong_terms() {
+        // Pure consonants already reach the choseong column unfielded.
+        assert_eq!(compile("ㅅㅅㅈㅈ").as_deref(), Some("\"ㅅㅅㅈㅈ\""));
+        assert_eq!(
+            compile("삼성ㅈㅈ").as_deref(),
+            Some(
+                "(\"삼성ㅈㅈ\" OR \"ㅅㅏㅁㅅㅓㅇㅈㅈ\" OR \
+                 (text_choseong:\"ㅅㅅㅈㅈ\" AND text_jamo:\"ㅅㅏㅁㅅㅓㅇ\"))"
+            )
+        );
+        assert_eq!(
+            compile("choseong:\"ㅅㅅ ㅈㅈ\"").as_deref(),
+            Some("text_choseong:\"ㅅㅅㅈㅈ\"")
+        );
+        // Too short for trigram either way; the LIKE fallback takes it.
+        assert!(compile("ㅅㅅ").is_none());
+    }
+
+    #[test]
+    fn compiles_stem_branch_for_particles() {
+        assert_eq!(
+            compile("삼성전자를").as_deref(),
+            Some(
+                "(\"삼성전자를\" OR \"ㅅㅏㅁㅅㅓㅇㅈㅓㄴㅈㅏㄹㅡㄹ\" OR text_stem:\"삼성전자\" \
+                 OR text_roman:\"samseongjeonja\")"
+            )
+        );
+        assert_eq!(
+            compile("stem:하락세였다").as_deref(),
+            Some("text_stem:\"하락세\"")
+        );
+        // A two-syllable stem is below the trigram minimum.
+        assert!(compile("stem:상승했다").is_none());
+    }
+
+    #[test]
+    fn compiles_roman_branch_and_field() {
+        assert_eq!(
+            compile("삼성").as_deref(),
+            Some("(\"ㅅㅏㅁㅅㅓㅇ\" OR text_roman:\"samseong\")")
+        );
+        assert_eq!(
+            compile("roman:삼성").as_deref(),
+            Some("text_roman:\"samseong\"")
+        );
+    }
+
+    #[test]
+    fn compiles_keyboard_layout_branch() {
+        assert_eq!(
+            compile("tkatjdwjswk").as_deref(),
+            Some("(\"tkatjdwjswk\" OR \"삼성전자\")")
+        );
+        // English words leave stray jamo behind and get no branch.
+        assert_eq!(compile("galaxy").as_deref(), Some("\"galaxy\""));
+    }
+
+    #[test]
+    fn positive_and_negative_terms() {
+        let q = parse_query("(btc OR \"bitcoin etf\") -rumor -(scam OR fud)").unwrap();
+        assert_eq!(q.positive_terms(), vec!["btc", "bitcoin etf"]);
+        assert_eq!(q.negative_terms(), vec!["rumor", "scam", "fud"]);
+    }
+}
@@ -0,0 +1,214 @@
+//! Query autocomplete from recent searches, chat titles and wiki page
+//! titles/aliases, in that order.
+//!
+//! Matching compares compat-jamo decompositions, so a syllable still
+//! being composed completes: `ㅅㅏㅁ` and `삼성저` both reach
+//! `삼성전자`. Words from indexed messages are not offered yet; see
+//! `docs/specs/2026-10-16-suggest-term-vocabulary.md`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::Store;
+
+use super::hangul::decompose_jamo;
+
+pub const DEFAULT_SUGGEST_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    History,
+    Chat,
+    WikiPage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+}
+
+/// Up to `limit` completions of `prefix`, deduplicated ignoring case.
+/// An empty prefix lists recent searches.
+pub fn suggest(
+    store: &Store,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<Suggestion>, sqlite::Error> {
+    let prefix = prefix.trim_start();
+    let jamo = decompose_jamo(&prefix.to_lowercase());
+    let mut out = Collector {
+        typed: prefix.trim_end().to_lowercase(),
+        limit,
+        items: Vec::new(),
+    };
+
+    if jamo.trim().is_empty() {
+        for entry in store.recent_searches(limit)? {
+            out.push(entry.query, SuggestionKind::History);
+        }
+        return Ok(out.items);
+    }
+
+    for entry in store.searches_with_jamo_prefix(&jamo, limit)? {
+        out.push(entry.query, SuggestionKind::History);
+    }
+    for chat in store.get_active_chats()? {
+        if out.full() {
+            break;
+        }
+        if name_matches(&chat.title, &jamo) {
+            out.push(chat.title, SuggestionKind::Chat);
+        }
+    }
+    for name in store.wiki_page_names()? {
+        if out.full() {
+            break;
+        }
+        if name_matches(&name, &jamo) {
+            out.push(name, SuggestionKind::WikiPage);
+        }
+    }
+    Ok(out.items)
+}
+
+/// True if some word of `name` onwards starts with `jamo_prefix`, so
+/// `전자` completes the chat title `삼성 전자 공시`.
+fn name_matches(name: &str, jamo_prefix: &str) -> bool {
+    let lower = name.to_lowercase();
+    let mut at_word_start = true;
+    for (i, c) in lower.char_indices() {
+        if at_word_start
+            && !c.is_whitespace()
+            && decompose_jamo(&lower[i..]).starts_with(jamo_prefix)
+        {
+            return true;
+        }
+        at_word_start = c.is_whitespace();
+    }
+    false
+}
+
+struct Collector {
+    typed: String,
+    limit: usize,
+    items: Vec<Suggestion>,
+}
+
+impl Collector {
+    fn full(&self) -> bool {
+        self.items.len() >= self.limit
+    }
+
+    /// Skip duplicates and the prefix itself.
+    fn push(&mut self, text: String, kind: SuggestionKind) {
+        let lower = text.to_lowercase();
+        if self.full()
+            || lower == self.typed
+            || self.items.iter().any(|s| s.text.to_lowercase() == lower)
+        {
+            return;
+        }
+        self.items.push(Suggestion { text, kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::chat::ChatRow;
+    use crate::store::message::{strip_whitespace, MessageRow};
+
+    fn store_with(texts: &[&str]) -> Store {
+        let store = Store::open_in_memory().unwrap();
+        let rows: Vec<MessageRow> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| MessageRow {
+                message_id: i as i64 + 1,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1000 + i as i64,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            })
+            .collect();
+        store.insert_messages_batch(&rows).unwrap();
+        store
+    }
+
+    fn texts(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn completes_half_typed_jamo() {
+        let store = store_with(&["삼성전자 실적"]);
+        store.record_search("삼성전자").unwrap();
+        store.record_search("삼양식품").unwrap();
+        let got = suggest(&store, "ㅅㅏㅁ", 10).unwrap();
+        let mut got = texts(&got);
+        got.sort();
+        assert_eq!(got, vec!["삼성전자", "삼양식품"]);
+
+        assert_eq!(
+            texts(&suggest(&store, "삼성저", 10).unwrap()),
+            vec!["삼성전자"]
+        );
+        // Words of indexed messages are not a source.
+        assert!(suggest(&store, "실ㅈ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn history_ranks_before_chats_and_wiki() {
+        let store = store_with(&["삼성전자 실적"]);
+        store.record_search("삼성 파운드리").unwrap();
+        store
+            .upsert_chat(&ChatRow {
+                account_id: 0,
+                chat_id: 2,
+                title: "삼성 공시 알림".into(),
+                chat_type: "channel".into(),
+                username: None,
+                access_hash: None,
+                is_excluded: false,
+            })
+            .unwrap();
+        store
+            .dedup_or_insert_page_v2("entity", "삼성바이오로직스", &["Samsung Biologics".into()])
+            .unwrap();
+
+        let got = suggest(&store, "삼ㅅ", 10).unwrap();
+        let kinds: Vec<SuggestionKind> = got.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SuggestionKind::History,
+                SuggestionKind::Chat,
+                SuggestionKind::WikiPage,
+            ]
+        );
+        assert_eq!(
+            texts(&suggest(&store, "samsung", 10).unwrap()),
+            vec!["Samsung Biologics"]
+        );
+        // Word starts inside a title complete too.
+        assert_eq!(
+            texts(&suggest(&store, "공시", 10).unwrap()),
+            vec!["삼성 공시 알림"]
+        );
+        // Empty prefix: recent searches.
+        assert_eq!(
+            texts(&suggest(&store, "", 10).unwrap()),
+            vec!["삼성 파운드리"]
+        );
+        assert_eq!(suggest(&store, "ㅅ", 2).unwrap().len(), 2);
+    }
+}
@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use super::outbox::{enqueue_chat_meta, enqueue_chat_purge};
 use super::Store;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatRow {
+    /// Telegram account the chat was seen from. Chat ids are only
+    /// unique per account; 0 is the account of a single-account store.
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub title: String,
     pub chat_type: String,
@@ -12,39 +17,87 @@ pub struct ChatRow {
     pub is_excluded: bool,
 }
 
+/// A chat as the chat list shows it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatOverview {
+    #[serde(flatten)]
+    pub chat: ChatRow,
+    /// Indexed messages, tombstones left out.
+    pub message_count: i64,
+    /// Timestamp of the newest indexed message.
+    pub last_message_at: Option<i64>,
+    /// When messages of the chat were last indexed (unix seconds).
+    /// `None` if not since the store learned to record it.
+    pub last_indexed_at: Option<i64>,
+}
+
 impl Store {
+    /// Insert or update a chat. A change to its title, type or handle
+    /// is queued for cloud sync.
     pub fn upsert_chat(&self, chat: &ChatRow) -> Result<(), sqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "INSERT INTO chats (chat_id, title, chat_type, username, access_hash, is_excluded)
-             VALUES (?, ?, ?, ?, ?, ?)
-             ON CONFLICT(chat_id) DO UPDATE SET
-                title = excluded.title,
-                chat_type = excluded.chat_type,
-                username = excluded.username,
-                access_hash = excluded.access_hash",
-        )?;
-        stmt.bind((1, chat.chat_id))?;
-        stmt.bind((2, chat.title.as_str()))?;
-        stmt.bind((3, chat.chat_type.as_str()))?;
-        match &chat.username {
-            Some(u) => stmt.bind((4, u.as_str()))?,
-            None => stmt.bind((4, sqlite::Value::Null))?,
-        };
-        match chat.access_hash {
-            Some(h) => stmt.bind((5, h))?,
-            None => stmt.bind((5, sqlite::Value::Null))?,
-        };
-        stmt.bind((6, chat.is_excluded as i64))?;
-        stmt.next()?;
-        Ok(())
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<(), sqlite::Error> {
+            let prior = self.get_chat(chat.account_id, chat.chat_id)?;
+            let mut stmt = self.conn.prepare(
+                "INSERT INTO chats (chat_id, title, chat_type, username, access_hash, is_excluded,
+                                    account_id)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(account_id, chat_id) DO UPDATE SET
+                    title = excluded.title,
+                    chat_type = excluded.chat_type,
+                    username = excluded.username,
+                    access_hash = excluded.access_hash",
+            )?;
+            stmt.bind((1, chat.chat_id))?;
+            stmt.bind((2, chat.title.as_str()))?;
+            stmt.bind((3, chat.chat_type.as_str()))?;
+            match &chat.username {
+                Some(u) => stmt.bind((4, u.as_str()))?,
+                None => stmt.bind((4, sqlite::Value::Null))?,
+            };
+            match chat.access_hash {
+                Some(h) => stmt.bind((5, h))?,
+                None => stmt.bind((5, sqlite::Value::Null))?,
+            };
+            stmt.bind((6, chat.is_excluded as i64))?;
+            stmt.bind((7, chat.account_id))?;
+            stmt.next()?;
+
+            let changed = prior.is_none_or(|p| {
+                (&p.title, &p.chat_type, &p.username, p.access_hash)
+                    != (
+                        &chat.title,
+                        &chat.chat_type,
+                        &chat.username,
+                        chat.access_hash,
+                    )
+            });
+            if changed {
+                enqueue_chat_meta(&self.conn, chat)?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => self.conn.execute("COMMIT"),
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
     }
 
-    pub fn get_chat(&self, chat_id: i64) -> Result<Option<ChatRow>, sqlite::Error> {
+    pub fn get_chat(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<Option<ChatRow>, sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "SELECT chat_id, title, chat_type, username, access_hash, is_excluded
-             FROM chats WHERE chat_id = ?",
+            "SELECT account_id, chat_id, title, chat_type, username, access_hash, is_excluded
+             FROM chats WHERE account_id = ? AND chat_id = ?",
         )?;
-        stmt.bind((1, chat_id))?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
         if let Ok(sqlite::State::Row) = stmt.next() {
             Ok(Some(read_chat_row(&stmt)?))
         } else {
@@ -54,7 +107,7 @@ impl Store {
 
     pub fn get_active_chats(&self) -> Result<Vec<ChatRow>, sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "SELECT chat_id, title, chat_type, username, access_hash, is_excluded
+            "SELECT account_id, chat_id, title, chat_type, username, access_hash, is_excluded
              FROM chats WHERE is_excluded = 0 ORDER BY title",
         )?;
         let mut results = Vec::new();
@@ -66,7 +119,7 @@ impl Store {
 
     pub fn get_all_chats(&self) -> Result<Vec<ChatRow>, sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "SELECT chat_id, title, chat_type, username, access_hash, is_excluded
+            "SELECT account_id, chat_id, title, chat_type, username, access_hash, is_excluded
              FROM chats WHERE TRIM(title) != '' ORDER BY title",
         )?;
         let mut results = Vec::new();
@@ -76,14 +129,76 @@ impl Store {
         Ok(results)
     }
 
-    pub fn set_chat_excluded(&self, chat_id: i64, excluded: bool) -> Result<(), sqlite::Error> {
-        let mut stmt = self
-            .conn
-            .prepare("UPDATE chats SET is_excluded = ? WHERE chat_id = ?")?;
-        stmt.bind((1, excluded as i64))?;
-        stmt.bind((2, chat_id))?;
-        stmt.next()?;
-        Ok(())
+    /// Excluding a chat also withdraws it from cloud sync: its queued
+    /// ops are cancelled and a `chat_purge` is queued.
+    pub fn set_chat_excluded(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        excluded: bool,
+    ) -> Result<(), sqlite::Error> {
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<(), sqlite::Error> {
+            let mut stmt = self.conn.prepare(
+                "UPDATE chats SET is_excluded = ?
+                 WHERE account_id = ? AND chat_id = ? AND is_excluded != ?",
+            )?;
+            stmt.bind((1, excluded as i64))?;
+            stmt.bind((2, account_id))?;
+            stmt.bind((3, chat_id))?;
+            stmt.bind((4, excluded as i64))?;
+            stmt.next()?;
+            if excluded && self.conn.change_count() > 0 {
+                enqueue_chat_purge(&self.conn, account_id, chat_id)?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => self.conn.execute("COMMIT"),
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Chats with their message counts, by title. Stub chats created
+    /// by indexing before the shell sent their details are listed with
+    /// an empty title so they can be filled in.
+    pub fn list_chats(
+        &self,
+        account_id: Option<i64>,
+        include_excluded: bool,
+    ) -> Result<Vec<ChatOverview>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.account_id, c.chat_id, c.title, c.chat_type, c.username, c.access_hash,
+                    c.is_excluded, c.last_indexed_at,
+                    COUNT(m.message_id) AS message_count,
+                    MAX(m.timestamp) AS last_message_at
+             FROM chats c
+             LEFT JOIN messages m
+               ON m.account_id = c.account_id AND m.chat_id = c.chat_id
+              AND m.deleted_at IS NULL
+             WHERE (?1 IS NULL OR c.account_id = ?1) AND (?2 OR c.is_excluded = 0)
+             GROUP BY c.account_id, c.chat_id
+             ORDER BY c.title, c.account_id, c.chat_id",
+        )?;
+        match account_id {
+            Some(v) => stmt.bind((1, v))?,
+            None => stmt.bind((1, sqlite::Value::Null))?,
+        };
+        stmt.bind((2, include_excluded as i64))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(ChatOverview {
+                chat: read_chat_row(&stmt)?,
+                message_count: stmt.read::<i64, _>("message_count")?,
+                last_message_at: stmt.read::<Option<i64>, _>("last_message_at")?,
+                last_indexed_at: stmt.read::<Option<i64>, _>("last_indexed_at")?,
+            });
+        }
+        Ok(out)
     }
 
     pub fn chat_count(&self) -> Result<i64, sqlite::Error> {
@@ -95,6 +210,7 @@ impl Store {
 
 fn read_chat_row(stmt: &sqlite::Statement) -> Result<ChatRow, sqlite::Error> {
     Ok(ChatRow {
+        account_id: stmt.read::<i64, _>("account_id")?,
         chat_id: stmt.read::<i64, _>("chat_id")?,
         title: stmt.read::<String, _>("title")?,
         chat_type: stmt.read::<String, _>("chat_type")?,
@@ -114,6 +230,7 @@ mod tests {
 
     fn sample_chat(id: i64) -> ChatRow {
         ChatRow {
+            account_id: 0,
             chat_id: id,
             title: format!("Chat {}", id),
             chat_type: "supergroup".to_string(),
@@ -129,7 +246,7 @@ mod tests {
         let chat = sample_chat(100);
         store.upsert_chat(&chat).unwrap();
 
-        let fetched = store.get_chat(100).unwrap().unwrap();
+        let fetched = store.get_chat(0, 100).unwrap().unwrap();
         assert_eq!(fetched.title, "Chat 100");
         assert_eq!(fetched.chat_type, "supergroup");
         assert_eq!(fetched.username, Some("chat_100".to_string()));
@@ -144,14 +261,14 @@ mod tests {
         chat.title = "Updated Title".to_string();
         store.upsert_chat(&chat).unwrap();
 
-        let fetched = store.get_chat(100).unwrap().unwrap();
+        let fetched = store.get_chat(0, 100).unwrap().unwrap();
         assert_eq!(fetched.title, "Updated Title");
     }
 
     #[test]
     fn test_get_nonexistent() {
         let store = test_store();
-        assert!(store.get_chat(999).unwrap().is_none());
+        assert!(store.get_chat(0, 999).unwrap().is_none());
     }
 
     #[test]
@@ -159,13 +276,84 @@ mod tests {
         let store = test_store();
         store.upsert_chat(&sample_chat(1)).unwrap();
         store.upsert_chat(&sample_chat(2)).unwrap();
-        store.set_chat_excluded(2, true).unwrap();
+        store.set_chat_excluded(0, 2, true).unwrap();
 
         let active = store.get_active_chats().unwrap();
         assert_eq!(active.len(), 1);
         assert_eq!(active[0].chat_id, 1);
     }
 
+    #[test]
+    fn test_same_chat_id_per_account() {
+        let store = test_store();
+        store.upsert_chat(&sample_chat(100)).unwrap();
+        let mut other = sample_chat(100);
+        other.account_id = 2;
+        other.title = "Second account".to_string();
+        store.upsert_chat(&other).unwrap();
+        store.set_chat_excluded(2, 100, true).unwrap();
+
+        assert_eq!(store.get_chat(0, 100).unwrap().unwrap().title, "Chat 100");
+        assert_eq!(
+            store.get_chat(2, 100).unwrap().unwrap().title,
+            "Second account"
+        );
+        let active = store.get_active_chats().unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].account_id, 0);
+    }
+
+    #[test]
+    fn test_list_chats_counts_live_messages() {
+        use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+        let store = test_store();
+        store.upsert_chat(&sample_chat(1)).unwrap();
+        store.upsert_chat(&sample_chat(2)).unwrap();
+        store.set_chat_excluded(0, 2, true).unwrap();
+        let rows: Vec<MessageRow> = [(1, 10), (2, 10), (3, 30)]
+            .into_iter()
+            .map(|(message_id, chat_id)| MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id,
+                timestamp: 1_700_000_000 + message_id,
+                text_plain: "공모주".to_string(),
+                text_stripped: strip_whitespace("공모주"),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            })
+            .collect();
+        store.insert_messages_batch(&rows).unwrap();
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 10,
+                message_id: 2,
+            }])
+            .unwrap();
+
+        let listed = store.list_chats(None, false).unwrap();
+        let ids: Vec<i64> = listed.iter().map(|c| c.chat.chat_id).collect();
+        // Stubs from indexing sort first with their empty titles.
+        assert_eq!(ids, vec![10, 30, 1]);
+        assert_eq!(listed[0].chat.title, "");
+        assert_eq!(listed[0].message_count, 1);
+        assert_eq!(listed[0].last_message_at, Some(1_700_000_001));
+        assert!(listed[0].last_indexed_at.is_some());
+        assert_eq!(listed[2].message_count, 0);
+        assert_eq!(listed[2].last_message_at, None);
+        assert_eq!(listed[2].last_indexed_at, None);
+
+        assert_eq!(store.list_chats(None, true).unwrap().len(), 4);
+        assert!(store.list_chats(Some(3), true).unwrap().is_empty());
+    }
+
     #[test]
     fn test_chat_count() {
         let store = test_store();
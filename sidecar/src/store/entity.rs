@@ -0,0 +1,555 @@
+//! Links, hashtags, mentions and cashtags pulled out of message text at
+//! index time into `message_entities`, backing the `has:`, `domain:`,
+//! `tag:`, `mention:` and `cashtag:` search filters and the per-chat
+//! entity rankings.
+//!
+//! Values are stored normalized so filters compare with `=`: hashtags
+//! and mentions lowercased without their sigil, cashtags uppercased,
+//! links as written with their host (minus `www.`) in `domain`.
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+pub const DEFAULT_TOP_ENTITIES_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Link,
+    Hashtag,
+    Mention,
+    Cashtag,
+}
+
+impl EntityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Link => "link",
+            Self::Hashtag => "hashtag",
+            Self::Mention => "mention",
+            Self::Cashtag => "cashtag",
+        }
+    }
+
+    /// The kind named by `has:` in a query; plurals and the usual
+    /// synonyms are accepted.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "link" | "links" | "url" | "urls" => Some(Self::Link),
+            "hashtag" | "hashtags" | "tag" | "tags" => Some(Self::Hashtag),
+            "mention" | "mentions" => Some(Self::Mention),
+            "cashtag" | "cashtags" | "ticker" => Some(Self::Cashtag),
+            _ => None,
+        }
+    }
+
+    /// Normalize a filter value the way values of this kind are
+    /// stored: `#Airdrop` → `airdrop`, `$btc` → `BTC`, and for links
+    /// any URL or host → its domain. `None` when nothing is left.
+    pub fn normalize(self, raw: &str) -> Option<String> {
+        let value = match self {
+            Self::Link => return link_domain(raw),
+            Self::Hashtag => raw.trim_start_matches('#').to_lowercase(),
+            Self::Mention => raw.trim_start_matches('@').to_lowercase(),
+            Self::Cashtag => raw.trim_start_matches('$').to_uppercase(),
+        };
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub value: String,
+    /// Host of a link, `None` for other kinds.
+    pub domain: Option<String>,
+}
+
+/// How many distinct messages carry an entity. Links are counted per
+/// domain, so `value` is the host for [`EntityKind::Link`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityCount {
+    pub kind: EntityKind,
+    pub value: String,
+    pub count: u64,
+}
+
+/// Every entity in `text`, first occurrence order, without duplicates.
+/// Sigils inside a link (`example.com/#section`, `/@user`) are part
+/// of the link, not entities of their own.
+pub fn extract_entities(text: &str) -> Vec<Entity> {
+    let mut out: Vec<Entity> = Vec::new();
+    let mut push = |kind: EntityKind, value: String, domain: Option<String>| {
+        if !out.iter().any(|e| e.kind == kind && e.value == value) {
+            out.push(Entity {
+                kind,
+                value,
+                domain,
+            });
+        }
+    };
+
+    let links = find_links(text);
+    for &(start, end) in &links {
+        let url = &text[start..end];
+        if let Some(domain) = link_domain(url) {
+            push(EntityKind::Link, url.to_string(), Some(domain));
+        }
+    }
+
+    for (i, c) in text.char_indices() {
+        if !matches!(c, '#' | '@' | '$') || links.iter().any(|&(s, e)| (s..e).contains(&i)) {
+            continue;
+        }
+        // `C#`, `a@b.com`, `US$` and `&#39;` are not entities.
+        if text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|p| p.is_alphanumeric() || matches!(p, '_' | '&' | '#' | '@' | '$'))
+        {
+            continue;
+        }
+        let rest = &text[i + c.len_utf8()..];
+        let found = match c {
+            '#' => hashtag(rest).map(|v| (EntityKind::Hashtag, v)),
+            '@' => mention(rest).map(|v| (EntityKind::Mention, v)),
+            _ => cashtag(rest).map(|v| (EntityKind::Cashtag, v)),
+        };
+        if let Some((kind, value)) = found {
+            push(kind, value, None);
+        }
+    }
+    out
+}
+
+/// Byte ranges of `http://`, `https://` and `www.` links. A link ends
+/// at whitespace, a quote or bracket, or the first non-ASCII character,
+/// since Korean particles are usually typed straight after a URL
+/// (`https://x.com/abc에서`). Trailing sentence punctuation and
+/// unbalanced closing brackets are trimmed.
+fn find_links(text: &str) -> Vec<(usize, usize)> {
+    let lower = text.to_ascii_lowercase();
+    let mut out = Vec::new();
+    let mut resume = 0;
+    for (i, _) in text.char_indices() {
+        if i < resume {
+            continue;
+        }
+        let rest = &lower[i..];
+        if !(rest.starts_with("http://")
+            || rest.starts_with("https://")
+            || rest.starts_with("www."))
+        {
+            continue;
+        }
+        if text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|p| p.is_ascii_alphanumeric() || matches!(p, '/' | '.' | '@' | '_' | '-'))
+        {
+            continue;
+        }
+        let len = rest
+            .find(|c: char| {
+                c.is_whitespace() || !c.is_ascii() || matches!(c, '<' | '>' | '"' | '\'' | '`')
+            })
+            .unwrap_or(rest.len());
+        let end = i + trim_link_end(&text[i..i + len]);
+        out.push((i, end));
+        resume = end;
+    }
+    out
+}
+
+fn trim_link_end(link: &str) -> usize {
+    let mut end = link.len();
+    while let Some(last) = link[..end].chars().next_back() {
+        let open = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' => {
+                end -= 1;
+                continue;
+            }
+            ')' => '(',
+            ']' => '[',
+            '}' => '{',
+            _ => break,
+        };
+        // `wiki/Foo_(bar)` keeps its bracket; `(see https://x.com)` does not.
+        if link[..end].matches(open).count() >= link[..end].matches(last).count() {
+            break;
+        }
+        end -= 1;
+    }
+    end
+}
+
+/// Host of a link or bare domain, lowercased, without scheme, `www.`,
+/// credentials or port. `None` unless it looks like a dotted hostname.
+pub fn link_domain(link: &str) -> Option<String> {
+    let lower = link.trim().to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+        .unwrap_or(&lower);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let valid = host.contains('.')
+        && !host.starts_with(['.', '-'])
+        && !host.ends_with(['.', '-'])
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'));
+    valid.then(|| host.to_string())
+}
+
+/// `#airdrop`, `#삼성전자`: letters, digits and `_`, with at least one
+/// letter (`#1` is a numbering, not a tag).
+fn hashtag(rest: &str) -> Option<String> {
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let tag = &rest[..end];
+    (tag.chars().count() <= 100 && tag.chars().any(char::is_alphabetic)).then(|| tag.to_lowercase())
+}
+
+/// Telegram usernames: 4 to 32 of `[A-Za-z0-9_]`, starting with a
+/// letter. `@example.com` is a host, not a mention.
+fn mention(rest: &str) -> Option<String> {
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = &rest[..end];
+    let continues_as_host = rest[end..]
+        .strip_prefix('.')
+        .and_then(|after| after.chars().next())
+        .is_some_and(|c| c.is_ascii_alphanumeric());
+    ((4..=32).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !continues_as_host)
+        .then(|| name.to_lowercase())
+}
+
+/// `$BTC`, `$eth`: one to eight ASCII letters and nothing word-like
+/// straight after, so `$5` and `$ETH2` are not tickers.
+fn cashtag(rest: &str) -> Option<String> {
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(rest.len());
+    let ticker = &rest[..end];
+    let clean_end = !rest[end..]
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_');
+    ((1..=8).contains(&ticker.len()) && clean_end).then(|| ticker.to_uppercase())
+}
+
+/// Replace the stored entities of one message with those of `text`.
+/// Runs inside the caller's transaction.
+pub(crate) fn index_entities(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+    text: &str,
+) -> Result<(), sqlite::Error> {
+    delete_entities(conn, account_id, chat_id, message_id)?;
+    for entity in extract_entities(text) {
+        let mut stmt = conn.prepare(
+            "INSERT OR IGNORE INTO message_entities
+                (account_id, chat_id, message_id, kind, value, domain)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, message_id))?;
+        stmt.bind((4, entity.kind.as_str()))?;
+        stmt.bind((5, entity.value.as_str()))?;
+        stmt.bind((6, entity.domain.as_deref()))?;
+        stmt.next()?;
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_entities(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "DELETE FROM message_entities WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+fn read_kind(stmt: &sqlite::Statement<'_>, idx: usize) -> Result<EntityKind, sqlite::Error> {
+    let kind = stmt.read::<String, _>(idx)?;
+    EntityKind::parse(&kind).ok_or_else(|| sqlite::Error {
+        code: None,
+        message: Some(format!("unknown entity kind {kind:?}")),
+    })
+}
+
+impl Store {
+    /// Entities extracted from one message, in extraction order.
+    pub fn message_entities(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Vec<Entity>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, value, domain FROM message_entities
+             WHERE account_id = ? AND chat_id = ? AND message_id = ?
+             ORDER BY rowid",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, message_id))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(Entity {
+                kind: read_kind(&stmt, 0)?,
+                value: stmt.read::<String, _>(1)?,
+                domain: stmt.read::<Option<String>, _>(2)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Most frequent entities, by the number of live messages carrying
+    /// them, optionally limited to one kind, one `(account_id,
+    /// chat_id)` and a window of unix seconds (both ends inclusive).
+    /// Ties break by value.
+    pub fn top_entities(
+        &self,
+        kind: Option<EntityKind>,
+        chat: Option<(i64, i64)>,
+        since: Option<i64>,
+        until: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<EntityCount>, sqlite::Error> {
+        let mut clause = String::new();
+        if kind.is_some() {
+            clause.push_str(" AND e.kind = ?");
+        }
+        if chat.is_some() {
+            clause.push_str(" AND e.account_id = ? AND e.chat_id = ?");
+        }
+        if since.is_some() {
+            clause.push_str(" AND m.timestamp >= ?");
+        }
+        if until.is_some() {
+            clause.push_str(" AND m.timestamp <= ?");
+        }
+        // DISTINCT first: two links to one domain in a message count once.
+        let mut stmt = self.conn.prepare(format!(
+            "SELECT kind, label, COUNT(*) AS n FROM (
+                SELECT DISTINCT e.kind,
+                       CASE WHEN e.kind = 'link' THEN e.domain ELSE e.value END AS label,
+                       e.account_id, e.chat_id, e.message_id
+                FROM message_entities e
+                JOIN messages m ON m.account_id = e.account_id AND m.chat_id = e.chat_id
+                               AND m.message_id = e.message_id
+                JOIN chats c ON c.account_id = e.account_id AND c.chat_id = e.chat_id
+                WHERE c.is_excluded = 0 AND m.deleted_at IS NULL{clause}
+             )
+             GROUP BY kind, label
+             ORDER BY n DESC, label ASC
+             LIMIT ?"
+        ))?;
+        let mut bind_idx = 1;
+        if let Some(kind) = kind {
+            stmt.bind((bind_idx, kind.as_str()))?;
+            bind_idx += 1;
+        }
+        let chat = chat.map(|(account_id, chat_id)| [account_id, chat_id]);
+        for value in chat.iter().flatten().chain(&since).chain(&until) {
+            stmt.bind((bind_idx, *value))?;
+            bind_idx += 1;
+        }
+        stmt.bind((bind_idx, limit as i64))?;
+
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(EntityCount {
+                kind: read_kind(&stmt, 0)?,
+                value: stmt.read::<String, _>(1)?,
+                count: stmt.read::<i64, _>(2)? as u64,
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+    fn entity(kind: EntityKind, value: &str) -> (EntityKind, String) {
+        (kind, value.to_string())
+    }
+
+    fn extracted(text: &str) -> Vec<(EntityKind, String)> {
+        extract_entities(text)
+            .into_iter()
+            .map(|e| (e.kind, e.value))
+            .collect()
+    }
+
+    fn insert(store: &Store, chat_id: i64, message_id: i64, ts: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id,
+                timestamp: ts,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    #[test]
+    fn extracts_each_kind_and_normalizes() {
+        assert_eq!(
+            extracted("#Airdrop 스냅샷 @Durov_Channel 공지, $btc 상승! https://X.com/a?b=1."),
+            vec![
+                entity(EntityKind::Link, "https://X.com/a?b=1"),
+                entity(EntityKind::Hashtag, "airdrop"),
+                entity(EntityKind::Mention, "durov_channel"),
+                entity(EntityKind::Cashtag, "BTC"),
+            ]
+        );
+        assert_eq!(
+            extracted("#삼성전자 #삼성전자 #1 #_ #2024년"),
+            vec![
+                entity(EntityKind::Hashtag, "삼성전자"),
+                entity(EntityKind::Hashtag, "2024년"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_lookalikes() {
+        // Emails, C#, short handles, prices, sigils inside links.
+        assert!(extracted("mail me at a.b@example.com").is_empty());
+        assert!(extracted("C# and F# @abc $5 $ETH2 US$10").is_empty());
+        assert!(extracted("@example.com").is_empty());
+        assert_eq!(
+            extracted("https://t.me/s/chan#top www.naver.com에서 확인"),
+            vec![
+                entity(EntityKind::Link, "https://t.me/s/chan#top"),
+                entity(EntityKind::Link, "www.naver.com"),
+            ]
+        );
+        assert_eq!(
+            extracted("(see https://en.wikipedia.org/wiki/Foo_(bar))"),
+            vec![entity(
+                EntityKind::Link,
+                "https://en.wikipedia.org/wiki/Foo_(bar)"
+            )]
+        );
+    }
+
+    #[test]
+    fn link_domain_strips_decoration() {
+        assert_eq!(
+            link_domain("https://user:pw@WWW.Example.com:8443/x").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(link_domain("x.com").as_deref(), Some("x.com"));
+        assert_eq!(link_domain("localhost"), None);
+        assert_eq!(
+            EntityKind::Link.normalize("www.X.com/").as_deref(),
+            Some("x.com")
+        );
+    }
+
+    #[test]
+    fn entities_follow_edits_and_deletes() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, 1, 1000, "#airdrop https://x.com/1");
+        assert_eq!(store.message_entities(0, 1, 1).unwrap().len(), 2);
+
+        insert(&store, 1, 1, 1000, "#airdrop 마감");
+        let entities = store.message_entities(0, 1, 1).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].value, "airdrop");
+
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id: 1,
+            }])
+            .unwrap();
+        store
+            .purge_tombstones(crate::wiki::norm::unix_now() + 31 * 86_400)
+            .unwrap();
+        assert!(store.message_entities(0, 1, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn top_entities_counts_messages_per_window() {
+        let store = Store::open_in_memory().unwrap();
+        insert(
+            &store,
+            1,
+            1,
+            1000,
+            "#airdrop https://x.com/1 https://x.com/2",
+        );
+        insert(&store, 1, 2, 2000, "#airdrop https://mobile.x.com/3");
+        insert(&store, 1, 3, 3000, "#etf $BTC");
+        insert(&store, 2, 1, 2000, "#etf");
+
+        let top = store
+            .top_entities(None, Some((0, 1)), None, None, 10)
+            .unwrap();
+        assert_eq!(
+            top[0],
+            EntityCount {
+                kind: EntityKind::Hashtag,
+                value: "airdrop".into(),
+                count: 2,
+            }
+        );
+        // Chat 1 of another account is another chat.
+        assert!(store
+            .top_entities(None, Some((1, 1)), None, None, 10)
+            .unwrap()
+            .is_empty());
+        let links = store
+            .top_entities(Some(EntityKind::Link), None, None, None, 10)
+            .unwrap();
+        assert_eq!(
+            links
+                .iter()
+                .map(|e| (e.value.as_str(), e.count))
+                .collect::<Vec<_>>(),
+            vec![("mobile.x.com", 1), ("x.com", 1)]
+        );
+
+        let etf = store
+            .top_entities(Some(EntityKind::Hashtag), None, Some(2000), Some(3000), 10)
+            .unwrap();
+        assert_eq!(etf[0].value, "etf");
+        assert_eq!(etf[0].count, 2);
+        assert_eq!(etf.len(), 2);
+    }
+}
@@ -0,0 +1,284 @@
+//! What a message carries besides its text: the attachment (photo,
+//! document, voice note, ...) and where it was forwarded from. Kept in
+//! `message_media`, one row per message that has either.
+//!
+//! The caption and file name are also indexed: as written in the
+//! `text_media` column of `messages_fts`, and appended to the text
+//! before the Korean forms (jamo, choseong, stem, roman) are derived.
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Photo,
+    Video,
+    Document,
+    Audio,
+    Voice,
+    VideoNote,
+    Sticker,
+    Animation,
+    Other,
+}
+
+impl MediaKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Photo => "photo",
+            Self::Video => "video",
+            Self::Document => "document",
+            Self::Audio => "audio",
+            Self::Voice => "voice",
+            Self::VideoNote => "video_note",
+            Self::Sticker => "sticker",
+            Self::Animation => "animation",
+            Self::Other => "other",
+        }
+    }
+
+    /// The kind named by `has:` or `type:` in a query; plurals and the
+    /// usual synonyms are accepted.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "photo" | "photos" | "image" | "images" | "picture" => Some(Self::Photo),
+            "video" | "videos" => Some(Self::Video),
+            "document" | "documents" | "doc" | "file" | "files" => Some(Self::Document),
+            "audio" | "music" => Some(Self::Audio),
+            "voice" | "voices" => Some(Self::Voice),
+            "video_note" | "round" => Some(Self::VideoNote),
+            "sticker" | "stickers" => Some(Self::Sticker),
+            "animation" | "gif" | "gifs" => Some(Self::Animation),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Attachment and forward metadata sent alongside a message. Every
+/// field is optional; the shell fills in what Telegram reports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageMedia {
+    pub kind: Option<MediaKind>,
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub caption: Option<String>,
+    /// Display name of the original author or channel of a forward.
+    pub forward_from_name: Option<String>,
+    pub forward_from_chat_id: Option<i64>,
+    pub forward_from_message_id: Option<i64>,
+}
+
+impl MessageMedia {
+    /// Caption and file name, the text indexed in `text_media`.
+    pub(crate) fn search_text(media: Option<&Self>) -> String {
+        let Some(media) = media else {
+            return String::new();
+        };
+        [&media.caption, &media.file_name]
+            .into_iter()
+            .flatten()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Replace the stored media row of one message. `None` and an
+/// all-empty [`MessageMedia`] both leave no row. Runs inside the
+/// caller's transaction.
+pub(crate) fn write_media(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+    media: Option<&MessageMedia>,
+) -> Result<(), sqlite::Error> {
+    delete_media(conn, account_id, chat_id, message_id)?;
+    let Some(media) = media.filter(|m| **m != MessageMedia::default()) else {
+        return Ok(());
+    };
+    let mut stmt = conn.prepare(
+        "INSERT INTO message_media
+            (chat_id, message_id, kind, file_name, mime_type, size_bytes, caption,
+             forward_from_name, forward_from_chat_id, forward_from_message_id, account_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, chat_id))?;
+    stmt.bind((2, message_id))?;
+    stmt.bind((3, media.kind.map(MediaKind::as_str)))?;
+    stmt.bind((4, media.file_name.as_deref()))?;
+    stmt.bind((5, media.mime_type.as_deref()))?;
+    stmt.bind((6, media.size_bytes))?;
+    stmt.bind((7, media.caption.as_deref()))?;
+    stmt.bind((8, media.forward_from_name.as_deref()))?;
+    stmt.bind((9, media.forward_from_chat_id))?;
+    stmt.bind((10, media.forward_from_message_id))?;
+    stmt.bind((11, account_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub(crate) fn delete_media(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "DELETE FROM message_media WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub(crate) fn read_media(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<Option<MessageMedia>, sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT kind, file_name, mime_type, size_bytes, caption,
+                forward_from_name, forward_from_chat_id, forward_from_message_id
+         FROM message_media WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    if let sqlite::State::Row = stmt.next()? {
+        Ok(Some(MessageMedia {
+            kind: stmt
+                .read::<Option<String>, _>(0)?
+                .as_deref()
+                .and_then(MediaKind::parse),
+            file_name: stmt.read::<Option<String>, _>(1)?,
+            mime_type: stmt.read::<Option<String>, _>(2)?,
+            size_bytes: stmt.read::<Option<i64>, _>(3)?,
+            caption: stmt.read::<Option<String>, _>(4)?,
+            forward_from_name: stmt.read::<Option<String>, _>(5)?,
+            forward_from_chat_id: stmt.read::<Option<i64>, _>(6)?,
+            forward_from_message_id: stmt.read::<Option<i64>, _>(7)?,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+impl Store {
+    /// Attachment and forward metadata of one message, if it has any.
+    pub fn message_media(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Option<MessageMedia>, sqlite::Error> {
+        read_media(&self.conn, account_id, chat_id, message_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+    fn insert(store: &Store, text: &str, media: Option<MessageMedia>) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id: 1,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1000,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    #[test]
+    fn media_follows_edits_and_deletes() {
+        let store = Store::open_in_memory().unwrap();
+        let forwarded = MessageMedia {
+            kind: Some(MediaKind::Video),
+            caption: Some("#airdrop 영상".into()),
+            size_bytes: Some(1 << 20),
+            forward_from_name: Some("코인 뉴스".into()),
+            forward_from_chat_id: Some(-100),
+            forward_from_message_id: Some(7),
+            ..Default::default()
+        };
+        insert(&store, "", Some(forwarded.clone()));
+        assert_eq!(store.message_media(0, 1, 1).unwrap(), Some(forwarded));
+        // Captions are scanned for entities like the text is.
+        assert_eq!(store.message_entities(0, 1, 1).unwrap().len(), 1);
+        let message = store.get_message(0, 1, 1).unwrap().unwrap();
+        assert_eq!(message.media.unwrap().kind, Some(MediaKind::Video));
+
+        // An edit that drops the attachment clears the row and the index.
+        insert(&store, "텍스트만", Some(MessageMedia::default()));
+        assert_eq!(store.message_media(0, 1, 1).unwrap(), None);
+        assert!(store.message_entities(0, 1, 1).unwrap().is_empty());
+        let text_media: String = {
+            let mut stmt = store
+                .conn
+                .prepare("SELECT text_media FROM messages WHERE chat_id = 1 AND message_id = 1")
+                .unwrap();
+            stmt.next().unwrap();
+            stmt.read(0).unwrap()
+        };
+        assert_eq!(text_media, "");
+
+        insert(
+            &store,
+            "텍스트만",
+            Some(MessageMedia {
+                kind: Some(MediaKind::Sticker),
+                ..Default::default()
+            }),
+        );
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id: 1,
+            }])
+            .unwrap();
+        // Deleting keeps the row for undo; the purge removes it.
+        assert!(store.message_media(0, 1, 1).unwrap().is_some());
+        store
+            .purge_tombstones(crate::wiki::norm::unix_now() + 31 * 86_400)
+            .unwrap();
+        assert_eq!(store.message_media(0, 1, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn search_text_joins_caption_and_file_name() {
+        let media = MessageMedia {
+            caption: Some(" 3분기 실적 ".into()),
+            file_name: Some("ir.pdf".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            MessageMedia::search_text(Some(&media)),
+            "3분기 실적\nir.pdf"
+        );
+        assert_eq!(MessageMedia::search_text(None), "");
+        assert_eq!(MediaKind::parse("GIF"), Some(MediaKind::Animation));
+        assert_eq!(MediaKind::parse("pdf"), None);
+    }
+}
@@ -1,22 +1,68 @@
 use serde::{Deserialize, Serialize};
 
+use super::entity::{index_entities, EntityKind};
+use super::media::{read_media, write_media, MediaKind, MessageMedia};
+use super::outbox::enqueue_message;
+use super::recon::note_message_seen;
+use super::revision::record_revision;
+use super::saved_search::{evaluate_saved_searches, SavedSearchMatch};
+use super::sender::{name_key, record_sender};
+use super::vocab::{adjust_vocab, vocab_words};
 use super::Store;
 
+/// The text columns mirrored into `messages_fts`, in index order.
+/// Everything but `plain` and `stripped` is derived at index time;
+/// `media` is the caption and file name, which the Korean forms cover
+/// too so a short Hangul query finds a caption like it finds text.
+#[derive(Debug, Clone, PartialEq)]
+struct SearchText {
+    plain: String,
+    stripped: String,
+    jamo: String,
+    choseong: String,
+    stem: String,
+    roman: String,
+    media: String,
+}
+
+impl SearchText {
+    fn derive(plain: &str, stripped: &str, media: Option<&MessageMedia>) -> Self {
+        let media = MessageMedia::search_text(media);
+        let all = if media.is_empty() {
+            plain.to_string()
+        } else {
+            format!("{plain}\n{media}")
+        };
+        Self {
+            plain: plain.to_string(),
+            stripped: stripped.to_string(),
+            jamo: crate::search::hangul::decompose_jamo(&all),
+            choseong: crate::search::hangul::to_choseong(&all),
+            stem: crate::search::hangul::stem_korean(&all),
+            roman: crate::search::hangul::romanize(&all),
+            media,
+        }
+    }
+}
+
 fn fts_insert(
     conn: &sqlite::Connection,
     rowid: i64,
-    text_plain: &str,
-    text_stripped: &str,
-    text_jamo: &str,
+    text: &SearchText,
 ) -> Result<(), sqlite::Error> {
     let mut stmt = conn.prepare(
-        "INSERT INTO messages_fts(rowid, text_plain, text_stripped, text_jamo)
-         VALUES (?, ?, ?, ?)",
+        "INSERT INTO messages_fts(rowid, text_plain, text_stripped, text_jamo, text_choseong,
+                                  text_stem, text_roman, text_media)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )?;
     stmt.bind((1, rowid))?;
-    stmt.bind((2, text_plain))?;
-    stmt.bind((3, text_stripped))?;
-    stmt.bind((4, text_jamo))?;
+    stmt.bind((2, text.plain.as_str()))?;
+    stmt.bind((3, text.stripped.as_str()))?;
+    stmt.bind((4, text.jamo.as_str()))?;
+    stmt.bind((5, text.choseong.as_str()))?;
+    stmt.bind((6, text.stem.as_str()))?;
+    stmt.bind((7, text.roman.as_str()))?;
+    stmt.bind((8, text.media.as_str()))?;
     stmt.next()?;
     Ok(())
 }
@@ -24,18 +70,21 @@ fn fts_insert(
 fn fts_delete(
     conn: &sqlite::Connection,
     rowid: i64,
-    text_plain: &str,
-    text_stripped: &str,
-    text_jamo: &str,
+    text: &SearchText,
 ) -> Result<(), sqlite::Error> {
     let mut stmt = conn.prepare(
-        "INSERT INTO messages_fts(messages_fts, rowid, text_plain, text_stripped, text_jamo)
-         VALUES('delete', ?, ?, ?, ?)",
+        "INSERT INTO messages_fts(messages_fts, rowid, text_plain, text_stripped, text_jamo,
+                                  text_choseong, text_stem, text_roman, text_media)
+         VALUES('delete', ?, ?, ?, ?, ?, ?, ?, ?)",
     )?;
     stmt.bind((1, rowid))?;
-    stmt.bind((2, text_plain))?;
-    stmt.bind((3, text_stripped))?;
-    stmt.bind((4, text_jamo))?;
+    stmt.bind((2, text.plain.as_str()))?;
+    stmt.bind((3, text.stripped.as_str()))?;
+    stmt.bind((4, text.jamo.as_str()))?;
+    stmt.bind((5, text.choseong.as_str()))?;
+    stmt.bind((6, text.stem.as_str()))?;
+    stmt.bind((7, text.roman.as_str()))?;
+    stmt.bind((8, text.media.as_str()))?;
     stmt.next()?;
     Ok(())
 }
@@ -43,22 +92,65 @@ fn fts_delete(
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageRow {
     pub message_id: i64,
+    /// Telegram account the message was mirrored from; see
+    /// [`crate::store::chat::ChatRow::account_id`].
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub timestamp: i64,
     pub text_plain: String,
     pub text_stripped: String,
     pub link: Option<String>,
     pub sender_id: i64,
+    /// Attachment and forward metadata, if any.
+    #[serde(default)]
+    pub media: Option<MessageMedia>,
+    /// The message this one replies to, in the same chat.
+    #[serde(default)]
+    pub reply_to_message_id: Option<i64>,
+    /// Forum topic or discussion thread the message belongs to, by the
+    /// id of the thread's root message.
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    /// Sender's display name and username as of this message; recorded
+    /// in the sender directory (see [`crate::store::sender`]).
+    #[serde(default)]
+    pub sender_name: Option<String>,
+    #[serde(default)]
+    pub sender_username: Option<String>,
+}
+
+impl MessageRow {
+    /// Message text plus caption: what entities are extracted from.
+    fn entity_text(&self) -> String {
+        match self.media.as_ref().and_then(|m| m.caption.as_deref()) {
+            Some(caption) => format!("{}\n{caption}", self.text_plain),
+            None => self.text_plain.clone(),
+        }
+    }
+
+    fn message_ref(&self) -> MessageRef {
+        MessageRef {
+            account_id: self.account_id,
+            chat_id: self.chat_id,
+            message_id: self.message_id,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct IndexOutcome {
     pub inserted: u64,
     pub updated: u64,
+    /// Saved searches that picked up new matches from this batch.
+    #[serde(default)]
+    pub saved_search_matches: Vec<SavedSearchMatch>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageRef {
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
 }
@@ -66,21 +158,377 @@ pub struct MessageRef {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageWithChat {
     pub message_id: i64,
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub timestamp: i64,
     pub text_plain: String,
     pub link: Option<String>,
     pub chat_title: String,
     pub rank: f64,
+    /// Current display name of the sender, when known.
+    #[serde(default)]
+    pub sender_name: Option<String>,
+    /// Set when only an earlier version of the message matched (see
+    /// [`SearchFilters::include_revisions`]): the newest such
+    /// `msg_version`. `text_plain` is still the current text.
+    #[serde(default)]
+    pub matched_revision: Option<i64>,
 }
 
+/// Keyset position after the last row of a page. Only valid with the
+/// [`SearchSort`] that produced it; `rank` is ignored by the time
+/// orders.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cursor {
     #[serde(default)]
     pub rank: f64,
     pub timestamp: i64,
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
+    /// Reference time of [`SearchSort::RelevanceWithDecay`] ranks, fixed
+    /// by the first page so later pages compute identical ranks.
+    #[serde(default)]
+    pub decay_anchor: Option<i64>,
+}
+
+/// Result order. Ties always break on newest first, then account,
+/// chat and message id, so every mode paginates stably.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SearchSort {
+    /// BM25 with a slight linear preference for newer messages.
+    #[default]
+    Relevance,
+    Newest,
+    Oldest,
+    /// BM25 halved for every `half_life_days` of age.
+    RelevanceWithDecay {
+        half_life_days: f64,
+    },
+}
+
+impl SearchSort {
+    /// The LIKE fallback has no rank; relevance modes degrade to newest.
+    fn unranked(self) -> Self {
+        match self {
+            Self::Oldest => Self::Oldest,
+            _ => Self::Newest,
+        }
+    }
+
+    fn is_ranked(self) -> bool {
+        matches!(self, Self::Relevance | Self::RelevanceWithDecay { .. })
+    }
+
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Relevance | Self::RelevanceWithDecay { .. } => {
+                "r.rank ASC, m.timestamp DESC, m.account_id ASC, m.chat_id ASC, m.message_id ASC"
+            }
+            Self::Newest => "m.timestamp DESC, m.account_id ASC, m.chat_id ASC, m.message_id ASC",
+            Self::Oldest => "m.timestamp ASC, m.account_id ASC, m.chat_id ASC, m.message_id ASC",
+        }
+    }
+
+    /// `AND ...` fragment selecting rows after `cursor` in this order.
+    fn keyset_clause(self) -> &'static str {
+        match self {
+            Self::Relevance | Self::RelevanceWithDecay { .. } => {
+                "AND (r.rank > ?
+                  OR (r.rank = ? AND m.timestamp < ?)
+                  OR (r.rank = ? AND m.timestamp = ?
+                      AND (m.account_id, m.chat_id, m.message_id) > (?, ?, ?)))"
+            }
+            Self::Newest => {
+                "AND (m.timestamp < ?
+                  OR (m.timestamp = ? AND (m.account_id, m.chat_id, m.message_id) > (?, ?, ?)))"
+            }
+            Self::Oldest => {
+                "AND (m.timestamp > ?
+                  OR (m.timestamp = ? AND (m.account_id, m.chat_id, m.message_id) > (?, ?, ?)))"
+            }
+        }
+    }
+
+    fn bind_keyset(
+        self,
+        stmt: &mut sqlite::Statement<'_>,
+        bind_idx: &mut usize,
+        c: &Cursor,
+    ) -> Result<(), sqlite::Error> {
+        let mut values: Vec<sqlite::Value> = Vec::with_capacity(8);
+        let ranked = self.is_ranked();
+        let rank = || sqlite::Value::Float(c.rank);
+        if ranked {
+            values.push(rank());
+            values.push(rank());
+        }
+        values.push(sqlite::Value::Integer(c.timestamp));
+        if ranked {
+            values.push(rank());
+        }
+        values.push(sqlite::Value::Integer(c.timestamp));
+        values.push(sqlite::Value::Integer(c.account_id));
+        values.push(sqlite::Value::Integer(c.chat_id));
+        values.push(sqlite::Value::Integer(c.message_id));
+        for value in values {
+            stmt.bind((*bind_idx, value))?;
+            *bind_idx += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Hit counts over every message a query matches, not just one page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub total: u64,
+    /// Most hits first.
+    pub by_chat: Vec<ChatFacet>,
+    /// Oldest day first; days without hits are omitted.
+    pub by_day: Vec<DayFacet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatFacet {
+    #[serde(default)]
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub chat_title: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayFacet {
+    /// Unix seconds at 00:00 UTC of the day.
+    pub day_start: i64,
+    pub count: u64,
+}
+
+/// The match a facet count covers: the MATCH expression or the LIKE
+/// match the page query ran with.
+#[derive(Debug, Clone, Copy)]
+pub enum FacetSource<'a> {
+    Fts(&'a str),
+    Like(Option<&'a LikeMatch>),
+}
+
+/// Narrowing applied on top of the text match. Empty fields do not
+/// restrict; non-empty sets match any member, except the entity fields
+/// (`has` onwards), where every listed entity must be present.
+/// `since`/`until` are unix seconds, both inclusive.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub sender_ids: Vec<i64>,
+    pub chat_ids: Vec<i64>,
+    /// Accounts to search; empty searches every account in the store.
+    pub account_ids: Vec<i64>,
+    /// `group`, `supergroup`, `channel` or `dm`.
+    pub chat_types: Vec<String>,
+    /// Kinds of entity the message must carry (`has:link`).
+    pub has: Vec<EntityKind>,
+    /// Link hosts (`domain:x.com`); subdomains match too.
+    pub domains: Vec<String>,
+    /// Hashtags (`tag:airdrop`), with or without `#`.
+    pub tags: Vec<String>,
+    /// Usernames (`mention:durov`), with or without `@`.
+    pub mentions: Vec<String>,
+    /// Tickers (`cashtag:btc`), with or without `$`.
+    pub cashtags: Vec<String>,
+    /// Attachment kinds (`has:photo`, `type:document`); any of them.
+    pub media_kinds: Vec<MediaKind>,
+    /// Sender names or usernames (`from:김철수`); any of them. Part of
+    /// any name the sender has used matches, compared by jamo.
+    pub from: Vec<String>,
+    /// Set from [`crate::search::engine::SearchScope::Thread`]: only
+    /// the thread's root and the messages in it. Meaningful with a
+    /// chat scope only, so it is not part of the wire format.
+    #[serde(skip)]
+    pub(crate) thread_id: Option<i64>,
+    /// Also match text that edits have since replaced. Hits found only
+    /// that way carry [`MessageWithChat::matched_revision`]. Facet
+    /// counts cover current text only.
+    pub include_revisions: bool,
+}
+
+/// Correlates a `message_entities e` subquery with `messages m`.
+const ENTITY_EXISTS: &str = "SELECT 1 FROM message_entities e
+                 WHERE e.account_id = m.account_id AND e.chat_id = m.chat_id
+                 AND e.message_id = m.message_id";
+
+impl SearchFilters {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// True if any entity, media or `from` field is set. Such filters
+    /// select messages on their own, so a query of nothing but
+    /// `tag:airdrop`, `type:document` or `from:김철수` still lists
+    /// results.
+    pub fn has_content_filters(&self) -> bool {
+        !(self.has.is_empty()
+            && self.domains.is_empty()
+            && self.tags.is_empty()
+            && self.mentions.is_empty()
+            && self.cashtags.is_empty()
+            && self.media_kinds.is_empty()
+            && self.from.is_empty())
+    }
+
+    /// Entity values in stored form, one per placeholder of
+    /// [`SearchFilters::sql_clause`]. A value that normalizes to
+    /// nothing binds as `''` and matches nothing.
+    fn entity_values(&self) -> impl Iterator<Item = (EntityKind, String)> + '_ {
+        [
+            (EntityKind::Hashtag, &self.tags),
+            (EntityKind::Mention, &self.mentions),
+            (EntityKind::Cashtag, &self.cashtags),
+        ]
+        .into_iter()
+        .flat_map(|(kind, values)| {
+            values
+                .iter()
+                .map(move |v| (kind, kind.normalize(v).unwrap_or_default()))
+        })
+    }
+
+    /// `AND ...` fragment over `messages m JOIN chats c`; bind with
+    /// [`SearchFilters::bind`] at the matching position.
+    pub(crate) fn sql_clause(&self) -> String {
+        fn placeholders(n: usize) -> String {
+            vec!["?"; n].join(", ")
+        }
+        let mut clause = String::new();
+        if self.since.is_some() {
+            clause.push_str(" AND m.timestamp >= ?");
+        }
+        if self.until.is_some() {
+            clause.push_str(" AND m.timestamp <= ?");
+        }
+        if !self.sender_ids.is_empty() {
+            clause.push_str(&format!(
+                " AND m.sender_id IN ({})",
+                placeholders(self.sender_ids.len())
+            ));
+        }
+        if !self.chat_ids.is_empty() {
+            clause.push_str(&format!(
+                " AND m.chat_id IN ({})",
+                placeholders(self.chat_ids.len())
+            ));
+        }
+        if !self.account_ids.is_empty() {
+            clause.push_str(&format!(
+                " AND m.account_id IN ({})",
+                placeholders(self.account_ids.len())
+            ));
+        }
+        if !self.chat_types.is_empty() {
+            clause.push_str(&format!(
+                " AND c.chat_type IN ({})",
+                placeholders(self.chat_types.len())
+            ));
+        }
+        for _ in &self.has {
+            clause.push_str(&format!(" AND EXISTS ({ENTITY_EXISTS} AND e.kind = ?)"));
+        }
+        for _ in &self.domains {
+            clause.push_str(&format!(
+                " AND EXISTS ({ENTITY_EXISTS} AND e.kind = 'link'
+                     AND (e.domain = ? OR e.domain GLOB ?))"
+            ));
+        }
+        for _ in self.entity_values() {
+            clause.push_str(&format!(
+                " AND EXISTS ({ENTITY_EXISTS} AND e.kind = ? AND e.value = ?)"
+            ));
+        }
+        if self.thread_id.is_some() {
+            clause.push_str(" AND (m.thread_id = ? OR m.message_id = ?)");
+        }
+        if !self.from.is_empty() {
+            let names = vec!["sn.name_jamo LIKE '%' || ? || '%'"; self.from.len()].join(" OR ");
+            let usernames = placeholders(self.from.len());
+            clause.push_str(&format!(
+                " AND m.sender_id IN (SELECT sn.sender_id FROM sender_names sn WHERE {names}
+                     UNION SELECT s.sender_id FROM senders s WHERE s.username IN ({usernames}))"
+            ));
+        }
+        if !self.media_kinds.is_empty() {
+            clause.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM message_media md
+                     WHERE md.account_id = m.account_id AND md.chat_id = m.chat_id
+                     AND md.message_id = m.message_id
+                     AND md.kind IN ({}))",
+                placeholders(self.media_kinds.len())
+            ));
+        }
+        clause
+    }
+
+    pub(crate) fn bind(
+        &self,
+        stmt: &mut sqlite::Statement<'_>,
+        bind_idx: &mut usize,
+    ) -> Result<(), sqlite::Error> {
+        for ts in self.since.iter().chain(&self.until) {
+            stmt.bind((*bind_idx, *ts))?;
+            *bind_idx += 1;
+        }
+        for id in self
+            .sender_ids
+            .iter()
+            .chain(&self.chat_ids)
+            .chain(&self.account_ids)
+        {
+            stmt.bind((*bind_idx, *id))?;
+            *bind_idx += 1;
+        }
+        for chat_type in &self.chat_types {
+            stmt.bind((*bind_idx, chat_type.as_str()))?;
+            *bind_idx += 1;
+        }
+        for kind in &self.has {
+            stmt.bind((*bind_idx, kind.as_str()))?;
+            *bind_idx += 1;
+        }
+        for domain in &self.domains {
+            let host = EntityKind::Link.normalize(domain).unwrap_or_default();
+            stmt.bind((*bind_idx, host.as_str()))?;
+            stmt.bind((*bind_idx + 1, format!("*.{host}").as_str()))?;
+            *bind_idx += 2;
+        }
+        for (kind, value) in self.entity_values() {
+            stmt.bind((*bind_idx, kind.as_str()))?;
+            stmt.bind((*bind_idx + 1, value.as_str()))?;
+            *bind_idx += 2;
+        }
+        if let Some(thread_id) = self.thread_id {
+            stmt.bind((*bind_idx, thread_id))?;
+            stmt.bind((*bind_idx + 1, thread_id))?;
+            *bind_idx += 2;
+        }
+        for name in &self.from {
+            stmt.bind((*bind_idx, name_key(name).as_str()))?;
+            *bind_idx += 1;
+        }
+        for name in &self.from {
+            let username = name.trim().trim_start_matches('@').to_lowercase();
+            stmt.bind((*bind_idx, username.as_str()))?;
+            *bind_idx += 1;
+        }
+        for kind in &self.media_kinds {
+            stmt.bind((*bind_idx, kind.as_str()))?;
+            *bind_idx += 1;
+        }
+        Ok(())
+    }
 }
 
 pub fn strip_whitespace(text: &str) -> String {
@@ -89,6 +537,7 @@ pub fn strip_whitespace(text: &str) -> String {
 
 fn enqueue_wiki_classify(
     conn: &sqlite::Connection,
+    account_id: i64,
     chat_id: i64,
     message_id: i64,
     text_plain: &str,
@@ -103,10 +552,11 @@ fn enqueue_wiki_classify(
     let existing: Option<(String, Vec<u8>)> = {
         let mut stmt = conn.prepare(
             "SELECT status, text_hash FROM wiki_classify_queue_v2
-             WHERE msg_id = ? AND chat_id = ?",
+             WHERE msg_id = ? AND chat_id = ? AND account_id = ?",
         )?;
         stmt.bind((1, message_id))?;
         stmt.bind((2, chat_id))?;
+        stmt.bind((3, account_id))?;
         if let sqlite::State::Row = stmt.next()? {
             Some((stmt.read::<String, _>(0)?, stmt.read::<Vec<u8>, _>(1)?))
         } else {
@@ -119,14 +569,15 @@ fn enqueue_wiki_classify(
             let mut stmt = conn.prepare(
                 "INSERT INTO wiki_classify_queue_v2
                     (msg_id, chat_id, status, attempts, text_hash,
-                     enqueued_at, next_attempt_at)
-                 VALUES (?, ?, 'pending', 0, ?, ?, ?)",
+                     enqueued_at, next_attempt_at, account_id)
+                 VALUES (?, ?, 'pending', 0, ?, ?, ?, ?)",
             )?;
             stmt.bind((1, message_id))?;
             stmt.bind((2, chat_id))?;
             stmt.bind((3, text_hash.as_slice()))?;
             stmt.bind((4, now))?;
             stmt.bind((5, now))?;
+            stmt.bind((6, account_id))?;
             stmt.next()?;
         }
         Some((_, prior_hash)) if prior_hash == text_hash => {
@@ -137,13 +588,14 @@ fn enqueue_wiki_classify(
             let mut stmt = conn.prepare(
                 "UPDATE wiki_classify_queue_v2
                     SET text_hash = ?, next_attempt_at = ?, enqueued_at = ?
-                  WHERE msg_id = ? AND chat_id = ?",
+                  WHERE msg_id = ? AND chat_id = ? AND account_id = ?",
             )?;
             stmt.bind((1, text_hash.as_slice()))?;
             stmt.bind((2, now))?;
             stmt.bind((3, now))?;
             stmt.bind((4, message_id))?;
             stmt.bind((5, chat_id))?;
+            stmt.bind((6, account_id))?;
             stmt.next()?;
         }
         Some(_) => {
@@ -154,27 +606,273 @@ fn enqueue_wiki_classify(
                         hint = NULL, hint_page_id = NULL,
                         text_hash = ?, claimed_at = NULL,
                         next_attempt_at = ?, enqueued_at = ?
-                  WHERE msg_id = ? AND chat_id = ?",
+                  WHERE msg_id = ? AND chat_id = ? AND account_id = ?",
             )?;
             stmt.bind((1, text_hash.as_slice()))?;
             stmt.bind((2, now))?;
             stmt.bind((3, now))?;
             stmt.bind((4, message_id))?;
             stmt.bind((5, chat_id))?;
+            stmt.bind((6, account_id))?;
             stmt.next()?;
         }
     }
     Ok(())
 }
 
-fn like_variants(term: &str) -> [String; 3] {
+fn like_variants(term: &str) -> [String; 7] {
+    // Only choseong-shaped terms probe the choseong column; for any
+    // other term the plain text stands in, which can only match there
+    // when it also matches text_stripped.
+    let choseong = if crate::search::hangul::is_choseong_query(term) {
+        crate::search::hangul::to_choseong(term)
+    } else {
+        term.to_string()
+    };
     [
         term.to_string(),
         strip_whitespace(term),
         crate::search::hangul::decompose_jamo(term),
+        choseong,
+        crate::search::hangul::stem_korean(term),
+        crate::search::hangul::romanize(&crate::search::hangul::stem_korean(term)),
+        term.to_string(),
     ]
 }
 
+/// One term against every searchable column of the row aliased `alias`
+/// (`messages` or `message_revisions`, which share column names).
+fn like_any_column(alias: &str) -> String {
+    format!(
+        "({alias}.text_plain LIKE '%' || ? || '%'
+                  OR {alias}.text_stripped LIKE '%' || ? || '%'
+                  OR {alias}.text_jamo LIKE '%' || ? || '%'
+                  OR {alias}.text_choseong LIKE '%' || ? || '%'
+                  OR {alias}.text_stem LIKE '%' || ? || '%'
+                  OR {alias}.text_roman LIKE '%' || ? || '%'
+                  OR {alias}.text_media LIKE '%' || ? || '%')"
+    )
+}
+
+/// Literal text and its jamo form against the row aliased `alias`: what
+/// a negated term excludes. The stem and choseong variants are recall
+/// aids for finding messages and would exclude far more than was typed.
+fn like_literal_columns(alias: &str) -> String {
+    format!(
+        "({alias}.text_plain LIKE '%' || ? || '%'
+                  OR {alias}.text_stripped LIKE '%' || ? || '%'
+                  OR {alias}.text_jamo LIKE '%' || ? || '%'
+                  OR {alias}.text_media LIKE '%' || ? || '%')"
+    )
+}
+
+/// Text match for the LIKE fallback, shaped like the parsed query so
+/// `OR` and `-` mean what they do on the FTS path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LikeMatch {
+    /// `text` in any searchable column, or only in `column` (already
+    /// normalized into its alphabet) when the term was pinned.
+    Term {
+        text: String,
+        column: Option<&'static str>,
+    },
+    Not(Box<LikeMatch>),
+    And(Vec<LikeMatch>),
+    Or(Vec<LikeMatch>),
+}
+
+impl LikeMatch {
+    /// An unpinned term.
+    pub fn term(text: &str) -> Self {
+        Self::Term {
+            text: text.to_string(),
+            column: None,
+        }
+    }
+
+    /// True if some message must contain a term for this to match; a
+    /// purely negative match would page through the whole archive.
+    pub fn has_positive(&self) -> bool {
+        self.has_positive_under(false)
+    }
+
+    fn has_positive_under(&self, negated: bool) -> bool {
+        match self {
+            Self::Term { .. } => !negated,
+            Self::Not(inner) => inner.has_positive_under(!negated),
+            Self::And(nodes) | Self::Or(nodes) => {
+                nodes.iter().any(|n| n.has_positive_under(negated))
+            }
+        }
+    }
+
+    /// WHERE fragment over the row aliased `alias` (`messages` or
+    /// `message_revisions`, which share column names) and the values to
+    /// bind into it, in order.
+    pub(crate) fn sql(&self, alias: &str) -> (String, Vec<String>) {
+        let mut params = Vec::new();
+        let sql = self.sql_into(alias, false, &mut params);
+        (sql, params)
+    }
+
+    fn sql_into(&self, alias: &str, negated: bool, params: &mut Vec<String>) -> String {
+        match self {
+            Self::Term {
+                text,
+                column: Some(column),
+            } => {
+                params.push(text.clone());
+                format!("{alias}.{column} LIKE '%' || ? || '%'")
+            }
+            Self::Term { text, column: None } if negated => {
+                params.extend([
+                    text.clone(),
+                    strip_whitespace(text),
+                    crate::search::hangul::decompose_jamo(text),
+                    text.clone(),
+                ]);
+                like_literal_columns(alias)
+            }
+            Self::Term { text, column: None } => {
+                params.extend(like_variants(text));
+                like_any_column(alias)
+            }
+            Self::Not(inner) => format!("NOT {}", inner.sql_into(alias, !negated, params)),
+            Self::And(nodes) | Self::Or(nodes) => {
+                let op = if matches!(self, Self::And(_)) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+                let parts: Vec<String> = nodes
+                    .iter()
+                    .map(|n| n.sql_into(alias, negated, params))
+                    .collect();
+                format!("({})", parts.join(op))
+            }
+        }
+    }
+}
+
+/// WHERE fragment for the LIKE fallback over `messages m`, with its
+/// bind values; see [`bind_like`]. Without a text match it matches
+/// everything, for filter-only browsing.
+pub(crate) fn like_where(text: Option<&LikeMatch>) -> (String, Vec<String>) {
+    like_where_on("m", text)
+}
+
+fn like_where_on(alias: &str, text: Option<&LikeMatch>) -> (String, Vec<String>) {
+    match text {
+        Some(text) => text.sql(alias),
+        None => ("1".to_string(), Vec::new()),
+    }
+}
+
+/// Correlates a `message_revisions r` subquery with `messages m`.
+const REVISIONS_OF_M: &str = "FROM message_revisions r
+                 WHERE r.account_id = m.account_id AND r.chat_id = m.chat_id
+                 AND r.message_id = m.message_id";
+
+pub(crate) fn bind_like(
+    stmt: &mut sqlite::Statement<'_>,
+    bind_idx: &mut usize,
+    params: &[String],
+) -> Result<(), sqlite::Error> {
+    for value in params {
+        stmt.bind((*bind_idx, value.as_str()))?;
+        *bind_idx += 1;
+    }
+    Ok(())
+}
+
+/// Page size of [`Store::thread_messages`] when the caller has none.
+pub const DEFAULT_THREAD_PAGE_SIZE: usize = 50;
+
+/// Hops [`Store::reply_chain`] follows before giving up.
+pub const MAX_REPLY_DEPTH: i64 = 1000;
+
+/// Neighbours of a context anchor walk `idx_messages_chat_timestamp`
+/// from the anchor outwards, so cost scales with `before + after`
+/// rather than with the size of the chat.
+const CONTEXT_SELECT: &str = "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link,
+                    c.title, sn.display_name, m.account_id
+             FROM messages m INDEXED BY idx_messages_chat_timestamp
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE m.account_id = ? AND m.chat_id = ?
+             AND c.is_excluded = 0 AND m.deleted_at IS NULL";
+
+fn read_context_row(stmt: &sqlite::Statement<'_>) -> Result<MessageWithChat, sqlite::Error> {
+    Ok(MessageWithChat {
+        message_id: stmt.read::<i64, _>(0)?,
+        account_id: stmt.read::<i64, _>(7)?,
+        chat_id: stmt.read::<i64, _>(1)?,
+        timestamp: stmt.read::<i64, _>(2)?,
+        text_plain: stmt.read::<String, _>(3)?,
+        link: stmt.read::<Option<String>, _>(4)?,
+        chat_title: stmt.read::<String, _>(5)?,
+        rank: 0.0,
+        sender_name: stmt.read::<Option<String>, _>(6)?,
+        matched_revision: None,
+    })
+}
+
+/// Row id and indexed text of one message: a live one when
+/// `deleted_after` is `None`, else one tombstoned after that time.
+fn read_search_text(
+    conn: &sqlite::Connection,
+    msg: &MessageRef,
+    deleted_after: Option<i64>,
+) -> Result<Option<(i64, SearchText)>, sqlite::Error> {
+    let state = match deleted_after {
+        None => "deleted_at IS NULL",
+        Some(_) => "deleted_at > ?",
+    };
+    let mut stmt = conn.prepare(format!(
+        "SELECT rowid, text_plain, text_stripped, text_jamo, text_choseong, text_stem,
+                text_roman, text_media
+         FROM messages WHERE account_id = ? AND chat_id = ? AND message_id = ? AND {state}"
+    ))?;
+    stmt.bind((1, msg.account_id))?;
+    stmt.bind((2, msg.chat_id))?;
+    stmt.bind((3, msg.message_id))?;
+    if let Some(cutoff) = deleted_after {
+        stmt.bind((4, cutoff))?;
+    }
+    if let sqlite::State::Row = stmt.next()? {
+        Ok(Some((
+            stmt.read::<i64, _>(0)?,
+            SearchText {
+                plain: stmt.read::<String, _>(1)?,
+                stripped: stmt.read::<String, _>(2)?,
+                jamo: stmt.read::<String, _>(3)?,
+                choseong: stmt.read::<String, _>(4)?,
+                stem: stmt.read::<String, _>(5)?,
+                roman: stmt.read::<String, _>(6)?,
+                media: stmt.read::<String, _>(7)?,
+            },
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// v1 wiki topics that list one message.
+fn topics_of(conn: &sqlite::Connection, msg: &MessageRef) -> Result<Vec<i64>, sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT topic_id FROM wiki_topic_messages
+         WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, msg.account_id))?;
+    stmt.bind((2, msg.chat_id))?;
+    stmt.bind((3, msg.message_id))?;
+    let mut topics = Vec::new();
+    while let sqlite::State::Row = stmt.next()? {
+        topics.push(stmt.read::<i64, _>(0)?);
+    }
+    Ok(topics)
+}
+
 impl Store {
     pub fn insert_messages_batch(
         &self,
@@ -187,39 +885,82 @@ impl Store {
         self.conn.execute("BEGIN")?;
         let result = (|| -> Result<IndexOutcome, sqlite::Error> {
             let mut outcome = IndexOutcome::default();
+            // Rows whose searchable text is new, for saved-search alerts.
+            let mut touched = Vec::new();
             // Satisfy the FK from messages.chat_id -> chats.chat_id without
             // requiring callers to call upsert_chat first. The shell (Swift)
             // mirror may upsert a richer ChatInfo later; this stub keeps
             // ingestion unblocked when it does not.
-            let mut seen_chats: std::collections::HashSet<i64> = std::collections::HashSet::new();
+            let mut seen_chats: std::collections::HashSet<(i64, i64)> =
+                std::collections::HashSet::new();
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
             for msg in messages {
-                if seen_chats.insert(msg.chat_id) {
+                if seen_chats.insert((msg.account_id, msg.chat_id)) {
                     let mut stmt = self.conn.prepare(
-                    "INSERT OR IGNORE INTO chats (chat_id, title, chat_type, username, access_hash, is_excluded)
-                     VALUES (?, '', 'dm', NULL, NULL, 0)",
+                    "INSERT OR IGNORE INTO chats (account_id, chat_id, title, chat_type, username, access_hash, is_excluded)
+                     VALUES (?, ?, '', 'dm', NULL, NULL, 0)",
                 )?;
-                    stmt.bind((1, msg.chat_id))?;
+                    stmt.bind((1, msg.account_id))?;
+                    stmt.bind((2, msg.chat_id))?;
+                    stmt.next()?;
+                    let mut stmt = self.conn.prepare(
+                        "UPDATE chats SET last_indexed_at = ? WHERE account_id = ? AND chat_id = ?",
+                    )?;
+                    stmt.bind((1, now))?;
+                    stmt.bind((2, msg.account_id))?;
+                    stmt.bind((3, msg.chat_id))?;
                     stmt.next()?;
                 }
             }
             for msg in messages {
-                let jamo = crate::search::hangul::decompose_jamo(&msg.text_plain);
+                // Names are recorded even for otherwise unchanged rows:
+                // a re-mirror is how renames reach the directory.
+                record_sender(
+                    &self.conn,
+                    msg.sender_id,
+                    msg.sender_name.as_deref(),
+                    msg.sender_username.as_deref(),
+                    msg.timestamp,
+                )?;
+                let text =
+                    SearchText::derive(&msg.text_plain, &msg.text_stripped, msg.media.as_ref());
                 let prior = {
                     let mut stmt = self.conn.prepare(
-                        "SELECT rowid, timestamp, text_plain, text_stripped, text_jamo, link, sender_id
-                         FROM messages WHERE chat_id = ? AND message_id = ?",
+                        "SELECT rowid, timestamp, text_plain, text_stripped, text_jamo, text_choseong,
+                                text_stem, text_roman, link, sender_id, text_media,
+                                reply_to_message_id, thread_id, deleted_at
+                         FROM messages WHERE account_id = ? AND chat_id = ? AND message_id = ?",
                     )?;
-                    stmt.bind((1, msg.chat_id))?;
-                    stmt.bind((2, msg.message_id))?;
+                    stmt.bind((1, msg.account_id))?;
+                    stmt.bind((2, msg.chat_id))?;
+                    stmt.bind((3, msg.message_id))?;
                     if let sqlite::State::Row = stmt.next()? {
+                        // A tombstone wins over a late re-mirror until it
+                        // is undone or purged.
+                        if stmt.read::<Option<i64>, _>(13)?.is_some() {
+                            continue;
+                        }
                         Some((
                             stmt.read::<i64, _>(0)?,
                             stmt.read::<i64, _>(1)?,
-                            stmt.read::<String, _>(2)?,
-                            stmt.read::<String, _>(3)?,
-                            stmt.read::<String, _>(4)?,
-                            stmt.read::<Option<String>, _>(5)?,
-                            stmt.read::<Option<i64>, _>(6)?,
+                            SearchText {
+                                plain: stmt.read::<String, _>(2)?,
+                                stripped: stmt.read::<String, _>(3)?,
+                                jamo: stmt.read::<String, _>(4)?,
+                                choseong: stmt.read::<String, _>(5)?,
+                                stem: stmt.read::<String, _>(6)?,
+                                roman: stmt.read::<String, _>(7)?,
+                                media: stmt.read::<String, _>(10)?,
+                            },
+                            stmt.read::<Option<String>, _>(8)?,
+                            stmt.read::<Option<i64>, _>(9)?,
+                            (
+                                stmt.read::<Option<i64>, _>(11)?,
+                                stmt.read::<Option<i64>, _>(12)?,
+                            ),
                         ))
                     } else {
                         None
@@ -231,8 +972,9 @@ impl Store {
                         let mut stmt = self.conn.prepare(
                             "INSERT INTO messages
                                 (message_id, chat_id, timestamp, text_plain, text_stripped, link,
-                                 text_jamo, sender_id)
-                             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                                 text_jamo, text_choseong, text_stem, text_roman, sender_id,
+                                 text_media, reply_to_message_id, thread_id, account_id)
+                             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                         )?;
                         stmt.bind((1, msg.message_id))?;
                         stmt.bind((2, msg.chat_id))?;
@@ -243,55 +985,78 @@ impl Store {
                             Some(l) => stmt.bind((6, l.as_str()))?,
                             None => stmt.bind((6, sqlite::Value::Null))?,
                         };
-                        stmt.bind((7, jamo.as_str()))?;
-                        stmt.bind((8, msg.sender_id))?;
+                        stmt.bind((7, text.jamo.as_str()))?;
+                        stmt.bind((8, text.choseong.as_str()))?;
+                        stmt.bind((9, text.stem.as_str()))?;
+                        stmt.bind((10, text.roman.as_str()))?;
+                        stmt.bind((11, msg.sender_id))?;
+                        stmt.bind((12, text.media.as_str()))?;
+                        stmt.bind((13, msg.reply_to_message_id))?;
+                        stmt.bind((14, msg.thread_id))?;
+                        stmt.bind((15, msg.account_id))?;
                         stmt.next()?;
 
                         let mut rowid_stmt = self.conn.prepare("SELECT last_insert_rowid()")?;
                         rowid_stmt.next()?;
                         let rowid: i64 = rowid_stmt.read(0)?;
 
-                        fts_insert(
+                        fts_insert(&self.conn, rowid, &text)?;
+                        adjust_vocab(&self.conn, &vocab_words(&text.stem), 1)?;
+                        index_entities(
                             &self.conn,
-                            rowid,
-                            &msg.text_plain,
-                            &msg.text_stripped,
-                            &jamo,
+                            msg.account_id,
+                            msg.chat_id,
+                            msg.message_id,
+                            &msg.entity_text(),
                         )?;
+                        write_media(
+                            &self.conn,
+                            msg.account_id,
+                            msg.chat_id,
+                            msg.message_id,
+                            msg.media.as_ref(),
+                        )?;
+                        touched.push(rowid);
                         enqueue_wiki_classify(
                             &self.conn,
+                            msg.account_id,
                             msg.chat_id,
                             msg.message_id,
                             &msg.text_plain,
                         )?;
+                        note_message_seen(&self.conn, msg.account_id, msg.chat_id, msg.message_id)?;
+                        enqueue_message(&self.conn, &msg.message_ref())?;
                         outcome.inserted += 1;
                     }
-                    Some((
-                        rowid,
-                        old_ts,
-                        old_plain,
-                        old_stripped,
-                        old_jamo,
-                        old_link,
-                        old_sender,
-                    )) => {
+                    Some((rowid, old_ts, old_text, old_link, old_sender, old_thread)) => {
+                        let old_media =
+                            read_media(&self.conn, msg.account_id, msg.chat_id, msg.message_id)?;
+                        let media_changed = old_media.as_ref()
+                            != msg
+                                .media
+                                .as_ref()
+                                .filter(|m| **m != MessageMedia::default());
                         if old_ts == msg.timestamp
-                            && old_plain == msg.text_plain
-                            && old_stripped == msg.text_stripped
-                            && old_jamo == jamo
+                            && old_text == text
                             && old_link == msg.link
                             && old_sender == Some(msg.sender_id)
+                            && old_thread == (msg.reply_to_message_id, msg.thread_id)
+                            && !media_changed
                         {
                             continue;
                         }
 
-                        let text_changed = old_plain != msg.text_plain
-                            || old_stripped != msg.text_stripped
-                            || old_jamo != jamo;
+                        let text_changed = old_text != text;
+                        if text_changed {
+                            record_revision(&self.conn, rowid)?;
+                        }
 
                         let mut stmt = self.conn.prepare(
                             "UPDATE messages
-                             SET timestamp = ?, text_plain = ?, text_stripped = ?, link = ?, text_jamo = ?, sender_id = ?
+                             SET timestamp = ?, text_plain = ?, text_stripped = ?, link = ?, text_jamo = ?,
+                                 text_choseong = ?, text_stem = ?, text_roman = ?, sender_id = ?,
+                                 text_media = ?, reply_to_message_id = ?, thread_id = ?,
+                                 msg_version = msg_version + 1
                              WHERE rowid = ?",
                         )?;
                         stmt.bind((1, msg.timestamp))?;
@@ -301,31 +1066,54 @@ impl Store {
                             Some(l) => stmt.bind((4, l.as_str()))?,
                             None => stmt.bind((4, sqlite::Value::Null))?,
                         };
-                        stmt.bind((5, jamo.as_str()))?;
-                        stmt.bind((6, msg.sender_id))?;
-                        stmt.bind((7, rowid))?;
+                        stmt.bind((5, text.jamo.as_str()))?;
+                        stmt.bind((6, text.choseong.as_str()))?;
+                        stmt.bind((7, text.stem.as_str()))?;
+                        stmt.bind((8, text.roman.as_str()))?;
+                        stmt.bind((9, msg.sender_id))?;
+                        stmt.bind((10, text.media.as_str()))?;
+                        stmt.bind((11, msg.reply_to_message_id))?;
+                        stmt.bind((12, msg.thread_id))?;
+                        stmt.bind((13, rowid))?;
                         stmt.next()?;
 
+                        if media_changed {
+                            write_media(
+                                &self.conn,
+                                msg.account_id,
+                                msg.chat_id,
+                                msg.message_id,
+                                msg.media.as_ref(),
+                            )?;
+                        }
+
                         if text_changed {
-                            fts_delete(&self.conn, rowid, &old_plain, &old_stripped, &old_jamo)?;
-                            fts_insert(
+                            fts_delete(&self.conn, rowid, &old_text)?;
+                            fts_insert(&self.conn, rowid, &text)?;
+                            adjust_vocab(&self.conn, &vocab_words(&old_text.stem), -1)?;
+                            adjust_vocab(&self.conn, &vocab_words(&text.stem), 1)?;
+                            index_entities(
                                 &self.conn,
-                                rowid,
-                                &msg.text_plain,
-                                &msg.text_stripped,
-                                &jamo,
+                                msg.account_id,
+                                msg.chat_id,
+                                msg.message_id,
+                                &msg.entity_text(),
                             )?;
+                            touched.push(rowid);
                             enqueue_wiki_classify(
                                 &self.conn,
+                                msg.account_id,
                                 msg.chat_id,
                                 msg.message_id,
                                 &msg.text_plain,
                             )?;
                         }
+                        enqueue_message(&self.conn, &msg.message_ref())?;
                         outcome.updated += 1;
                     }
                 }
             }
+            outcome.saved_search_matches = evaluate_saved_searches(&self.conn, &touched)?;
             Ok(outcome)
         })();
         match result {
@@ -340,6 +1128,11 @@ impl Store {
         }
     }
 
+    /// Soft-delete: each message keeps its row under a tombstone
+    /// (`deleted_at`) but leaves the search index, and wiki evidence
+    /// quoting it is hidden. [`Store::undelete_messages`] brings it back
+    /// until [`Store::purge_tombstones`] removes it for good. Returns
+    /// how many live messages were deleted.
     pub fn delete_messages(&self, refs: &[MessageRef]) -> Result<u64, sqlite::Error> {
         if refs.is_empty() {
             return Ok(0);
@@ -348,102 +1141,65 @@ impl Store {
         let _ = self.conn.execute("ROLLBACK");
         self.conn.execute("BEGIN")?;
         let result = (|| -> Result<u64, sqlite::Error> {
+            let now = crate::wiki::norm::unix_now();
             let mut deleted = 0_u64;
             let mut affected_topics: std::collections::BTreeSet<i64> =
                 std::collections::BTreeSet::new();
             for msg in refs {
-                let prior = {
-                    let mut stmt = self.conn.prepare(
-                        "SELECT rowid, text_plain, text_stripped, text_jamo
-                         FROM messages WHERE chat_id = ? AND message_id = ?",
-                    )?;
-                    stmt.bind((1, msg.chat_id))?;
-                    stmt.bind((2, msg.message_id))?;
-                    if let sqlite::State::Row = stmt.next()? {
-                        Some((
-                            stmt.read::<i64, _>(0)?,
-                            stmt.read::<String, _>(1)?,
-                            stmt.read::<String, _>(2)?,
-                            stmt.read::<String, _>(3)?,
-                        ))
-                    } else {
-                        None
-                    }
-                };
-                let Some((rowid, text_plain, text_stripped, text_jamo)) = prior else {
+                let Some((rowid, text)) = read_search_text(&self.conn, msg, None)? else {
                     continue;
                 };
 
-                fts_delete(&self.conn, rowid, &text_plain, &text_stripped, &text_jamo)?;
+                fts_delete(&self.conn, rowid, &text)?;
+                adjust_vocab(&self.conn, &vocab_words(&text.stem), -1)?;
+
+                let mut msg_stmt = self
+                    .conn
+                    .prepare("UPDATE messages SET deleted_at = ?, msg_version = msg_version + 1 WHERE rowid = ?")?;
+                msg_stmt.bind((1, now))?;
+                msg_stmt.bind((2, rowid))?;
+                msg_stmt.next()?;
+                enqueue_message(&self.conn, msg)?;
 
+                let mut evidence_stmt = self.conn.prepare(
+                    "UPDATE wiki_evidence SET hidden_at = ?
+                     WHERE account_id = ? AND chat_id = ? AND msg_id = ? AND hidden_at IS NULL",
+                )?;
+                evidence_stmt.bind((1, now))?;
+                evidence_stmt.bind((2, msg.account_id))?;
+                evidence_stmt.bind((3, msg.chat_id))?;
+                evidence_stmt.bind((4, msg.message_id))?;
+                evidence_stmt.next()?;
+
+                // The v1 queue predates accounts; its row goes with the
+                // last copy of the message.
                 let mut queue_stmt = self.conn.prepare(
-                    "DELETE FROM wiki_classify_queue WHERE chat_id = ? AND message_id = ?",
+                    "DELETE FROM wiki_classify_queue WHERE chat_id = ?1 AND message_id = ?2
+                     AND NOT EXISTS (SELECT 1 FROM messages
+                                     WHERE chat_id = ?1 AND message_id = ?2 AND account_id != ?3
+                                     AND deleted_at IS NULL)",
                 )?;
                 queue_stmt.bind((1, msg.chat_id))?;
                 queue_stmt.bind((2, msg.message_id))?;
+                queue_stmt.bind((3, msg.account_id))?;
                 queue_stmt.next()?;
 
+                // Unclassified text is not worth an LLM call any more. A
+                // `done` row stays, so an undo does not classify again.
                 let mut v2_queue_stmt = self.conn.prepare(
-                    "DELETE FROM wiki_classify_queue_v2 WHERE chat_id = ? AND msg_id = ?",
+                    "DELETE FROM wiki_classify_queue_v2
+                     WHERE account_id = ? AND chat_id = ? AND msg_id = ? AND status != 'done'",
                 )?;
-                v2_queue_stmt.bind((1, msg.chat_id))?;
-                v2_queue_stmt.bind((2, msg.message_id))?;
+                v2_queue_stmt.bind((1, msg.account_id))?;
+                v2_queue_stmt.bind((2, msg.chat_id))?;
+                v2_queue_stmt.bind((3, msg.message_id))?;
                 v2_queue_stmt.next()?;
 
-                {
-                    let mut find_stmt = self.conn.prepare(
-                        "SELECT topic_id FROM wiki_topic_messages WHERE chat_id = ? AND message_id = ?",
-                    )?;
-                    find_stmt.bind((1, msg.chat_id))?;
-                    find_stmt.bind((2, msg.message_id))?;
-                    while let sqlite::State::Row = find_stmt.next()? {
-                        affected_topics.insert(find_stmt.read::<i64, _>(0)?);
-                    }
-                }
-                let mut topic_stmt = self.conn.prepare(
-                    "DELETE FROM wiki_topic_messages WHERE chat_id = ? AND message_id = ?",
-                )?;
-                topic_stmt.bind((1, msg.chat_id))?;
-                topic_stmt.bind((2, msg.message_id))?;
-                topic_stmt.next()?;
-
-                let mut msg_stmt = self.conn.prepare("DELETE FROM messages WHERE rowid = ?")?;
-                msg_stmt.bind((1, rowid))?;
-                msg_stmt.next()?;
+                affected_topics.extend(topics_of(&self.conn, msg)?);
                 deleted += 1;
             }
             for topic_id in &affected_topics {
-                self.conn.execute(format!(
-                    "UPDATE wiki_topics SET
-                        message_count = (SELECT COUNT(*) FROM wiki_topic_messages WHERE topic_id = {0}),
-                        channel_count = (SELECT COUNT(DISTINCT chat_id) FROM wiki_topic_messages WHERE topic_id = {0}),
-                        first_seen_at = (SELECT MIN(m.timestamp) FROM wiki_topic_messages tm
-                            JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
-                            WHERE tm.topic_id = {0}),
-                        last_seen_at = (SELECT MAX(m.timestamp) FROM wiki_topic_messages tm
-                            JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
-                            WHERE tm.topic_id = {0}),
-                        updated_at = datetime('now')
-                     WHERE topic_id = {0}",
-                    topic_id
-                ))?;
-                self.conn.execute(format!(
-                    "DELETE FROM topic_stats_daily WHERE topic_id = {0};
-                     INSERT INTO topic_stats_daily (topic_id, date, msg_count)
-                     SELECT {0}, date(m.timestamp, 'unixepoch') AS d, COUNT(*)
-                     FROM wiki_topic_messages tm
-                     JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
-                     WHERE tm.topic_id = {0}
-                     GROUP BY d;
-                     DELETE FROM topic_channel_membership WHERE topic_id = {0};
-                     INSERT OR IGNORE INTO topic_channel_membership (topic_id, date, chat_id)
-                     SELECT {0}, date(m.timestamp, 'unixepoch'), m.chat_id
-                     FROM wiki_topic_messages tm
-                     JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
-                     WHERE tm.topic_id = {0};",
-                    topic_id
-                ))?;
-                self.recompute_topic_trending_score(*topic_id)?;
+                self.rebuild_topic_stats(*topic_id)?;
             }
             Ok(deleted)
         })();
@@ -459,115 +1215,395 @@ impl Store {
         }
     }
 
+    /// Undo [`Store::delete_messages`] for tombstones still inside the
+    /// retention window: the message is searchable again and its wiki
+    /// evidence reappears. Returns how many were restored.
+    pub fn undelete_messages(&self, refs: &[MessageRef]) -> Result<u64, sqlite::Error> {
+        if refs.is_empty() {
+            return Ok(0);
+        }
+        let cutoff = crate::wiki::norm::unix_now() - self.tombstone_retention_secs()?;
+
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<u64, sqlite::Error> {
+            let mut restored = 0_u64;
+            let mut affected_topics: std::collections::BTreeSet<i64> =
+                std::collections::BTreeSet::new();
+            for msg in refs {
+                let Some((rowid, text)) = read_search_text(&self.conn, msg, Some(cutoff))? else {
+                    continue;
+                };
+
+                let mut msg_stmt = self
+                    .conn
+                    .prepare("UPDATE messages SET deleted_at = NULL, msg_version = msg_version + 1 WHERE rowid = ?")?;
+                msg_stmt.bind((1, rowid))?;
+                msg_stmt.next()?;
+                enqueue_message(&self.conn, msg)?;
+                fts_insert(&self.conn, rowid, &text)?;
+                adjust_vocab(&self.conn, &vocab_words(&text.stem), 1)?;
+
+                let mut evidence_stmt = self.conn.prepare(
+                    "UPDATE wiki_evidence SET hidden_at = NULL
+                     WHERE account_id = ? AND chat_id = ? AND msg_id = ?",
+                )?;
+                evidence_stmt.bind((1, msg.account_id))?;
+                evidence_stmt.bind((2, msg.chat_id))?;
+                evidence_stmt.bind((3, msg.message_id))?;
+                evidence_stmt.next()?;
+
+                enqueue_wiki_classify(
+                    &self.conn,
+                    msg.account_id,
+                    msg.chat_id,
+                    msg.message_id,
+                    &text.plain,
+                )?;
+                affected_topics.extend(topics_of(&self.conn, msg)?);
+                restored += 1;
+            }
+            for topic_id in &affected_topics {
+                self.rebuild_topic_stats(*topic_id)?;
+            }
+            Ok(restored)
+        })();
+        match result {
+            Ok(restored) => {
+                self.conn.execute("COMMIT")?;
+                Ok(restored)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
     pub fn get_message(
         &self,
+        account_id: i64,
         chat_id: i64,
         message_id: i64,
     ) -> Result<Option<MessageRow>, sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "SELECT message_id, chat_id, timestamp, text_plain, text_stripped, link, sender_id
-             FROM messages WHERE chat_id = ? AND message_id = ? AND deleted_at IS NULL",
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.text_stripped, m.link,
+                    m.sender_id, m.reply_to_message_id, m.thread_id, sn.display_name, sn.username
+             FROM messages m
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE m.account_id = ? AND m.chat_id = ? AND m.message_id = ?
+             AND m.deleted_at IS NULL",
         )?;
-        stmt.bind((1, chat_id))?;
-        stmt.bind((2, message_id))?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, message_id))?;
         if let Ok(sqlite::State::Row) = stmt.next() {
             Ok(Some(MessageRow {
                 message_id: stmt.read::<i64, _>(0)?,
+                account_id,
                 chat_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
                 text_plain: stmt.read::<String, _>(3)?,
                 text_stripped: stmt.read::<String, _>(4)?,
                 link: stmt.read::<Option<String>, _>(5)?,
                 sender_id: stmt.read::<Option<i64>, _>(6)?.unwrap_or(0),
+                media: read_media(&self.conn, account_id, chat_id, message_id)?,
+                reply_to_message_id: stmt.read::<Option<i64>, _>(7)?,
+                thread_id: stmt.read::<Option<i64>, _>(8)?,
+                sender_name: stmt.read::<Option<String>, _>(9)?,
+                sender_username: stmt.read::<Option<String>, _>(10)?,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Up to `before` messages preceding and `after` messages following
+    /// `message_id` in its chat, with the anchor itself in between, all
+    /// oldest first. Empty when the anchor is missing, deleted or in an
+    /// excluded chat. Ties on timestamp break by message id.
+    pub fn message_context(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+        before: usize,
+        after: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
+             FROM messages m
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE m.account_id = ? AND m.chat_id = ? AND m.message_id = ?
+             AND c.is_excluded = 0 AND m.deleted_at IS NULL",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, message_id))?;
+        let anchor = match stmt.next()? {
+            sqlite::State::Row => read_context_row(&stmt)?,
+            sqlite::State::Done => return Ok(vec![]),
+        };
+
+        let before_sql = format!(
+            "{CONTEXT_SELECT} AND (m.timestamp < ? OR (m.timestamp = ? AND m.message_id < ?))
+             ORDER BY m.timestamp DESC, m.message_id DESC
+             LIMIT ?"
+        );
+        let mut out = self.context_side(&before_sql, &anchor, before)?;
+        out.reverse();
+        out.push(anchor.clone());
+
+        let after_sql = format!(
+            "{CONTEXT_SELECT} AND (m.timestamp > ? OR (m.timestamp = ? AND m.message_id > ?))
+             ORDER BY m.timestamp ASC, m.message_id ASC
+             LIMIT ?"
+        );
+        out.extend(self.context_side(&after_sql, &anchor, after)?);
+        Ok(out)
+    }
+
+    /// The replies leading to `message_id`, root first and the message
+    /// itself last. The walk stops at the first ancestor that is not
+    /// indexed or was deleted, and after [`MAX_REPLY_DEPTH`] hops so a
+    /// reply cycle in bad input cannot run away. Empty when the message
+    /// is missing, deleted or in an excluded chat.
+    pub fn reply_chain(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE chain(message_id, reply_to, depth) AS (
+                 SELECT message_id, reply_to_message_id, 0 FROM messages
+                 WHERE account_id = ?4 AND chat_id = ?1 AND message_id = ?2
+                 AND deleted_at IS NULL
+                 UNION ALL
+                 SELECT m.message_id, m.reply_to_message_id, chain.depth + 1
+                 FROM chain JOIN messages m ON m.account_id = ?4 AND m.chat_id = ?1
+                                           AND m.message_id = chain.reply_to
+                 WHERE m.deleted_at IS NULL AND chain.depth < ?3
+             )
+             SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
+             FROM chain
+             JOIN messages m ON m.account_id = ?4 AND m.chat_id = ?1
+                            AND m.message_id = chain.message_id
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE c.is_excluded = 0
+             ORDER BY chain.depth DESC",
+        )?;
+        stmt.bind((1, chat_id))?;
+        stmt.bind((2, message_id))?;
+        stmt.bind((3, MAX_REPLY_DEPTH))?;
+        stmt.bind((4, account_id))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(read_context_row(&stmt)?);
+        }
+        Ok(out)
+    }
+
+    /// Up to `limit` messages of a reply thread or forum topic in
+    /// message id order: the root `thread_id` itself and every message
+    /// filed under it. Pass the last id of a page as
+    /// `after_message_id` for the next one.
+    pub fn thread_messages(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        thread_id: i64,
+        after_message_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
+             FROM messages m
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE m.account_id = ?5 AND m.chat_id = ?1 AND (m.thread_id = ?2 OR m.message_id = ?2)
+             AND m.message_id > ?3
+             AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             ORDER BY m.message_id ASC
+             LIMIT ?4",
+        )?;
+        stmt.bind((1, chat_id))?;
+        stmt.bind((2, thread_id))?;
+        stmt.bind((3, after_message_id.unwrap_or(i64::MIN)))?;
+        stmt.bind((4, limit as i64))?;
+        stmt.bind((5, account_id))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(read_context_row(&stmt)?);
+        }
+        Ok(out)
+    }
+
+    fn context_side(
+        &self,
+        sql: &str,
+        anchor: &MessageWithChat,
+        limit: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let mut stmt = self.conn.prepare(sql)?;
+        stmt.bind((1, anchor.account_id))?;
+        stmt.bind((2, anchor.chat_id))?;
+        stmt.bind((3, anchor.timestamp))?;
+        stmt.bind((4, anchor.timestamp))?;
+        stmt.bind((5, anchor.message_id))?;
+        stmt.bind((6, limit as i64))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(read_context_row(&stmt)?);
+        }
+        Ok(out)
+    }
+
+    /// Page of FTS matches in `sort` order, in the `(account_id,
+    /// chat_id)` of `scope_chat` when given. `decay_anchor` is the
+    /// reference time (unix seconds) for
+    /// [`SearchSort::RelevanceWithDecay`] and ignored otherwise.
+    #[allow(clippy::too_many_arguments)]
     pub fn search_messages_bm25(
         &self,
         fts_query: &str,
-        scope_chat: Option<i64>,
+        scope_chat: Option<(i64, i64)>,
+        filters: &SearchFilters,
+        sort: SearchSort,
+        decay_anchor: i64,
         cursor: Option<&Cursor>,
         limit: usize,
     ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
         let chat_clause = if scope_chat.is_some() {
-            "AND m.chat_id = ?"
+            "AND m.account_id = ? AND m.chat_id = ?"
         } else {
             ""
         };
+        let filter_clause = filters.sql_clause();
         let cursor_clause = if cursor.is_some() {
-            "AND (r.rank > ?
-                  OR (r.rank = ? AND m.timestamp < ?)
-                  OR (r.rank = ? AND m.timestamp = ? AND m.chat_id > ?)
-                  OR (r.rank = ? AND m.timestamp = ? AND m.chat_id = ? AND m.message_id > ?))"
+            sort.keyset_clause()
         } else {
             ""
         };
+        // No exp()/pow() without SQLite's optional math functions, so
+        // 2^-age is an integer shift for whole half-lives times a
+        // polynomial for the fraction (error < 1e-4). Age is clamped to
+        // 62 half-lives, past which every rank is effectively zero.
+        let rank_expr = match sort {
+            SearchSort::RelevanceWithDecay { .. } => {
+                "s.score / (1 << CAST(s.age AS INTEGER))
+                   * (1.0 - (s.age - CAST(s.age AS INTEGER))
+                        * (0.6931472 - (s.age - CAST(s.age AS INTEGER))
+                        * (0.2402265 - (s.age - CAST(s.age AS INTEGER))
+                        * (0.0555041 - (s.age - CAST(s.age AS INTEGER)) * 0.0096181))))"
+            }
+            _ => "s.score - (s.timestamp / 86400.0) * 0.05",
+        };
+        let half_life_secs = match sort {
+            SearchSort::RelevanceWithDecay { half_life_days } => {
+                (half_life_days * 86400.0).max(1.0)
+            }
+            _ => 86400.0,
+        };
+
+        // Earlier versions score against their own index; a message
+        // matched by both itself and a revision keeps its best score
+        // and counts as a current-text hit.
+        let (revision_arm, merged) = if filters.include_revisions {
+            (
+                "UNION ALL
+                 SELECT m.rowid,
+                        m.timestamp,
+                        bm25(revisions_fts, 1.0, 0.7, 0.5, 0.3, 0.6, 0.2, 0.6),
+                        MIN(MAX((? - m.timestamp) / ?, 0.0), 62.0),
+                        mr.msg_version
+                 FROM revisions_fts rf
+                 JOIN message_revisions mr ON mr.revision_id = rf.rowid
+                 JOIN messages m ON m.account_id = mr.account_id AND m.chat_id = mr.chat_id
+                                AND m.message_id = mr.message_id
+                 WHERE revisions_fts MATCH ? AND m.deleted_at IS NULL",
+                "(SELECT rowid, MAX(timestamp) AS timestamp, MIN(score) AS score,
+                         MAX(age) AS age,
+                         CASE WHEN COUNT(revision) = COUNT(*) THEN MAX(revision) END AS revision
+                  FROM scored GROUP BY rowid)",
+            )
+        } else {
+            ("", "scored")
+        };
 
         let sql = format!(
-            "WITH ranked AS (
+            "WITH scored AS (
                  SELECT f.rowid,
-                        bm25(messages_fts, 1.0, 0.7, 0.5)
-                          - (m.timestamp / 86400.0) * 0.05 AS rank
+                        m.timestamp,
+                        bm25(messages_fts, 1.0, 0.7, 0.5, 0.3, 0.6, 0.2, 0.6) AS score,
+                        MIN(MAX((? - m.timestamp) / ?, 0.0), 62.0) AS age,
+                        NULL AS revision
                  FROM messages_fts f
                  JOIN messages m ON m.rowid = f.rowid
                  WHERE messages_fts MATCH ? AND m.deleted_at IS NULL
+                 {revision_arm}
+             ),
+             ranked AS (
+                 SELECT s.rowid, {rank_expr} AS rank, s.revision FROM {merged} s
              )
-             SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title, r.rank
+             SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title, r.rank,
+                    sn.display_name, m.account_id, r.revision
              FROM ranked r
              JOIN messages m ON m.rowid = r.rowid
-             JOIN chats c ON m.chat_id = c.chat_id
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
              WHERE c.is_excluded = 0 AND m.deleted_at IS NULL
              {chat_clause}
+             {filter_clause}
              {cursor_clause}
-             ORDER BY r.rank ASC, m.timestamp DESC, m.chat_id ASC, m.message_id ASC
-             LIMIT ?"
+             ORDER BY {}
+             LIMIT ?",
+            sort.order_by()
         );
 
         let mut stmt = self.conn.prepare(&sql)?;
         let mut bind_idx = 1;
-        stmt.bind((bind_idx, fts_query))?;
-        bind_idx += 1;
-        if let Some(chat_id) = scope_chat {
-            stmt.bind((bind_idx, chat_id))?;
+        let arms = if filters.include_revisions { 2 } else { 1 };
+        for _ in 0..arms {
+            stmt.bind((bind_idx, decay_anchor))?;
             bind_idx += 1;
-        }
-        if let Some(c) = cursor {
-            stmt.bind((bind_idx, c.rank))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.rank))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.rank))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.chat_id))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.rank))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.chat_id))?;
+            stmt.bind((bind_idx, half_life_secs))?;
             bind_idx += 1;
-            stmt.bind((bind_idx, c.message_id))?;
+            stmt.bind((bind_idx, fts_query))?;
             bind_idx += 1;
         }
+        if let Some((account_id, chat_id)) = scope_chat {
+            stmt.bind((bind_idx, account_id))?;
+            stmt.bind((bind_idx + 1, chat_id))?;
+            bind_idx += 2;
+        }
+        filters.bind(&mut stmt, &mut bind_idx)?;
+        if let Some(c) = cursor {
+            sort.bind_keyset(&mut stmt, &mut bind_idx, c)?;
+        }
         stmt.bind((bind_idx, limit as i64))?;
 
         let mut results = Vec::new();
         while let Ok(sqlite::State::Row) = stmt.next() {
             results.push(MessageWithChat {
                 message_id: stmt.read::<i64, _>(0)?,
+                account_id: stmt.read::<i64, _>(8)?,
                 chat_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
                 text_plain: stmt.read::<String, _>(3)?,
                 link: stmt.read::<Option<String>, _>(4)?,
                 chat_title: stmt.read::<String, _>(5)?,
                 rank: stmt.read::<f64, _>(6)?,
+                sender_name: stmt.read::<Option<String>, _>(7)?,
+                matched_revision: stmt.read::<Option<i64>, _>(9)?,
             });
         }
 
@@ -582,20 +1618,21 @@ impl Store {
     ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
         let cursor_clause = if cursor.is_some() {
             "AND (m.timestamp < ?
-                  OR (m.timestamp = ? AND m.chat_id > ?)
-                  OR (m.timestamp = ? AND m.chat_id = ? AND m.message_id > ?))"
+                  OR (m.timestamp = ? AND (m.account_id, m.chat_id, m.message_id) > (?, ?, ?)))"
         } else {
             ""
         };
 
         let sql = format!(
-            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
              FROM messages m
-             JOIN chats c ON m.chat_id = c.chat_id
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
              WHERE m.rowid IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?)
              AND c.is_excluded = 0 AND m.deleted_at IS NULL
              {}
-             ORDER BY m.timestamp DESC, m.chat_id ASC, m.message_id ASC
+             ORDER BY m.timestamp DESC, m.account_id ASC, m.chat_id ASC, m.message_id ASC
              LIMIT ?",
             cursor_clause
         );
@@ -609,9 +1646,7 @@ impl Store {
             bind_idx += 1;
             stmt.bind((bind_idx, c.timestamp))?;
             bind_idx += 1;
-            stmt.bind((bind_idx, c.chat_id))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
+            stmt.bind((bind_idx, c.account_id))?;
             bind_idx += 1;
             stmt.bind((bind_idx, c.chat_id))?;
             bind_idx += 1;
@@ -624,12 +1659,15 @@ impl Store {
         while let Ok(sqlite::State::Row) = stmt.next() {
             results.push(MessageWithChat {
                 message_id: stmt.read::<i64, _>(0)?,
+                account_id: stmt.read::<i64, _>(7)?,
                 chat_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
                 text_plain: stmt.read::<String, _>(3)?,
                 link: stmt.read::<Option<String>, _>(4)?,
                 chat_title: stmt.read::<String, _>(5)?,
                 rank: 0.0,
+                sender_name: stmt.read::<Option<String>, _>(6)?,
+                matched_revision: None,
             });
         }
 
@@ -639,23 +1677,26 @@ impl Store {
     pub fn search_messages_fts_in_chat(
         &self,
         fts_query: &str,
+        account_id: i64,
         chat_id: i64,
         cursor: Option<&Cursor>,
         limit: usize,
     ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
         let cursor_clause = if cursor.is_some() {
-            "AND (m.timestamp < ?
-                  OR (m.timestamp = ? AND m.message_id > ?))"
+            "AND (m.timestamp < ? OR (m.timestamp = ? AND m.message_id > ?))"
         } else {
             ""
         };
 
         let sql = format!(
-            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
              FROM messages m
-             JOIN chats c ON m.chat_id = c.chat_id
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
              WHERE m.rowid IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?)
-             AND m.chat_id = ? AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             AND m.account_id = ? AND m.chat_id = ? AND c.is_excluded = 0
+             AND m.deleted_at IS NULL
              {}
              ORDER BY m.timestamp DESC, m.message_id ASC
              LIMIT ?",
@@ -666,6 +1707,8 @@ impl Store {
         let mut bind_idx = 1;
         stmt.bind((bind_idx, fts_query))?;
         bind_idx += 1;
+        stmt.bind((bind_idx, account_id))?;
+        bind_idx += 1;
         stmt.bind((bind_idx, chat_id))?;
         bind_idx += 1;
         if let Some(c) = cursor {
@@ -682,81 +1725,135 @@ impl Store {
         while let Ok(sqlite::State::Row) = stmt.next() {
             results.push(MessageWithChat {
                 message_id: stmt.read::<i64, _>(0)?,
+                account_id: stmt.read::<i64, _>(7)?,
                 chat_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
                 text_plain: stmt.read::<String, _>(3)?,
                 link: stmt.read::<Option<String>, _>(4)?,
                 chat_title: stmt.read::<String, _>(5)?,
                 rank: 0.0,
+                sender_name: stmt.read::<Option<String>, _>(6)?,
+                matched_revision: None,
             });
         }
 
         Ok(results)
     }
 
-    /// LIKE-based search fallback for queries with terms shorter than 3 chars
-    /// (FTS5 trigram needs >= 3 chars to produce trigrams).
+    /// LIKE fallback for queries MATCH cannot express, such as terms
+    /// shorter than the 3 chars an FTS5 trigram needs. `None` lists
+    /// what `filters` select on their own. Newest first unless `sort`
+    /// is [`SearchSort::Oldest`]; there is no rank to sort by relevance.
     pub fn search_messages_like(
         &self,
-        terms: &[String],
+        text: Option<&LikeMatch>,
+        filters: &SearchFilters,
+        sort: SearchSort,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        self.like_page(text, None, filters, sort, cursor, limit)
+    }
+
+    /// [`Store::search_messages_like`] within one chat.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_messages_like_in_chat(
+        &self,
+        text: Option<&LikeMatch>,
+        account_id: i64,
+        chat_id: i64,
+        filters: &SearchFilters,
+        sort: SearchSort,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        self.like_page(
+            text,
+            Some((account_id, chat_id)),
+            filters,
+            sort,
+            cursor,
+            limit,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn like_page(
+        &self,
+        text: Option<&LikeMatch>,
+        scope_chat: Option<(i64, i64)>,
+        filters: &SearchFilters,
+        sort: SearchSort,
         cursor: Option<&Cursor>,
         limit: usize,
     ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
-        if terms.is_empty() {
+        // Without a required term only entity filters can pick messages
+        // out; anything else would page through the whole archive.
+        if !text.is_some_and(LikeMatch::has_positive) && !filters.has_content_filters() {
             return Ok(vec![]);
         }
 
-        let like_clauses: Vec<String> = terms
-            .iter()
-            .map(|_| {
-                "(m.text_plain LIKE '%' || ? || '%'
-                  OR m.text_stripped LIKE '%' || ? || '%'
-                  OR m.text_jamo LIKE '%' || ? || '%')"
-                    .to_string()
-            })
-            .collect();
-        let like_where = like_clauses.join(" AND ");
-
+        let sort = sort.unranked();
+        let (like_where, like_params) = like_where(text);
+        // An earlier version matches on the same terms as the current
+        // text; the column repeats the test to tell the two apart.
+        let with_revisions = filters.include_revisions && text.is_some();
+        let (revision_column, match_clause) = if with_revisions {
+            let (revision_where, _) = like_where_on("r", text);
+            (
+                format!(
+                    "CASE WHEN {like_where} THEN NULL
+                     ELSE (SELECT MAX(r.msg_version) {REVISIONS_OF_M} AND {revision_where}) END"
+                ),
+                format!(
+                    "({like_where} OR EXISTS (SELECT 1 {REVISIONS_OF_M} AND {revision_where}))"
+                ),
+            )
+        } else {
+            ("NULL".to_string(), like_where)
+        };
+        let chat_clause = if scope_chat.is_some() {
+            "AND m.account_id = ? AND m.chat_id = ?"
+        } else {
+            ""
+        };
         let cursor_clause = if cursor.is_some() {
-            "AND (m.timestamp < ?
-                  OR (m.timestamp = ? AND m.chat_id > ?)
-                  OR (m.timestamp = ? AND m.chat_id = ? AND m.message_id > ?))"
+            sort.keyset_clause()
         } else {
             ""
         };
 
         let sql = format!(
-            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id, {revision_column}
              FROM messages m
-             JOIN chats c ON m.chat_id = c.chat_id
-             WHERE {} AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE {match_clause} AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             {chat_clause}
              {}
-             ORDER BY m.timestamp DESC, m.chat_id ASC, m.message_id ASC
+             {cursor_clause}
+             ORDER BY {}
              LIMIT ?",
-            like_where, cursor_clause
+            filters.sql_clause(),
+            sort.order_by()
         );
 
         let mut stmt = self.conn.prepare(&sql)?;
         let mut bind_idx = 1;
-        for term in terms {
-            for variant in like_variants(term) {
-                stmt.bind((bind_idx, variant.as_str()))?;
-                bind_idx += 1;
-            }
+        // Column then WHERE, current text before revisions in each.
+        let passes = if with_revisions { 4 } else { 1 };
+        for _ in 0..passes {
+            bind_like(&mut stmt, &mut bind_idx, &like_params)?;
+        }
+        if let Some((account_id, chat_id)) = scope_chat {
+            stmt.bind((bind_idx, account_id))?;
+            stmt.bind((bind_idx + 1, chat_id))?;
+            bind_idx += 2;
         }
+        filters.bind(&mut stmt, &mut bind_idx)?;
         if let Some(c) = cursor {
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.chat_id))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.chat_id))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.message_id))?;
-            bind_idx += 1;
+            sort.bind_keyset(&mut stmt, &mut bind_idx, c)?;
         }
         stmt.bind((bind_idx, limit as i64))?;
 
@@ -764,92 +1861,110 @@ impl Store {
         while let Ok(sqlite::State::Row) = stmt.next() {
             results.push(MessageWithChat {
                 message_id: stmt.read::<i64, _>(0)?,
+                account_id: stmt.read::<i64, _>(7)?,
                 chat_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
                 text_plain: stmt.read::<String, _>(3)?,
                 link: stmt.read::<Option<String>, _>(4)?,
                 chat_title: stmt.read::<String, _>(5)?,
                 rank: 0.0,
+                sender_name: stmt.read::<Option<String>, _>(6)?,
+                matched_revision: stmt.read::<Option<i64>, _>(8)?,
             });
         }
 
         Ok(results)
     }
 
-    pub fn search_messages_like_in_chat(
+    /// Total, per-chat and per-day hit counts for everything `source`
+    /// matches under the same scope and filters as the page query.
+    pub fn search_facets(
         &self,
-        terms: &[String],
-        chat_id: i64,
-        cursor: Option<&Cursor>,
-        limit: usize,
-    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
-        if terms.is_empty() {
-            return Ok(vec![]);
-        }
-
-        let like_clauses: Vec<String> = terms
-            .iter()
-            .map(|_| {
-                "(m.text_plain LIKE '%' || ? || '%'
-                  OR m.text_stripped LIKE '%' || ? || '%'
-                  OR m.text_jamo LIKE '%' || ? || '%')"
-                    .to_string()
-            })
-            .collect();
-        let like_where = like_clauses.join(" AND ");
-
-        let cursor_clause = if cursor.is_some() {
-            "AND (m.timestamp < ?
-                  OR (m.timestamp = ? AND m.message_id > ?))"
+        source: FacetSource<'_>,
+        scope_chat: Option<(i64, i64)>,
+        filters: &SearchFilters,
+    ) -> Result<SearchFacets, sqlite::Error> {
+        let mut like_params = Vec::new();
+        let (from, match_clause) = match source {
+            FacetSource::Fts(_) => (
+                "messages_fts f JOIN messages m ON m.rowid = f.rowid",
+                "messages_fts MATCH ?".to_string(),
+            ),
+            FacetSource::Like(text)
+                if !text.is_some_and(LikeMatch::has_positive) && !filters.has_content_filters() =>
+            {
+                return Ok(SearchFacets::default());
+            }
+            FacetSource::Like(text) => {
+                let (clause, params) = like_where(text);
+                like_params = params;
+                ("messages m", clause)
+            }
+        };
+        let chat_clause = if scope_chat.is_some() {
+            "AND m.account_id = ? AND m.chat_id = ?"
         } else {
             ""
         };
-
         let sql = format!(
-            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title
-             FROM messages m
-             JOIN chats c ON m.chat_id = c.chat_id
-             WHERE {} AND m.chat_id = ? AND c.is_excluded = 0 AND m.deleted_at IS NULL
+            "SELECT m.chat_id, c.title, m.timestamp / 86400 AS day, COUNT(*), m.account_id
+             FROM {from}
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             WHERE {match_clause} AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             {chat_clause}
              {}
-             ORDER BY m.timestamp DESC, m.message_id ASC
-             LIMIT ?",
-            like_where, cursor_clause
+             GROUP BY m.account_id, m.chat_id, day",
+            filters.sql_clause()
         );
 
         let mut stmt = self.conn.prepare(&sql)?;
         let mut bind_idx = 1;
-        for term in terms {
-            for variant in like_variants(term) {
-                stmt.bind((bind_idx, variant.as_str()))?;
+        match source {
+            FacetSource::Fts(fts_query) => {
+                stmt.bind((bind_idx, fts_query))?;
                 bind_idx += 1;
             }
+            FacetSource::Like(_) => bind_like(&mut stmt, &mut bind_idx, &like_params)?,
         }
-        stmt.bind((bind_idx, chat_id))?;
-        bind_idx += 1;
-        if let Some(c) = cursor {
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.timestamp))?;
-            bind_idx += 1;
-            stmt.bind((bind_idx, c.message_id))?;
-            bind_idx += 1;
+        if let Some((account_id, chat_id)) = scope_chat {
+            stmt.bind((bind_idx, account_id))?;
+            stmt.bind((bind_idx + 1, chat_id))?;
+            bind_idx += 2;
         }
-        stmt.bind((bind_idx, limit as i64))?;
-
-        let mut results = Vec::new();
-        while let Ok(sqlite::State::Row) = stmt.next() {
-            results.push(MessageWithChat {
-                message_id: stmt.read::<i64, _>(0)?,
-                chat_id: stmt.read::<i64, _>(1)?,
-                timestamp: stmt.read::<i64, _>(2)?,
-                text_plain: stmt.read::<String, _>(3)?,
-                link: stmt.read::<Option<String>, _>(4)?,
-                chat_title: stmt.read::<String, _>(5)?,
-                rank: 0.0,
-            });
+        filters.bind(&mut stmt, &mut bind_idx)?;
+
+        let mut facets = SearchFacets::default();
+        let mut by_chat: std::collections::HashMap<(i64, i64), ChatFacet> =
+            std::collections::HashMap::new();
+        let mut by_day: std::collections::BTreeMap<i64, u64> = std::collections::BTreeMap::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let chat_id = stmt.read::<i64, _>(0)?;
+            let account_id = stmt.read::<i64, _>(4)?;
+            let count = stmt.read::<i64, _>(3)? as u64;
+            facets.total += count;
+            by_chat
+                .entry((account_id, chat_id))
+                .or_insert(ChatFacet {
+                    account_id,
+                    chat_id,
+                    chat_title: stmt.read::<String, _>(1)?,
+                    count: 0,
+                })
+                .count += count;
+            *by_day.entry(stmt.read::<i64, _>(2)? * 86400).or_default() += count;
         }
-
-        Ok(results)
+        facets.by_chat = by_chat.into_values().collect();
+        facets.by_chat.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.account_id.cmp(&b.account_id))
+                .then(a.chat_id.cmp(&b.chat_id))
+        });
+        facets.by_day = by_day
+            .into_iter()
+            .map(|(day_start, count)| DayFacet { day_start, count })
+            .collect();
+        Ok(facets)
     }
 
     pub fn message_count(&self) -> Result<i64, sqlite::Error> {
@@ -873,6 +1988,7 @@ mod tests {
     fn setup_chat(store: &Store, chat_id: i64) {
         store
             .upsert_chat(&ChatRow {
+                account_id: 0,
                 chat_id,
                 title: format!("Chat {}", chat_id),
                 chat_type: "supergroup".to_string(),
@@ -886,12 +2002,18 @@ mod tests {
     fn make_message(chat_id: i64, msg_id: i64, ts: i64, text: &str) -> MessageRow {
         MessageRow {
             message_id: msg_id,
+            account_id: 0,
             chat_id,
             timestamp: ts,
             text_plain: text.to_string(),
             text_stripped: strip_whitespace(text),
             link: None,
             sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         }
     }
 
@@ -903,7 +2025,7 @@ mod tests {
         let msg = make_message(1, 100, 1000, "hello world");
         store.insert_messages_batch(&[msg]).unwrap();
 
-        let fetched = store.get_message(1, 100).unwrap().unwrap();
+        let fetched = store.get_message(0, 1, 100).unwrap().unwrap();
         assert_eq!(fetched.text_plain, "hello world");
         assert_eq!(fetched.text_stripped, "helloworld");
     }
@@ -964,8 +2086,84 @@ mod tests {
             .unwrap();
 
         // LIKE fallback for < 3 char queries
-        let terms = vec!["삼성".to_string()];
-        let results = store.search_messages_like(&terms, None, 10).unwrap();
+        let results = store
+            .search_messages_like(
+                Some(&LikeMatch::term("삼성")),
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_id, 1);
+    }
+
+    #[test]
+    fn like_search_matches_romanized_text() {
+        let store = test_store();
+        setup_chat(&store, 1);
+
+        store
+            .insert_messages_batch(&[
+                make_message(1, 1, 1000, "삼성 실적 발표"),
+                make_message(1, 2, 1001, "오늘 날씨가 좋습니다"),
+            ])
+            .unwrap();
+
+        // Too short for the trigram index; `text_roman` holds "samseong".
+        let results = store
+            .search_messages_like(
+                Some(&LikeMatch::term("sa")),
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_id, 1);
+    }
+
+    #[test]
+    fn like_search_excludes_terms() {
+        let store = test_store();
+        setup_chat(&store, 1);
+
+        store
+            .insert_messages_batch(&[
+                make_message(1, 1, 1000, "삼성 실적 발표"),
+                make_message(1, 2, 1001, "삼성 루머 정리"),
+            ])
+            .unwrap();
+
+        let text = LikeMatch::And(vec![
+            LikeMatch::term("삼성"),
+            LikeMatch::Not(Box::new(LikeMatch::term("루머"))),
+        ]);
+        let results = store
+            .search_messages_like(
+                Some(&text),
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_id, 1);
+
+        let results = store
+            .search_messages_like_in_chat(
+                Some(&text),
+                0,
+                1,
+                &SearchFilters::default(),
+                SearchSort::default(),
+                None,
+                10,
+            )
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].message_id, 1);
     }
@@ -984,7 +2182,7 @@ mod tests {
             .unwrap();
 
         let results = store
-            .search_messages_fts_in_chat("\"hello\"", 1, None, 10)
+            .search_messages_fts_in_chat("\"hello\"", 0, 1, None, 10)
             .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].chat_id, 1);
@@ -1016,12 +2214,18 @@ mod tests {
         let store = Store::open_in_memory().unwrap();
         let msg = MessageRow {
             message_id: 42,
+            account_id: 0,
             chat_id: 1,
             timestamp: 1_700_000_000,
             text_plain: "first".into(),
             text_stripped: "first".into(),
             link: None,
             sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         };
         store
             .insert_messages_batch(std::slice::from_ref(&msg))
@@ -1085,16 +2289,23 @@ mod tests {
         let store = Store::open_in_memory().unwrap();
         let msg = MessageRow {
             message_id: 7,
+            account_id: 0,
             chat_id: 1,
             timestamp: 1_700_000_000,
             text_plain: "foo".into(),
             text_stripped: "foo".into(),
             link: None,
             sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         };
         store.insert_messages_batch(&[msg]).unwrap();
         store
             .delete_messages(&[MessageRef {
+                account_id: 0,
                 chat_id: 1,
                 message_id: 7,
             }])
@@ -1113,21 +2324,33 @@ mod tests {
         let msgs = vec![
             MessageRow {
                 message_id: 10,
+                account_id: 0,
                 chat_id: 1,
                 timestamp: 1_700_000_000,
                 text_plain: "테스트 메시지".into(),
                 text_stripped: "테스트메시지".into(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
             MessageRow {
                 message_id: 11,
+                account_id: 0,
                 chat_id: 1,
                 timestamp: 1_700_000_001,
                 text_plain: "another".into(),
                 text_stripped: "another".into(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
         ];
         store.insert_messages_batch(&msgs).unwrap();
@@ -1166,10 +2389,10 @@ mod tests {
         store
             .insert_messages_batch(&[make_message(1, 100, 1_000, "hello")])
             .unwrap();
-        assert!(store.get_message(1, 100).unwrap().is_some());
+        assert!(store.get_message(0, 1, 100).unwrap().is_some());
         mark_deleted(&store, 1, 100);
         assert!(
-            store.get_message(1, 100).unwrap().is_none(),
+            store.get_message(0, 1, 100).unwrap().is_none(),
             "soft-deleted row must not surface from get_message"
         );
     }
@@ -1204,7 +2427,7 @@ mod tests {
             .unwrap();
         mark_deleted(&store, 1, 101);
         let res = store
-            .search_messages_fts_in_chat("hello", 1, None, 30)
+            .search_messages_fts_in_chat("hello", 0, 1, None, 30)
             .unwrap();
         let ids: Vec<i64> = res.iter().map(|h| h.message_id).collect();
         assert_eq!(ids, vec![100]);
@@ -1225,10 +2448,28 @@ mod tests {
                 make_message(1, 101, 1_001, "hello korea"),
             ])
             .unwrap();
-        let pre = engine::search(&store, "hello", &SearchScope::All, None, None).unwrap();
+        let pre = engine::search(
+            &store,
+            "hello",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(pre.items.len(), 2);
         mark_deleted(&store, 1, 100);
-        let post = engine::search(&store, "hello", &SearchScope::All, None, None).unwrap();
+        let post = engine::search(
+            &store,
+            "hello",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = post.items.iter().map(|h| h.message_id).collect();
         assert_eq!(ids, vec![101]);
     }
@@ -1247,10 +2488,28 @@ mod tests {
             ])
             .unwrap();
         // 2-char term forces LIKE branch in engine::search.
-        let pre = engine::search(&store, "ab", &SearchScope::All, None, None).unwrap();
+        let pre = engine::search(
+            &store,
+            "ab",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(pre.items.len(), 2);
         mark_deleted(&store, 1, 100);
-        let post = engine::search(&store, "ab", &SearchScope::All, None, None).unwrap();
+        let post = engine::search(
+            &store,
+            "ab",
+            &SearchScope::All,
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = post.items.iter().map(|h| h.message_id).collect();
         assert_eq!(ids, vec![101]);
     }
@@ -1268,7 +2527,19 @@ mod tests {
             ])
             .unwrap();
         mark_deleted(&store, 1, 100);
-        let post = engine::search(&store, "alpha", &SearchScope::Chat(1), None, None).unwrap();
+        let post = engine::search(
+            &store,
+            "alpha",
+            &SearchScope::Chat {
+                account_id: 0,
+                chat_id: 1,
+            },
+            &SearchFilters::default(),
+            SearchSort::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let ids: Vec<i64> = post.items.iter().map(|h| h.message_id).collect();
         assert_eq!(ids, vec![101]);
     }
@@ -1287,4 +2558,135 @@ mod tests {
         mark_deleted(&store, 1, 100);
         assert_eq!(store.message_count().unwrap(), 1);
     }
+
+    #[test]
+    fn message_context_returns_neighbours_oldest_first() {
+        let store = test_store();
+        setup_chat(&store, 1);
+        setup_chat(&store, 2);
+        store
+            .insert_messages_batch(&[
+                make_message(1, 1, 1000, "a"),
+                make_message(1, 2, 1010, "b"),
+                make_message(1, 3, 1020, "c"),
+                // Same timestamp as the anchor: ordered by message id.
+                make_message(1, 4, 1020, "d"),
+                make_message(1, 5, 1030, "e"),
+                make_message(1, 6, 1040, "f"),
+                make_message(2, 7, 1025, "other chat"),
+            ])
+            .unwrap();
+        mark_deleted(&store, 1, 2);
+
+        let ids = |rows: Vec<MessageWithChat>| -> Vec<i64> {
+            rows.into_iter().map(|m| m.message_id).collect()
+        };
+        assert_eq!(
+            ids(store.message_context(0, 1, 3, 2, 2).unwrap()),
+            vec![1, 3, 4, 5]
+        );
+        assert_eq!(
+            ids(store.message_context(0, 1, 4, 1, 1).unwrap()),
+            vec![3, 4, 5]
+        );
+        assert_eq!(ids(store.message_context(0, 1, 6, 0, 5).unwrap()), vec![6]);
+
+        // Deleted or unknown anchors have no context.
+        assert!(store.message_context(0, 1, 2, 2, 2).unwrap().is_empty());
+        assert!(store.message_context(0, 1, 99, 2, 2).unwrap().is_empty());
+
+        store.set_chat_excluded(0, 1, true).unwrap();
+        assert!(store.message_context(0, 1, 3, 2, 2).unwrap().is_empty());
+    }
+
+    fn reply(
+        chat_id: i64,
+        msg_id: i64,
+        reply_to: Option<i64>,
+        thread_id: Option<i64>,
+        text: &str,
+    ) -> MessageRow {
+        MessageRow {
+            reply_to_message_id: reply_to,
+            thread_id,
+            sender_name: None,
+            sender_username: None,
+            ..make_message(chat_id, msg_id, 1000 + msg_id, text)
+        }
+    }
+
+    #[test]
+    fn reply_chains_and_threads() {
+        let store = test_store();
+        setup_chat(&store, 1);
+        store
+            .insert_messages_batch(&[
+                reply(1, 10, None, None, "topic root 공지"),
+                reply(1, 11, Some(10), Some(10), "first 공지 reply"),
+                reply(1, 12, Some(11), Some(10), "second reply"),
+                reply(1, 13, Some(99), Some(10), "reply to unindexed"),
+                reply(1, 20, None, Some(20), "other topic 공지"),
+                // A cycle in bad input ends at the depth cap.
+                reply(1, 30, Some(31), None, "cycle a"),
+                reply(1, 31, Some(30), None, "cycle b"),
+            ])
+            .unwrap();
+
+        let ids = |rows: Vec<MessageWithChat>| -> Vec<i64> {
+            rows.into_iter().map(|m| m.message_id).collect()
+        };
+        assert_eq!(ids(store.reply_chain(0, 1, 12).unwrap()), vec![10, 11, 12]);
+        assert_eq!(ids(store.reply_chain(0, 1, 13).unwrap()), vec![13]);
+        assert_eq!(
+            store.reply_chain(0, 1, 30).unwrap().len() as i64,
+            MAX_REPLY_DEPTH + 1
+        );
+        assert!(store.reply_chain(0, 1, 99).unwrap().is_empty());
+
+        assert_eq!(
+            ids(store.thread_messages(0, 1, 10, None, 10).unwrap()),
+            vec![10, 11, 12, 13]
+        );
+        assert_eq!(
+            ids(store.thread_messages(0, 1, 10, Some(11), 1).unwrap()),
+            vec![12]
+        );
+
+        // A deleted middle link cuts the chain; an edit can move a
+        // message between threads.
+        mark_deleted(&store, 1, 11);
+        assert_eq!(ids(store.reply_chain(0, 1, 12).unwrap()), vec![12]);
+        store
+            .insert_messages_batch(&[reply(1, 12, Some(20), Some(20), "second reply")])
+            .unwrap();
+        let got = store.get_message(0, 1, 12).unwrap().unwrap();
+        assert_eq!(
+            (got.reply_to_message_id, got.thread_id),
+            (Some(20), Some(20))
+        );
+        assert_eq!(
+            ids(store.thread_messages(0, 1, 20, None, 10).unwrap()),
+            vec![12, 20]
+        );
+
+        use crate::search::engine::{search, SearchScope};
+        let found = search(
+            &store,
+            "공지",
+            &SearchScope::Thread {
+                account_id: 0,
+                chat_id: 1,
+                thread_id: 10,
+            },
+            &SearchFilters::default(),
+            SearchSort::Oldest,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            found.items.iter().map(|i| i.message_id).collect::<Vec<_>>(),
+            vec![10]
+        );
+    }
 }
@@ -1,8 +1,18 @@
 pub mod app_meta;
 pub mod chat;
+pub mod entity;
+pub mod media;
 pub mod message;
+pub mod outbox;
+pub mod recon;
+pub mod revision;
+pub mod saved_search;
 pub mod schema;
+pub mod search_history;
+pub mod sender;
 pub mod sync_state;
+pub mod tombstone;
+pub mod vocab;
 pub mod wiki_category;
 pub mod wiki_page;
 pub mod wiki_queue;
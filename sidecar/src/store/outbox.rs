@@ -0,0 +1,768 @@
+//! Producer side of cloud sync. Each message or chat mutation the
+//! cloud mirrors queues an op in `cloud_outbox`, inside the same
+//! transaction as the mutation. An uploader drains the queue with
+//! [`Store::peek_outbox`] and reports back through
+//! [`Store::ack_outbox`] or [`Store::fail_outbox`].
+//!
+//! A payload is one NDJSON line of the push protocol
+//! (`docs/specs/2026-04-27-cloud-wiki-architecture.md`), so it is sent
+//! as stored. `client_op_id` names the mutation rather than the
+//! attempt: queuing the same mutation twice leaves one row, and the
+//! server can drop an op it has already applied.
+//!
+//! Ops are queued whether or not cloud sync is on; the setting only
+//! decides whether [`crate::cloud`] pushes them. Excluded chats never
+//! upload.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::chat::ChatRow;
+use super::message::MessageRef;
+use super::Store;
+
+/// Version stamped on every payload.
+pub const PROTOCOL_VERSION: i64 = 1;
+
+/// `app_meta` key holding `"1"` once the user turned cloud sync on,
+/// which lets [`crate::cloud`] push the queue.
+const SYNC_ENABLED_KEY: &str = "cloud_sync_enabled";
+
+/// `app_meta` key holding the URL ops are pushed to.
+const SYNC_ENDPOINT_KEY: &str = "cloud_sync_endpoint";
+
+/// Most messages [`Store::requeue_outbox_gaps`] queues per call.
+const GAP_REQUEUE_BATCH: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxOp {
+    MsgUpsert,
+    MsgDelete,
+    ChatMeta,
+    ChatPurge,
+}
+
+impl OutboxOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MsgUpsert => "msg_upsert",
+            Self::MsgDelete => "msg_delete",
+            Self::ChatMeta => "chat_meta",
+            Self::ChatPurge => "chat_purge",
+        }
+    }
+
+    fn parse(op: &str) -> Option<Self> {
+        match op {
+            "msg_upsert" => Some(Self::MsgUpsert),
+            "msg_delete" => Some(Self::MsgDelete),
+            "chat_meta" => Some(Self::ChatMeta),
+            "chat_purge" => Some(Self::ChatPurge),
+            _ => None,
+        }
+    }
+}
+
+/// One queued op, oldest first in [`Store::peek_outbox`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub client_op_id: String,
+    pub op: OutboxOp,
+    pub account_id: i64,
+    pub chat_id: i64,
+    /// Set for `msg_upsert` and `msg_delete` only.
+    pub message_id: Option<i64>,
+    pub msg_version: Option<i64>,
+    /// The JSON line to send.
+    pub payload: String,
+    pub created_at: i64,
+    /// Failed sends so far, with the last error.
+    pub attempts: i64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutboxStats {
+    pub pending: i64,
+    pub failing: i64,
+    pub last_error: Option<String>,
+}
+
+pub(crate) fn sync_enabled(conn: &sqlite::Connection) -> Result<bool, sqlite::Error> {
+    let mut stmt = conn.prepare("SELECT value FROM app_meta WHERE key = ?")?;
+    stmt.bind((1, SYNC_ENABLED_KEY))?;
+    Ok(matches!(stmt.next()?, sqlite::State::Row) && stmt.read::<String, _>(0)? == "1")
+}
+
+fn chat_excluded(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+) -> Result<bool, sqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT is_excluded FROM chats WHERE account_id = ? AND chat_id = ?")?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    Ok(matches!(stmt.next()?, sqlite::State::Row) && stmt.read::<i64, _>(0)? != 0)
+}
+
+/// The column's CHECK constraint admits only known kinds, so an
+/// unknown one means the table is not ours; fail rather than skip a
+/// row that would stay queued.
+fn read_op(stmt: &sqlite::Statement<'_>, idx: usize) -> Result<OutboxOp, sqlite::Error> {
+    let op = stmt.read::<String, _>(idx)?;
+    OutboxOp::parse(&op).ok_or_else(|| sqlite::Error {
+        code: None,
+        message: Some(format!("unknown outbox op {op:?}")),
+    })
+}
+
+struct NewOp<'a> {
+    client_op_id: &'a str,
+    op: OutboxOp,
+    account_id: i64,
+    chat_id: i64,
+    message_id: Option<i64>,
+    msg_version: Option<i64>,
+    payload: serde_json::Value,
+}
+
+fn insert_op(conn: &sqlite::Connection, op: NewOp<'_>) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT OR IGNORE INTO cloud_outbox
+            (client_op_id, op, account_id, chat_id, message_id, msg_version, payload, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    stmt.bind((1, op.client_op_id))?;
+    stmt.bind((2, op.op.as_str()))?;
+    stmt.bind((3, op.account_id))?;
+    stmt.bind((4, op.chat_id))?;
+    stmt.bind((5, op.message_id))?;
+    stmt.bind((6, op.msg_version))?;
+    stmt.bind((7, op.payload.to_string().as_bytes()))?;
+    stmt.bind((8, crate::wiki::norm::unix_now()))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Queue the current state of one message: `msg_delete` for a
+/// tombstone, `msg_upsert` otherwise. Call after the row is written,
+/// with `msg_version` already bumped; runs inside the caller's
+/// transaction.
+pub(crate) fn enqueue_message(
+    conn: &sqlite::Connection,
+    msg: &MessageRef,
+) -> Result<(), sqlite::Error> {
+    if chat_excluded(conn, msg.account_id, msg.chat_id)? {
+        return Ok(());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT msg_version, deleted_at, timestamp, sender_id, text_plain, link FROM messages
+         WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, msg.account_id))?;
+    stmt.bind((2, msg.chat_id))?;
+    stmt.bind((3, msg.message_id))?;
+    if let sqlite::State::Done = stmt.next()? {
+        return Ok(());
+    }
+    let version = stmt.read::<i64, _>(0)?;
+    let op = if stmt.read::<Option<i64>, _>(1)?.is_some() {
+        OutboxOp::MsgDelete
+    } else {
+        OutboxOp::MsgUpsert
+    };
+    let client_op_id = format!(
+        "{}:{}:{}:{}:{version}",
+        op.as_str(),
+        msg.account_id,
+        msg.chat_id,
+        msg.message_id
+    );
+    let mut payload = json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "client_op_id": client_op_id,
+        "op": op,
+        "account_id": msg.account_id,
+        "chat_id": msg.chat_id,
+        "message_id": msg.message_id,
+        "msg_version": version,
+    });
+    if op == OutboxOp::MsgUpsert {
+        payload["timestamp"] = stmt.read::<i64, _>(2)?.into();
+        payload["sender_id"] = stmt.read::<Option<i64>, _>(3)?.unwrap_or(0).into();
+        payload["text"] = stmt.read::<String, _>(4)?.into();
+        payload["link"] = stmt.read::<Option<String>, _>(5)?.into();
+    }
+    insert_op(
+        conn,
+        NewOp {
+            client_op_id: &client_op_id,
+            op,
+            account_id: msg.account_id,
+            chat_id: msg.chat_id,
+            message_id: Some(msg.message_id),
+            msg_version: Some(version),
+            payload,
+        },
+    )
+}
+
+/// Queue the title, type and handle of a chat. Runs inside the
+/// caller's transaction.
+pub(crate) fn enqueue_chat_meta(
+    conn: &sqlite::Connection,
+    chat: &ChatRow,
+) -> Result<(), sqlite::Error> {
+    if chat_excluded(conn, chat.account_id, chat.chat_id)? {
+        return Ok(());
+    }
+    let mut payload = json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "op": OutboxOp::ChatMeta,
+        "account_id": chat.account_id,
+        "chat_id": chat.chat_id,
+        "title": chat.title,
+        "chat_type": chat.chat_type,
+        "username": chat.username,
+        "access_hash": chat.access_hash,
+    });
+    // Chats have no version; the content and the second tell two
+    // updates apart.
+    let digest = blake3::hash(payload.to_string().as_bytes());
+    let client_op_id = format!(
+        "chat_meta:{}:{}:{}:{}",
+        chat.account_id,
+        chat.chat_id,
+        crate::wiki::norm::unix_now(),
+        &digest.to_hex()[..16]
+    );
+    payload["client_op_id"] = client_op_id.clone().into();
+    insert_op(
+        conn,
+        NewOp {
+            client_op_id: &client_op_id,
+            op: OutboxOp::ChatMeta,
+            account_id: chat.account_id,
+            chat_id: chat.chat_id,
+            message_id: None,
+            msg_version: None,
+            payload,
+        },
+    )
+}
+
+/// Cancel every op still queued for a chat and queue a `chat_purge`
+/// so the cloud drops what it already has. Runs inside the caller's
+/// transaction.
+pub(crate) fn enqueue_chat_purge(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare("DELETE FROM cloud_outbox WHERE account_id = ? AND chat_id = ?")?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.next()?;
+
+    let client_op_id = format!(
+        "chat_purge:{account_id}:{chat_id}:{}",
+        crate::wiki::norm::unix_now()
+    );
+    insert_op(
+        conn,
+        NewOp {
+            client_op_id: &client_op_id,
+            op: OutboxOp::ChatPurge,
+            account_id,
+            chat_id,
+            message_id: None,
+            msg_version: None,
+            payload: json!({
+                "protocol_version": PROTOCOL_VERSION,
+                "client_op_id": client_op_id,
+                "op": OutboxOp::ChatPurge,
+                "account_id": account_id,
+                "chat_id": chat_id,
+            }),
+        },
+    )
+}
+
+impl Store {
+    pub fn cloud_sync_enabled(&self) -> Result<bool, sqlite::Error> {
+        sync_enabled(&self.conn)
+    }
+
+    /// Let [`crate::cloud`] push the outbox, or stop it. Mutations are
+    /// queued either way; turning sync off keeps what is queued.
+    pub fn set_cloud_sync_enabled(&self, enabled: bool) -> Result<(), sqlite::Error> {
+        self.set_meta(SYNC_ENABLED_KEY, if enabled { "1" } else { "0" })
+    }
+
+    pub fn cloud_sync_endpoint(&self) -> Result<Option<String>, sqlite::Error> {
+        Ok(self
+            .get_meta(SYNC_ENDPOINT_KEY)?
+            .filter(|endpoint| !endpoint.is_empty()))
+    }
+
+    /// Where [`crate::cloud`] pushes to; `None` forgets it. The URL is
+    /// stored as given; see [`crate::cloud::push::Endpoint::parse`].
+    pub fn set_cloud_sync_endpoint(&self, endpoint: Option<&str>) -> Result<(), sqlite::Error> {
+        self.set_meta(SYNC_ENDPOINT_KEY, endpoint.unwrap_or(""))
+    }
+
+    /// Queue messages the cloud is missing: ones at or below their
+    /// chat's reconciliation watermark whose current version was never
+    /// acked and has no op queued. That covers history indexed before
+    /// the outbox existed as well as ops cancelled or lost. Queues at
+    /// most a batch per call and returns how many it queued.
+    pub fn requeue_outbox_gaps(&self) -> Result<u64, sqlite::Error> {
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<u64, sqlite::Error> {
+            let missing = {
+                let mut stmt = self.conn.prepare(
+                    "SELECT m.account_id, m.chat_id, m.message_id
+                     FROM messages m
+                     JOIN postbox_recon_watermark w
+                       ON w.account_id = m.account_id AND w.chat_id = m.chat_id
+                     WHERE m.message_id <= w.max_msg_id
+                       AND NOT EXISTS (
+                           SELECT 1 FROM chats c
+                           WHERE c.account_id = m.account_id AND c.chat_id = m.chat_id
+                             AND c.is_excluded = 1)
+                       AND COALESCE(m.cloud_acked_version, 0) < m.msg_version
+                       AND NOT EXISTS (
+                           SELECT 1 FROM cloud_outbox o
+                           WHERE o.account_id = m.account_id AND o.chat_id = m.chat_id
+                             AND o.message_id = m.message_id AND o.msg_version = m.msg_version)
+                     ORDER BY m.timestamp, m.chat_id, m.message_id
+                     LIMIT ?",
+                )?;
+                stmt.bind((1, GAP_REQUEUE_BATCH))?;
+                let mut refs = Vec::new();
+                while let sqlite::State::Row = stmt.next()? {
+                    refs.push(MessageRef {
+                        account_id: stmt.read::<i64, _>(0)?,
+                        chat_id: stmt.read::<i64, _>(1)?,
+                        message_id: stmt.read::<i64, _>(2)?,
+                    });
+                }
+                refs
+            };
+            for msg in &missing {
+                enqueue_message(&self.conn, msg)?;
+            }
+            Ok(missing.len() as u64)
+        })();
+        match result {
+            Ok(queued) => {
+                self.conn.execute("COMMIT")?;
+                Ok(queued)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// `limit` queued ops. Each chat's ops stay in queue order, since
+    /// `chat_meta` and `chat_purge` carry no version to order them by on
+    /// the server. Chats whose ops failed fewer times go first, so a
+    /// chat the server keeps rejecting goes behind the rest instead of
+    /// holding them up. Nothing is claimed; an op stays queued until
+    /// acked.
+    pub fn peek_outbox(&self, limit: usize) -> Result<Vec<OutboxEntry>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT o.id, o.client_op_id, o.op, o.account_id, o.chat_id, o.message_id,
+                    o.msg_version, o.payload, o.created_at, o.attempts, o.last_error
+             FROM cloud_outbox o
+             JOIN (SELECT account_id, chat_id, MAX(attempts) AS chat_attempts
+                   FROM cloud_outbox GROUP BY account_id, chat_id) c
+               ON c.account_id = o.account_id AND c.chat_id = o.chat_id
+             ORDER BY c.chat_attempts, o.id LIMIT ?",
+        )?;
+        stmt.bind((1, limit as i64))?;
+        let mut entries = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            entries.push(OutboxEntry {
+                id: stmt.read::<i64, _>(0)?,
+                client_op_id: stmt.read::<String, _>(1)?,
+                op: read_op(&stmt, 2)?,
+                account_id: stmt.read::<i64, _>(3)?,
+                chat_id: stmt.read::<i64, _>(4)?,
+                message_id: stmt.read::<Option<i64>, _>(5)?,
+                msg_version: stmt.read::<Option<i64>, _>(6)?,
+                payload: String::from_utf8_lossy(&stmt.read::<Vec<u8>, _>(7)?).into_owned(),
+                created_at: stmt.read::<i64, _>(8)?,
+                attempts: stmt.read::<i64, _>(9)?,
+                last_error: stmt.read::<Option<String>, _>(10)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn outbox_len(&self) -> Result<i64, sqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM cloud_outbox")?;
+        stmt.next()?;
+        stmt.read::<i64, _>(0)
+    }
+
+    /// Queued ops, how many of them failed at least once, and the
+    /// error of the oldest failing one.
+    pub fn outbox_stats(&self) -> Result<OutboxStats, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(*), COUNT(last_error),
+                    (SELECT last_error FROM cloud_outbox
+                     WHERE last_error IS NOT NULL ORDER BY id LIMIT 1)
+             FROM cloud_outbox",
+        )?;
+        stmt.next()?;
+        Ok(OutboxStats {
+            pending: stmt.read::<i64, _>(0)?,
+            failing: stmt.read::<i64, _>(1)?,
+            last_error: stmt.read::<Option<String>, _>(2)?,
+        })
+    }
+
+    /// Drop ops the cloud applied (or already had a newer version of),
+    /// recording each message version as acked so its tombstone may be
+    /// purged. Unknown ids are ignored. Returns how many ops were acked.
+    pub fn ack_outbox(&self, client_op_ids: &[String]) -> Result<u64, sqlite::Error> {
+        if client_op_ids.is_empty() {
+            return Ok(0);
+        }
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<u64, sqlite::Error> {
+            let mut acked = 0_u64;
+            for client_op_id in client_op_ids {
+                let message = {
+                    let mut stmt = self.conn.prepare(
+                        "SELECT account_id, chat_id, message_id, msg_version FROM cloud_outbox
+                         WHERE client_op_id = ?",
+                    )?;
+                    stmt.bind((1, client_op_id.as_str()))?;
+                    if let sqlite::State::Done = stmt.next()? {
+                        continue;
+                    }
+                    match (
+                        stmt.read::<Option<i64>, _>(2)?,
+                        stmt.read::<Option<i64>, _>(3)?,
+                    ) {
+                        (Some(message_id), Some(version)) => Some((
+                            stmt.read::<i64, _>(0)?,
+                            stmt.read::<i64, _>(1)?,
+                            message_id,
+                            version,
+                        )),
+                        _ => None,
+                    }
+                };
+                if let Some((account_id, chat_id, message_id, version)) = message {
+                    let mut stmt = self.conn.prepare(
+                        "UPDATE messages
+                         SET cloud_acked_version = MAX(COALESCE(cloud_acked_version, 0), ?)
+                         WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+                    )?;
+                    stmt.bind((1, version))?;
+                    stmt.bind((2, account_id))?;
+                    stmt.bind((3, chat_id))?;
+                    stmt.bind((4, message_id))?;
+                    stmt.next()?;
+                }
+
+                let mut stmt = self
+                    .conn
+                    .prepare("DELETE FROM cloud_outbox WHERE client_op_id = ?")?;
+                stmt.bind((1, client_op_id.as_str()))?;
+                stmt.next()?;
+                acked += 1;
+            }
+            Ok(acked)
+        })();
+        match result {
+            Ok(acked) => {
+                self.conn.execute("COMMIT")?;
+                Ok(acked)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Record a failed send. The op stays queued for the next attempt.
+    pub fn fail_outbox(&self, client_op_id: &str, error: &str) -> Result<(), sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "UPDATE cloud_outbox SET attempts = attempts + 1, last_error = ?
+             WHERE client_op_id = ?",
+        )?;
+        stmt.bind((1, error))?;
+        stmt.bind((2, client_op_id))?;
+        stmt.next()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRow};
+
+    fn insert(store: &Store, chat_id: i64, message_id: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id,
+                timestamp: 1_700_000_000,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 42,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    fn message_ref(chat_id: i64, message_id: i64) -> MessageRef {
+        MessageRef {
+            account_id: 0,
+            chat_id,
+            message_id,
+        }
+    }
+
+    fn chat(chat_id: i64, title: &str) -> ChatRow {
+        ChatRow {
+            account_id: 0,
+            chat_id,
+            title: title.into(),
+            chat_type: "channel".into(),
+            username: None,
+            access_hash: None,
+            is_excluded: false,
+        }
+    }
+
+    fn ops(store: &Store) -> Vec<(OutboxOp, Option<i64>, Option<i64>)> {
+        store
+            .peek_outbox(100)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.op, e.message_id, e.msg_version))
+            .collect()
+    }
+
+    #[test]
+    fn mutations_are_queued_while_sync_is_off() {
+        let store = Store::open_in_memory().unwrap();
+        assert!(!store.cloud_sync_enabled().unwrap());
+        insert(&store, 1, 1, "공모주 청약");
+        store.upsert_chat(&chat(1, "공모주 방")).unwrap();
+        assert_eq!(
+            ops(&store),
+            vec![
+                (OutboxOp::MsgUpsert, Some(1), Some(1)),
+                (OutboxOp::ChatMeta, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn mutations_queue_versioned_ops() {
+        let store = Store::open_in_memory().unwrap();
+        store.upsert_chat(&chat(1, "공모주 방")).unwrap();
+        // Same metadata again is not a change.
+        store.upsert_chat(&chat(1, "공모주 방")).unwrap();
+        insert(&store, 1, 1, "공모주 청약 내일");
+        insert(&store, 1, 1, "공모주 청약 내일");
+        insert(&store, 1, 1, "공모주 청약 모레");
+        store.delete_messages(&[message_ref(1, 1)]).unwrap();
+        assert_eq!(
+            ops(&store),
+            vec![
+                (OutboxOp::ChatMeta, None, None),
+                (OutboxOp::MsgUpsert, Some(1), Some(1)),
+                (OutboxOp::MsgUpsert, Some(1), Some(2)),
+                (OutboxOp::MsgDelete, Some(1), Some(3)),
+            ]
+        );
+
+        let entries = store.peek_outbox(100).unwrap();
+        let upsert: serde_json::Value = serde_json::from_str(&entries[2].payload).unwrap();
+        assert_eq!(upsert["client_op_id"], entries[2].client_op_id.as_str());
+        assert_eq!(upsert["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(upsert["op"], "msg_upsert");
+        assert_eq!(upsert["text"], "공모주 청약 모레");
+        assert_eq!(upsert["sender_id"], 42);
+        let delete: serde_json::Value = serde_json::from_str(&entries[3].payload).unwrap();
+        assert_eq!(delete["msg_version"], 3);
+        assert!(delete.get("text").is_none());
+    }
+
+    #[test]
+    fn ack_advances_version_and_failures_are_kept() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, 1, "배당 기준일");
+        insert(&store, 1, 2, "배당 지급일");
+        let entries = store.peek_outbox(10).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        store
+            .fail_outbox(&entries[1].client_op_id, "timeout")
+            .unwrap();
+        assert_eq!(
+            store
+                .ack_outbox(&[entries[0].client_op_id.clone(), "nope".into()])
+                .unwrap(),
+            1
+        );
+        let left = store.peek_outbox(10).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].attempts, 1);
+        assert_eq!(left[0].last_error.as_deref(), Some("timeout"));
+
+        let acked: Option<i64> = {
+            let mut stmt = store
+                .conn
+                .prepare("SELECT cloud_acked_version FROM messages WHERE message_id = 1")
+                .unwrap();
+            stmt.next().unwrap();
+            stmt.read(0).unwrap()
+        };
+        assert_eq!(acked, Some(1));
+    }
+
+    #[test]
+    fn failing_chats_go_behind_fresh_ones() {
+        let store = Store::open_in_memory().unwrap();
+        for message_id in 1..=3 {
+            insert(&store, message_id, message_id, "청약 공지");
+        }
+        let entries = store.peek_outbox(2).unwrap();
+        store
+            .fail_outbox(&entries[0].client_op_id, "rejected")
+            .unwrap();
+        store
+            .fail_outbox(&entries[1].client_op_id, "rejected")
+            .unwrap();
+
+        let ids = |entries: Vec<OutboxEntry>| -> Vec<Option<i64>> {
+            entries.into_iter().map(|e| e.message_id).collect()
+        };
+        assert_eq!(ids(store.peek_outbox(2).unwrap()), vec![Some(3), Some(1)]);
+        store
+            .fail_outbox(&entries[0].client_op_id, "rejected")
+            .unwrap();
+        assert_eq!(
+            ids(store.peek_outbox(10).unwrap()),
+            vec![Some(3), Some(2), Some(1)]
+        );
+    }
+
+    #[test]
+    fn a_chat_keeps_its_ops_in_order_when_one_fails() {
+        let store = Store::open_in_memory().unwrap();
+        store.upsert_chat(&chat(1, "공모주 방")).unwrap();
+        store.set_chat_excluded(0, 1, true).unwrap();
+        store.set_chat_excluded(0, 1, false).unwrap();
+        store.upsert_chat(&chat(1, "공모주 청약 방")).unwrap();
+        insert(&store, 2, 1, "다른 방");
+        let purge = store.peek_outbox(1).unwrap().remove(0);
+        assert_eq!(purge.op, OutboxOp::ChatPurge);
+        store.fail_outbox(&purge.client_op_id, "rejected").unwrap();
+
+        // The metadata queued after the purge must not reach the cloud
+        // first, or the purge would drop the chat again.
+        assert_eq!(
+            store
+                .peek_outbox(10)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.op, e.chat_id))
+                .collect::<Vec<_>>(),
+            vec![
+                (OutboxOp::MsgUpsert, 2),
+                (OutboxOp::ChatPurge, 1),
+                (OutboxOp::ChatMeta, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn tombstones_wait_for_the_cloud_to_ack_the_delete() {
+        let store = Store::open_in_memory().unwrap();
+        store.set_cloud_sync_enabled(true).unwrap();
+        store.set_tombstone_retention_days(0).unwrap();
+        insert(&store, 1, 1, "삭제될 메시지");
+        store.delete_messages(&[message_ref(1, 1)]).unwrap();
+        let now = crate::wiki::norm::unix_now();
+        assert_eq!(store.purge_tombstones(now).unwrap(), 0);
+
+        let ids: Vec<String> = store
+            .peek_outbox(10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.client_op_id)
+            .collect();
+        store.ack_outbox(&ids).unwrap();
+        assert_eq!(store.purge_tombstones(now).unwrap(), 1);
+    }
+
+    #[test]
+    fn excluding_a_chat_cancels_its_ops_and_queues_a_purge() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, 1, "비공개 방 메시지");
+        insert(&store, 2, 1, "공개 방 메시지");
+        store.set_chat_excluded(0, 1, true).unwrap();
+        insert(&store, 1, 2, "제외 후 메시지");
+        assert_eq!(
+            store
+                .peek_outbox(10)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.op, e.chat_id))
+                .collect::<Vec<_>>(),
+            vec![(OutboxOp::MsgUpsert, 2), (OutboxOp::ChatPurge, 1)]
+        );
+    }
+
+    #[test]
+    fn gaps_below_the_watermark_are_requeued() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, 1, "예전 메시지");
+        insert(&store, 1, 2, "예전 답장");
+        // Queued on insert, so not a gap.
+        assert_eq!(store.requeue_outbox_gaps().unwrap(), 0);
+
+        // Indexed before the outbox existed: nothing queued, nothing acked.
+        store.conn.execute("DELETE FROM cloud_outbox").unwrap();
+        assert_eq!(store.requeue_outbox_gaps().unwrap(), 2);
+        // Already queued, so not again.
+        assert_eq!(store.requeue_outbox_gaps().unwrap(), 0);
+
+        let entries = store.peek_outbox(10).unwrap();
+        store
+            .ack_outbox(&[entries[0].client_op_id.clone()])
+            .unwrap();
+        // A lost op is queued again; the acked message is not.
+        store.fail_outbox(&entries[1].client_op_id, "lost").unwrap();
+        store.conn.execute("DELETE FROM cloud_outbox").unwrap();
+        assert_eq!(store.requeue_outbox_gaps().unwrap(), 1);
+        assert_eq!(ops(&store), vec![(OutboxOp::MsgUpsert, Some(2), Some(1))]);
+
+        let stats = store.outbox_stats().unwrap();
+        assert_eq!((stats.pending, stats.failing), (1, 0));
+    }
+}
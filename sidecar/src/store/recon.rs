@@ -0,0 +1,493 @@
+//! Per-chat reconciliation against the shell's copy of a chat.
+//!
+//! When the shell misses updates (sleep, crash) the index drifts from
+//! Postbox without anyone noticing. The shell describes what it holds
+//! for a chat, either as exact runs of message ids or as per-bucket
+//! digests, and [`Store::reconcile_chat`] answers with the id ranges
+//! the shell should index again.
+//!
+//! `postbox_recon_watermark` keeps, per chat, `max_msg_id` (the highest
+//! message id known from indexing or reconciliation) and
+//! `last_full_diff` (when the shell last described the whole chat).
+//! Cloud sync scans each chat up to the watermark for messages the
+//! cloud never acked (see [`Store::requeue_outbox_gaps`]).
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+/// Inclusive run of message ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+/// The shell's summary of the messages it holds with ids in
+/// `from..=to`: how many, and [`bucket_digest`] over them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketDigest {
+    pub from: i64,
+    pub to: i64,
+    pub count: i64,
+    pub digest: String,
+}
+
+/// What the shell holds for one chat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ChatSummary {
+    /// Every message id the shell holds, as runs.
+    Ranges { ranges: Vec<IdRange> },
+    /// Digests of consecutive id buckets; catches edits as well.
+    Digest { buckets: Vec<BucketDigest> },
+}
+
+/// Ranges the shell should send again, and the watermark after the
+/// diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconReport {
+    pub account_id: i64,
+    pub chat_id: i64,
+    /// Ids (or buckets) the shell holds and the index does not.
+    pub missing: Vec<IdRange>,
+    /// Buckets whose content differs from the shell's.
+    pub stale: Vec<IdRange>,
+    /// Ids indexed here that the shell no longer holds, within the
+    /// span it described. Reported only; deleting them is up to the
+    /// shell.
+    pub extra: Vec<IdRange>,
+    pub max_msg_id: i64,
+    pub last_full_diff: i64,
+}
+
+impl ReconReport {
+    /// `missing` and `stale` merged: what to backfill.
+    pub fn backfill_ranges(&self) -> Vec<IdRange> {
+        let mut ranges: Vec<IdRange> = self.missing.iter().chain(&self.stale).copied().collect();
+        ranges.sort_by_key(|r| r.from);
+        coalesce(ranges)
+    }
+}
+
+/// Digest of one bucket as the shell computes it: blake3 over each
+/// message in ascending id order, as the id (8 bytes, little endian)
+/// followed by the blake3 hash of its text. Hex encoded.
+pub fn bucket_digest<'a>(messages: impl IntoIterator<Item = (i64, &'a str)>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for (message_id, text) in messages {
+        hasher.update(&message_id.to_le_bytes());
+        hasher.update(blake3::hash(text.as_bytes()).as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Raise the watermark of a chat to `message_id`. Runs inside the
+/// caller's transaction.
+pub(crate) fn note_message_seen(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO postbox_recon_watermark (account_id, chat_id, max_msg_id)
+         VALUES (?, ?, ?)
+         ON CONFLICT(account_id, chat_id) DO UPDATE SET
+            max_msg_id = MAX(max_msg_id, excluded.max_msg_id)",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Sort-merged runs, overlapping or adjacent ones joined.
+fn coalesce(sorted: Vec<IdRange>) -> Vec<IdRange> {
+    let mut out: Vec<IdRange> = Vec::with_capacity(sorted.len());
+    for range in sorted {
+        match out.last_mut() {
+            Some(last) if range.from <= last.to.saturating_add(1) => {
+                last.to = last.to.max(range.to);
+            }
+            _ => out.push(range),
+        }
+    }
+    out
+}
+
+impl Store {
+    /// `(max_msg_id, last_full_diff)` of a chat, if anything was
+    /// indexed or reconciled in it.
+    pub fn recon_watermark(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<Option<(i64, i64)>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT max_msg_id, last_full_diff FROM postbox_recon_watermark
+             WHERE account_id = ? AND chat_id = ?",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        if let sqlite::State::Row = stmt.next()? {
+            Ok(Some((stmt.read::<i64, _>(0)?, stmt.read::<i64, _>(1)?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Diff a chat against the shell's `summary` and raise its
+    /// watermark to the highest id described. `full` says the summary
+    /// covers the whole chat, which stamps `last_full_diff` with `now`
+    /// (unix seconds).
+    ///
+    /// Deleted messages count as held: re-sending them would not bring
+    /// them back, so they are never reported missing. A digest bucket
+    /// matches with its tombstones left out (the shell dropped them
+    /// too) or counted in (it still holds them); one where the shell
+    /// holds only some of them stays stale until they are purged.
+    pub fn reconcile_chat(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        summary: &ChatSummary,
+        full: bool,
+        now: i64,
+    ) -> Result<ReconReport, sqlite::Error> {
+        let mut report = ReconReport {
+            account_id,
+            chat_id,
+            ..ReconReport::default()
+        };
+        let mut top: Option<i64> = None;
+        match summary {
+            ChatSummary::Ranges { ranges } => {
+                let mut held: Vec<IdRange> =
+                    ranges.iter().filter(|r| r.from <= r.to).copied().collect();
+                held.sort_by_key(|r| r.from);
+                let held = coalesce(held);
+                let mut missing = Vec::new();
+                for range in &held {
+                    // `None` once past `i64::MAX`.
+                    let mut next = Some(range.from);
+                    for id in self.recon_ids(account_id, chat_id, range, true)? {
+                        if let Some(from) = next.filter(|&n| id > n) {
+                            missing.push(IdRange { from, to: id - 1 });
+                        }
+                        next = id.checked_add(1);
+                    }
+                    if let Some(from) = next.filter(|&n| n <= range.to) {
+                        missing.push(IdRange { from, to: range.to });
+                    }
+                }
+                report.missing = coalesce(missing);
+
+                if let (Some(first), Some(last)) = (held.first(), held.last()) {
+                    let span = IdRange {
+                        from: first.from,
+                        to: last.to,
+                    };
+                    let mut extra = Vec::new();
+                    let mut held_runs = held.iter().peekable();
+                    for id in self.recon_ids(account_id, chat_id, &span, false)? {
+                        while held_runs.next_if(|r| r.to < id).is_some() {}
+                        if held_runs.peek().is_none_or(|r| id < r.from) {
+                            extra.push(IdRange { from: id, to: id });
+                        }
+                    }
+                    report.extra = coalesce(extra);
+                    top = Some(last.to);
+                }
+            }
+            ChatSummary::Digest { buckets } => {
+                for bucket in buckets.iter().filter(|b| b.from <= b.to) {
+                    let range = IdRange {
+                        from: bucket.from,
+                        to: bucket.to,
+                    };
+                    let rows = self.recon_texts(account_id, chat_id, &range)?;
+                    let matches = |with_deleted: bool| {
+                        let digest = bucket_digest(
+                            rows.iter()
+                                .filter(|(_, _, deleted)| with_deleted || !deleted)
+                                .map(|(id, text, _)| (*id, text.as_str())),
+                        );
+                        digest.eq_ignore_ascii_case(&bucket.digest)
+                    };
+                    if (rows.len() as i64) < bucket.count {
+                        report.missing.push(range);
+                    } else if !matches(false) && !matches(true) {
+                        report.stale.push(range);
+                    }
+                    if bucket.count > 0 {
+                        top = Some(top.map_or(bucket.to, |t| t.max(bucket.to)));
+                    }
+                }
+                report.missing.sort_by_key(|r| r.from);
+                report.stale.sort_by_key(|r| r.from);
+            }
+        }
+
+        if let Some(top) = top {
+            note_message_seen(&self.conn, account_id, chat_id, top)?;
+        }
+        if full {
+            let mut stmt = self.conn.prepare(
+                "INSERT INTO postbox_recon_watermark (account_id, chat_id, max_msg_id, last_full_diff)
+                 VALUES (?, ?, 0, ?)
+                 ON CONFLICT(account_id, chat_id) DO UPDATE SET
+                    last_full_diff = excluded.last_full_diff",
+            )?;
+            stmt.bind((1, account_id))?;
+            stmt.bind((2, chat_id))?;
+            stmt.bind((3, now))?;
+            stmt.next()?;
+        }
+        if let Some((max_msg_id, last_full_diff)) = self.recon_watermark(account_id, chat_id)? {
+            report.max_msg_id = max_msg_id;
+            report.last_full_diff = last_full_diff;
+        }
+        Ok(report)
+    }
+
+    /// Ids in `range`, ascending. Tombstones are left out unless
+    /// `with_deleted`.
+    fn recon_ids(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        range: &IdRange,
+        with_deleted: bool,
+    ) -> Result<Vec<i64>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id FROM messages
+             WHERE account_id = ? AND chat_id = ? AND message_id BETWEEN ? AND ?
+               AND (? OR deleted_at IS NULL)
+             ORDER BY message_id",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, range.from))?;
+        stmt.bind((4, range.to))?;
+        stmt.bind((5, i64::from(with_deleted)))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(stmt.read::<i64, _>(0)?);
+        }
+        Ok(out)
+    }
+
+    /// `(id, text, deleted)` of every message in `range`, tombstones
+    /// included, ascending.
+    fn recon_texts(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        range: &IdRange,
+    ) -> Result<Vec<(i64, String, bool)>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, text_plain, deleted_at IS NOT NULL FROM messages
+             WHERE account_id = ? AND chat_id = ? AND message_id BETWEEN ? AND ?
+             ORDER BY message_id",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, range.from))?;
+        stmt.bind((4, range.to))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push((
+                stmt.read::<i64, _>(0)?,
+                stmt.read::<String, _>(1)?,
+                stmt.read::<i64, _>(2)? != 0,
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+    fn insert(store: &Store, message_id: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1_700_000_000 + message_id % 86_400,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    fn range(from: i64, to: i64) -> IdRange {
+        IdRange { from, to }
+    }
+
+    fn delete(store: &Store, message_id: i64) {
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id,
+            }])
+            .unwrap();
+    }
+
+    fn digest_summary(shell: &[(i64, &str)], from: i64, to: i64) -> ChatSummary {
+        let held: Vec<(i64, &str)> = shell
+            .iter()
+            .copied()
+            .filter(|(id, _)| (from..=to).contains(id))
+            .collect();
+        ChatSummary::Digest {
+            buckets: vec![BucketDigest {
+                from,
+                to,
+                count: held.len() as i64,
+                digest: bucket_digest(held),
+            }],
+        }
+    }
+
+    #[test]
+    fn ranges_report_missing_and_extra_ids() {
+        let store = Store::open_in_memory().unwrap();
+        for id in [1, 2, 5, 6, 9, 20] {
+            insert(&store, id, &format!("메시지 {id}"));
+        }
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id: 6,
+            }])
+            .unwrap();
+
+        // The shell holds 1..=7 and 9..=12; 20 is beyond what it sent.
+        let summary = ChatSummary::Ranges {
+            ranges: vec![range(9, 12), range(1, 7)],
+        };
+        let report = store.reconcile_chat(0, 1, &summary, false, 0).unwrap();
+        assert_eq!(
+            report.missing,
+            vec![range(3, 4), range(7, 7), range(10, 12)]
+        );
+        assert_eq!(report.extra, vec![]);
+        assert_eq!(report.max_msg_id, 20);
+        assert_eq!(report.last_full_diff, 0);
+
+        // Without 2 and 5 the shell lost them: reported, not deleted.
+        let summary = ChatSummary::Ranges {
+            ranges: vec![range(1, 1), range(3, 4), range(6, 30)],
+        };
+        let report = store
+            .reconcile_chat(0, 1, &summary, true, 1_700_000_500)
+            .unwrap();
+        assert_eq!(report.extra, vec![range(2, 2), range(5, 5)]);
+        assert_eq!(report.max_msg_id, 30);
+        assert_eq!(report.last_full_diff, 1_700_000_500);
+        assert!(store.get_message(0, 1, 2).unwrap().is_some());
+        assert_eq!(store.recon_watermark(0, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn digests_find_missing_and_edited_buckets() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, "공모주 청약");
+        insert(&store, 2, "환불일 안내");
+        insert(&store, 11, "배당 기준일");
+        insert(&store, 25, "상장일");
+
+        let shell = [
+            (1, "공모주 청약"),
+            (2, "환불일 안내"),
+            (11, "배당 기준일 변경"),
+            (25, "상장일"),
+            (27, "시초가"),
+        ];
+        let buckets: Vec<BucketDigest> = [(1, 10), (11, 20), (21, 30)]
+            .into_iter()
+            .map(|(from, to)| {
+                let held: Vec<(i64, &str)> = shell
+                    .iter()
+                    .copied()
+                    .filter(|(id, _)| (from..=to).contains(id))
+                    .collect();
+                BucketDigest {
+                    from,
+                    to,
+                    count: held.len() as i64,
+                    digest: bucket_digest(held),
+                }
+            })
+            .collect();
+        let report = store
+            .reconcile_chat(0, 1, &ChatSummary::Digest { buckets }, false, 0)
+            .unwrap();
+        assert_eq!(report.missing, vec![range(21, 30)]);
+        assert_eq!(report.stale, vec![range(11, 20)]);
+        assert_eq!(report.backfill_ranges(), vec![range(11, 30)]);
+        assert_eq!(report.max_msg_id, 30);
+    }
+
+    #[test]
+    fn digests_settle_whether_or_not_the_shell_holds_tombstones() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, "공모주 청약");
+        insert(&store, 2, "환불일 안내");
+        insert(&store, 3, "상장일");
+        delete(&store, 2);
+
+        // Deleted on both sides.
+        let summary = digest_summary(&[(1, "공모주 청약"), (3, "상장일")], 1, 10);
+        let report = store.reconcile_chat(0, 1, &summary, false, 0).unwrap();
+        assert_eq!((report.missing, report.stale), (vec![], vec![]));
+
+        // Deleted here only: re-sending would not restore it.
+        let shell = [(1, "공모주 청약"), (2, "환불일 안내"), (3, "상장일")];
+        let report = store
+            .reconcile_chat(0, 1, &digest_summary(&shell, 1, 10), false, 0)
+            .unwrap();
+        assert_eq!((report.missing, report.stale), (vec![], vec![]));
+
+        // A message really missing is still asked for.
+        let shell = [(1, "공모주 청약"), (3, "상장일"), (4, "시초가")];
+        let report = store
+            .reconcile_chat(0, 1, &digest_summary(&shell, 1, 10), false, 0)
+            .unwrap();
+        assert_eq!(report.backfill_ranges(), vec![range(1, 10)]);
+    }
+
+    #[test]
+    fn ranges_up_to_the_last_id_do_not_overflow() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 5, "공모주 청약");
+        insert(&store, i64::MAX, "환불일 안내");
+
+        let summary = ChatSummary::Ranges {
+            ranges: vec![range(1, i64::MAX), range(i64::MAX, i64::MAX)],
+        };
+        let report = store.reconcile_chat(0, 1, &summary, false, 0).unwrap();
+        assert_eq!(report.missing, vec![range(1, 4), range(6, i64::MAX - 1)]);
+        assert_eq!(report.extra, vec![]);
+        assert_eq!(report.max_msg_id, i64::MAX);
+
+        let summary = digest_summary(&[(i64::MAX, "환불일 안내")], i64::MAX - 9, i64::MAX);
+        let report = store.reconcile_chat(0, 1, &summary, false, 0).unwrap();
+        assert_eq!((report.missing, report.stale), (vec![], vec![]));
+    }
+}
@@ -0,0 +1,191 @@
+//! Earlier text of edited messages. Before an edit overwrites a row in
+//! `messages`, the text it replaces is copied to `message_revisions`
+//! under the version it had, and `messages.msg_version` moves on by
+//! one. The current text is never duplicated here.
+//!
+//! `revisions_fts` indexes the same seven columns as `messages_fts`, so
+//! a search can match what a message used to say when asked to
+//! (`SearchFilters::include_revisions`).
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+/// One superseded version of a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageRevision {
+    pub msg_version: i64,
+    pub text_plain: String,
+    /// Caption and file name the version carried, if any.
+    pub text_media: String,
+    /// Unix seconds when the next version replaced this one.
+    pub replaced_at: i64,
+}
+
+/// Copy the current text of the `messages` row at `rowid` into
+/// `message_revisions` and its index. Call before the row is
+/// overwritten; runs inside the caller's transaction.
+pub(crate) fn record_revision(conn: &sqlite::Connection, rowid: i64) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO message_revisions
+            (account_id, chat_id, message_id, msg_version, text_plain, text_stripped,
+             text_jamo, text_choseong, text_stem, text_roman, text_media, replaced_at)
+         SELECT account_id, chat_id, message_id, msg_version, text_plain, text_stripped,
+                text_jamo, text_choseong, text_stem, text_roman, text_media,
+                CAST(strftime('%s', 'now') AS INTEGER)
+         FROM messages WHERE rowid = ?",
+    )?;
+    stmt.bind((1, rowid))?;
+    stmt.next()?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO revisions_fts(rowid, text_plain, text_stripped, text_jamo, text_choseong,
+                                   text_stem, text_roman, text_media)
+         SELECT revision_id, text_plain, text_stripped, text_jamo, text_choseong,
+                text_stem, text_roman, text_media
+         FROM message_revisions WHERE revision_id = last_insert_rowid()",
+    )?;
+    stmt.next()?;
+    Ok(())
+}
+
+/// Drop every stored revision of one message. Runs inside the caller's
+/// transaction.
+pub(crate) fn delete_revisions(
+    conn: &sqlite::Connection,
+    account_id: i64,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<(), sqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO revisions_fts(revisions_fts, rowid, text_plain, text_stripped, text_jamo,
+                                   text_choseong, text_stem, text_roman, text_media)
+         SELECT 'delete', revision_id, text_plain, text_stripped, text_jamo,
+                text_choseong, text_stem, text_roman, text_media
+         FROM message_revisions WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    stmt.next()?;
+
+    let mut stmt = conn.prepare(
+        "DELETE FROM message_revisions WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+    )?;
+    stmt.bind((1, account_id))?;
+    stmt.bind((2, chat_id))?;
+    stmt.bind((3, message_id))?;
+    stmt.next()?;
+    Ok(())
+}
+
+impl Store {
+    /// Earlier versions of one message, oldest first. The current text
+    /// is not included; it is what [`Store::get_message`] returns.
+    pub fn message_revisions(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Vec<MessageRevision>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT msg_version, text_plain, text_media, replaced_at FROM message_revisions
+             WHERE account_id = ? AND chat_id = ? AND message_id = ?
+             ORDER BY msg_version",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, message_id))?;
+        let mut revisions = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            revisions.push(MessageRevision {
+                msg_version: stmt.read::<i64, _>(0)?,
+                text_plain: stmt.read::<String, _>(1)?,
+                text_media: stmt.read::<String, _>(2)?,
+                replaced_at: stmt.read::<i64, _>(3)?,
+            });
+        }
+        Ok(revisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+    fn insert(store: &Store, ts: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id: 1,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: ts,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    fn revision_fts_hits(store: &Store, term: &str) -> i64 {
+        let mut stmt = store
+            .conn
+            .prepare("SELECT COUNT(*) FROM revisions_fts WHERE revisions_fts MATCH ?")
+            .unwrap();
+        stmt.bind((1, term)).unwrap();
+        stmt.next().unwrap();
+        stmt.read(0).unwrap()
+    }
+
+    #[test]
+    fn edits_keep_prior_text_and_bump_version() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1000, "공모주 청약 내일");
+        // A re-mirror with the same text is not an edit.
+        insert(&store, 1000, "공모주 청약 내일");
+        assert!(store.message_revisions(0, 1, 1).unwrap().is_empty());
+
+        insert(&store, 1000, "공모주 청약 모레");
+        insert(&store, 1000, "청약 취소");
+        let revisions = store.message_revisions(0, 1, 1).unwrap();
+        assert_eq!(
+            revisions
+                .iter()
+                .map(|r| (r.msg_version, r.text_plain.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "공모주 청약 내일"), (2, "공모주 청약 모레")]
+        );
+        let version: i64 = {
+            let mut stmt = store
+                .conn
+                .prepare("SELECT msg_version FROM messages WHERE chat_id = 1 AND message_id = 1")
+                .unwrap();
+            stmt.next().unwrap();
+            stmt.read(0).unwrap()
+        };
+        assert_eq!(version, 3);
+        assert_eq!(revision_fts_hits(&store, "공모주"), 2);
+
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id: 1,
+            }])
+            .unwrap();
+        // Revisions outlive the delete until its tombstone is purged.
+        assert_eq!(store.message_revisions(0, 1, 1).unwrap().len(), 2);
+        store
+            .purge_tombstones(crate::wiki::norm::unix_now() + 31 * 86_400)
+            .unwrap();
+        assert!(store.message_revisions(0, 1, 1).unwrap().is_empty());
+        assert_eq!(revision_fts_hits(&store, "공모주"), 0);
+    }
+}
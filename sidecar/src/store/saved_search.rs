@@ -0,0 +1,487 @@
+//! Saved searches: queries the user reruns all day, checked against
+//! every indexed batch so the shell can alert on fresh matches.
+//!
+//! Queries are stored as typed and planned on each evaluation with the
+//! same logic as interactive search (`search::engine`), so a saved
+//! search matches exactly what running it by hand would.
+
+use serde::{Deserialize, Serialize};
+
+use super::message::{bind_like, like_where, MessageWithChat};
+use super::Store;
+use crate::search::engine::build_match_query;
+use crate::search::query::{parse_filters, parse_query, QueryNode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub saved_search_id: i64,
+    pub name: String,
+    pub query: String,
+    /// Only match messages in this chat of `scope_account_id`; all
+    /// chats when `None`.
+    pub scope_account_id: Option<i64>,
+    pub scope_chat_id: Option<i64>,
+    pub created_at: i64,
+    /// Matches recorded since the shell last marked this search seen.
+    pub unseen_count: i64,
+    pub last_matched_at: Option<i64>,
+}
+
+/// Fresh matches one saved search picked up from one indexed batch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedSearchMatch {
+    pub saved_search_id: i64,
+    pub name: String,
+    pub new_matches: u64,
+}
+
+/// Check the messages at `rowids` (just inserted or re-texted) against
+/// every saved search and record the hits. Runs inside the caller's
+/// transaction. A message already recorded for a search is not
+/// counted again, so edits do not re-alert.
+pub(crate) fn evaluate_saved_searches(
+    conn: &sqlite::Connection,
+    rowids: &[i64],
+) -> Result<Vec<SavedSearchMatch>, sqlite::Error> {
+    let (Some(&min_rowid), Some(&max_rowid)) = (rowids.iter().min(), rowids.iter().max()) else {
+        return Ok(vec![]);
+    };
+    let searches = {
+        let mut stmt = conn.prepare(
+            "SELECT saved_search_id, name, query, scope_account_id, scope_chat_id
+             FROM saved_searches",
+        )?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            let scope_account_id = stmt.read::<Option<i64>, _>(3)?;
+            let scope_chat_id = stmt.read::<Option<i64>, _>(4)?;
+            out.push((
+                stmt.read::<i64, _>(0)?,
+                stmt.read::<String, _>(1)?,
+                stmt.read::<String, _>(2)?,
+                scope_chat_id.map(|chat_id| (scope_account_id.unwrap_or(0), chat_id)),
+            ));
+        }
+        out
+    };
+    if searches.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let batch: std::collections::HashSet<i64> = rowids.iter().copied().collect();
+    let now = crate::wiki::norm::unix_now();
+    let mut matches = Vec::new();
+    for (saved_search_id, name, query, scope_chat) in searches {
+        let hits = match_batch(conn, &query, scope_chat, min_rowid, max_rowid)?;
+        let mut new_matches = 0;
+        for (rowid, account_id, chat_id, message_id) in hits {
+            if !batch.contains(&rowid) {
+                continue;
+            }
+            let mut stmt = conn.prepare(
+                "INSERT OR IGNORE INTO saved_search_hits
+                    (saved_search_id, account_id, chat_id, message_id, matched_at)
+                 VALUES (?, ?, ?, ?, ?)",
+            )?;
+            stmt.bind((1, saved_search_id))?;
+            stmt.bind((2, account_id))?;
+            stmt.bind((3, chat_id))?;
+            stmt.bind((4, message_id))?;
+            stmt.bind((5, now))?;
+            stmt.next()?;
+            new_matches += conn.change_count() as u64;
+        }
+        if new_matches > 0 {
+            matches.push(SavedSearchMatch {
+                saved_search_id,
+                name,
+                new_matches,
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// `(rowid, account_id, chat_id, message_id)` of live messages in the rowid range
+/// that `query` matches, planned like interactive search: FTS when the
+/// query compiles to a MATCH, the LIKE fallback otherwise.
+fn match_batch(
+    conn: &sqlite::Connection,
+    query: &str,
+    scope_chat: Option<(i64, i64)>,
+    min_rowid: i64,
+    max_rowid: i64,
+) -> Result<Vec<(i64, i64, i64, i64)>, sqlite::Error> {
+    let parsed = parse_query(query.trim());
+    let filters = parse_filters(query);
+    if parsed.is_none() && !filters.has_content_filters() {
+        return Ok(vec![]);
+    }
+    let chat_clause = if scope_chat.is_some() {
+        "AND m.account_id = ? AND m.chat_id = ?"
+    } else {
+        ""
+    };
+    let filter_clause = filters.sql_clause();
+    let fts_query = build_match_query(query.trim());
+    let like = parsed.as_ref().map(QueryNode::to_like_match);
+    let (like_clause, like_params) = like_where(like.as_ref());
+    let mut stmt = match &fts_query {
+        Some(_) => conn.prepare(format!(
+            "SELECT m.rowid, m.account_id, m.chat_id, m.message_id
+             FROM messages_fts
+             JOIN messages m ON m.rowid = messages_fts.rowid
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             WHERE messages_fts MATCH ? AND messages_fts.rowid BETWEEN ? AND ?
+             AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             {chat_clause} {filter_clause}"
+        ))?,
+        None if !like.as_ref().is_some_and(|l| l.has_positive())
+            && !filters.has_content_filters() =>
+        {
+            return Ok(vec![])
+        }
+        None => conn.prepare(format!(
+            "SELECT m.rowid, m.account_id, m.chat_id, m.message_id
+             FROM messages m
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             WHERE {} AND m.rowid BETWEEN ? AND ?
+             AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             {chat_clause} {filter_clause}",
+            like_clause
+        ))?,
+    };
+    let mut bind_idx = 1;
+    match &fts_query {
+        Some(fts_query) => {
+            stmt.bind((bind_idx, fts_query.as_str()))?;
+            bind_idx += 1;
+        }
+        None => bind_like(&mut stmt, &mut bind_idx, &like_params)?,
+    }
+    stmt.bind((bind_idx, min_rowid))?;
+    stmt.bind((bind_idx + 1, max_rowid))?;
+    bind_idx += 2;
+    if let Some((account_id, chat_id)) = scope_chat {
+        stmt.bind((bind_idx, account_id))?;
+        stmt.bind((bind_idx + 1, chat_id))?;
+        bind_idx += 2;
+    }
+    filters.bind(&mut stmt, &mut bind_idx)?;
+
+    let mut out = Vec::new();
+    while let sqlite::State::Row = stmt.next()? {
+        out.push((
+            stmt.read::<i64, _>(0)?,
+            stmt.read::<i64, _>(1)?,
+            stmt.read::<i64, _>(2)?,
+            stmt.read::<i64, _>(3)?,
+        ));
+    }
+    Ok(out)
+}
+
+const SAVED_SEARCH_SELECT: &str =
+    "SELECT s.saved_search_id, s.name, s.query, s.scope_account_id, s.scope_chat_id,
+        s.created_at,
+        (SELECT COUNT(*) FROM saved_search_hits h
+         WHERE h.saved_search_id = s.saved_search_id AND h.seen = 0) AS unseen_count,
+        (SELECT MAX(h.matched_at) FROM saved_search_hits h
+         WHERE h.saved_search_id = s.saved_search_id) AS last_matched_at
+     FROM saved_searches s";
+
+impl Store {
+    /// Save `query` under `name` and return its id, optionally scoped
+    /// to one `(account_id, chat_id)`. Only messages indexed from now
+    /// on are checked; use search for the backlog.
+    pub fn create_saved_search(
+        &self,
+        name: &str,
+        query: &str,
+        scope_chat: Option<(i64, i64)>,
+    ) -> Result<i64, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO saved_searches
+                (name, query, scope_account_id, scope_chat_id, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )?;
+        stmt.bind((1, name))?;
+        stmt.bind((2, query))?;
+        stmt.bind((3, scope_chat.map(|(account_id, _)| account_id)))?;
+        stmt.bind((4, scope_chat.map(|(_, chat_id)| chat_id)))?;
+        stmt.bind((5, crate::wiki::norm::unix_now()))?;
+        stmt.next()?;
+
+        let mut stmt = self.conn.prepare("SELECT last_insert_rowid()")?;
+        stmt.next()?;
+        stmt.read::<i64, _>(0)
+    }
+
+    /// Rename or re-target a saved search. Changing the query or scope
+    /// drops the recorded hits, which belonged to the old one. Returns
+    /// `false` if no such search exists.
+    pub fn update_saved_search(
+        &self,
+        saved_search_id: i64,
+        name: &str,
+        query: &str,
+        scope_chat: Option<(i64, i64)>,
+    ) -> Result<bool, sqlite::Error> {
+        let Some(current) = self.get_saved_search(saved_search_id)? else {
+            return Ok(false);
+        };
+        let current_scope = current
+            .scope_chat_id
+            .map(|chat_id| (current.scope_account_id.unwrap_or(0), chat_id));
+        if current.query != query || current_scope != scope_chat {
+            let mut stmt = self
+                .conn
+                .prepare("DELETE FROM saved_search_hits WHERE saved_search_id = ?")?;
+            stmt.bind((1, saved_search_id))?;
+            stmt.next()?;
+        }
+        let mut stmt = self.conn.prepare(
+            "UPDATE saved_searches
+             SET name = ?, query = ?, scope_account_id = ?, scope_chat_id = ?
+             WHERE saved_search_id = ?",
+        )?;
+        stmt.bind((1, name))?;
+        stmt.bind((2, query))?;
+        stmt.bind((3, scope_chat.map(|(account_id, _)| account_id)))?;
+        stmt.bind((4, scope_chat.map(|(_, chat_id)| chat_id)))?;
+        stmt.bind((5, saved_search_id))?;
+        stmt.next()?;
+        Ok(true)
+    }
+
+    /// Delete a saved search and its hits. Returns `false` if no such
+    /// search exists.
+    pub fn delete_saved_search(&self, saved_search_id: i64) -> Result<bool, sqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM saved_searches WHERE saved_search_id = ?")?;
+        stmt.bind((1, saved_search_id))?;
+        stmt.next()?;
+        Ok(self.conn.change_count() > 0)
+    }
+
+    pub fn get_saved_search(
+        &self,
+        saved_search_id: i64,
+    ) -> Result<Option<SavedSearch>, sqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare(format!("{SAVED_SEARCH_SELECT} WHERE s.saved_search_id = ?"))?;
+        stmt.bind((1, saved_search_id))?;
+        if let sqlite::State::Row = stmt.next()? {
+            Ok(Some(read_saved_search(&stmt)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(format!(
+            "{SAVED_SEARCH_SELECT} ORDER BY s.created_at, s.saved_search_id"
+        ))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(read_saved_search(&stmt)?);
+        }
+        Ok(out)
+    }
+
+    /// Recorded matches of a saved search, newest message first.
+    /// Messages deleted since or in chats excluded since are skipped.
+    pub fn saved_search_hits(
+        &self,
+        saved_search_id: i64,
+        limit: usize,
+    ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, c.title,
+                    sn.display_name, m.account_id
+             FROM saved_search_hits h
+             JOIN messages m ON m.account_id = h.account_id AND m.chat_id = h.chat_id
+                            AND m.message_id = h.message_id
+             JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE h.saved_search_id = ? AND c.is_excluded = 0 AND m.deleted_at IS NULL
+             ORDER BY m.timestamp DESC, m.account_id ASC, m.chat_id ASC, m.message_id ASC
+             LIMIT ?",
+        )?;
+        stmt.bind((1, saved_search_id))?;
+        stmt.bind((2, limit as i64))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(MessageWithChat {
+                message_id: stmt.read::<i64, _>(0)?,
+                account_id: stmt.read::<i64, _>(7)?,
+                chat_id: stmt.read::<i64, _>(1)?,
+                timestamp: stmt.read::<i64, _>(2)?,
+                text_plain: stmt.read::<String, _>(3)?,
+                link: stmt.read::<Option<String>, _>(4)?,
+                chat_title: stmt.read::<String, _>(5)?,
+                rank: 0.0,
+                sender_name: stmt.read::<Option<String>, _>(6)?,
+                matched_revision: None,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Clear the unseen count of a saved search.
+    pub fn mark_saved_search_seen(&self, saved_search_id: i64) -> Result<(), sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "UPDATE saved_search_hits SET seen = 1 WHERE saved_search_id = ? AND seen = 0",
+        )?;
+        stmt.bind((1, saved_search_id))?;
+        stmt.next()?;
+        Ok(())
+    }
+}
+
+fn read_saved_search(stmt: &sqlite::Statement) -> Result<SavedSearch, sqlite::Error> {
+    Ok(SavedSearch {
+        saved_search_id: stmt.read::<i64, _>("saved_search_id")?,
+        name: stmt.read::<String, _>("name")?,
+        query: stmt.read::<String, _>("query")?,
+        scope_account_id: stmt.read::<Option<i64>, _>("scope_account_id")?,
+        scope_chat_id: stmt.read::<Option<i64>, _>("scope_chat_id")?,
+        created_at: stmt.read::<i64, _>("created_at")?,
+        unseen_count: stmt.read::<i64, _>("unseen_count")?,
+        last_matched_at: stmt.read::<Option<i64>, _>("last_matched_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRow};
+
+    fn insert(store: &Store, chat_id: i64, message_id: i64, text: &str) -> Vec<SavedSearchMatch> {
+        insert_in(store, 0, chat_id, message_id, text)
+    }
+
+    fn insert_in(
+        store: &Store,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+        text: &str,
+    ) -> Vec<SavedSearchMatch> {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id,
+                chat_id,
+                timestamp: 1000 + message_id,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap()
+            .saved_search_matches
+    }
+
+    #[test]
+    fn new_messages_are_matched_and_recorded_once() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, 1, "airdrop snapshot 어제 완료");
+        let airdrop = store
+            .create_saved_search("Airdrops", "airdrop snapshot", None)
+            .unwrap();
+        let listing = store
+            .create_saved_search("상장", "상장 공지", None)
+            .unwrap();
+
+        let matches = insert(&store, 1, 2, "Airdrop SNAPSHOT tomorrow");
+        assert_eq!(
+            matches,
+            vec![SavedSearchMatch {
+                saved_search_id: airdrop,
+                name: "Airdrops".into(),
+                new_matches: 1,
+            }]
+        );
+        let matches = insert(&store, 2, 3, "업비트 상장 공지 나왔다");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].saved_search_id, listing);
+
+        // An edit that still matches does not alert again.
+        assert!(insert(&store, 1, 2, "airdrop snapshot moved").is_empty());
+
+        let searches = store.list_saved_searches().unwrap();
+        assert_eq!(searches.len(), 2);
+        // Message 1 predates the saved search.
+        assert_eq!(searches[0].unseen_count, 1);
+        let hits = store.saved_search_hits(airdrop, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, 2);
+
+        store.mark_saved_search_seen(airdrop).unwrap();
+        assert_eq!(
+            store
+                .get_saved_search(airdrop)
+                .unwrap()
+                .unwrap()
+                .unseen_count,
+            0
+        );
+    }
+
+    #[test]
+    fn scope_and_exclusions_apply() {
+        let store = Store::open_in_memory().unwrap();
+        let id = store
+            .create_saved_search("Scoped", "snapshot -rumor", Some((0, 1)))
+            .unwrap();
+        assert!(insert(&store, 2, 1, "snapshot elsewhere").is_empty());
+        assert!(insert_in(&store, 1, 1, 1, "snapshot in another account").is_empty());
+        assert!(insert(&store, 1, 2, "snapshot rumor").is_empty());
+        assert_eq!(insert(&store, 1, 3, "snapshot confirmed").len(), 1);
+        assert_eq!(store.saved_search_hits(id, 10).unwrap().len(), 1);
+        let scoped = store.get_saved_search(id).unwrap().unwrap();
+        assert_eq!(
+            (scoped.scope_account_id, scoped.scope_chat_id),
+            (Some(0), Some(1))
+        );
+
+        // Moving the scope to another account re-targets the search.
+        assert!(store
+            .update_saved_search(id, "Scoped", "snapshot -rumor", Some((1, 1)))
+            .unwrap());
+        assert!(store.saved_search_hits(id, 10).unwrap().is_empty());
+        assert_eq!(insert_in(&store, 1, 1, 2, "snapshot again").len(), 1);
+    }
+
+    #[test]
+    fn update_and_delete() {
+        let store = Store::open_in_memory().unwrap();
+        let id = store.create_saved_search("a", "snapshot", None).unwrap();
+        insert(&store, 1, 1, "snapshot");
+
+        // Renaming keeps hits; re-targeting drops them.
+        assert!(store
+            .update_saved_search(id, "b", "snapshot", None)
+            .unwrap());
+        assert_eq!(store.saved_search_hits(id, 10).unwrap().len(), 1);
+        assert!(store.update_saved_search(id, "b", "airdrop", None).unwrap());
+        assert!(store.saved_search_hits(id, 10).unwrap().is_empty());
+        assert_eq!(
+            store.get_saved_search(id).unwrap().unwrap().query,
+            "airdrop"
+        );
+
+        assert!(store.delete_saved_search(id).unwrap());
+        assert!(!store.delete_saved_search(id).unwrap());
+        assert!(!store.update_saved_search(id, "c", "x", None).unwrap());
+        assert!(store.list_saved_searches().unwrap().is_empty());
+    }
+}
@@ -78,6 +78,72 @@ pub fn run_migrations(conn: &Connection) -> Result<(), sqlite::Error> {
     // wiki module code (10 files) compiling until phase 6 rebuild.
     migrate_to_v9(conn)?;
 
+    // Phase 10: Choseong (initial-consonant) column in messages_fts so
+    // `ㅅㅅㅈㅈ` finds `삼성전자`. Phase 7 dropped an earlier attempt
+    // that lived in its own table; this one rides the v8 combined index.
+    migrate_choseong_index(conn)?;
+
+    // Phase 11: Josa/eomi-stripped column so `삼성전자를` and
+    // `삼성전자` share one indexed form.
+    migrate_stem_index(conn)?;
+
+    // Phase 12: Word vocabulary for the fuzzy "did you mean" fallback.
+    migrate_search_vocab(conn)?;
+
+    // Phase 13: Revised Romanization column so Latin and Hangul
+    // spellings of the same word find each other.
+    migrate_roman_index(conn)?;
+
+    // Phase 14: Saved searches, evaluated against each indexed batch.
+    migrate_saved_searches(conn)?;
+
+    // Phase 15: Search history, feeding query autocomplete.
+    migrate_search_history(conn)?;
+
+    // Phase 16: Links, hashtags, mentions and cashtags per message, for
+    // entity filters and rankings.
+    migrate_message_entities(conn)?;
+
+    // Phase 17: Attachment and forward metadata, with captions and file
+    // names indexed in a seventh messages_fts column.
+    migrate_message_media(conn)?;
+
+    // Phase 18: Reply and forum-topic links between messages, for reply
+    // chains, thread listings and thread-scoped search.
+    migrate_message_threads(conn)?;
+
+    // Phase 19: Sender directory with name history, for `from:` and
+    // sender names on hits.
+    migrate_senders(conn)?;
+
+    // Phase 20: Account id in the keys of chats, messages, sync state
+    // and every per-message table, so two Telegram accounts can share
+    // one store.
+    migrate_accounts(conn)?;
+
+    // Phase 21: Earlier text of edited messages, with its own FTS index
+    // so search can optionally look at what a message used to say.
+    migrate_message_revisions(conn)?;
+
+    // Phase 22: Deletes leave a tombstone (`messages.deleted_at`) that
+    // hides the message and its wiki evidence until undone or purged.
+    migrate_tombstones(conn)?;
+
+    // Phase 23: Account id on the cloud outbox, now that mutations
+    // are queued there for upload.
+    migrate_cloud_outbox(conn)?;
+
+    // Phase 24: Account id in the key of the per-chat reconciliation
+    // watermark, seeded with the highest message id of each chat.
+    migrate_recon_watermark(conn)?;
+
+    // Phase 25: When each chat last had messages indexed, for chat
+    // management over IPC.
+    migrate_chat_last_indexed(conn)?;
+
+    // Phase 26: Account of a saved search's chat scope.
+    migrate_saved_search_account(conn)?;
+
     Ok(())
 }
 
@@ -319,9 +385,12 @@ fn migrate_to_v9(conn: &Connection) -> Result<(), sqlite::Error> {
 
         seed_wiki_settings(conn)?;
 
-        conn.execute(
-            "INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '9')",
-        )?;
+        // v9 re-runs on every open; never step a later version back.
+        if get_schema_version(conn) < 9 {
+            conn.execute(
+                "INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '9')",
+            )?;
+        }
         Ok(())
     })();
 
@@ -371,6 +440,826 @@ fn seed_wiki_settings(conn: &Connection) -> Result<(), sqlite::Error> {
     Ok(())
 }
 
+fn migrate_choseong_index(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 10 {
+        return Ok(());
+    }
+
+    if !column_exists(conn, "messages", "text_choseong")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN text_choseong TEXT NOT NULL DEFAULT ''")?;
+    }
+
+    backfill_text_column(conn, "text_choseong", crate::search::hangul::to_choseong)?;
+
+    conn.execute("DROP TABLE IF EXISTS messages_fts")?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE messages_fts USING fts5(
+            text_plain, text_stripped, text_jamo, text_choseong,
+            content='messages',
+            content_rowid='rowid',
+            tokenize='trigram case_sensitive 0'
+        )",
+    )?;
+
+    conn.execute("INSERT INTO messages_fts(messages_fts) VALUES('rebuild')")?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '10')")?;
+
+    Ok(())
+}
+
+fn migrate_stem_index(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 11 {
+        return Ok(());
+    }
+
+    if !column_exists(conn, "messages", "text_stem")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN text_stem TEXT NOT NULL DEFAULT ''")?;
+    }
+
+    backfill_text_column(conn, "text_stem", crate::search::hangul::stem_korean)?;
+
+    conn.execute("DROP TABLE IF EXISTS messages_fts")?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE messages_fts USING fts5(
+            text_plain, text_stripped, text_jamo, text_choseong, text_stem,
+            content='messages',
+            content_rowid='rowid',
+            tokenize='trigram case_sensitive 0'
+        )",
+    )?;
+
+    conn.execute("INSERT INTO messages_fts(messages_fts) VALUES('rebuild')")?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '11')")?;
+
+    Ok(())
+}
+
+fn migrate_roman_index(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 13 {
+        return Ok(());
+    }
+
+    if !column_exists(conn, "messages", "text_roman")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN text_roman TEXT NOT NULL DEFAULT ''")?;
+    }
+
+    backfill_text_column(conn, "text_roman", crate::search::hangul::romanize)?;
+
+    conn.execute("DROP TABLE IF EXISTS messages_fts")?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE messages_fts USING fts5(
+            text_plain, text_stripped, text_jamo, text_choseong, text_stem, text_roman,
+            content='messages',
+            content_rowid='rowid',
+            tokenize='trigram case_sensitive 0'
+        )",
+    )?;
+
+    conn.execute("INSERT INTO messages_fts(messages_fts) VALUES('rebuild')")?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '13')")?;
+
+    Ok(())
+}
+
+fn migrate_saved_searches(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 14 {
+        return Ok(());
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS saved_searches (
+            saved_search_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name            TEXT NOT NULL,
+            query           TEXT NOT NULL,
+            scope_chat_id   INTEGER,
+            created_at      INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_search_hits (
+            saved_search_id INTEGER NOT NULL
+                REFERENCES saved_searches(saved_search_id) ON DELETE CASCADE,
+            account_id      INTEGER NOT NULL DEFAULT 0,
+            chat_id         INTEGER NOT NULL,
+            message_id      INTEGER NOT NULL,
+            matched_at      INTEGER NOT NULL,
+            seen            INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (saved_search_id, account_id, chat_id, message_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_saved_search_hits_seen
+            ON saved_search_hits (saved_search_id, seen);",
+    )?;
+
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '14')")?;
+
+    Ok(())
+}
+
+fn migrate_search_history(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 15 {
+        return Ok(());
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS search_history (
+            query        TEXT PRIMARY KEY,
+            query_jamo   TEXT NOT NULL,
+            use_count    INTEGER NOT NULL DEFAULT 1,
+            last_used_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_search_history_jamo
+            ON search_history (query_jamo);
+        CREATE INDEX IF NOT EXISTS idx_search_history_recent
+            ON search_history (last_used_at DESC);",
+    )?;
+
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '15')")?;
+
+    Ok(())
+}
+
+fn migrate_saved_search_account(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 26 {
+        return Ok(());
+    }
+
+    // Set exactly when `scope_chat_id` is. Existing scopes take the
+    // first account that has the chat.
+    if !column_exists(conn, "saved_searches", "scope_account_id")? {
+        conn.execute(
+            "ALTER TABLE saved_searches ADD COLUMN scope_account_id INTEGER;
+             UPDATE saved_searches SET scope_account_id = COALESCE(
+                 (SELECT MIN(c.account_id) FROM chats c
+                  WHERE c.chat_id = saved_searches.scope_chat_id), 0)
+             WHERE scope_chat_id IS NOT NULL;",
+        )?;
+    }
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '26')")?;
+
+    Ok(())
+}
+
+fn migrate_chat_last_indexed(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 25 {
+        return Ok(());
+    }
+
+    // Unknown (NULL) for chats indexed before this phase.
+    if !column_exists(conn, "chats", "last_indexed_at")? {
+        conn.execute("ALTER TABLE chats ADD COLUMN last_indexed_at INTEGER")?;
+    }
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '25')")?;
+
+    Ok(())
+}
+
+fn migrate_recon_watermark(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 24 {
+        return Ok(());
+    }
+
+    conn.execute("BEGIN")?;
+    let result = (|| -> Result<(), sqlite::Error> {
+        add_account_key(
+            conn,
+            "postbox_recon_watermark",
+            "CREATE TABLE postbox_recon_watermark_new (
+                account_id     INTEGER NOT NULL DEFAULT 0,
+                chat_id        INTEGER NOT NULL,
+                max_msg_id     INTEGER NOT NULL,
+                last_full_diff INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (account_id, chat_id)
+            )",
+            "chat_id, max_msg_id, last_full_diff",
+            "",
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO postbox_recon_watermark (account_id, chat_id, max_msg_id)
+             SELECT account_id, chat_id, MAX(message_id) FROM messages
+             GROUP BY account_id, chat_id",
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '24')",
+        )?;
+        Ok(())
+    })();
+    match result {
+        Ok(()) => conn.execute("COMMIT"),
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+fn migrate_cloud_outbox(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 23 {
+        return Ok(());
+    }
+
+    if !column_exists(conn, "cloud_outbox", "account_id")? {
+        conn.execute("ALTER TABLE cloud_outbox ADD COLUMN account_id INTEGER NOT NULL DEFAULT 0")?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS ix_outbox_message
+            ON cloud_outbox (account_id, chat_id, message_id)",
+    )?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '23')")?;
+
+    Ok(())
+}
+
+fn migrate_tombstones(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 22 {
+        return Ok(());
+    }
+
+    if !column_exists(conn, "wiki_evidence", "hidden_at")? {
+        conn.execute("ALTER TABLE wiki_evidence ADD COLUMN hidden_at INTEGER")?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_messages_deleted_at
+            ON messages (deleted_at) WHERE deleted_at IS NOT NULL",
+    )?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '22')")?;
+
+    Ok(())
+}
+
+fn migrate_message_revisions(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 21 {
+        return Ok(());
+    }
+
+    // Edits were overwritten in place until now, so history starts
+    // with the next edit of each message.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_revisions (
+            revision_id   INTEGER PRIMARY KEY,
+            account_id    INTEGER NOT NULL DEFAULT 0,
+            chat_id       INTEGER NOT NULL,
+            message_id    INTEGER NOT NULL,
+            msg_version   INTEGER NOT NULL,
+            text_plain    TEXT NOT NULL,
+            text_stripped TEXT NOT NULL,
+            text_jamo     TEXT NOT NULL DEFAULT '',
+            text_choseong TEXT NOT NULL DEFAULT '',
+            text_stem     TEXT NOT NULL DEFAULT '',
+            text_roman    TEXT NOT NULL DEFAULT '',
+            text_media    TEXT NOT NULL DEFAULT '',
+            replaced_at   INTEGER NOT NULL,
+            UNIQUE (account_id, chat_id, message_id, msg_version)
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS revisions_fts USING fts5(
+            text_plain, text_stripped, text_jamo, text_choseong, text_stem, text_roman,
+            text_media,
+            content='message_revisions',
+            content_rowid='revision_id',
+            tokenize='trigram case_sensitive 0'
+        );",
+    )?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '21')")?;
+
+    Ok(())
+}
+
+fn migrate_accounts(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 20 {
+        return Ok(());
+    }
+
+    // Everything indexed so far came from one account; it becomes
+    // account 0. Keys change, so each table is rebuilt; rowids are
+    // copied along so messages_fts and evidence_fts stay valid.
+    // Tables that already have the column (created by phases 14, 16
+    // and 17 on this version) are left alone.
+    conn.execute("PRAGMA foreign_keys = OFF")?;
+    conn.execute("BEGIN")?;
+    let result = (|| -> Result<(), sqlite::Error> {
+        add_account_key(
+            conn,
+            "chats",
+            "CREATE TABLE chats_new (
+                account_id    INTEGER NOT NULL DEFAULT 0,
+                chat_id       INTEGER NOT NULL,
+                title         TEXT NOT NULL,
+                chat_type     TEXT NOT NULL CHECK (chat_type IN ('group', 'supergroup', 'channel', 'dm')),
+                username      TEXT,
+                access_hash   INTEGER,
+                is_excluded   INTEGER NOT NULL DEFAULT 0,
+                created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (account_id, chat_id)
+            )",
+            "chat_id, title, chat_type, username, access_hash, is_excluded, created_at",
+            "",
+        )?;
+        add_account_key(
+            conn,
+            "messages",
+            "CREATE TABLE messages_new (
+                account_id          INTEGER NOT NULL DEFAULT 0,
+                message_id          INTEGER NOT NULL,
+                chat_id             INTEGER NOT NULL,
+                timestamp           INTEGER NOT NULL,
+                text_plain          TEXT NOT NULL,
+                text_stripped       TEXT NOT NULL,
+                link                TEXT,
+                text_jamo           TEXT NOT NULL DEFAULT '',
+                msg_version         INTEGER NOT NULL DEFAULT 1,
+                deleted_at          INTEGER,
+                cloud_acked_version INTEGER,
+                sender_id           INTEGER,
+                text_choseong       TEXT NOT NULL DEFAULT '',
+                text_stem           TEXT NOT NULL DEFAULT '',
+                text_roman          TEXT NOT NULL DEFAULT '',
+                text_media          TEXT NOT NULL DEFAULT '',
+                reply_to_message_id INTEGER,
+                thread_id           INTEGER,
+                PRIMARY KEY (account_id, chat_id, message_id),
+                FOREIGN KEY (account_id, chat_id) REFERENCES chats(account_id, chat_id)
+            )",
+            "message_id, chat_id, timestamp, text_plain, text_stripped, link, text_jamo,
+             msg_version, deleted_at, cloud_acked_version, sender_id, text_choseong,
+             text_stem, text_roman, text_media, reply_to_message_id, thread_id",
+            "CREATE INDEX idx_messages_timestamp
+                ON messages (timestamp DESC);
+             CREATE INDEX idx_messages_chat_timestamp
+                ON messages (chat_id, timestamp DESC);
+             CREATE INDEX idx_messages_reply_to
+                ON messages (chat_id, reply_to_message_id) WHERE reply_to_message_id IS NOT NULL;
+             CREATE INDEX idx_messages_thread
+                ON messages (chat_id, thread_id, message_id) WHERE thread_id IS NOT NULL;",
+        )?;
+        add_account_key(
+            conn,
+            "sync_state",
+            "CREATE TABLE sync_state_new (
+                account_id        INTEGER NOT NULL DEFAULT 0,
+                chat_id           INTEGER NOT NULL,
+                last_message_id   INTEGER NOT NULL DEFAULT 0,
+                oldest_message_id INTEGER,
+                initial_done      INTEGER NOT NULL DEFAULT 0,
+                last_sync_at      TEXT,
+                PRIMARY KEY (account_id, chat_id),
+                FOREIGN KEY (account_id, chat_id) REFERENCES chats(account_id, chat_id)
+            )",
+            "chat_id, last_message_id, oldest_message_id, initial_done, last_sync_at",
+            "",
+        )?;
+        add_account_key(
+            conn,
+            "message_entities",
+            "CREATE TABLE message_entities_new (
+                account_id INTEGER NOT NULL DEFAULT 0,
+                chat_id    INTEGER NOT NULL,
+                message_id INTEGER NOT NULL,
+                kind       TEXT NOT NULL CHECK (kind IN ('link', 'hashtag', 'mention', 'cashtag')),
+                value      TEXT NOT NULL,
+                domain     TEXT,
+                PRIMARY KEY (account_id, chat_id, message_id, kind, value)
+            )",
+            "chat_id, message_id, kind, value, domain",
+            "CREATE INDEX idx_message_entities_value
+                ON message_entities (kind, value);",
+        )?;
+        add_account_key(
+            conn,
+            "message_media",
+            "CREATE TABLE message_media_new (
+                account_id              INTEGER NOT NULL DEFAULT 0,
+                chat_id                 INTEGER NOT NULL,
+                message_id              INTEGER NOT NULL,
+                kind                    TEXT CHECK (kind IN ('photo', 'video', 'document', 'audio',
+                                            'voice', 'video_note', 'sticker', 'animation', 'other')),
+                file_name               TEXT,
+                mime_type               TEXT,
+                size_bytes              INTEGER,
+                caption                 TEXT,
+                forward_from_name       TEXT,
+                forward_from_chat_id    INTEGER,
+                forward_from_message_id INTEGER,
+                PRIMARY KEY (account_id, chat_id, message_id)
+            )",
+            "chat_id, message_id, kind, file_name, mime_type, size_bytes, caption,
+             forward_from_name, forward_from_chat_id, forward_from_message_id",
+            "CREATE INDEX idx_message_media_kind
+                ON message_media (kind) WHERE kind IS NOT NULL;",
+        )?;
+        add_account_key(
+            conn,
+            "saved_search_hits",
+            "CREATE TABLE saved_search_hits_new (
+                saved_search_id INTEGER NOT NULL
+                    REFERENCES saved_searches(saved_search_id) ON DELETE CASCADE,
+                account_id      INTEGER NOT NULL DEFAULT 0,
+                chat_id         INTEGER NOT NULL,
+                message_id      INTEGER NOT NULL,
+                matched_at      INTEGER NOT NULL,
+                seen            INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (saved_search_id, account_id, chat_id, message_id)
+            )",
+            "saved_search_id, chat_id, message_id, matched_at, seen",
+            "CREATE INDEX idx_saved_search_hits_seen
+                ON saved_search_hits (saved_search_id, seen);",
+        )?;
+        add_account_key(
+            conn,
+            "wiki_evidence",
+            "CREATE TABLE wiki_evidence_new (
+                id           INTEGER PRIMARY KEY,
+                page_id      INTEGER NOT NULL
+                                 REFERENCES wiki_pages_v2(id) ON DELETE CASCADE,
+                account_id   INTEGER NOT NULL DEFAULT 0,
+                msg_id       INTEGER NOT NULL,
+                chat_id      INTEGER NOT NULL,
+                sender_id    INTEGER NOT NULL,
+                ts           INTEGER NOT NULL,
+                excerpt      TEXT NOT NULL,
+                excerpt_jamo TEXT NOT NULL DEFAULT '',
+                source_hash  BLOB NOT NULL,
+                salience     REAL NOT NULL DEFAULT 0.5,
+                cited        INTEGER NOT NULL DEFAULT 0,
+                created_at   INTEGER NOT NULL,
+                UNIQUE (page_id, account_id, msg_id, chat_id)
+            )",
+            "page_id, msg_id, chat_id, sender_id, ts, excerpt, excerpt_jamo, source_hash,
+             salience, cited, created_at",
+            "CREATE INDEX ix_evidence_source_hash
+                ON wiki_evidence (source_hash);
+             CREATE INDEX ix_evidence_page_ts
+                ON wiki_evidence (page_id, ts DESC);
+             CREATE INDEX ix_evidence_chat_ts
+                ON wiki_evidence (chat_id, ts DESC);
+             CREATE INDEX ix_evidence_ts
+                ON wiki_evidence (ts DESC);
+             CREATE INDEX ix_evidence_msg
+                ON wiki_evidence (msg_id, chat_id);",
+        )?;
+        add_account_key(
+            conn,
+            "wiki_classify_queue_v2",
+            "CREATE TABLE wiki_classify_queue_v2_new (
+                account_id      INTEGER NOT NULL DEFAULT 0,
+                msg_id          INTEGER NOT NULL,
+                chat_id         INTEGER NOT NULL,
+                status          TEXT NOT NULL DEFAULT 'pending'
+                                    CHECK (status IN ('pending','processing','failed','done')),
+                attempts        INTEGER NOT NULL DEFAULT 0,
+                last_error      TEXT,
+                hint            TEXT,
+                hint_page_id    INTEGER REFERENCES wiki_pages_v2(id) ON DELETE SET NULL,
+                text_hash       BLOB NOT NULL,
+                enqueued_at     INTEGER NOT NULL,
+                claimed_at      INTEGER,
+                next_attempt_at INTEGER,
+                PRIMARY KEY (account_id, msg_id, chat_id)
+            )",
+            "msg_id, chat_id, status, attempts, last_error, hint, hint_page_id, text_hash,
+             enqueued_at, claimed_at, next_attempt_at",
+            "CREATE INDEX ix_classify_v2_ready
+                ON wiki_classify_queue_v2 (status, next_attempt_at)
+                WHERE status = 'pending';",
+        )?;
+        // v1 topic links keep pointing at a message key, so the foreign
+        // key has to follow the new messages key.
+        add_account_key(
+            conn,
+            "wiki_topic_messages",
+            "CREATE TABLE wiki_topic_messages_new (
+                topic_id          INTEGER NOT NULL REFERENCES wiki_topics(topic_id) ON DELETE CASCADE,
+                account_id        INTEGER NOT NULL DEFAULT 0,
+                chat_id           INTEGER NOT NULL,
+                message_id        INTEGER NOT NULL,
+                relevance         REAL NOT NULL DEFAULT 1.0,
+                assigned_category TEXT,
+                PRIMARY KEY (topic_id, account_id, chat_id, message_id),
+                FOREIGN KEY (account_id, chat_id, message_id)
+                    REFERENCES messages(account_id, chat_id, message_id)
+            )",
+            "topic_id, chat_id, message_id, relevance, assigned_category",
+            "CREATE INDEX idx_topic_messages_msg
+                ON wiki_topic_messages (chat_id, message_id);",
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '20')",
+        )?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => conn.execute("COMMIT")?,
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK");
+            let _ = conn.execute("PRAGMA foreign_keys = ON");
+            return Err(e);
+        }
+    }
+    conn.execute("PRAGMA foreign_keys = ON")?;
+
+    Ok(())
+}
+
+/// Rebuild `table` from `create` (a `{table}_new` definition keyed by
+/// account), moving every row to account 0 with its rowid kept, then
+/// recreate its `indexes`. No-op if the table has the column already.
+fn add_account_key(
+    conn: &Connection,
+    table: &str,
+    create: &str,
+    columns: &str,
+    indexes: &str,
+) -> Result<(), sqlite::Error> {
+    if column_exists(conn, table, "account_id")? {
+        return Ok(());
+    }
+    conn.execute(format!("DROP TABLE IF EXISTS {table}_new"))?;
+    conn.execute(create)?;
+    conn.execute(format!(
+        "INSERT INTO {table}_new (rowid, account_id, {columns})
+             SELECT rowid, 0, {columns} FROM {table};
+         DROP TABLE {table};
+         ALTER TABLE {table}_new RENAME TO {table};"
+    ))?;
+    if !indexes.is_empty() {
+        conn.execute(indexes)?;
+    }
+    Ok(())
+}
+
+fn migrate_senders(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 19 {
+        return Ok(());
+    }
+
+    // Names were dropped at ingest until now, so both start empty.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS senders (
+            sender_id    INTEGER PRIMARY KEY,
+            display_name TEXT NOT NULL,
+            username     TEXT,
+            name_seen_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_senders_username
+            ON senders (username) WHERE username IS NOT NULL;
+        CREATE TABLE IF NOT EXISTS sender_names (
+            sender_id  INTEGER NOT NULL,
+            name       TEXT NOT NULL,
+            name_jamo  TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            last_seen  INTEGER NOT NULL,
+            PRIMARY KEY (sender_id, name)
+        );",
+    )?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '19')")?;
+
+    Ok(())
+}
+
+fn migrate_message_threads(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 18 {
+        return Ok(());
+    }
+
+    // Both stay NULL for messages indexed before now; the shell fills
+    // them in as it re-mirrors history.
+    if !column_exists(conn, "messages", "reply_to_message_id")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN reply_to_message_id INTEGER")?;
+    }
+    if !column_exists(conn, "messages", "thread_id")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN thread_id INTEGER")?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_messages_reply_to
+            ON messages (chat_id, reply_to_message_id) WHERE reply_to_message_id IS NOT NULL;
+         CREATE INDEX IF NOT EXISTS idx_messages_thread
+            ON messages (chat_id, thread_id, message_id) WHERE thread_id IS NOT NULL;",
+    )?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '18')")?;
+
+    Ok(())
+}
+
+fn migrate_message_media(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 17 {
+        return Ok(());
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_media (
+            account_id              INTEGER NOT NULL DEFAULT 0,
+            chat_id                 INTEGER NOT NULL,
+            message_id              INTEGER NOT NULL,
+            kind                    TEXT CHECK (kind IN ('photo', 'video', 'document', 'audio',
+                                        'voice', 'video_note', 'sticker', 'animation', 'other')),
+            file_name               TEXT,
+            mime_type               TEXT,
+            size_bytes              INTEGER,
+            caption                 TEXT,
+            forward_from_name       TEXT,
+            forward_from_chat_id    INTEGER,
+            forward_from_message_id INTEGER,
+            PRIMARY KEY (account_id, chat_id, message_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_media_kind
+            ON message_media (kind) WHERE kind IS NOT NULL;",
+    )?;
+
+    // Nothing indexed so far had media, so the new column starts empty
+    // everywhere and the rebuild only reshapes the index.
+    if !column_exists(conn, "messages", "text_media")? {
+        conn.execute("ALTER TABLE messages ADD COLUMN text_media TEXT NOT NULL DEFAULT ''")?;
+    }
+
+    conn.execute("DROP TABLE IF EXISTS messages_fts")?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE messages_fts USING fts5(
+            text_plain, text_stripped, text_jamo, text_choseong, text_stem, text_roman,
+            text_media,
+            content='messages',
+            content_rowid='rowid',
+            tokenize='trigram case_sensitive 0'
+        )",
+    )?;
+
+    conn.execute("INSERT INTO messages_fts(messages_fts) VALUES('rebuild')")?;
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '17')")?;
+
+    Ok(())
+}
+
+fn migrate_message_entities(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 16 {
+        return Ok(());
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_entities (
+            account_id INTEGER NOT NULL DEFAULT 0,
+            chat_id    INTEGER NOT NULL,
+            message_id INTEGER NOT NULL,
+            kind       TEXT NOT NULL CHECK (kind IN ('link', 'hashtag', 'mention', 'cashtag')),
+            value      TEXT NOT NULL,
+            domain     TEXT,
+            PRIMARY KEY (account_id, chat_id, message_id, kind, value)
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_entities_value
+            ON message_entities (kind, value);
+        DELETE FROM message_entities;",
+    )?;
+
+    const BATCH: usize = 5000;
+    let mut after_rowid = 0_i64;
+    loop {
+        let mut rows: Vec<(i64, i64, String)> = Vec::with_capacity(BATCH);
+        let mut last_rowid = None;
+        {
+            let mut stmt = conn.prepare(
+                "SELECT rowid, chat_id, message_id, text_plain FROM messages
+                 WHERE rowid > ?
+                 ORDER BY rowid
+                 LIMIT ?",
+            )?;
+            stmt.bind((1, after_rowid))?;
+            stmt.bind((2, BATCH as i64))?;
+            while let sqlite::State::Row = stmt.next()? {
+                last_rowid = Some(stmt.read::<i64, _>(0)?);
+                rows.push((
+                    stmt.read::<i64, _>(1)?,
+                    stmt.read::<i64, _>(2)?,
+                    stmt.read::<String, _>(3)?,
+                ));
+            }
+        }
+        let Some(last_rowid) = last_rowid else {
+            break;
+        };
+
+        conn.execute("BEGIN")?;
+        for (chat_id, message_id, text) in &rows {
+            // Everything indexed before phase 20 belongs to account 0.
+            crate::store::entity::index_entities(conn, 0, *chat_id, *message_id, text)?;
+        }
+        conn.execute("COMMIT")?;
+
+        if rows.len() < BATCH {
+            break;
+        }
+        after_rowid = last_rowid;
+    }
+
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '16')")?;
+
+    Ok(())
+}
+
+fn migrate_search_vocab(conn: &Connection) -> Result<(), sqlite::Error> {
+    if get_schema_version(conn) >= 12 {
+        return Ok(());
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS search_vocab (
+            term       TEXT PRIMARY KEY,
+            term_jamo  TEXT NOT NULL,
+            jamo_len   INTEGER NOT NULL,
+            freq       INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_search_vocab_len_freq
+            ON search_vocab (jamo_len, freq DESC);
+        DELETE FROM search_vocab;",
+    )?;
+
+    const BATCH: usize = 5000;
+    let mut after_rowid = 0_i64;
+    loop {
+        let mut counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        let mut last_rowid = None;
+        let mut seen = 0;
+        {
+            let mut stmt = conn.prepare(
+                "SELECT rowid, text_stem FROM messages
+                 WHERE rowid > ? AND deleted_at IS NULL
+                 ORDER BY rowid
+                 LIMIT ?",
+            )?;
+            stmt.bind((1, after_rowid))?;
+            stmt.bind((2, BATCH as i64))?;
+            while let sqlite::State::Row = stmt.next()? {
+                last_rowid = Some(stmt.read::<i64, _>(0)?);
+                seen += 1;
+                for word in crate::store::vocab::vocab_words(&stmt.read::<String, _>(1)?) {
+                    *counts.entry(word).or_default() += 1;
+                }
+            }
+        }
+        let Some(last_rowid) = last_rowid else {
+            break;
+        };
+
+        conn.execute("BEGIN")?;
+        for (word, freq) in &counts {
+            crate::store::vocab::adjust_vocab(conn, std::slice::from_ref(word), *freq)?;
+        }
+        conn.execute("COMMIT")?;
+
+        if seen < BATCH {
+            break;
+        }
+        after_rowid = last_rowid;
+    }
+
+    conn.execute("INSERT OR REPLACE INTO app_meta (key, value) VALUES ('schema_version', '12')")?;
+
+    Ok(())
+}
+
+/// Fill a derived search column from `text_plain` for rows indexed
+/// before the column existed. Walks by rowid rather than `WHERE column
+/// = ''` because some text legitimately derives to an empty form
+/// (no syllables for choseong, for instance).
+fn backfill_text_column(
+    conn: &Connection,
+    column: &str,
+    derive: fn(&str) -> String,
+) -> Result<(), sqlite::Error> {
+    const BATCH: usize = 5000;
+    let mut after_rowid = 0_i64;
+    loop {
+        let mut rows: Vec<(i64, String)> = Vec::with_capacity(BATCH);
+        {
+            let mut stmt = conn.prepare(
+                "SELECT rowid, text_plain FROM messages
+                 WHERE rowid > ?
+                 ORDER BY rowid
+                 LIMIT ?",
+            )?;
+            stmt.bind((1, after_rowid))?;
+            stmt.bind((2, BATCH as i64))?;
+            while let sqlite::State::Row = stmt.next()? {
+                rows.push((stmt.read::<i64, _>(0)?, stmt.read::<String, _>(1)?));
+            }
+        }
+        let Some(&(last_rowid, _)) = rows.last() else {
+            return Ok(());
+        };
+
+        conn.execute("BEGIN")?;
+        for (rowid, text) in &rows {
+            let value = derive(text);
+            let mut stmt =
+                conn.prepare(format!("UPDATE messages SET {column} = ? WHERE rowid = ?"))?;
+            stmt.bind((1, value.as_str()))?;
+            stmt.bind((2, *rowid))?;
+            stmt.next()?;
+        }
+        conn.execute("COMMIT")?;
+
+        if rows.len() < BATCH {
+            return Ok(());
+        }
+        after_rowid = last_rowid;
+    }
+}
+
 fn migrate_message_index_v8(conn: &Connection) -> Result<(), sqlite::Error> {
     if get_schema_version(conn) >= 8 {
         return Ok(());
@@ -988,14 +1877,14 @@ mod tests {
     }
 
     #[test]
-    fn test_schema_version_is_9() {
+    fn test_schema_version_is_26() {
         let store = Store::open_in_memory().unwrap();
         let mut stmt = store
             .conn()
             .prepare("SELECT value FROM app_meta WHERE key = 'schema_version'")
             .unwrap();
         assert!(matches!(stmt.next(), Ok(sqlite::State::Row)));
-        assert_eq!(stmt.read::<String, _>(0).unwrap(), "9");
+        assert_eq!(stmt.read::<String, _>(0).unwrap(), "26");
     }
 
     #[test]
@@ -1065,7 +1954,220 @@ mod tests {
             .prepare("SELECT value FROM app_meta WHERE key = 'schema_version'")
             .unwrap();
         assert!(matches!(stmt.next(), Ok(sqlite::State::Row)));
-        assert_eq!(stmt.read::<String, _>(0).unwrap(), "9");
+        assert_eq!(stmt.read::<String, _>(0).unwrap(), "26");
+    }
+
+    #[test]
+    fn test_upgrade_from_v9_backfills_search_columns() {
+        let store = Store::open_in_memory().unwrap();
+        let conn = store.conn();
+        // Recreate a v9 install: messages without the choseong, stem and
+        // roman columns, the three-column combined index, version
+        // stamped 9.
+        conn.execute(
+            "DROP TABLE messages_fts;
+             ALTER TABLE messages DROP COLUMN text_choseong;
+             ALTER TABLE messages DROP COLUMN text_stem;
+             ALTER TABLE messages DROP COLUMN text_roman;
+             CREATE VIRTUAL TABLE messages_fts USING fts5(
+                 text_plain, text_stripped, text_jamo,
+                 content='messages', content_rowid='rowid',
+                 tokenize='trigram case_sensitive 0'
+             );
+             INSERT INTO chats (chat_id, title, chat_type) VALUES (1, 'C', 'channel');
+             INSERT INTO messages (message_id, chat_id, timestamp, text_plain, text_stripped, text_jamo)
+                 VALUES (1, 1, 1000, '삼성 전자 실적', '삼성전자실적', ''),
+                        (2, 1, 1001, 'hello', 'hello', '');
+             INSERT INTO messages_fts(messages_fts) VALUES('rebuild');
+             UPDATE app_meta SET value = '9' WHERE key = 'schema_version';",
+        )
+        .unwrap();
+
+        super::run_migrations(conn).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT text_choseong, text_stem FROM messages ORDER BY message_id")
+            .unwrap();
+        let mut forms = Vec::new();
+        while let Ok(sqlite::State::Row) = stmt.next() {
+            forms.push((
+                stmt.read::<String, _>(0).unwrap(),
+                stmt.read::<String, _>(1).unwrap(),
+            ));
+        }
+        assert_eq!(
+            forms,
+            vec![
+                ("ㅅㅅㅈㅈㅅㅈ".to_string(), "삼성 전자 실적".to_string()),
+                ("hello".to_string(), "hello".to_string()),
+            ]
+        );
+
+        for query in [
+            "text_choseong MATCH '\"ㅅㅅㅈㅈ\"'",
+            "text_stem MATCH '\"전자 실적\"'",
+            "text_roman MATCH '\"samseong\"'",
+        ] {
+            let mut stmt = conn
+                .prepare(format!("SELECT rowid FROM messages_fts WHERE {query}"))
+                .unwrap();
+            assert!(matches!(stmt.next(), Ok(sqlite::State::Row)), "{query}");
+        }
+        assert_eq!(super::get_schema_version(conn), 26);
+
+        // Phase 12 harvested the vocabulary from the backfilled stems.
+        let mut stmt = conn
+            .prepare("SELECT freq FROM search_vocab WHERE term = '실적'")
+            .unwrap();
+        assert!(matches!(stmt.next(), Ok(sqlite::State::Row)));
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_upgrade_from_v15_backfills_message_entities() {
+        let store = Store::open_in_memory().unwrap();
+        let conn = store.conn();
+        conn.execute(
+            "DROP TABLE message_entities;
+             INSERT INTO chats (chat_id, title, chat_type) VALUES (1, 'C', 'channel');
+             INSERT INTO messages (message_id, chat_id, timestamp, text_plain, text_stripped)
+                 VALUES (1, 1, 1000, '#airdrop https://x.com/a', '#airdrophttps://x.com/a');
+             UPDATE app_meta SET value = '15' WHERE key = 'schema_version';",
+        )
+        .unwrap();
+
+        super::run_migrations(conn).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT kind, value FROM message_entities ORDER BY kind")
+            .unwrap();
+        let mut entities = Vec::new();
+        while let Ok(sqlite::State::Row) = stmt.next() {
+            entities.push((
+                stmt.read::<String, _>(0).unwrap(),
+                stmt.read::<String, _>(1).unwrap(),
+            ));
+        }
+        assert_eq!(
+            entities,
+            vec![
+                ("hashtag".to_string(), "airdrop".to_string()),
+                ("link".to_string(), "https://x.com/a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_from_v16_adds_media_column() {
+        let store = Store::open_in_memory().unwrap();
+        let conn = store.conn();
+        conn.execute(
+            "DROP TABLE message_media;
+             DROP TABLE messages_fts;
+             ALTER TABLE messages DROP COLUMN text_media;
+             CREATE VIRTUAL TABLE messages_fts USING fts5(
+                 text_plain, text_stripped, text_jamo, text_choseong, text_stem, text_roman,
+                 content='messages', content_rowid='rowid',
+                 tokenize='trigram case_sensitive 0');
+             INSERT INTO chats (chat_id, title, chat_type) VALUES (1, 'C', 'channel');
+             INSERT INTO messages (message_id, chat_id, timestamp, text_plain, text_stripped)
+                 VALUES (1, 1, 1000, '실적 발표', '실적발표');
+             UPDATE app_meta SET value = '16' WHERE key = 'schema_version';",
+        )
+        .unwrap();
+
+        super::run_migrations(conn).unwrap();
+
+        assert!(super::column_exists(conn, "messages", "text_media").unwrap());
+        let mut stmt = conn
+            .prepare(
+                "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'text_plain:\"실적 발\"'",
+            )
+            .unwrap();
+        stmt.next().unwrap();
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 1);
+        let mut stmt = conn.prepare("SELECT COUNT(*) FROM message_media").unwrap();
+        stmt.next().unwrap();
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_upgrade_from_v19_moves_rows_to_account_0() {
+        let store = Store::open_in_memory().unwrap();
+        let conn = store.conn();
+        // A v19 install: chats, messages and evidence keyed without an
+        // account.
+        conn.execute(
+            "PRAGMA foreign_keys = OFF;
+             DROP TABLE chats;
+             CREATE TABLE chats (
+                 chat_id INTEGER PRIMARY KEY, title TEXT NOT NULL, chat_type TEXT NOT NULL,
+                 username TEXT, access_hash INTEGER, is_excluded INTEGER NOT NULL DEFAULT 0,
+                 created_at TEXT NOT NULL DEFAULT (datetime('now')));
+             DROP TABLE messages;
+             CREATE TABLE messages (
+                 message_id INTEGER NOT NULL, chat_id INTEGER NOT NULL,
+                 timestamp INTEGER NOT NULL, text_plain TEXT NOT NULL,
+                 text_stripped TEXT NOT NULL, link TEXT,
+                 text_jamo TEXT NOT NULL DEFAULT '', msg_version INTEGER NOT NULL DEFAULT 1,
+                 deleted_at INTEGER, cloud_acked_version INTEGER, sender_id INTEGER,
+                 text_choseong TEXT NOT NULL DEFAULT '', text_stem TEXT NOT NULL DEFAULT '',
+                 text_roman TEXT NOT NULL DEFAULT '', text_media TEXT NOT NULL DEFAULT '',
+                 reply_to_message_id INTEGER, thread_id INTEGER,
+                 PRIMARY KEY (chat_id, message_id));
+             DROP TABLE wiki_evidence;
+             CREATE TABLE wiki_evidence (
+                 id INTEGER PRIMARY KEY, page_id INTEGER NOT NULL, msg_id INTEGER NOT NULL,
+                 chat_id INTEGER NOT NULL, sender_id INTEGER NOT NULL, ts INTEGER NOT NULL,
+                 excerpt TEXT NOT NULL, excerpt_jamo TEXT NOT NULL DEFAULT '',
+                 source_hash BLOB NOT NULL, salience REAL NOT NULL DEFAULT 0.5,
+                 cited INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL,
+                 UNIQUE (page_id, msg_id, chat_id));
+             INSERT INTO chats (chat_id, title, chat_type) VALUES (1, 'C', 'channel');
+             INSERT INTO messages (rowid, message_id, chat_id, timestamp, text_plain, text_stripped)
+                 VALUES (7, 1, 1, 1000, '실적 발표', '실적발표');
+             INSERT INTO messages_fts(messages_fts) VALUES('rebuild');
+             INSERT INTO wiki_evidence (id, page_id, msg_id, chat_id, sender_id, ts, excerpt,
+                                        source_hash, created_at)
+                 VALUES (3, 1, 1, 1, 0, 1000, '실적', x'00', 1000);
+             UPDATE app_meta SET value = '19' WHERE key = 'schema_version';
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+
+        super::run_migrations(conn).unwrap();
+
+        assert_eq!(super::get_schema_version(conn), 26);
+        for table in ["chats", "messages", "sync_state", "wiki_evidence"] {
+            assert!(
+                super::column_exists(conn, table, "account_id").unwrap(),
+                "{table}"
+            );
+        }
+        let mut stmt = conn
+            .prepare(
+                "SELECT m.account_id, m.rowid FROM messages_fts f
+                 JOIN messages m ON m.rowid = f.rowid
+                 WHERE messages_fts MATCH 'text_plain:\"실적 발\"'",
+            )
+            .unwrap();
+        assert!(matches!(stmt.next(), Ok(sqlite::State::Row)));
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 0);
+        assert_eq!(stmt.read::<i64, _>(1).unwrap(), 7);
+        let mut stmt = conn
+            .prepare("SELECT id, account_id FROM wiki_evidence")
+            .unwrap();
+        assert!(matches!(stmt.next(), Ok(sqlite::State::Row)));
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 3);
+        assert_eq!(stmt.read::<i64, _>(1).unwrap(), 0);
+
+        // The same chat and message id under a second account is a new row.
+        conn.execute(
+            "INSERT INTO chats (account_id, chat_id, title, chat_type) VALUES (2, 1, 'C', 'channel');
+             INSERT INTO messages (account_id, message_id, chat_id, timestamp, text_plain, text_stripped)
+                 VALUES (2, 1, 1, 1000, 'other', 'other');",
+        )
+        .unwrap();
     }
 
     #[test]
@@ -0,0 +1,141 @@
+//! Recently run message searches, the first source of autocomplete
+//! (`search::suggest`). Recording is on by default and can be turned
+//! off; turning it off does not clear what is already stored.
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+use crate::search::hangul::decompose_jamo;
+
+/// `app_meta` key holding `"0"` when the user opted out.
+const HISTORY_ENABLED_KEY: &str = "search_history_enabled";
+
+/// Oldest entries beyond this many are dropped on each write.
+const MAX_HISTORY: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub use_count: i64,
+    pub last_used_at: i64,
+}
+
+impl Store {
+    pub fn search_history_enabled(&self) -> Result<bool, sqlite::Error> {
+        Ok(self.get_meta(HISTORY_ENABLED_KEY)?.as_deref() != Some("0"))
+    }
+
+    pub fn set_search_history_enabled(&self, enabled: bool) -> Result<(), sqlite::Error> {
+        self.set_meta(HISTORY_ENABLED_KEY, if enabled { "1" } else { "0" })
+    }
+
+    /// Remember `query` as just run, unless history is off. Whitespace
+    /// is collapsed so `삼성  전자` and `삼성 전자` share one entry.
+    pub fn record_search(&self, query: &str) -> Result<(), sqlite::Error> {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        if query.is_empty() || !self.search_history_enabled()? {
+            return Ok(());
+        }
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO search_history (query, query_jamo, use_count, last_used_at)
+             VALUES (?, ?, 1, ?)
+             ON CONFLICT(query) DO UPDATE SET
+                use_count = use_count + 1,
+                last_used_at = excluded.last_used_at",
+        )?;
+        stmt.bind((1, query.as_str()))?;
+        stmt.bind((2, decompose_jamo(&query.to_lowercase()).as_str()))?;
+        stmt.bind((3, crate::wiki::norm::unix_now()))?;
+        stmt.next()?;
+
+        let mut stmt = self.conn.prepare(
+            "DELETE FROM search_history WHERE query NOT IN (
+                SELECT query FROM search_history
+                ORDER BY last_used_at DESC, use_count DESC
+                LIMIT ?
+             )",
+        )?;
+        stmt.bind((1, MAX_HISTORY))?;
+        stmt.next()?;
+        Ok(())
+    }
+
+    /// Most recent first.
+    pub fn recent_searches(&self, limit: usize) -> Result<Vec<SearchHistoryEntry>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT query, use_count, last_used_at FROM search_history
+             ORDER BY last_used_at DESC, use_count DESC, query ASC
+             LIMIT ?",
+        )?;
+        stmt.bind((1, limit as i64))?;
+        read_entries(&mut stmt)
+    }
+
+    /// Entries whose lowercased jamo form starts with `jamo_prefix`,
+    /// most recent first.
+    pub fn searches_with_jamo_prefix(
+        &self,
+        jamo_prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchHistoryEntry>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT query, use_count, last_used_at FROM search_history
+             WHERE query_jamo >= ? AND query_jamo < ?
+             ORDER BY last_used_at DESC, use_count DESC, query ASC
+             LIMIT ?",
+        )?;
+        stmt.bind((1, jamo_prefix))?;
+        stmt.bind((2, format!("{jamo_prefix}\u{10FFFF}").as_str()))?;
+        stmt.bind((3, limit as i64))?;
+        read_entries(&mut stmt)
+    }
+
+    pub fn clear_search_history(&self) -> Result<(), sqlite::Error> {
+        self.conn.execute("DELETE FROM search_history")
+    }
+}
+
+fn read_entries(stmt: &mut sqlite::Statement) -> Result<Vec<SearchHistoryEntry>, sqlite::Error> {
+    let mut out = Vec::new();
+    while let sqlite::State::Row = stmt.next()? {
+        out.push(SearchHistoryEntry {
+            query: stmt.read::<String, _>(0)?,
+            use_count: stmt.read::<i64, _>(1)?,
+            last_used_at: stmt.read::<i64, _>(2)?,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_dedupes_and_respects_opt_out() {
+        let store = Store::open_in_memory().unwrap();
+        store.record_search("삼성전자").unwrap();
+        store.record_search("  airdrop   snapshot ").unwrap();
+        store.record_search("삼성전자").unwrap();
+        store.record_search("   ").unwrap();
+
+        let recent = store.recent_searches(10).unwrap();
+        assert_eq!(recent.len(), 2);
+        let samsung = recent.iter().find(|e| e.query == "삼성전자").unwrap();
+        assert_eq!(samsung.use_count, 2);
+        assert!(recent.iter().any(|e| e.query == "airdrop snapshot"));
+
+        let prefixed = store
+            .searches_with_jamo_prefix(&decompose_jamo("삼서"), 10)
+            .unwrap();
+        assert_eq!(prefixed.len(), 1);
+
+        store.set_search_history_enabled(false).unwrap();
+        assert!(!store.search_history_enabled().unwrap());
+        store.record_search("bitcoin").unwrap();
+        assert_eq!(store.recent_searches(10).unwrap().len(), 2);
+
+        store.clear_search_history().unwrap();
+        assert!(store.recent_searches(10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Who wrote what. `senders` keeps the latest display name and username
+//! of each sender id; `sender_names` keeps every name a sender has
+//! been seen under, so `from:` still finds messages written before a
+//! rename.
+//!
+//! Rows are filled in at ingest from the name the shell sends with
+//! each message. Backfilled history arrives out of order, so the
+//! current name is the one carried by the newest message, not the
+//! last one indexed.
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::hangul::decompose_jamo;
+
+use super::Store;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sender {
+    pub sender_id: i64,
+    pub display_name: String,
+    pub username: Option<String>,
+    /// Every name seen, most recently used first.
+    pub names: Vec<SenderName>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SenderName {
+    pub name: String,
+    /// Unix seconds of the oldest and newest message under this name.
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// The form names and `from:` values are compared in: lowercased,
+/// `@` dropped, Hangul split into compat jamo so a half-typed `김철ㅅ`
+/// still matches `김철수`.
+pub(crate) fn name_key(name: &str) -> String {
+    decompose_jamo(&name.trim().trim_start_matches('@').to_lowercase())
+}
+
+/// Record that `sender_id` posted under `name` (and `username`) at
+/// `seen_at`. A missing or blank name records nothing. Runs inside the
+/// caller's transaction.
+pub(crate) fn record_sender(
+    conn: &sqlite::Connection,
+    sender_id: i64,
+    name: Option<&str>,
+    username: Option<&str>,
+    seen_at: i64,
+) -> Result<(), sqlite::Error> {
+    let Some(name) = name.map(str::trim).filter(|n| !n.is_empty()) else {
+        return Ok(());
+    };
+    if sender_id == 0 {
+        return Ok(());
+    }
+    let username = username
+        .map(|u| u.trim().trim_start_matches('@').to_lowercase())
+        .filter(|u| !u.is_empty());
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO senders (sender_id, display_name, username, name_seen_at)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT(sender_id) DO UPDATE SET
+             display_name = CASE WHEN excluded.name_seen_at >= name_seen_at
+                                 THEN excluded.display_name ELSE display_name END,
+             username = CASE WHEN excluded.name_seen_at >= name_seen_at
+                             THEN COALESCE(excluded.username, username) ELSE username END,
+             name_seen_at = MAX(name_seen_at, excluded.name_seen_at)",
+    )?;
+    stmt.bind((1, sender_id))?;
+    stmt.bind((2, name))?;
+    stmt.bind((3, username.as_deref()))?;
+    stmt.bind((4, seen_at))?;
+    stmt.next()?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO sender_names (sender_id, name, name_jamo, first_seen, last_seen)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(sender_id, name) DO UPDATE SET
+             first_seen = MIN(first_seen, excluded.first_seen),
+             last_seen = MAX(last_seen, excluded.last_seen)",
+    )?;
+    stmt.bind((1, sender_id))?;
+    stmt.bind((2, name))?;
+    stmt.bind((3, name_key(name).as_str()))?;
+    stmt.bind((4, seen_at))?;
+    stmt.bind((5, seen_at))?;
+    stmt.next()?;
+    Ok(())
+}
+
+impl Store {
+    /// A sender with its name history, if any message from it carried a
+    /// name.
+    pub fn get_sender(&self, sender_id: i64) -> Result<Option<Sender>, sqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT display_name, username FROM senders WHERE sender_id = ?")?;
+        stmt.bind((1, sender_id))?;
+        let (display_name, username) = match stmt.next()? {
+            sqlite::State::Row => (
+                stmt.read::<String, _>(0)?,
+                stmt.read::<Option<String>, _>(1)?,
+            ),
+            sqlite::State::Done => return Ok(None),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT name, first_seen, last_seen FROM sender_names
+             WHERE sender_id = ?
+             ORDER BY last_seen DESC, name",
+        )?;
+        stmt.bind((1, sender_id))?;
+        let mut names = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            names.push(SenderName {
+                name: stmt.read::<String, _>(0)?,
+                first_seen: stmt.read::<i64, _>(1)?,
+                last_seen: stmt.read::<i64, _>(2)?,
+            });
+        }
+        Ok(Some(Sender {
+            sender_id,
+            display_name,
+            username,
+            names,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRow};
+
+    fn insert(store: &Store, message_id: i64, ts: i64, name: &str, username: Option<&str>) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: ts,
+                text_plain: "배당 공지".into(),
+                text_stripped: strip_whitespace("배당 공지"),
+                link: None,
+                sender_id: 7,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: Some(name.into()),
+                sender_username: username.map(Into::into),
+            }])
+            .unwrap();
+    }
+
+    #[test]
+    fn newest_message_names_the_sender() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 2, 2000, "김철수 (공지)", Some("@CheolSu"));
+        // Backfill of an older message under a former name.
+        insert(&store, 1, 1000, "김철수", None);
+        insert(&store, 3, 1500, "김철수", None);
+
+        let sender = store.get_sender(7).unwrap().unwrap();
+        assert_eq!(sender.display_name, "김철수 (공지)");
+        assert_eq!(sender.username.as_deref(), Some("cheolsu"));
+        assert_eq!(
+            sender.names,
+            vec![
+                SenderName {
+                    name: "김철수 (공지)".into(),
+                    first_seen: 2000,
+                    last_seen: 2000,
+                },
+                SenderName {
+                    name: "김철수".into(),
+                    first_seen: 1000,
+                    last_seen: 1500,
+                },
+            ]
+        );
+        assert_eq!(store.get_sender(8).unwrap(), None);
+    }
+
+    #[test]
+    fn name_key_folds_case_sigil_and_jamo() {
+        assert_eq!(name_key("@Durov"), "durov");
+        assert!(name_key("김철수").starts_with(&name_key("김철ㅅ")));
+    }
+}
@@ -1,9 +1,17 @@
+//! Per-chat fetch bookkeeping for the shell: the newest message id
+//! fetched, how far back the initial backfill has reached, and whether
+//! it finished. The shell resumes backfill from `oldest_message_id`
+//! after a restart; [`Store::backfill_progress`] turns the same row
+//! into a progress report.
+
 use serde::{Deserialize, Serialize};
 
 use super::Store;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncStateRow {
+    #[serde(default)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub last_message_id: i64,
     pub oldest_message_id: Option<i64>,
@@ -11,15 +19,58 @@ pub struct SyncStateRow {
     pub last_sync_at: Option<String>,
 }
 
+/// How far the initial backfill of a chat has come. Telegram ids grow
+/// from 1, so `progress` is the share of `1..=last_message_id` at or
+/// above `oldest_message_id`; 1.0 once `initial_done`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub account_id: i64,
+    pub chat_id: i64,
+    /// Messages indexed in the chat, deleted ones left out.
+    pub indexed_messages: i64,
+    pub last_message_id: i64,
+    pub oldest_message_id: Option<i64>,
+    pub initial_done: bool,
+    pub progress: f64,
+}
+
+fn progress_of(last_message_id: i64, oldest_message_id: Option<i64>, initial_done: bool) -> f64 {
+    if initial_done {
+        return 1.0;
+    }
+    match oldest_message_id {
+        Some(oldest) if last_message_id > 0 => {
+            let covered = (last_message_id - oldest.max(1) + 1) as f64;
+            (covered / last_message_id as f64).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Columns read by [`read_backfill_progress`]; callers add the WHERE.
+const BACKFILL_PROGRESS_SELECT: &str =
+    "SELECT s.account_id, s.chat_id, s.last_message_id, s.oldest_message_id,
+            s.initial_done,
+            (SELECT COUNT(*) FROM messages m
+             WHERE m.account_id = s.account_id AND m.chat_id = s.chat_id
+               AND m.deleted_at IS NULL)
+     FROM sync_state s";
+
 impl Store {
-    pub fn get_sync_state(&self, chat_id: i64) -> Result<Option<SyncStateRow>, sqlite::Error> {
+    pub fn get_sync_state(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<Option<SyncStateRow>, sqlite::Error> {
         let mut stmt = self.conn.prepare(
             "SELECT chat_id, last_message_id, oldest_message_id, initial_done, last_sync_at
-             FROM sync_state WHERE chat_id = ?",
+             FROM sync_state WHERE account_id = ? AND chat_id = ?",
         )?;
-        stmt.bind((1, chat_id))?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
         if let Ok(sqlite::State::Row) = stmt.next() {
             Ok(Some(SyncStateRow {
+                account_id,
                 chat_id: stmt.read::<i64, _>(0)?,
                 last_message_id: stmt.read::<i64, _>(1)?,
                 oldest_message_id: stmt.read::<Option<i64>, _>(2)?,
@@ -33,9 +84,10 @@ impl Store {
 
     pub fn upsert_sync_state(&self, state: &SyncStateRow) -> Result<(), sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "INSERT INTO sync_state (chat_id, last_message_id, oldest_message_id, initial_done, last_sync_at)
-             VALUES (?, ?, ?, ?, ?)
-             ON CONFLICT(chat_id) DO UPDATE SET
+            "INSERT INTO sync_state (chat_id, last_message_id, oldest_message_id, initial_done, last_sync_at,
+                                     account_id)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(account_id, chat_id) DO UPDATE SET
                 last_message_id = excluded.last_message_id,
                 oldest_message_id = excluded.oldest_message_id,
                 initial_done = excluded.initial_done,
@@ -52,48 +104,120 @@ impl Store {
             Some(v) => stmt.bind((5, v.as_str()))?,
             None => stmt.bind((5, sqlite::Value::Null))?,
         };
+        stmt.bind((6, state.account_id))?;
         stmt.next()?;
         Ok(())
     }
 
+    /// Like the other setters below, creates the chat's row when it
+    /// has none yet; the chat itself must exist.
     pub fn update_last_message_id(
         &self,
+        account_id: i64,
         chat_id: i64,
         last_message_id: i64,
         last_sync_at: &str,
     ) -> Result<(), sqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "UPDATE sync_state SET last_message_id = ?, last_sync_at = ? WHERE chat_id = ?",
+            "INSERT INTO sync_state (account_id, chat_id, last_message_id, last_sync_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(account_id, chat_id) DO UPDATE SET
+                last_message_id = excluded.last_message_id,
+                last_sync_at = excluded.last_sync_at",
         )?;
-        stmt.bind((1, last_message_id))?;
-        stmt.bind((2, last_sync_at))?;
-        stmt.bind((3, chat_id))?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        stmt.bind((3, last_message_id))?;
+        stmt.bind((4, last_sync_at))?;
         stmt.next()?;
         Ok(())
     }
 
     pub fn update_oldest_message_id(
         &self,
+        account_id: i64,
         chat_id: i64,
         oldest_message_id: i64,
     ) -> Result<(), sqlite::Error> {
-        let mut stmt = self
-            .conn
-            .prepare("UPDATE sync_state SET oldest_message_id = ? WHERE chat_id = ?")?;
-        stmt.bind((1, oldest_message_id))?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO sync_state (account_id, chat_id, oldest_message_id)
+             VALUES (?, ?, ?)
+             ON CONFLICT(account_id, chat_id) DO UPDATE SET
+                oldest_message_id = excluded.oldest_message_id",
+        )?;
+        stmt.bind((1, account_id))?;
         stmt.bind((2, chat_id))?;
+        stmt.bind((3, oldest_message_id))?;
         stmt.next()?;
         Ok(())
     }
 
-    pub fn mark_initial_done(&self, chat_id: i64) -> Result<(), sqlite::Error> {
-        let mut stmt = self
-            .conn
-            .prepare("UPDATE sync_state SET initial_done = 1 WHERE chat_id = ?")?;
-        stmt.bind((1, chat_id))?;
+    pub fn mark_initial_done(&self, account_id: i64, chat_id: i64) -> Result<(), sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO sync_state (account_id, chat_id, initial_done) VALUES (?, ?, 1)
+             ON CONFLICT(account_id, chat_id) DO UPDATE SET initial_done = 1",
+        )?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
         stmt.next()?;
         Ok(())
     }
+
+    /// Backfill progress of one chat, `None` before any sync state was
+    /// recorded for it.
+    pub fn backfill_progress(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<Option<BackfillProgress>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(format!(
+            "{BACKFILL_PROGRESS_SELECT} WHERE s.account_id = ? AND s.chat_id = ?"
+        ))?;
+        stmt.bind((1, account_id))?;
+        stmt.bind((2, chat_id))?;
+        if let sqlite::State::Row = stmt.next()? {
+            Ok(Some(read_backfill_progress(&stmt)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Backfill progress of every chat with sync state, optionally of
+    /// one account, least complete first.
+    pub fn list_backfill_progress(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<Vec<BackfillProgress>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(format!(
+            "{BACKFILL_PROGRESS_SELECT} WHERE ?1 IS NULL OR s.account_id = ?1
+             ORDER BY s.account_id, s.chat_id"
+        ))?;
+        match account_id {
+            Some(v) => stmt.bind((1, v))?,
+            None => stmt.bind((1, sqlite::Value::Null))?,
+        };
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(read_backfill_progress(&stmt)?);
+        }
+        out.sort_by(|a, b| a.progress.total_cmp(&b.progress));
+        Ok(out)
+    }
+}
+
+fn read_backfill_progress(stmt: &sqlite::Statement) -> Result<BackfillProgress, sqlite::Error> {
+    let last_message_id = stmt.read::<i64, _>(2)?;
+    let oldest_message_id = stmt.read::<Option<i64>, _>(3)?;
+    let initial_done = stmt.read::<i64, _>(4)? != 0;
+    Ok(BackfillProgress {
+        account_id: stmt.read::<i64, _>(0)?,
+        chat_id: stmt.read::<i64, _>(1)?,
+        indexed_messages: stmt.read::<i64, _>(5)?,
+        last_message_id,
+        oldest_message_id,
+        initial_done,
+        progress: progress_of(last_message_id, oldest_message_id, initial_done),
+    })
 }
 
 #[cfg(test)]
@@ -105,6 +229,7 @@ mod tests {
         let store = Store::open_in_memory().unwrap();
         store
             .upsert_chat(&ChatRow {
+                account_id: 0,
                 chat_id: 1,
                 title: "Test".to_string(),
                 chat_type: "supergroup".to_string(),
@@ -120,6 +245,7 @@ mod tests {
     fn test_upsert_and_get() {
         let store = test_store();
         let state = SyncStateRow {
+            account_id: 0,
             chat_id: 1,
             last_message_id: 500,
             oldest_message_id: Some(100),
@@ -128,7 +254,7 @@ mod tests {
         };
         store.upsert_sync_state(&state).unwrap();
 
-        let fetched = store.get_sync_state(1).unwrap().unwrap();
+        let fetched = store.get_sync_state(0, 1).unwrap().unwrap();
         assert_eq!(fetched.last_message_id, 500);
         assert_eq!(fetched.oldest_message_id, Some(100));
         assert!(!fetched.initial_done);
@@ -138,6 +264,7 @@ mod tests {
     fn test_mark_initial_done() {
         let store = test_store();
         let state = SyncStateRow {
+            account_id: 0,
             chat_id: 1,
             last_message_id: 0,
             oldest_message_id: None,
@@ -145,15 +272,44 @@ mod tests {
             last_sync_at: None,
         };
         store.upsert_sync_state(&state).unwrap();
-        store.mark_initial_done(1).unwrap();
+        store.mark_initial_done(0, 1).unwrap();
 
-        let fetched = store.get_sync_state(1).unwrap().unwrap();
+        let fetched = store.get_sync_state(0, 1).unwrap().unwrap();
         assert!(fetched.initial_done);
     }
 
+    #[test]
+    fn setters_create_the_row_and_progress_follows() {
+        let store = test_store();
+        store
+            .update_last_message_id(0, 1, 400, "2025-02-10T12:00:00Z")
+            .unwrap();
+        let progress = store.backfill_progress(0, 1).unwrap().unwrap();
+        assert_eq!(progress.progress, 0.0);
+        assert_eq!(progress.oldest_message_id, None);
+
+        store.update_oldest_message_id(0, 1, 301).unwrap();
+        let state = store.get_sync_state(0, 1).unwrap().unwrap();
+        assert_eq!(state.last_message_id, 400);
+        assert_eq!(state.last_sync_at.as_deref(), Some("2025-02-10T12:00:00Z"));
+        let progress = store.backfill_progress(0, 1).unwrap().unwrap();
+        assert_eq!(progress.progress, 0.25);
+        assert_eq!(progress.indexed_messages, 0);
+
+        store.mark_initial_done(0, 1).unwrap();
+        assert_eq!(
+            store.backfill_progress(0, 1).unwrap().unwrap().progress,
+            1.0
+        );
+        assert!(store.backfill_progress(1, 1).unwrap().is_none());
+        assert_eq!(store.list_backfill_progress(None).unwrap().len(), 1);
+        // Unknown chats are still rejected.
+        assert!(store.mark_initial_done(0, 999).is_err());
+    }
+
     #[test]
     fn test_get_nonexistent() {
         let store = test_store();
-        assert!(store.get_sync_state(999).unwrap().is_none());
+        assert!(store.get_sync_state(0, 999).unwrap().is_none());
     }
 }
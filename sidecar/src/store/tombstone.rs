@@ -0,0 +1,301 @@
+//! Deleted messages are kept as tombstones (`messages.deleted_at`) for
+//! a retention period so a delete can be undone, then purged with
+//! everything hanging off them. Reads already skip tombstones; this
+//! module owns the retention setting and the purge.
+
+use super::entity::delete_entities;
+use super::media::delete_media;
+use super::outbox::sync_enabled;
+use super::revision::delete_revisions;
+use super::Store;
+
+/// `app_meta` key holding the retention in days.
+const RETENTION_KEY: &str = "tombstone_retention_days";
+
+pub const DEFAULT_TOMBSTONE_RETENTION_DAYS: u32 = 30;
+
+impl Store {
+    /// How long a deleted message can still be restored.
+    pub fn tombstone_retention_days(&self) -> Result<u32, sqlite::Error> {
+        Ok(self
+            .get_meta(RETENTION_KEY)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TOMBSTONE_RETENTION_DAYS))
+    }
+
+    /// `0` purges tombstones on the next sweep, which also turns undo
+    /// off.
+    pub fn set_tombstone_retention_days(&self, days: u32) -> Result<(), sqlite::Error> {
+        self.set_meta(RETENTION_KEY, &days.to_string())
+    }
+
+    pub(crate) fn tombstone_retention_secs(&self) -> Result<i64, sqlite::Error> {
+        Ok(i64::from(self.tombstone_retention_days()?) * 86_400)
+    }
+
+    /// Hard-delete messages deleted at least the retention period
+    /// before `now` (unix seconds), with their entities, media,
+    /// revisions, wiki evidence and topic links. Returns how many
+    /// messages were purged.
+    pub fn purge_tombstones(&self, now: i64) -> Result<u64, sqlite::Error> {
+        let cutoff = now - self.tombstone_retention_secs()?;
+
+        let _ = self.conn.execute("ROLLBACK");
+        self.conn.execute("BEGIN")?;
+        let result = (|| -> Result<u64, sqlite::Error> {
+            let expired = {
+                // With cloud sync on, a tombstone also waits for the
+                // cloud to ack the delete. Excluded chats queue no ops
+                // and the cloud dropped them on `chat_purge`, so their
+                // tombstones have nothing to wait for.
+                let mut stmt = self.conn.prepare(
+                    "SELECT m.rowid, m.account_id, m.chat_id, m.message_id FROM messages m
+                     WHERE m.deleted_at IS NOT NULL AND m.deleted_at <= ?
+                       AND (? = 0
+                            OR COALESCE(m.cloud_acked_version, 0) >= m.msg_version
+                            OR EXISTS (
+                                SELECT 1 FROM chats c
+                                WHERE c.account_id = m.account_id AND c.chat_id = m.chat_id
+                                  AND c.is_excluded = 1))",
+                )?;
+                stmt.bind((1, cutoff))?;
+                stmt.bind((2, i64::from(sync_enabled(&self.conn)?)))?;
+                let mut rows = Vec::new();
+                while let sqlite::State::Row = stmt.next()? {
+                    rows.push((
+                        stmt.read::<i64, _>(0)?,
+                        stmt.read::<i64, _>(1)?,
+                        stmt.read::<i64, _>(2)?,
+                        stmt.read::<i64, _>(3)?,
+                    ));
+                }
+                rows
+            };
+
+            let mut pages: std::collections::BTreeSet<i64> = std::collections::BTreeSet::new();
+            for &(rowid, account_id, chat_id, message_id) in &expired {
+                delete_entities(&self.conn, account_id, chat_id, message_id)?;
+                delete_media(&self.conn, account_id, chat_id, message_id)?;
+                delete_revisions(&self.conn, account_id, chat_id, message_id)?;
+
+                let mut evidence_stmt = self.conn.prepare(
+                    "SELECT id, page_id FROM wiki_evidence
+                     WHERE account_id = ? AND chat_id = ? AND msg_id = ?",
+                )?;
+                evidence_stmt.bind((1, account_id))?;
+                evidence_stmt.bind((2, chat_id))?;
+                evidence_stmt.bind((3, message_id))?;
+                let mut evidence_ids = Vec::new();
+                while let sqlite::State::Row = evidence_stmt.next()? {
+                    evidence_ids.push(evidence_stmt.read::<i64, _>(0)?);
+                    pages.insert(evidence_stmt.read::<i64, _>(1)?);
+                }
+                for id in evidence_ids {
+                    let mut stmt = self
+                        .conn
+                        .prepare("DELETE FROM evidence_fts WHERE rowid = ?")?;
+                    stmt.bind((1, id))?;
+                    stmt.next()?;
+                    let mut stmt = self
+                        .conn
+                        .prepare("DELETE FROM wiki_evidence WHERE id = ?")?;
+                    stmt.bind((1, id))?;
+                    stmt.next()?;
+                }
+
+                for sql in [
+                    "DELETE FROM wiki_classify_queue_v2
+                     WHERE account_id = ? AND chat_id = ? AND msg_id = ?",
+                    "DELETE FROM wiki_topic_messages
+                     WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+                    "DELETE FROM saved_search_hits
+                     WHERE account_id = ? AND chat_id = ? AND message_id = ?",
+                ] {
+                    let mut stmt = self.conn.prepare(sql)?;
+                    stmt.bind((1, account_id))?;
+                    stmt.bind((2, chat_id))?;
+                    stmt.bind((3, message_id))?;
+                    stmt.next()?;
+                }
+
+                let mut stmt = self.conn.prepare("DELETE FROM messages WHERE rowid = ?")?;
+                stmt.bind((1, rowid))?;
+                stmt.next()?;
+            }
+            for page_id in pages {
+                let mut stmt = self.conn.prepare(
+                    "UPDATE wiki_pages_v2
+                     SET evidence_count = (SELECT COUNT(*) FROM wiki_evidence WHERE page_id = ?1)
+                     WHERE id = ?1",
+                )?;
+                stmt.bind((1, page_id))?;
+                stmt.next()?;
+            }
+            Ok(expired.len() as u64)
+        })();
+        match result {
+            Ok(purged) => {
+                self.conn.execute("COMMIT")?;
+                Ok(purged)
+            }
+            Err(e) => {
+                let _ = self.conn.execute("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+    use crate::store::wiki_page::NewEvidenceV2;
+
+    fn insert(store: &Store, message_id: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1_700_000_000,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    fn message_ref(message_id: i64) -> MessageRef {
+        MessageRef {
+            account_id: 0,
+            chat_id: 1,
+            message_id,
+        }
+    }
+
+    fn count(store: &Store, sql: &str) -> i64 {
+        let mut stmt = store.conn.prepare(sql).unwrap();
+        stmt.next().unwrap();
+        stmt.read(0).unwrap()
+    }
+
+    fn fts_hits(store: &Store, term: &str) -> i64 {
+        count(
+            store,
+            &format!("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH '\"{term}\"'"),
+        )
+    }
+
+    #[test]
+    fn delete_hides_and_undo_restores() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, "#airdrop 스냅샷 공지");
+        insert(&store, 1, "#airdrop 스냅샷 연기");
+        let page_id = store
+            .dedup_or_insert_page_v2("topic", "Airdrop", &[])
+            .unwrap()
+            .id;
+        store
+            .insert_evidence_v2(&NewEvidenceV2 {
+                page_id,
+                account_id: 0,
+                msg_id: 1,
+                chat_id: 1,
+                sender_id: 0,
+                ts: 1_700_000_000,
+                excerpt: "스냅샷 연기",
+                salience: 0.5,
+            })
+            .unwrap();
+
+        assert_eq!(store.delete_messages(&[message_ref(1)]).unwrap(), 1);
+        // Deleting a tombstone again is a no-op.
+        assert_eq!(store.delete_messages(&[message_ref(1)]).unwrap(), 0);
+        assert!(store.get_message(0, 1, 1).unwrap().is_none());
+        assert_eq!(fts_hits(&store, "스냅샷"), 0);
+        assert_eq!(
+            count(
+                &store,
+                "SELECT COUNT(*) FROM wiki_evidence WHERE hidden_at IS NULL"
+            ),
+            0
+        );
+        // A late re-mirror does not resurrect the message.
+        insert(&store, 1, "#airdrop 스냅샷 연기");
+        assert!(store.get_message(0, 1, 1).unwrap().is_none());
+
+        assert_eq!(store.undelete_messages(&[message_ref(1)]).unwrap(), 1);
+        assert_eq!(
+            store.get_message(0, 1, 1).unwrap().unwrap().text_plain,
+            "#airdrop 스냅샷 연기"
+        );
+        assert_eq!(fts_hits(&store, "스냅샷"), 1);
+        assert_eq!(
+            count(
+                &store,
+                "SELECT COUNT(*) FROM wiki_evidence WHERE hidden_at IS NULL"
+            ),
+            1
+        );
+        assert_eq!(store.undelete_messages(&[message_ref(1)]).unwrap(), 0);
+    }
+
+    #[test]
+    fn purge_respects_retention() {
+        let store = Store::open_in_memory().unwrap();
+        assert_eq!(
+            store.tombstone_retention_days().unwrap(),
+            DEFAULT_TOMBSTONE_RETENTION_DAYS
+        );
+        insert(&store, 1, "#airdrop 스냅샷 공지");
+        insert(&store, 1, "#airdrop 스냅샷 연기");
+        insert(&store, 2, "남는 메시지");
+        store.delete_messages(&[message_ref(1)]).unwrap();
+
+        let now = crate::wiki::norm::unix_now();
+        assert_eq!(store.purge_tombstones(now).unwrap(), 0);
+        assert_eq!(store.purge_tombstones(now + 31 * 86_400).unwrap(), 1);
+        for table in ["message_entities", "message_revisions"] {
+            assert_eq!(
+                count(&store, &format!("SELECT COUNT(*) FROM {table}")),
+                0,
+                "{table}"
+            );
+        }
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM messages"), 1);
+        assert_eq!(store.undelete_messages(&[message_ref(1)]).unwrap(), 0);
+
+        // With no retention, undo is off and the next sweep purges.
+        store.set_tombstone_retention_days(0).unwrap();
+        store.delete_messages(&[message_ref(2)]).unwrap();
+        assert_eq!(store.undelete_messages(&[message_ref(2)]).unwrap(), 0);
+        assert_eq!(store.purge_tombstones(now).unwrap(), 1);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM messages"), 0);
+    }
+
+    #[test]
+    fn purge_waits_for_cloud_ack_except_in_excluded_chats() {
+        let store = Store::open_in_memory().unwrap();
+        store.set_cloud_sync_enabled(true).unwrap();
+        insert(&store, 1, "#airdrop 스냅샷 공지");
+        insert(&store, 2, "#airdrop 스냅샷 연기");
+        store
+            .delete_messages(&[message_ref(1), message_ref(2)])
+            .unwrap();
+        let later = crate::wiki::norm::unix_now() + 31 * 86_400;
+        assert_eq!(store.purge_tombstones(later).unwrap(), 0);
+
+        // Nothing is queued for an excluded chat, so nothing will ever
+        // be acked; retention alone decides.
+        store.set_chat_excluded(0, 1, true).unwrap();
+        assert_eq!(store.purge_tombstones(later).unwrap(), 2);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM messages"), 0);
+    }
+}
@@ -0,0 +1,193 @@
+//! Word vocabulary harvested from indexed messages. Feeds the fuzzy
+//! "did you mean" fallback in `search::fuzzy`, which needs whole words
+//! to compare against; the trigram FTS index only knows trigrams.
+//!
+//! `freq` counts live messages containing the word, maintained by the
+//! insert/delete paths in `store::message`.
+
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+use crate::search::hangul::decompose_jamo;
+
+/// Words longer than this are almost always URLs or pasted blobs.
+const MAX_WORD_CHARS: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VocabEntry {
+    pub term: String,
+    pub term_jamo: String,
+    pub freq: i64,
+}
+
+/// Distinct vocabulary words of one message, from its stemmed text so
+/// `삼성전자를` and `삼성전자` count as one word.
+pub(crate) fn vocab_words(text_stem: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for raw in text_stem.split_whitespace() {
+        let word = raw
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        let chars = word.chars().count();
+        if (2..=MAX_WORD_CHARS).contains(&chars) && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Add `delta` to the frequency of each word, dropping words whose
+/// count reaches zero. Runs inside the caller's transaction.
+pub(crate) fn adjust_vocab(
+    conn: &sqlite::Connection,
+    words: &[String],
+    delta: i64,
+) -> Result<(), sqlite::Error> {
+    for word in words {
+        if delta > 0 {
+            let jamo = decompose_jamo(word);
+            let mut stmt = conn.prepare(
+                "INSERT INTO search_vocab (term, term_jamo, jamo_len, freq)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(term) DO UPDATE SET freq = freq + excluded.freq",
+            )?;
+            stmt.bind((1, word.as_str()))?;
+            stmt.bind((2, jamo.as_str()))?;
+            stmt.bind((3, jamo.chars().count() as i64))?;
+            stmt.bind((4, delta))?;
+            stmt.next()?;
+        } else if delta < 0 {
+            let mut stmt =
+                conn.prepare("UPDATE search_vocab SET freq = freq + ? WHERE term = ?")?;
+            stmt.bind((1, delta))?;
+            stmt.bind((2, word.as_str()))?;
+            stmt.next()?;
+            let mut stmt = conn.prepare("DELETE FROM search_vocab WHERE term = ? AND freq <= 0")?;
+            stmt.bind((1, word.as_str()))?;
+            stmt.next()?;
+        }
+    }
+    Ok(())
+}
+
+impl Store {
+    pub fn vocab_contains(&self, term: &str) -> Result<bool, sqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM search_vocab WHERE term = ?")?;
+        stmt.bind((1, term))?;
+        Ok(matches!(stmt.next()?, sqlite::State::Row))
+    }
+
+    /// Most frequent words whose jamo length lies in `min_len..=max_len`.
+    pub fn vocab_candidates(
+        &self,
+        min_len: usize,
+        max_len: usize,
+        limit: usize,
+    ) -> Result<Vec<VocabEntry>, sqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT term, term_jamo, freq FROM search_vocab
+             WHERE jamo_len BETWEEN ? AND ?
+             ORDER BY freq DESC, term ASC
+             LIMIT ?",
+        )?;
+        stmt.bind((1, min_len as i64))?;
+        stmt.bind((2, max_len as i64))?;
+        stmt.bind((3, limit as i64))?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            out.push(VocabEntry {
+                term: stmt.read::<String, _>(0)?,
+                term_jamo: stmt.read::<String, _>(1)?,
+                freq: stmt.read::<i64, _>(2)?,
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::message::{strip_whitespace, MessageRef, MessageRow};
+
+    fn insert(store: &Store, message_id: i64, text: &str) {
+        store
+            .insert_messages_batch(&[MessageRow {
+                message_id,
+                account_id: 0,
+                chat_id: 1,
+                timestamp: 1000 + message_id,
+                text_plain: text.to_string(),
+                text_stripped: strip_whitespace(text),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            }])
+            .unwrap();
+    }
+
+    fn freq(store: &Store, term: &str) -> Option<i64> {
+        let mut stmt = store
+            .conn()
+            .prepare("SELECT freq FROM search_vocab WHERE term = ?")
+            .unwrap();
+        stmt.bind((1, term)).unwrap();
+        match stmt.next().unwrap() {
+            sqlite::State::Row => Some(stmt.read::<i64, _>(0).unwrap()),
+            sqlite::State::Done => None,
+        }
+    }
+
+    #[test]
+    fn words_are_stemmed_trimmed_and_deduped() {
+        assert_eq!(
+            vocab_words("삼성전자 실적, 삼성전자 (Galaxy) a"),
+            vec!["삼성전자", "실적", "galaxy"]
+        );
+    }
+
+    #[test]
+    fn insert_update_and_delete_maintain_counts() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, "삼성전자가 상승했다");
+        insert(&store, 2, "삼성전자를 매수");
+        assert_eq!(freq(&store, "삼성전자"), Some(2));
+        assert_eq!(freq(&store, "상승"), Some(1));
+        assert!(store.vocab_contains("매수").unwrap());
+
+        // Edit replaces the old words.
+        insert(&store, 2, "애플 매도");
+        assert_eq!(freq(&store, "삼성전자"), Some(1));
+        assert_eq!(freq(&store, "매수"), None);
+        assert_eq!(freq(&store, "매도"), Some(1));
+
+        store
+            .delete_messages(&[MessageRef {
+                account_id: 0,
+                chat_id: 1,
+                message_id: 1,
+            }])
+            .unwrap();
+        assert_eq!(freq(&store, "삼성전자"), None);
+    }
+
+    #[test]
+    fn candidates_filter_by_jamo_length() {
+        let store = Store::open_in_memory().unwrap();
+        insert(&store, 1, "삼성전자 삼성 hello");
+        let len = decompose_jamo("삼성전자").chars().count();
+        let terms: Vec<String> = store
+            .vocab_candidates(len - 1, len + 1, 10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.term)
+            .collect();
+        assert_eq!(terms, vec!["삼성전자"]);
+    }
+}
@@ -238,6 +238,7 @@ pub struct PageForRewrite {
 pub struct NewEvidenceV2<'a> {
     pub page_id: i64,
     pub msg_id: i64,
+    pub account_id: i64,
     pub chat_id: i64,
     pub sender_id: i64,
     pub ts: i64,
@@ -518,7 +519,7 @@ impl Store {
     }
 
     /// Insert evidence row, bump page counters, and insert `evidence_fts`.
-    /// Returns `None` on duplicate `(page_id,msg_id,chat_id)`.
+    /// Returns `None` on duplicate `(page_id,account_id,msg_id,chat_id)`.
     /// Must be called inside the caller's transaction.
     pub fn insert_evidence_v2(
         &self,
@@ -539,11 +540,13 @@ impl Store {
 
         {
             let mut s = self.conn().prepare(
-                "SELECT 1 FROM wiki_evidence WHERE page_id = ? AND msg_id = ? AND chat_id = ?",
+                "SELECT 1 FROM wiki_evidence
+                  WHERE page_id = ? AND account_id = ? AND msg_id = ? AND chat_id = ?",
             )?;
             s.bind((1, evidence.page_id))?;
-            s.bind((2, evidence.msg_id))?;
-            s.bind((3, evidence.chat_id))?;
+            s.bind((2, evidence.account_id))?;
+            s.bind((3, evidence.msg_id))?;
+            s.bind((4, evidence.chat_id))?;
             if let sqlite::State::Row = s.next()? {
                 return Ok(None);
             }
@@ -552,8 +555,8 @@ impl Store {
         let mut ins = self.conn().prepare(
             "INSERT INTO wiki_evidence
                 (page_id, msg_id, chat_id, sender_id, ts,
-                 excerpt, excerpt_jamo, source_hash, salience, created_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                 excerpt, excerpt_jamo, source_hash, salience, created_at, account_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )?;
         ins.bind((1, evidence.page_id))?;
         ins.bind((2, evidence.msg_id))?;
@@ -565,6 +568,7 @@ impl Store {
         ins.bind((8, source_hash.as_slice()))?;
         ins.bind((9, evidence.salience))?;
         ins.bind((10, now))?;
+        ins.bind((11, evidence.account_id))?;
         ins.next()?;
         let evid_id = self.last_insert_rowid()?;
 
@@ -678,6 +682,29 @@ impl Store {
             aliases,
         })
     }
+
+    /// Titles and aliases of visible pages, best-evidenced first. Feeds
+    /// query autocomplete.
+    pub fn wiki_page_names(&self) -> Result<Vec<String>, sqlite::Error> {
+        let mut s = self.conn().prepare(
+            "SELECT name FROM (
+                SELECT p.title AS name, p.evidence_count AS weight
+                  FROM wiki_pages_v2 p
+                 WHERE p.state IN ('active','resolved')
+                UNION ALL
+                SELECT a.alias_raw, p.evidence_count
+                  FROM wiki_page_aliases a
+                  JOIN wiki_pages_v2 p ON p.id = a.page_id
+                 WHERE p.state IN ('active','resolved')
+             )
+             ORDER BY weight DESC, name ASC",
+        )?;
+        let mut out = Vec::new();
+        while let sqlite::State::Row = s.next()? {
+            out.push(s.read::<String, _>(0)?);
+        }
+        Ok(out)
+    }
 }
 
 /// Validated rewrite payload to apply in a single txn.
@@ -811,7 +838,7 @@ impl Store {
             let mut s = self.conn().prepare(
                 "SELECT id, msg_id, chat_id, ts, excerpt, salience, cited
                    FROM wiki_evidence
-                  WHERE page_id = ? AND id > ? AND id <= ?
+                  WHERE page_id = ? AND id > ? AND id <= ? AND hidden_at IS NULL
                   ORDER BY id DESC
                   LIMIT 30",
             )?;
@@ -843,6 +870,7 @@ impl Store {
                 "SELECT id, msg_id, chat_id, ts, excerpt, salience, cited
                    FROM wiki_evidence
                   WHERE page_id = ? AND id <= ? AND id NOT IN {placeholders}
+                    AND hidden_at IS NULL
                   ORDER BY salience DESC, ts DESC
                   LIMIT 20"
             );
@@ -865,7 +893,7 @@ impl Store {
             let mut s = self.conn().prepare(
                 "SELECT id, msg_id, chat_id, ts, excerpt, salience, cited
                    FROM wiki_evidence
-                  WHERE page_id = ? AND cited > 0
+                  WHERE page_id = ? AND cited > 0 AND hidden_at IS NULL
                   ORDER BY cited DESC, ts DESC",
             )?;
             s.bind((1, page_id))?;
@@ -1098,7 +1126,7 @@ impl Store {
             WITH window_e AS (
                 SELECT page_id, chat_id, sender_id, ts
                   FROM wiki_evidence
-                 WHERE id <= ?1 AND ts >= ?2 AND ts < ?3
+                 WHERE id <= ?1 AND ts >= ?2 AND ts < ?3 AND hidden_at IS NULL
             ),
             agg AS (
                 SELECT page_id,
@@ -1112,7 +1140,7 @@ impl Store {
             prior AS (
                 SELECT page_id, COUNT(*) AS ec2
                   FROM wiki_evidence
-                 WHERE id <= ?1 AND ts >= ?4 AND ts < ?2
+                 WHERE id <= ?1 AND ts >= ?4 AND ts < ?2 AND hidden_at IS NULL
                  GROUP BY page_id
             )
             SELECT p.id, p.kind, p.title, p.created_at,
@@ -1189,6 +1217,7 @@ impl Store {
         let mut s = self.conn().prepare(
             "SELECT excerpt FROM wiki_evidence
               WHERE page_id = ? AND id <= ? AND ts >= ? AND ts < ?
+                AND hidden_at IS NULL
               ORDER BY salience DESC, ts DESC
               LIMIT ?",
         )?;
@@ -1216,7 +1245,8 @@ impl Store {
         let mut buckets = [0u32; 24];
         let mut s = self.conn().prepare(
             "SELECT ts FROM wiki_evidence
-              WHERE page_id = ? AND id <= ? AND ts >= ? AND ts < ?",
+              WHERE page_id = ? AND id <= ? AND ts >= ? AND ts < ?
+                AND hidden_at IS NULL",
         )?;
         s.bind((1, page_id))?;
         s.bind((2, snap.max_evidence_id))?;
@@ -1444,6 +1474,7 @@ impl Store {
                        (SELECT last_open_at FROM wiki_last_open
                           WHERE chat_id = e.chat_id),
                        0)
+               AND e.hidden_at IS NULL
                AND p.state != 'hidden'
                AND p.state != 'resolved'
              GROUP BY e.chat_id, e.page_id
@@ -1504,6 +1535,7 @@ pub struct AskEvidence {
     pub evidence_id: i64,
     pub page_id: i64,
     pub page_title: String,
+    pub account_id: i64,
     pub chat_id: i64,
     pub chat_title: String,
     pub msg_id: i64,
@@ -1631,10 +1663,13 @@ impl Store {
                AND EXISTS (
                    SELECT 1
                      FROM wiki_evidence e
-                     JOIN messages m ON m.chat_id = e.chat_id
+                     JOIN messages m ON m.account_id = e.account_id
+                                    AND m.chat_id = e.chat_id
                                     AND m.message_id = e.msg_id
-                     JOIN chats c    ON c.chat_id = e.chat_id
+                     JOIN chats c    ON c.account_id = e.account_id
+                                    AND c.chat_id = e.chat_id
                     WHERE e.page_id = p.id
+                      AND e.hidden_at IS NULL
                       AND m.deleted_at IS NULL
                       AND c.is_excluded = 0
                )
@@ -1660,7 +1695,7 @@ impl Store {
     /// applies the time-decay multiplier and picks `limit`. Math is
     /// done in Rust because SQLite needs `SQLITE_ENABLE_MATH_FUNCTIONS`
     /// for `EXP`, which is not portable (matches the trending decision).
-    /// Same `(chat_id, msg_id)` is collapsed to a single row — duplicates
+    /// Same `(account_id, chat_id, msg_id)` is collapsed to a single row — duplicates
     /// would burn presentation slots without adding signal.
     pub fn ask_fts_evidence(
         &self,
@@ -1688,35 +1723,38 @@ impl Store {
         // statement that joins the FTS5 table — not inside aggregates
         // or window functions referencing it. So:
         //   1. `raw` materializes bm25 into a regular column.
-        //   2. `deduped` picks the best-ranked row per (chat, msg)
+        //   2. `deduped` picks the best-ranked row per (account, chat, msg)
         //      using ROW_NUMBER over the regular `rank` column.
         let q = "
             WITH raw AS (
-                SELECT e.id, e.page_id, p.title AS page_title, e.chat_id,
+                SELECT e.id, e.page_id, p.title AS page_title, e.account_id, e.chat_id,
                        COALESCE(c.title, '') AS chat_title,
                        e.msg_id, e.sender_id, e.ts, e.excerpt,
                        bm25(evidence_fts) AS rank
                   FROM evidence_fts f
                   JOIN wiki_evidence e  ON e.id = f.rowid
                   JOIN wiki_pages_v2 p  ON p.id = e.page_id
-                  JOIN messages m       ON m.chat_id = e.chat_id
+                  JOIN messages m       ON m.account_id = e.account_id
+                                       AND m.chat_id = e.chat_id
                                        AND m.message_id = e.msg_id
-                  JOIN chats c          ON c.chat_id = e.chat_id
+                  JOIN chats c          ON c.account_id = e.account_id
+                                       AND c.chat_id = e.chat_id
                  WHERE evidence_fts MATCH ?
                    AND p.state != 'hidden'
+                   AND e.hidden_at IS NULL
                    AND m.deleted_at IS NULL
                    AND c.is_excluded = 0
             ),
             deduped AS (
-                SELECT id, page_id, page_title, chat_id, chat_title,
+                SELECT id, page_id, page_title, account_id, chat_id, chat_title,
                        msg_id, sender_id, ts, excerpt, rank,
                        ROW_NUMBER() OVER (
-                           PARTITION BY chat_id, msg_id
+                           PARTITION BY account_id, chat_id, msg_id
                            ORDER BY rank ASC, id ASC
                        ) AS rn
                   FROM raw
             )
-            SELECT id, page_id, page_title AS title, chat_id, chat_title,
+            SELECT id, page_id, page_title AS title, account_id, chat_id, chat_title,
                    msg_id, sender_id, ts, excerpt, rank
               FROM deduped
              WHERE rn = 1
@@ -1741,6 +1779,7 @@ impl Store {
                     evidence_id: s.read::<i64, _>("id")?,
                     page_id: s.read::<i64, _>("page_id")?,
                     page_title: s.read::<String, _>("title")?,
+                    account_id: s.read::<i64, _>("account_id")?,
                     chat_id,
                     chat_title: s.read::<String, _>("chat_title")?,
                     msg_id,
@@ -1852,21 +1891,33 @@ mod tests {
             .insert_messages_batch(&[
                 MessageRow {
                     message_id: 1,
+                    account_id: 0,
                     chat_id: 1,
                     timestamp: 1000,
                     text_plain: "test msg 1".to_string(),
                     text_stripped: "testmsg1".to_string(),
                     link: None,
                     sender_id: 0,
+                    media: None,
+                    reply_to_message_id: None,
+                    thread_id: None,
+                    sender_name: None,
+                    sender_username: None,
                 },
                 MessageRow {
                     message_id: 2,
+                    account_id: 0,
                     chat_id: 1,
                     timestamp: 2000,
                     text_plain: "test msg 2".to_string(),
                     text_stripped: "testmsg2".to_string(),
                     link: None,
                     sender_id: 0,
+                    media: None,
+                    reply_to_message_id: None,
+                    thread_id: None,
+                    sender_name: None,
+                    sender_username: None,
                 },
             ])
             .unwrap();
@@ -1987,6 +2038,7 @@ mod tests {
         let evidence = NewEvidenceV2 {
             page_id: p.id,
             msg_id: 1,
+            account_id: 0,
             chat_id: 1,
             sender_id: 0,
             ts: 1000,
@@ -2030,6 +2082,7 @@ mod tests {
         let n = NewEvidenceV2 {
             page_id,
             msg_id,
+            account_id: 0,
             chat_id: 1,
             sender_id: 0,
             ts,
@@ -2379,6 +2432,7 @@ mod tests {
         let n = NewEvidenceV2 {
             page_id,
             msg_id,
+            account_id: 0,
             chat_id,
             sender_id,
             ts,
@@ -2780,6 +2834,7 @@ mod tests {
             .insert_evidence_v2(&NewEvidenceV2 {
                 page_id,
                 msg_id,
+                account_id: 0,
                 chat_id,
                 sender_id: 0,
                 ts,
@@ -3101,12 +3156,18 @@ mod tests {
             store
                 .insert_messages_batch(&[crate::store::message::MessageRow {
                     message_id: msg_id,
+                    account_id: 0,
                     chat_id,
                     timestamp: ts,
                     text_plain: text.to_string(),
                     text_stripped: text.replace(' ', ""),
                     link: None,
                     sender_id: 7,
+                    media: None,
+                    reply_to_message_id: None,
+                    thread_id: None,
+                    sender_name: None,
+                    sender_username: None,
                 }])
                 .unwrap();
         }
@@ -3115,6 +3176,7 @@ mod tests {
             .insert_evidence_v2(&NewEvidenceV2 {
                 page_id,
                 msg_id,
+                account_id: 0,
                 chat_id,
                 sender_id: 7,
                 ts,
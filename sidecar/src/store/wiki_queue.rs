@@ -226,6 +226,7 @@ impl Store {
 /// Row claimed for v2 classification.
 #[derive(Debug, Clone)]
 pub struct ClassifyV2Item {
+    pub account_id: i64,
     pub msg_id: i64,
     pub chat_id: i64,
     pub attempts: i64,
@@ -247,7 +248,7 @@ impl Store {
         self.conn().execute("BEGIN IMMEDIATE")?;
         let result = (|| -> Result<Vec<ClassifyV2Item>, sqlite::Error> {
             let mut sel = self.conn().prepare(format!(
-                "SELECT msg_id, chat_id, attempts, hint, hint_page_id, text_hash
+                "SELECT account_id, msg_id, chat_id, attempts, hint, hint_page_id, text_hash
                    FROM wiki_classify_queue_v2
                   WHERE status = 'pending'
                     AND (next_attempt_at IS NULL OR next_attempt_at <= ?)
@@ -259,6 +260,7 @@ impl Store {
             let mut rows = Vec::new();
             while let sqlite::State::Row = sel.next()? {
                 rows.push(ClassifyV2Item {
+                    account_id: sel.read::<i64, _>("account_id")?,
                     msg_id: sel.read::<i64, _>("msg_id")?,
                     chat_id: sel.read::<i64, _>("chat_id")?,
                     attempts: sel.read::<i64, _>("attempts")?,
@@ -274,12 +276,13 @@ impl Store {
             let mut upd = self.conn().prepare(
                 "UPDATE wiki_classify_queue_v2
                     SET status = 'processing', claimed_at = ?
-                  WHERE msg_id = ? AND chat_id = ?",
+                  WHERE account_id = ? AND msg_id = ? AND chat_id = ?",
             )?;
             for r in &rows {
                 upd.bind((1, now))?;
-                upd.bind((2, r.msg_id))?;
-                upd.bind((3, r.chat_id))?;
+                upd.bind((2, r.account_id))?;
+                upd.bind((3, r.msg_id))?;
+                upd.bind((4, r.chat_id))?;
                 upd.next()?;
                 upd.reset()?;
             }
@@ -298,15 +301,21 @@ impl Store {
     }
 
     /// Terminal success.
-    pub fn mark_classify_v2_done(&self, msg_id: i64, chat_id: i64) -> Result<(), sqlite::Error> {
+    pub fn mark_classify_v2_done(
+        &self,
+        account_id: i64,
+        msg_id: i64,
+        chat_id: i64,
+    ) -> Result<(), sqlite::Error> {
         let mut s = self.conn().prepare(
             "UPDATE wiki_classify_queue_v2
                 SET status = 'done', attempts = attempts + 1,
                     claimed_at = NULL, last_error = NULL
-              WHERE msg_id = ? AND chat_id = ?",
+              WHERE account_id = ? AND msg_id = ? AND chat_id = ?",
         )?;
-        s.bind((1, msg_id))?;
-        s.bind((2, chat_id))?;
+        s.bind((1, account_id))?;
+        s.bind((2, msg_id))?;
+        s.bind((3, chat_id))?;
         s.next()?;
         Ok(())
     }
@@ -314,6 +323,7 @@ impl Store {
     /// Bump attempts, back off, and transition to `failed` when exhausted.
     pub fn mark_classify_v2_retry(
         &self,
+        account_id: i64,
         msg_id: i64,
         chat_id: i64,
         err: &str,
@@ -330,15 +340,16 @@ impl Store {
                         WHEN attempts + 1 >= ? THEN ?
                         ELSE ? + (30 * (1 << MIN(attempts + 1, 8)))
                     END
-              WHERE msg_id = ? AND chat_id = ?",
+              WHERE account_id = ? AND msg_id = ? AND chat_id = ?",
         )?;
         s.bind((1, err))?;
         s.bind((2, max_attempts))?;
         s.bind((3, max_attempts))?;
         s.bind((4, now))?;
         s.bind((5, now))?;
-        s.bind((6, msg_id))?;
-        s.bind((7, chat_id))?;
+        s.bind((6, account_id))?;
+        s.bind((7, msg_id))?;
+        s.bind((8, chat_id))?;
         s.next()?;
         Ok(())
     }
@@ -346,6 +357,7 @@ impl Store {
     /// Re-queue with successor hint per spec §6.2 apply step.
     pub fn mark_classify_v2_successor_needed(
         &self,
+        account_id: i64,
         msg_id: i64,
         chat_id: i64,
         hint_page_id: i64,
@@ -359,12 +371,13 @@ impl Store {
                     attempts = attempts + 1,
                     claimed_at = NULL,
                     next_attempt_at = ? + 30
-              WHERE msg_id = ? AND chat_id = ?",
+              WHERE account_id = ? AND msg_id = ? AND chat_id = ?",
         )?;
         s.bind((1, hint_page_id))?;
         s.bind((2, now))?;
-        s.bind((3, msg_id))?;
-        s.bind((4, chat_id))?;
+        s.bind((3, account_id))?;
+        s.bind((4, msg_id))?;
+        s.bind((5, chat_id))?;
         s.next()?;
         Ok(())
     }
@@ -634,21 +647,33 @@ mod tests {
         let msgs = vec![
             MessageRow {
                 message_id: 1,
+                account_id: 0,
                 chat_id: 1,
                 timestamp: 1000,
                 text_plain: "hello".to_string(),
                 text_stripped: "hello".to_string(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
             MessageRow {
                 message_id: 2,
+                account_id: 0,
                 chat_id: 1,
                 timestamp: 2000,
                 text_plain: "world".to_string(),
                 text_stripped: "world".to_string(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
         ];
         store.insert_messages_batch(&msgs).unwrap();
@@ -741,7 +766,7 @@ mod tests {
         let stats = store.get_classify_v2_stats().unwrap();
         assert_eq!(stats.processing, 2);
 
-        store.mark_classify_v2_done(1, 1).unwrap();
+        store.mark_classify_v2_done(0, 1, 1).unwrap();
         let stats = store.get_classify_v2_stats().unwrap();
         assert_eq!(stats.done, 1);
         assert_eq!(stats.processing, 1);
@@ -763,9 +788,9 @@ mod tests {
         s.bind((2, now)).unwrap();
         s.next().unwrap();
 
-        store.mark_classify_v2_retry(1, 1, "err1", 3).unwrap();
-        store.mark_classify_v2_retry(1, 1, "err2", 3).unwrap();
-        store.mark_classify_v2_retry(1, 1, "err3", 3).unwrap();
+        store.mark_classify_v2_retry(0, 1, 1, "err1", 3).unwrap();
+        store.mark_classify_v2_retry(0, 1, 1, "err2", 3).unwrap();
+        store.mark_classify_v2_retry(0, 1, 1, "err3", 3).unwrap();
         let stats = store.get_classify_v2_stats().unwrap();
         assert_eq!(stats.failed, 1);
     }
@@ -95,13 +95,15 @@ impl Store {
                 (SELECT COUNT(DISTINCT wtm.topic_id)
                  FROM wiki_topic_messages wtm
                  JOIN messages m
-                   ON m.chat_id = wtm.chat_id AND m.message_id = wtm.message_id
-                 WHERE m.timestamp >= ?1),
+                   ON m.account_id = wtm.account_id AND m.chat_id = wtm.chat_id
+                  AND m.message_id = wtm.message_id
+                 WHERE m.timestamp >= ?1 AND m.deleted_at IS NULL),
                 (SELECT COUNT(*)
                  FROM wiki_topic_messages wtm
                  JOIN messages m
-                   ON m.chat_id = wtm.chat_id AND m.message_id = wtm.message_id
-                 WHERE m.timestamp >= ?1)
+                   ON m.account_id = wtm.account_id AND m.chat_id = wtm.chat_id
+                  AND m.message_id = wtm.message_id
+                 WHERE m.timestamp >= ?1 AND m.deleted_at IS NULL)
             ",
         )?;
         stmt.bind((1, since_ts))?;
@@ -3,6 +3,11 @@ use serde::{Deserialize, Serialize};
 use super::message::MessageWithChat;
 use super::Store;
 
+/// `wiki_topic_messages tm` joined to the messages that are not deleted.
+const LIVE_TOPIC_MESSAGES: &str = "wiki_topic_messages tm
+                    JOIN messages m ON m.account_id = tm.account_id AND m.chat_id = tm.chat_id
+                        AND m.message_id = tm.message_id AND m.deleted_at IS NULL";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WikiTopic {
     pub topic_id: i64,
@@ -154,16 +159,17 @@ impl Store {
         Ok(())
     }
 
+    /// Counters over the topic's live messages; deleted ones keep
+    /// their membership row until purged but no longer count.
     fn refresh_topic_counters(&self, topic_id: i64) -> Result<(), sqlite::Error> {
         self.conn().execute(format!(
             "UPDATE wiki_topics SET
-                message_count = (SELECT COUNT(*) FROM wiki_topic_messages WHERE topic_id = {0}),
-                channel_count = (SELECT COUNT(DISTINCT chat_id) FROM wiki_topic_messages WHERE topic_id = {0}),
-                first_seen_at = (SELECT MIN(m.timestamp) FROM wiki_topic_messages tm
-                    JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
+                message_count = (SELECT COUNT(*) FROM {LIVE_TOPIC_MESSAGES} WHERE tm.topic_id = {0}),
+                channel_count = (SELECT COUNT(DISTINCT tm.chat_id) FROM {LIVE_TOPIC_MESSAGES}
+                    WHERE tm.topic_id = {0}),
+                first_seen_at = (SELECT MIN(m.timestamp) FROM {LIVE_TOPIC_MESSAGES}
                     WHERE tm.topic_id = {0}),
-                last_seen_at = (SELECT MAX(m.timestamp) FROM wiki_topic_messages tm
-                    JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
+                last_seen_at = (SELECT MAX(m.timestamp) FROM {LIVE_TOPIC_MESSAGES}
                     WHERE tm.topic_id = {0}),
                 updated_at = datetime('now')
              WHERE topic_id = {0}",
@@ -172,6 +178,28 @@ impl Store {
         Ok(())
     }
 
+    /// Recompute a topic's counters, daily stats, channel membership
+    /// and trending score after messages in it were deleted or
+    /// restored.
+    pub(crate) fn rebuild_topic_stats(&self, topic_id: i64) -> Result<(), sqlite::Error> {
+        self.refresh_topic_counters(topic_id)?;
+        self.conn().execute(format!(
+            "DELETE FROM topic_stats_daily WHERE topic_id = {0};
+             INSERT INTO topic_stats_daily (topic_id, date, msg_count)
+             SELECT {0}, date(m.timestamp, 'unixepoch') AS d, COUNT(*)
+             FROM {LIVE_TOPIC_MESSAGES}
+             WHERE tm.topic_id = {0}
+             GROUP BY d;
+             DELETE FROM topic_channel_membership WHERE topic_id = {0};
+             INSERT OR IGNORE INTO topic_channel_membership (topic_id, date, chat_id)
+             SELECT {0}, date(m.timestamp, 'unixepoch'), m.chat_id
+             FROM {LIVE_TOPIC_MESSAGES}
+             WHERE tm.topic_id = {0};",
+            topic_id
+        ))?;
+        self.recompute_topic_trending_score(topic_id)
+    }
+
     pub fn set_title_ko_if_absent(
         &self,
         topic_id: i64,
@@ -333,11 +361,14 @@ impl Store {
         offset: usize,
     ) -> Result<Vec<MessageWithChat>, sqlite::Error> {
         let mut stmt = self.conn().prepare(format!(
-            "SELECT m.message_id, m.chat_id, m.timestamp, m.text_plain, m.link, ch.title as chat_title
+            "SELECT m.message_id, m.account_id, m.chat_id, m.timestamp, m.text_plain, m.link,
+                    ch.title as chat_title, sn.display_name AS sender_name
              FROM wiki_topic_messages tm
-             JOIN messages m ON m.chat_id = tm.chat_id AND m.message_id = tm.message_id
-             JOIN chats ch ON ch.chat_id = m.chat_id
-             WHERE tm.topic_id = ?
+             JOIN messages m ON m.account_id = tm.account_id AND m.chat_id = tm.chat_id
+                            AND m.message_id = tm.message_id
+             JOIN chats ch ON ch.account_id = m.account_id AND ch.chat_id = m.chat_id
+             LEFT JOIN senders sn ON sn.sender_id = m.sender_id
+             WHERE tm.topic_id = ? AND m.deleted_at IS NULL
              ORDER BY tm.relevance DESC, m.timestamp DESC
              LIMIT {} OFFSET {}",
             limit, offset
@@ -347,12 +378,15 @@ impl Store {
         while let sqlite::State::Row = stmt.next()? {
             msgs.push(MessageWithChat {
                 message_id: stmt.read::<i64, _>("message_id")?,
+                account_id: stmt.read::<i64, _>("account_id")?,
                 chat_id: stmt.read::<i64, _>("chat_id")?,
                 timestamp: stmt.read::<i64, _>("timestamp")?,
                 text_plain: stmt.read::<String, _>("text_plain")?,
                 link: stmt.read::<Option<String>, _>("link")?,
                 chat_title: stmt.read::<String, _>("chat_title")?,
                 rank: 0.0,
+                sender_name: stmt.read::<Option<String>, _>("sender_name")?,
+                matched_revision: None,
             });
         }
         Ok(msgs)
@@ -412,12 +446,13 @@ impl Store {
     ) -> Result<Vec<TopicMessageRow>, sqlite::Error> {
         let mut stmt = self.conn().prepare(format!(
             "SELECT m.chat_id, m.message_id, m.timestamp, m.text_plain, m.link,
-                    COALESCE(c.title, '')
+                    COALESCE(c.title, ''), m.account_id
              FROM wiki_topic_messages wtm
-             JOIN messages m ON m.chat_id = wtm.chat_id
+             JOIN messages m ON m.account_id = wtm.account_id
+                             AND m.chat_id = wtm.chat_id
                              AND m.message_id = wtm.message_id
-             LEFT JOIN chats c ON c.chat_id = m.chat_id
-             WHERE wtm.topic_id = ?
+             LEFT JOIN chats c ON c.account_id = m.account_id AND c.chat_id = m.chat_id
+             WHERE wtm.topic_id = ? AND m.deleted_at IS NULL
              ORDER BY m.timestamp DESC
              LIMIT {limit}",
         ))?;
@@ -425,6 +460,7 @@ impl Store {
         let mut out = Vec::new();
         while let sqlite::State::Row = stmt.next()? {
             out.push(TopicMessageRow {
+                account_id: stmt.read::<i64, _>(6)?,
                 chat_id: stmt.read::<i64, _>(0)?,
                 message_id: stmt.read::<i64, _>(1)?,
                 timestamp: stmt.read::<i64, _>(2)?,
@@ -439,6 +475,7 @@ impl Store {
 
 #[derive(Debug, Clone)]
 pub struct TopicMessageRow {
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
     pub timestamp: i64,
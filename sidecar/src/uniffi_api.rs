@@ -26,12 +26,22 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
+use crate::cloud::client::{start_sync, SyncConfig, SyncHandle};
+use crate::cloud::push::Endpoint;
+use crate::search::highlight::SnippetOptions as CoreSnippetOptions;
+use crate::search::suggest::{self, SuggestionKind as CoreSuggestionKind, DEFAULT_SUGGEST_LIMIT};
 use crate::search::{engine, SearchResult as CoreSearchResult};
-use crate::store::chat::ChatRow;
+use crate::store::chat::{ChatOverview as CoreChatOverview, ChatRow};
+use crate::store::entity::{EntityKind as CoreEntityKind, DEFAULT_TOP_ENTITIES_LIMIT};
+use crate::store::media::{MediaKind as CoreMediaKind, MessageMedia as CoreMessageMedia};
 use crate::store::message::{
     strip_whitespace, Cursor, IndexOutcome as CoreIndexOutcome, MessageRef as CoreMessageRef,
-    MessageRow,
+    MessageRow, MessageWithChat, SearchFacets as CoreSearchFacets,
+    SearchFilters as CoreSearchFilters, SearchSort as CoreSearchSort, DEFAULT_THREAD_PAGE_SIZE,
 };
+use crate::store::recon::{self, BucketDigest, ChatSummary};
+use crate::store::revision::MessageRevision as CoreMessageRevision;
+use crate::store::sync_state::BackfillProgress as CoreBackfillProgress;
 use crate::store::wiki_page::{AskEvidence, AskPage};
 use crate::store::Store;
 use crate::wiki::llm::{
@@ -69,12 +79,145 @@ impl From<sqlite::Error> for SeoyuError {
 
 #[derive(uniffi::Record, Clone)]
 pub struct IndexedMessage {
+    /// Telegram account the message was read with; 0 when only one
+    /// account is signed in.
+    #[uniffi(default = 0)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
     pub timestamp: i64,
     pub text: String,
     pub link: Option<String>,
     pub sender_id: i64,
+    #[uniffi(default)]
+    pub media: Option<MessageMedia>,
+    #[uniffi(default)]
+    pub reply_to_message_id: Option<i64>,
+    /// Forum topic or discussion thread, by the id of its root message.
+    #[uniffi(default)]
+    pub thread_id: Option<i64>,
+    /// Sender's display name and username at the time of the message.
+    #[uniffi(default)]
+    pub sender_name: Option<String>,
+    #[uniffi(default)]
+    pub sender_username: Option<String>,
+}
+
+/// Attachment and forward metadata; every field is optional.
+#[derive(uniffi::Record, Clone, Default)]
+pub struct MessageMedia {
+    #[uniffi(default)]
+    pub kind: Option<MediaKind>,
+    #[uniffi(default)]
+    pub file_name: Option<String>,
+    #[uniffi(default)]
+    pub mime_type: Option<String>,
+    #[uniffi(default)]
+    pub size_bytes: Option<i64>,
+    #[uniffi(default)]
+    pub caption: Option<String>,
+    #[uniffi(default)]
+    pub forward_from_name: Option<String>,
+    #[uniffi(default)]
+    pub forward_from_chat_id: Option<i64>,
+    #[uniffi(default)]
+    pub forward_from_message_id: Option<i64>,
+}
+
+#[derive(uniffi::Enum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MediaKind {
+    Photo,
+    Video,
+    Document,
+    Audio,
+    Voice,
+    VideoNote,
+    Sticker,
+    Animation,
+    Other,
+}
+
+impl From<MediaKind> for CoreMediaKind {
+    fn from(kind: MediaKind) -> Self {
+        match kind {
+            MediaKind::Photo => CoreMediaKind::Photo,
+            MediaKind::Video => CoreMediaKind::Video,
+            MediaKind::Document => CoreMediaKind::Document,
+            MediaKind::Audio => CoreMediaKind::Audio,
+            MediaKind::Voice => CoreMediaKind::Voice,
+            MediaKind::VideoNote => CoreMediaKind::VideoNote,
+            MediaKind::Sticker => CoreMediaKind::Sticker,
+            MediaKind::Animation => CoreMediaKind::Animation,
+            MediaKind::Other => CoreMediaKind::Other,
+        }
+    }
+}
+
+impl From<CoreMediaKind> for MediaKind {
+    fn from(kind: CoreMediaKind) -> Self {
+        match kind {
+            CoreMediaKind::Photo => MediaKind::Photo,
+            CoreMediaKind::Video => MediaKind::Video,
+            CoreMediaKind::Document => MediaKind::Document,
+            CoreMediaKind::Audio => MediaKind::Audio,
+            CoreMediaKind::Voice => MediaKind::Voice,
+            CoreMediaKind::VideoNote => MediaKind::VideoNote,
+            CoreMediaKind::Sticker => MediaKind::Sticker,
+            CoreMediaKind::Animation => MediaKind::Animation,
+            CoreMediaKind::Other => MediaKind::Other,
+        }
+    }
+}
+
+impl From<MessageMedia> for CoreMessageMedia {
+    fn from(m: MessageMedia) -> Self {
+        CoreMessageMedia {
+            kind: m.kind.map(Into::into),
+            file_name: m.file_name,
+            mime_type: m.mime_type,
+            size_bytes: m.size_bytes,
+            caption: m.caption,
+            forward_from_name: m.forward_from_name,
+            forward_from_chat_id: m.forward_from_chat_id,
+            forward_from_message_id: m.forward_from_message_id,
+        }
+    }
+}
+
+impl From<CoreMessageMedia> for MessageMedia {
+    fn from(m: CoreMessageMedia) -> Self {
+        MessageMedia {
+            kind: m.kind.map(Into::into),
+            file_name: m.file_name,
+            mime_type: m.mime_type,
+            size_bytes: m.size_bytes,
+            caption: m.caption,
+            forward_from_name: m.forward_from_name,
+            forward_from_chat_id: m.forward_from_chat_id,
+            forward_from_message_id: m.forward_from_message_id,
+        }
+    }
+}
+
+/// A version of a message that a later edit replaced.
+#[derive(uniffi::Record, Clone)]
+pub struct MessageRevision {
+    pub msg_version: i64,
+    pub text_plain: String,
+    pub text_media: String,
+    /// Unix seconds when the next version replaced this one.
+    pub replaced_at: i64,
+}
+
+impl From<CoreMessageRevision> for MessageRevision {
+    fn from(r: CoreMessageRevision) -> Self {
+        MessageRevision {
+            msg_version: r.msg_version,
+            text_plain: r.text_plain,
+            text_media: r.text_media,
+            replaced_at: r.replaced_at,
+        }
+    }
 }
 
 #[derive(uniffi::Record, Clone)]
@@ -85,12 +228,76 @@ pub struct IndexOutcome {
 
 #[derive(uniffi::Record, Clone)]
 pub struct MessageRef {
+    #[uniffi(default = 0)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
 }
 
+/// Inclusive run of message ids.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct IdRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+/// The shell's count and [`Seoyu::recon_bucket_digest`] of the
+/// messages it holds with ids in `from..=to`.
+#[derive(uniffi::Record, Clone)]
+pub struct ReconBucket {
+    pub from: i64,
+    pub to: i64,
+    pub count: i64,
+    pub digest: String,
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct ReconMessage {
+    pub message_id: i64,
+    pub text: String,
+}
+
+/// Ranges to index again after a reconciliation. See
+/// `store::recon` for what each list means.
+#[derive(uniffi::Record, Clone)]
+pub struct ReconReport {
+    pub missing: Vec<IdRange>,
+    pub stale: Vec<IdRange>,
+    pub extra: Vec<IdRange>,
+    /// `missing` and `stale` merged.
+    pub backfill: Vec<IdRange>,
+    pub max_msg_id: i64,
+    pub last_full_diff: i64,
+}
+
+/// Where fetching a chat left off.
+#[derive(uniffi::Record, Clone)]
+pub struct SyncState {
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub last_message_id: i64,
+    pub oldest_message_id: Option<i64>,
+    pub initial_done: bool,
+    pub last_sync_at: Option<String>,
+}
+
+/// How far the initial backfill of a chat has come; `progress` runs
+/// from 0.0 to 1.0.
+#[derive(uniffi::Record, Clone)]
+pub struct BackfillProgress {
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub indexed_messages: i64,
+    pub last_message_id: i64,
+    pub oldest_message_id: Option<i64>,
+    pub initial_done: bool,
+    pub progress: f64,
+}
+
 #[derive(uniffi::Record, Clone)]
 pub struct ChatInfo {
+    #[uniffi(default = 0)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub title: String,
     pub chat_type: String,
@@ -99,36 +306,205 @@ pub struct ChatInfo {
     pub is_excluded: bool,
 }
 
+/// A chat as the chat list shows it.
+#[derive(uniffi::Record, Clone)]
+pub struct ChatOverview {
+    pub chat: ChatInfo,
+    /// Indexed messages, deleted ones left out.
+    pub message_count: i64,
+    /// Timestamp of the newest indexed message.
+    pub last_message_at: Option<i64>,
+    /// When messages of the chat were last indexed (unix seconds).
+    pub last_indexed_at: Option<i64>,
+}
+
 #[derive(uniffi::Record, Clone)]
 pub struct SearchHit {
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
     pub timestamp: i64,
     pub text: String,
     pub link: Option<String>,
     pub chat_title: String,
+    /// Current display name of the sender, when known.
+    pub sender_name: Option<String>,
     pub highlight_starts: Vec<u32>,
     pub highlight_ends: Vec<u32>,
+    /// `text` is a snippet, not the whole message.
+    pub is_snippet: bool,
+    /// Set when only an earlier version of the message matched, to that
+    /// version's number; see [`SearchOptions::include_revisions`].
+    pub matched_revision: Option<i64>,
 }
 
 #[derive(uniffi::Record, Clone)]
 pub struct SearchPage {
     pub items: Vec<SearchHit>,
     pub next_cursor: Option<SearchCursor>,
+    /// Set when the typed query matched nothing and these hits are for
+    /// this corrected query instead.
+    pub did_you_mean: Option<String>,
+    /// Present when requested through [`SearchOptions::include_facets`].
+    pub facets: Option<SearchFacets>,
+    /// Filters in the query left unapplied because they were negated
+    /// or inside an `OR`, as written; worth telling the user.
+    pub ignored_filters: Vec<String>,
+}
+
+/// Per-request knobs for [`Seoyu::search_with_options`]. The default
+/// is what plain [`Seoyu::search`] does.
+#[derive(uniffi::Record, Clone, Default)]
+pub struct SearchOptions {
+    /// Also count hits per chat and per day across all pages.
+    pub include_facets: bool,
+    /// A cursor is only valid with the sort that produced it.
+    pub sort: SearchSort,
+    /// Return snippets around the highlights instead of whole messages.
+    pub snippet: Option<SnippetOptions>,
+    /// Also match text that edits have since replaced. Facets still
+    /// count current text only.
+    #[uniffi(default = false)]
+    pub include_revisions: bool,
+}
+
+/// Lengths count grapheme clusters.
+#[derive(uniffi::Record, Clone)]
+pub struct SnippetOptions {
+    pub max_len: u32,
+    pub max_fragments: u32,
+}
+
+#[derive(uniffi::Enum, Clone, Copy, Default)]
+pub enum SearchSort {
+    /// BM25 with a slight preference for newer messages.
+    #[default]
+    Relevance,
+    Newest,
+    Oldest,
+    /// BM25 halved for every `half_life_days` of age.
+    RelevanceWithDecay {
+        half_life_days: f64,
+    },
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct SearchFacets {
+    pub total: u64,
+    pub by_chat: Vec<ChatFacet>,
+    pub by_day: Vec<DayFacet>,
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct ChatFacet {
+    pub account_id: i64,
+    pub chat_id: i64,
+    pub chat_title: String,
+    pub count: u64,
+}
+
+/// `day_start` is unix seconds at 00:00 UTC.
+#[derive(uniffi::Record, Clone)]
+pub struct DayFacet {
+    pub day_start: i64,
+    pub count: u64,
 }
 
 #[derive(uniffi::Record, Clone)]
 pub struct SearchCursor {
     pub rank: f64,
     pub timestamp: i64,
+    #[uniffi(default = 0)]
+    pub account_id: i64,
     pub chat_id: i64,
     pub message_id: i64,
+    pub decay_anchor: Option<i64>,
 }
 
+// UniFFI enum variants carry records by value; there is no boxing them.
+#[allow(clippy::large_enum_variant)]
 #[derive(uniffi::Enum, Clone)]
 pub enum SearchScope {
     All,
-    Chat { chat_id: i64 },
+    Chat {
+        #[uniffi(default = 0)]
+        account_id: i64,
+        chat_id: i64,
+    },
+    /// All chats, narrowed by any combination of filters.
+    Filtered {
+        filters: SearchFilters,
+    },
+    /// One reply thread or forum topic, its root message included.
+    Thread {
+        #[uniffi(default = 0)]
+        account_id: i64,
+        chat_id: i64,
+        thread_id: i64,
+    },
+}
+
+/// Empty lists and `None` bounds do not restrict. `since`/`until` are
+/// unix seconds, both inclusive; `chat_types` takes `group`,
+/// `supergroup`, `channel` or `dm`. The entity lists (`has` onwards)
+/// require every entry, and add to any `has:`/`tag:` typed in the query.
+#[derive(uniffi::Record, Clone, Default)]
+pub struct SearchFilters {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub sender_ids: Vec<i64>,
+    pub chat_ids: Vec<i64>,
+    pub chat_types: Vec<String>,
+    pub has: Vec<EntityKind>,
+    pub domains: Vec<String>,
+    pub tags: Vec<String>,
+    pub mentions: Vec<String>,
+    pub cashtags: Vec<String>,
+    /// Any of these attachment kinds.
+    pub media_kinds: Vec<MediaKind>,
+    /// Any of these sender names or usernames, as `from:` matches them.
+    pub from: Vec<String>,
+    /// Any of these accounts; empty searches every signed-in account.
+    #[uniffi(default)]
+    pub account_ids: Vec<i64>,
+}
+
+#[derive(uniffi::Enum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntityKind {
+    Link,
+    Hashtag,
+    Mention,
+    Cashtag,
+}
+
+impl From<EntityKind> for CoreEntityKind {
+    fn from(kind: EntityKind) -> Self {
+        match kind {
+            EntityKind::Link => CoreEntityKind::Link,
+            EntityKind::Hashtag => CoreEntityKind::Hashtag,
+            EntityKind::Mention => CoreEntityKind::Mention,
+            EntityKind::Cashtag => CoreEntityKind::Cashtag,
+        }
+    }
+}
+
+impl From<CoreEntityKind> for EntityKind {
+    fn from(kind: CoreEntityKind) -> Self {
+        match kind {
+            CoreEntityKind::Link => EntityKind::Link,
+            CoreEntityKind::Hashtag => EntityKind::Hashtag,
+            CoreEntityKind::Mention => EntityKind::Mention,
+            CoreEntityKind::Cashtag => EntityKind::Cashtag,
+        }
+    }
+}
+
+/// Messages carrying an entity; `value` is the domain for links.
+#[derive(uniffi::Record, Clone)]
+pub struct EntityCount {
+    pub kind: EntityKind,
+    pub value: String,
+    pub count: u64,
 }
 
 #[derive(uniffi::Record, Clone)]
@@ -196,6 +572,53 @@ pub struct WikiDigestRow {
     pub last_ts: i64,
 }
 
+#[derive(uniffi::Enum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SuggestionKind {
+    /// A query the user ran before.
+    History,
+    Chat,
+    WikiPage,
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub use_count: i64,
+    pub last_used_at: i64,
+}
+
+#[derive(uniffi::Record, Clone)]
+pub struct SavedSearch {
+    pub saved_search_id: i64,
+    pub name: String,
+    pub query: String,
+    /// Set exactly when `scope_chat_id` is.
+    pub scope_account_id: Option<i64>,
+    pub scope_chat_id: Option<i64>,
+    pub created_at: i64,
+    pub unseen_count: i64,
+    pub last_matched_at: Option<i64>,
+}
+
+/// Cloud sync settings and outbox backlog, for the settings pane.
+#[derive(uniffi::Record, Clone)]
+pub struct CloudSyncStatus {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    /// A sync client is running in this process.
+    pub running: bool,
+    pub pending_ops: i64,
+    /// Queued ops that failed at least once.
+    pub failing_ops: i64,
+    pub last_error: Option<String>,
+}
+
 #[derive(uniffi::Record, Clone)]
 pub struct WikiCategory {
     pub id: i64,
@@ -211,6 +634,13 @@ pub trait WikiObserver: Send + Sync {
     fn on_topics_changed(&self);
 }
 
+/// Told about fresh saved-search matches, once per search per indexed
+/// batch, on the thread that called `index_messages`.
+#[uniffi::export(with_foreign)]
+pub trait SavedSearchObserver: Send + Sync {
+    fn on_new_matches(&self, saved_search_id: i64, name: String, new_matches: u64);
+}
+
 /// One evidence row presented to the UI as a citable source. `source_id`
 /// is the 1-based presentation index — the LLM only ever sees this id,
 /// so unknown cites can be stripped before any character renders.
@@ -220,6 +650,7 @@ pub struct EvidenceSummary {
     pub evidence_id: i64,
     pub page_id: i64,
     pub page_title: String,
+    pub account_id: i64,
     pub chat_id: i64,
     pub chat_title: String,
     pub msg_id: i64,
@@ -380,7 +811,9 @@ impl Drop for AskHandle {
 pub struct Seoyu {
     store: Arc<Mutex<Store>>,
     wiki_worker: Mutex<Option<WorkerHandle>>,
+    cloud_sync: Mutex<Option<SyncHandle>>,
     wiki_observer: Arc<Mutex<Option<Arc<dyn WikiObserver>>>>,
+    saved_search_observer: Mutex<Option<Arc<dyn SavedSearchObserver>>>,
     wiki_wake: Arc<AtomicBool>,
     /// Map of ask_id → cancellation state. Lives only while the ask is
     /// in flight; the worker thread removes its entry in a `finally`-
@@ -399,10 +832,15 @@ impl Seoyu {
     pub fn new(db_path: String) -> Result<Arc<Self>, SeoyuError> {
         let path = std::path::PathBuf::from(db_path);
         let store = Store::open(&path)?;
+        if let Err(e) = store.purge_tombstones(crate::wiki::norm::unix_now()) {
+            log::warn!("seoyu: tombstone sweep failed: {e}");
+        }
         Ok(Arc::new(Seoyu {
             store: Arc::new(Mutex::new(store)),
             wiki_worker: Mutex::new(None),
+            cloud_sync: Mutex::new(None),
             wiki_observer: Arc::new(Mutex::new(None)),
+            saved_search_observer: Mutex::new(None),
             wiki_wake: Arc::new(AtomicBool::new(false)),
             active_asks: Arc::new(Mutex::new(HashMap::new())),
         }))
@@ -420,6 +858,7 @@ impl Seoyu {
     pub fn upsert_chat(&self, chat: ChatInfo) -> Result<(), SeoyuError> {
         let store = self.lock_store();
         store.upsert_chat(&ChatRow {
+            account_id: chat.account_id,
             chat_id: chat.chat_id,
             title: chat.title,
             chat_type: chat.chat_type,
@@ -430,6 +869,39 @@ impl Seoyu {
         Ok(())
     }
 
+    pub fn get_chat(&self, account_id: i64, chat_id: i64) -> Result<Option<ChatInfo>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.get_chat(account_id, chat_id)?.map(to_chat_info))
+    }
+
+    /// Leave a chat out of search and cloud sync, or bring it back.
+    /// Returns the chat as stored, `None` for an unknown chat.
+    pub fn set_chat_excluded(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        excluded: bool,
+    ) -> Result<Option<ChatInfo>, SeoyuError> {
+        let store = self.lock_store();
+        store.set_chat_excluded(account_id, chat_id, excluded)?;
+        Ok(store.get_chat(account_id, chat_id)?.map(to_chat_info))
+    }
+
+    /// Chats by title with message counts. Chats first seen through
+    /// indexing are listed with an empty title until upserted.
+    pub fn list_chats(
+        &self,
+        account_id: Option<i64>,
+        include_excluded: bool,
+    ) -> Result<Vec<ChatOverview>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .list_chats(account_id, include_excluded)?
+            .into_iter()
+            .map(to_chat_overview)
+            .collect())
+    }
+
     /// Mirror a batch of messages into the local store, updating FTS
     /// rows for edited text and returning accurate insert/update counts.
     pub fn index_messages(
@@ -446,31 +918,186 @@ impl Seoyu {
             .into_iter()
             .map(|m| MessageRow {
                 message_id: m.message_id,
+                account_id: m.account_id,
                 chat_id: m.chat_id,
                 timestamp: m.timestamp,
                 text_plain: m.text.clone(),
                 text_stripped: strip_whitespace(&m.text),
                 link: m.link,
                 sender_id: m.sender_id,
+                media: m.media.map(Into::into),
+                reply_to_message_id: m.reply_to_message_id,
+                thread_id: m.thread_id,
+                sender_name: m.sender_name,
+                sender_username: m.sender_username,
             })
             .collect();
-        let store = self.lock_store();
-        Ok(to_index_outcome(store.insert_messages_batch(&rows)?))
+        let outcome = self.lock_store().insert_messages_batch(&rows)?;
+        // Notify after the store lock is released so the observer may
+        // call back into `Seoyu`.
+        let observer = self
+            .saved_search_observer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(observer) = observer {
+            for m in &outcome.saved_search_matches {
+                observer.on_new_matches(m.saved_search_id, m.name.clone(), m.new_matches);
+            }
+        }
+        Ok(to_index_outcome(outcome))
     }
 
+    /// Hide messages from search and the wiki. They are kept for
+    /// [`Seoyu::tombstone_retention_days`] so the delete can be undone.
     pub fn delete_messages(&self, refs: Vec<MessageRef>) -> Result<u64, SeoyuError> {
         if refs.is_empty() {
             return Ok(0);
         }
-        let core_refs: Vec<CoreMessageRef> = refs
-            .into_iter()
-            .map(|r| CoreMessageRef {
-                chat_id: r.chat_id,
-                message_id: r.message_id,
-            })
-            .collect();
         let store = self.lock_store();
-        Ok(store.delete_messages(&core_refs)?)
+        Ok(store.delete_messages(&to_core_refs(refs))?)
+    }
+
+    /// Undo [`Seoyu::delete_messages`] within the retention period.
+    /// Returns how many messages came back.
+    pub fn undelete_messages(&self, refs: Vec<MessageRef>) -> Result<u64, SeoyuError> {
+        if refs.is_empty() {
+            return Ok(0);
+        }
+        let store = self.lock_store();
+        Ok(store.undelete_messages(&to_core_refs(refs))?)
+    }
+
+    pub fn tombstone_retention_days(&self) -> Result<u32, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.tombstone_retention_days()?)
+    }
+
+    pub fn set_tombstone_retention_days(&self, days: u32) -> Result<(), SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.set_tombstone_retention_days(days)?)
+    }
+
+    /// Permanently remove deleted messages past the retention period.
+    /// Also runs when the store is opened; call it on a timer for
+    /// long-lived sessions. Returns how many were purged.
+    pub fn purge_deleted_messages(&self) -> Result<u64, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.purge_tombstones(crate::wiki::norm::unix_now())?)
+    }
+
+    /// Diff a chat against the message ids the shell holds, as runs.
+    /// `full` says the runs cover the whole chat.
+    pub fn reconcile_chat_ranges(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        ranges: Vec<IdRange>,
+        full: bool,
+    ) -> Result<ReconReport, SeoyuError> {
+        let summary = ChatSummary::Ranges {
+            ranges: ranges.into_iter().map(to_core_range).collect(),
+        };
+        self.reconcile_chat(account_id, chat_id, &summary, full)
+    }
+
+    /// Diff a chat against per-bucket digests, which also catches
+    /// missed edits. `full` says the buckets cover the whole chat.
+    pub fn reconcile_chat_digest(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        buckets: Vec<ReconBucket>,
+        full: bool,
+    ) -> Result<ReconReport, SeoyuError> {
+        let summary = ChatSummary::Digest {
+            buckets: buckets
+                .into_iter()
+                .map(|b| BucketDigest {
+                    from: b.from,
+                    to: b.to,
+                    count: b.count,
+                    digest: b.digest,
+                })
+                .collect(),
+        };
+        self.reconcile_chat(account_id, chat_id, &summary, full)
+    }
+
+    /// Digest of one bucket of the shell's messages, to pass to
+    /// [`Seoyu::reconcile_chat_digest`]. Order does not matter.
+    pub fn recon_bucket_digest(&self, mut messages: Vec<ReconMessage>) -> String {
+        messages.sort_by_key(|m| m.message_id);
+        recon::bucket_digest(messages.iter().map(|m| (m.message_id, m.text.as_str())))
+    }
+
+    pub fn get_sync_state(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<Option<SyncState>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .get_sync_state(account_id, chat_id)?
+            .map(|s| SyncState {
+                account_id: s.account_id,
+                chat_id: s.chat_id,
+                last_message_id: s.last_message_id,
+                oldest_message_id: s.oldest_message_id,
+                initial_done: s.initial_done,
+                last_sync_at: s.last_sync_at,
+            }))
+    }
+
+    /// Record the newest message id fetched for a chat. Returns the
+    /// chat's backfill progress, like the other sync-state setters.
+    pub fn update_last_message_id(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        last_message_id: i64,
+        last_sync_at: String,
+    ) -> Result<BackfillProgress, SeoyuError> {
+        self.update_sync_state(account_id, chat_id, |store| {
+            store.update_last_message_id(account_id, chat_id, last_message_id, &last_sync_at)
+        })
+    }
+
+    /// Record how far back the initial backfill has reached; resume
+    /// from here after a restart.
+    pub fn update_oldest_message_id(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        oldest_message_id: i64,
+    ) -> Result<BackfillProgress, SeoyuError> {
+        self.update_sync_state(account_id, chat_id, |store| {
+            store.update_oldest_message_id(account_id, chat_id, oldest_message_id)
+        })
+    }
+
+    pub fn mark_initial_done(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+    ) -> Result<BackfillProgress, SeoyuError> {
+        self.update_sync_state(account_id, chat_id, |store| {
+            store.mark_initial_done(account_id, chat_id)
+        })
+    }
+
+    /// Backfill progress of every chat with sync state, least complete
+    /// first. `account_id` narrows to one account.
+    pub fn backfill_progress(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<Vec<BackfillProgress>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .list_backfill_progress(account_id)?
+            .into_iter()
+            .map(to_backfill_progress)
+            .collect())
     }
 
     /// Run the Korean-aware query planner. Passing `limit = 0` means
@@ -482,26 +1109,368 @@ impl Seoyu {
         limit: u32,
         cursor: Option<SearchCursor>,
     ) -> Result<SearchPage, SeoyuError> {
-        let core_scope = match scope {
-            SearchScope::All => engine::SearchScope::All,
-            SearchScope::Chat { chat_id } => engine::SearchScope::Chat(chat_id),
+        self.search_with_options(query, scope, limit, cursor, SearchOptions::default())
+    }
+
+    /// [`Seoyu::search`] with per-request options.
+    pub fn search_with_options(
+        &self,
+        query: String,
+        scope: SearchScope,
+        limit: u32,
+        cursor: Option<SearchCursor>,
+        options: SearchOptions,
+    ) -> Result<SearchPage, SeoyuError> {
+        let (core_scope, mut core_filters) = match scope {
+            SearchScope::All => (engine::SearchScope::All, CoreSearchFilters::default()),
+            SearchScope::Chat {
+                account_id,
+                chat_id,
+            } => (
+                engine::SearchScope::Chat {
+                    account_id,
+                    chat_id,
+                },
+                CoreSearchFilters::default(),
+            ),
+            SearchScope::Filtered { filters } => (
+                engine::SearchScope::All,
+                CoreSearchFilters {
+                    since: filters.since,
+                    until: filters.until,
+                    sender_ids: filters.sender_ids,
+                    chat_ids: filters.chat_ids,
+                    chat_types: filters.chat_types,
+                    has: filters.has.into_iter().map(Into::into).collect(),
+                    domains: filters.domains,
+                    tags: filters.tags,
+                    mentions: filters.mentions,
+                    cashtags: filters.cashtags,
+                    media_kinds: filters.media_kinds.into_iter().map(Into::into).collect(),
+                    from: filters.from,
+                    account_ids: filters.account_ids,
+                    ..Default::default()
+                },
+            ),
+            SearchScope::Thread {
+                account_id,
+                chat_id,
+                thread_id,
+            } => (
+                engine::SearchScope::Thread {
+                    account_id,
+                    chat_id,
+                    thread_id,
+                },
+                CoreSearchFilters::default(),
+            ),
         };
+        core_filters.include_revisions = options.include_revisions;
         let core_cursor = cursor.as_ref().map(|c| Cursor {
             rank: c.rank,
             timestamp: c.timestamp,
+            account_id: c.account_id,
             chat_id: c.chat_id,
             message_id: c.message_id,
+            decay_anchor: c.decay_anchor,
         });
+        let core_sort = match options.sort {
+            SearchSort::Relevance => CoreSearchSort::Relevance,
+            SearchSort::Newest => CoreSearchSort::Newest,
+            SearchSort::Oldest => CoreSearchSort::Oldest,
+            SearchSort::RelevanceWithDecay { half_life_days } => {
+                CoreSearchSort::RelevanceWithDecay { half_life_days }
+            }
+        };
         let limit_opt = if limit == 0 {
             None
         } else {
             Some(limit as usize)
         };
         let store = self.lock_store();
-        let result = engine::search(&store, &query, &core_scope, core_cursor.as_ref(), limit_opt)?;
+        let mut result = engine::search(
+            &store,
+            &query,
+            &core_scope,
+            &core_filters,
+            core_sort,
+            core_cursor.as_ref(),
+            limit_opt,
+        )?;
+        engine::record_history(&store, &query, core_cursor.as_ref(), &result);
+        if options.include_facets {
+            let effective = result.did_you_mean.as_deref().unwrap_or(&query);
+            result.facets = Some(engine::search_facets(
+                &store,
+                effective,
+                &core_scope,
+                &core_filters,
+            )?);
+        }
+        if let Some(snippet) = options.snippet {
+            let snippet = CoreSnippetOptions {
+                max_len: snippet.max_len as usize,
+                max_fragments: snippet.max_fragments as usize,
+            };
+            for item in &mut result.items {
+                item.apply_snippet(&snippet);
+            }
+        }
         Ok(to_search_page(result))
     }
 
+    /// Attachment and forward metadata of one message, if it has any.
+    pub fn message_media(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Option<MessageMedia>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .message_media(account_id, chat_id, message_id)?
+            .map(Into::into))
+    }
+
+    /// Earlier versions of one message, oldest first. The current text
+    /// is not among them.
+    pub fn message_revisions(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Vec<MessageRevision>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .message_revisions(account_id, chat_id, message_id)?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Messages around a hit, oldest first with the hit itself in the
+    /// middle. Empty if the message is gone or its chat is excluded.
+    pub fn message_context(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+        before: u32,
+        after: u32,
+    ) -> Result<Vec<SearchHit>, SeoyuError> {
+        let store = self.lock_store();
+        let rows = store.message_context(
+            account_id,
+            chat_id,
+            message_id,
+            before as usize,
+            after as usize,
+        )?;
+        Ok(rows.into_iter().map(message_to_hit).collect())
+    }
+
+    /// The replies leading to a message, root first and the message
+    /// itself last.
+    pub fn reply_chain(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Vec<SearchHit>, SeoyuError> {
+        let store = self.lock_store();
+        let rows = store.reply_chain(account_id, chat_id, message_id)?;
+        Ok(rows.into_iter().map(message_to_hit).collect())
+    }
+
+    /// A page of a reply thread or forum topic, its root first. Pass
+    /// the last `message_id` of a page as `after_message_id` for the
+    /// next; `limit = 0` means the crate default.
+    pub fn thread_messages(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        thread_id: i64,
+        after_message_id: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, SeoyuError> {
+        let limit = if limit == 0 {
+            DEFAULT_THREAD_PAGE_SIZE
+        } else {
+            limit as usize
+        };
+        let store = self.lock_store();
+        let rows =
+            store.thread_messages(account_id, chat_id, thread_id, after_message_id, limit)?;
+        Ok(rows.into_iter().map(message_to_hit).collect())
+    }
+
+    /// Most frequent links (by domain), hashtags, mentions and
+    /// cashtags, optionally one `kind`, in one chat (`chat_id` of
+    /// `account_id`) and within `since..=until` (unix seconds).
+    /// `limit = 0` means the crate default.
+    pub fn top_entities(
+        &self,
+        kind: Option<EntityKind>,
+        account_id: i64,
+        chat_id: Option<i64>,
+        since: Option<i64>,
+        until: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<EntityCount>, SeoyuError> {
+        let limit = if limit == 0 {
+            DEFAULT_TOP_ENTITIES_LIMIT
+        } else {
+            limit as usize
+        };
+        let store = self.lock_store();
+        Ok(store
+            .top_entities(
+                kind.map(Into::into),
+                chat_id.map(|chat_id| (account_id, chat_id)),
+                since,
+                until,
+                limit,
+            )?
+            .into_iter()
+            .map(|e| EntityCount {
+                kind: e.kind.into(),
+                value: e.value,
+                count: e.count,
+            })
+            .collect())
+    }
+
+    /// Autocomplete for the search box, jamo-aware so a syllable still
+    /// being typed completes. An empty `prefix` lists recent searches;
+    /// `limit = 0` means the crate default.
+    pub fn suggest(&self, prefix: String, limit: u32) -> Result<Vec<Suggestion>, SeoyuError> {
+        let limit = if limit == 0 {
+            DEFAULT_SUGGEST_LIMIT
+        } else {
+            limit as usize
+        };
+        let store = self.lock_store();
+        Ok(suggest::suggest(&store, &prefix, limit)?
+            .into_iter()
+            .map(|s| Suggestion {
+                text: s.text,
+                kind: match s.kind {
+                    CoreSuggestionKind::History => SuggestionKind::History,
+                    CoreSuggestionKind::Chat => SuggestionKind::Chat,
+                    CoreSuggestionKind::WikiPage => SuggestionKind::WikiPage,
+                },
+            })
+            .collect())
+    }
+
+    pub fn recent_searches(&self, limit: u32) -> Result<Vec<SearchHistoryEntry>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .recent_searches(limit as usize)?
+            .into_iter()
+            .map(|e| SearchHistoryEntry {
+                query: e.query,
+                use_count: e.use_count,
+                last_used_at: e.last_used_at,
+            })
+            .collect())
+    }
+
+    pub fn search_history_enabled(&self) -> Result<bool, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.search_history_enabled()?)
+    }
+
+    /// Stop (or resume) recording searches. Already recorded entries
+    /// stay until [`Seoyu::clear_search_history`].
+    pub fn set_search_history_enabled(&self, enabled: bool) -> Result<(), SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.set_search_history_enabled(enabled)?)
+    }
+
+    pub fn clear_search_history(&self) -> Result<(), SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.clear_search_history()?)
+    }
+
+    /// Save a query to be checked against every indexed batch,
+    /// optionally only in chat `scope_chat_id` of `scope_account_id`.
+    /// New matches are reported through the [`SavedSearchObserver`].
+    pub fn create_saved_search(
+        &self,
+        name: String,
+        query: String,
+        scope_account_id: i64,
+        scope_chat_id: Option<i64>,
+    ) -> Result<i64, SeoyuError> {
+        validate_saved_query(&query)?;
+        let store = self.lock_store();
+        let scope = scope_chat_id.map(|chat_id| (scope_account_id, chat_id));
+        Ok(store.create_saved_search(&name, &query, scope)?)
+    }
+
+    /// Returns `false` if no such saved search exists.
+    pub fn update_saved_search(
+        &self,
+        saved_search_id: i64,
+        name: String,
+        query: String,
+        scope_account_id: i64,
+        scope_chat_id: Option<i64>,
+    ) -> Result<bool, SeoyuError> {
+        validate_saved_query(&query)?;
+        let store = self.lock_store();
+        let scope = scope_chat_id.map(|chat_id| (scope_account_id, chat_id));
+        Ok(store.update_saved_search(saved_search_id, &name, &query, scope)?)
+    }
+
+    pub fn delete_saved_search(&self, saved_search_id: i64) -> Result<bool, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.delete_saved_search(saved_search_id)?)
+    }
+
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>, SeoyuError> {
+        let store = self.lock_store();
+        Ok(store
+            .list_saved_searches()?
+            .into_iter()
+            .map(|s| SavedSearch {
+                saved_search_id: s.saved_search_id,
+                name: s.name,
+                query: s.query,
+                scope_account_id: s.scope_account_id,
+                scope_chat_id: s.scope_chat_id,
+                created_at: s.created_at,
+                unseen_count: s.unseen_count,
+                last_matched_at: s.last_matched_at,
+            })
+            .collect())
+    }
+
+    /// Recorded matches of a saved search, newest first.
+    pub fn saved_search_hits(
+        &self,
+        saved_search_id: i64,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, SeoyuError> {
+        let store = self.lock_store();
+        let rows = store.saved_search_hits(saved_search_id, limit as usize)?;
+        Ok(rows.into_iter().map(message_to_hit).collect())
+    }
+
+    pub fn mark_saved_search_seen(&self, saved_search_id: i64) -> Result<(), SeoyuError> {
+        let store = self.lock_store();
+        Ok(store.mark_saved_search_seen(saved_search_id)?)
+    }
+
+    pub fn set_saved_search_observer(&self, observer: Option<Arc<dyn SavedSearchObserver>>) {
+        let mut slot = self
+            .saved_search_observer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *slot = observer;
+    }
+
     /// Top trending topics, optionally filtered by a category name
     /// (case-insensitive). Missing categories return an empty list
     /// rather than erroring so the UI can ignore stale filters.
@@ -779,6 +1748,85 @@ impl Seoyu {
         }
     }
 
+    /// Turn cloud sync on or off and set where it pushes to. Turning
+    /// it on needs an endpoint (`http://host:port[/path]` or
+    /// `unix:///socket/path`); turning it off stops a running client
+    /// but keeps what is queued. Call [`Seoyu::start_cloud_sync`] to
+    /// start pushing.
+    pub fn configure_cloud_sync(
+        &self,
+        enabled: bool,
+        endpoint: Option<String>,
+    ) -> Result<(), SeoyuError> {
+        if let Some(url) = &endpoint {
+            Endpoint::parse(url).map_err(|e| SeoyuError::InvalidArgument(e.to_string()))?;
+        } else if enabled {
+            return Err(SeoyuError::InvalidArgument(
+                "cloud sync needs an endpoint".into(),
+            ));
+        }
+        if !enabled {
+            self.stop_cloud_sync();
+        }
+        let store = self.lock_store();
+        store.set_cloud_sync_endpoint(endpoint.as_deref())?;
+        store.set_cloud_sync_enabled(enabled)?;
+        Ok(())
+    }
+
+    /// Start pushing the outbox in the background. No-op when already
+    /// running.
+    pub fn start_cloud_sync(&self) -> Result<(), SeoyuError> {
+        let mut guard = self.cloud_sync.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_some() {
+            return Ok(());
+        }
+        let endpoint = {
+            let store = self.lock_store();
+            if !store.cloud_sync_enabled()? {
+                return Err(SeoyuError::InvalidArgument("cloud sync is off".into()));
+            }
+            store
+                .cloud_sync_endpoint()?
+                .ok_or_else(|| SeoyuError::InvalidArgument("no cloud sync endpoint".into()))?
+        };
+        let endpoint =
+            Endpoint::parse(&endpoint).map_err(|e| SeoyuError::InvalidArgument(e.to_string()))?;
+        let handle = start_sync(Arc::clone(&self.store), endpoint, SyncConfig::default())
+            .map_err(|e| SeoyuError::Other(format!("spawn cloud sync: {e}")))?;
+        *guard = Some(handle);
+        Ok(())
+    }
+
+    pub fn stop_cloud_sync(&self) {
+        let handle = {
+            let mut guard = self.cloud_sync.lock().unwrap_or_else(|e| e.into_inner());
+            guard.take()
+        };
+        if let Some(h) = handle {
+            h.stop();
+            h.join();
+        }
+    }
+
+    pub fn cloud_sync_status(&self) -> Result<CloudSyncStatus, SeoyuError> {
+        let running = self
+            .cloud_sync
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some();
+        let store = self.lock_store();
+        let stats = store.outbox_stats()?;
+        Ok(CloudSyncStatus {
+            enabled: store.cloud_sync_enabled()?,
+            endpoint: store.cloud_sync_endpoint()?,
+            running,
+            pending_ops: stats.pending,
+            failing_ops: stats.failing,
+            last_error: stats.last_error,
+        })
+    }
+
     pub fn wiki_digest_today(&self) -> Result<WikiDigest, SeoyuError> {
         let store = self.lock_store();
         let now_secs = std::time::SystemTime::now()
@@ -829,6 +1877,53 @@ impl Seoyu {
     fn lock_store(&self) -> std::sync::MutexGuard<'_, Store> {
         self.store.lock().unwrap_or_else(|e| e.into_inner())
     }
+
+    fn update_sync_state(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        update: impl FnOnce(&Store) -> Result<(), sqlite::Error>,
+    ) -> Result<BackfillProgress, SeoyuError> {
+        let store = self.lock_store();
+        update(&store)?;
+        store
+            .backfill_progress(account_id, chat_id)?
+            .map(to_backfill_progress)
+            .ok_or_else(|| SeoyuError::Other("sync state missing after update".into()))
+    }
+
+    fn reconcile_chat(
+        &self,
+        account_id: i64,
+        chat_id: i64,
+        summary: &ChatSummary,
+        full: bool,
+    ) -> Result<ReconReport, SeoyuError> {
+        let store = self.lock_store();
+        let report = store.reconcile_chat(
+            account_id,
+            chat_id,
+            summary,
+            full,
+            crate::wiki::norm::unix_now(),
+        )?;
+        let ranges = |list: &[recon::IdRange]| {
+            list.iter()
+                .map(|r| IdRange {
+                    from: r.from,
+                    to: r.to,
+                })
+                .collect::<Vec<_>>()
+        };
+        Ok(ReconReport {
+            missing: ranges(&report.missing),
+            stale: ranges(&report.stale),
+            extra: ranges(&report.extra),
+            backfill: ranges(&report.backfill_ranges()),
+            max_msg_id: report.max_msg_id,
+            last_full_diff: report.last_full_diff,
+        })
+    }
 }
 
 // ---------- internal helpers (not exposed via UniFFI) ----------
@@ -844,14 +1939,18 @@ fn to_search_page(result: CoreSearchResult) -> SearchPage {
                 .map(|h| (h.start as u32, h.end as u32))
                 .unzip();
             SearchHit {
+                account_id: item.account_id,
                 chat_id: item.chat_id,
                 message_id: item.message_id,
                 timestamp: item.timestamp,
                 text: item.text,
                 link: item.link,
                 chat_title: item.chat_title,
+                sender_name: item.sender_name,
                 highlight_starts: starts,
                 highlight_ends: ends,
+                is_snippet: item.is_snippet,
+                matched_revision: item.matched_revision,
             }
         })
         .collect();
@@ -860,12 +1959,91 @@ fn to_search_page(result: CoreSearchResult) -> SearchPage {
         next_cursor: result.next_cursor.map(|c| SearchCursor {
             rank: c.rank,
             timestamp: c.timestamp,
+            account_id: c.account_id,
             chat_id: c.chat_id,
             message_id: c.message_id,
+            decay_anchor: c.decay_anchor,
         }),
+        did_you_mean: result.did_you_mean,
+        facets: result.facets.map(to_search_facets),
+        ignored_filters: result.ignored_filters,
+    }
+}
+
+fn to_search_facets(facets: CoreSearchFacets) -> SearchFacets {
+    SearchFacets {
+        total: facets.total,
+        by_chat: facets
+            .by_chat
+            .into_iter()
+            .map(|c| ChatFacet {
+                account_id: c.account_id,
+                chat_id: c.chat_id,
+                chat_title: c.chat_title,
+                count: c.count,
+            })
+            .collect(),
+        by_day: facets
+            .by_day
+            .into_iter()
+            .map(|d| DayFacet {
+                day_start: d.day_start,
+                count: d.count,
+            })
+            .collect(),
+    }
+}
+
+fn to_chat_info(c: ChatRow) -> ChatInfo {
+    ChatInfo {
+        account_id: c.account_id,
+        chat_id: c.chat_id,
+        title: c.title,
+        chat_type: c.chat_type,
+        username: c.username,
+        access_hash: c.access_hash,
+        is_excluded: c.is_excluded,
+    }
+}
+
+fn to_chat_overview(c: CoreChatOverview) -> ChatOverview {
+    ChatOverview {
+        chat: to_chat_info(c.chat),
+        message_count: c.message_count,
+        last_message_at: c.last_message_at,
+        last_indexed_at: c.last_indexed_at,
     }
 }
 
+fn to_backfill_progress(p: CoreBackfillProgress) -> BackfillProgress {
+    BackfillProgress {
+        account_id: p.account_id,
+        chat_id: p.chat_id,
+        indexed_messages: p.indexed_messages,
+        last_message_id: p.last_message_id,
+        oldest_message_id: p.oldest_message_id,
+        initial_done: p.initial_done,
+        progress: p.progress,
+    }
+}
+
+fn to_core_range(range: IdRange) -> recon::IdRange {
+    recon::IdRange {
+        from: range.from,
+        to: range.to,
+    }
+}
+
+fn to_core_refs(refs: Vec<MessageRef>) -> Vec<CoreMessageRef> {
+    refs.into_iter()
+        .map(|r| CoreMessageRef {
+            account_id: r.account_id,
+            chat_id: r.chat_id,
+            message_id: r.message_id,
+        })
+        .collect()
+}
+
 fn to_index_outcome(outcome: CoreIndexOutcome) -> IndexOutcome {
     IndexOutcome {
         inserted: outcome.inserted,
@@ -887,7 +2065,9 @@ fn wiki_topic_to_summary(t: crate::store::wiki_topic::WikiTopic) -> WikiTopicSum
 impl Drop for Seoyu {
     fn drop(&mut self) {
         self.set_wiki_observer(None);
+        self.set_saved_search_observer(None);
         self.stop_wiki_worker();
+        self.stop_cloud_sync();
         // Cancel any in-flight asks. The worker thread holds Arcs to
         // store + handler so it survives `Seoyu` dropping; without this
         // it would run to completion firing callbacks into a Swift
@@ -937,6 +2117,7 @@ fn build_evidence_summaries(rows: &[AskEvidence]) -> Vec<EvidenceSummary> {
             evidence_id: e.evidence_id,
             page_id: e.page_id,
             page_title: e.page_title.clone(),
+            account_id: e.account_id,
             chat_id: e.chat_id,
             chat_title: e.chat_title.clone(),
             msg_id: e.msg_id,
@@ -1304,6 +2485,7 @@ fn cited_sources_json(items: &[EvidenceSummary]) -> String {
                 "evidence_id": e.evidence_id,
                 "page_id": e.page_id,
                 "page_title": e.page_title,
+                "account_id": e.account_id,
                 "chat_id": e.chat_id,
                 "chat_title": e.chat_title,
                 "msg_id": e.msg_id,
@@ -1323,16 +2505,50 @@ fn truncate_chars(s: &str, max_chars: usize) -> &str {
     }
 }
 
+fn message_to_hit(m: MessageWithChat) -> SearchHit {
+    SearchHit {
+        account_id: m.account_id,
+        chat_id: m.chat_id,
+        message_id: m.message_id,
+        timestamp: m.timestamp,
+        text: m.text_plain,
+        link: m.link,
+        chat_title: m.chat_title,
+        sender_name: m.sender_name,
+        highlight_starts: Vec::new(),
+        highlight_ends: Vec::new(),
+        is_snippet: false,
+        matched_revision: None,
+    }
+}
+
+/// Reject queries the planner cannot use, so a saved search can never
+/// silently match nothing.
+fn validate_saved_query(query: &str) -> Result<(), SeoyuError> {
+    if crate::search::query::parse_query(query.trim()).is_none()
+        && !crate::search::query::parse_filters(query).has_content_filters()
+    {
+        return Err(SeoyuError::InvalidArgument(format!(
+            "unusable saved search query: {query:?}"
+        )));
+    }
+    Ok(())
+}
+
 fn topic_row_to_hit(row: crate::store::wiki_topic::TopicMessageRow) -> SearchHit {
     SearchHit {
+        account_id: row.account_id,
         chat_id: row.chat_id,
         message_id: row.message_id,
         timestamp: row.timestamp,
         text: row.text,
         link: row.link,
         chat_title: row.chat_title,
+        sender_name: None,
         highlight_starts: Vec::new(),
         highlight_ends: Vec::new(),
+        is_snippet: false,
+        matched_revision: None,
     }
 }
 
@@ -1385,6 +2601,7 @@ mod ask_tests {
             evidence_id,
             page_id: 1,
             page_title: "P".into(),
+            account_id: 0,
             chat_id,
             chat_title: "C".into(),
             msg_id: evidence_id,
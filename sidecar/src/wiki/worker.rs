@@ -318,16 +318,20 @@ async fn run_worker<E>(
             items
                 .into_iter()
                 .filter_map(|item| {
-                    let Some(m) = s.get_message(item.chat_id, item.msg_id).ok().flatten() else {
-                        let _ = s.mark_classify_v2_done(item.msg_id, item.chat_id);
+                    let Some(m) = s
+                        .get_message(item.account_id, item.chat_id, item.msg_id)
+                        .ok()
+                        .flatten()
+                    else {
+                        let _ = s.mark_classify_v2_done(item.account_id, item.msg_id, item.chat_id);
                         return None;
                     };
                     if m.text_plain.trim().is_empty() {
-                        let _ = s.mark_classify_v2_done(item.msg_id, item.chat_id);
+                        let _ = s.mark_classify_v2_done(item.account_id, item.msg_id, item.chat_id);
                         return None;
                     }
                     let chat_title = s
-                        .get_chat(item.chat_id)
+                        .get_chat(item.account_id, item.chat_id)
                         .ok()
                         .flatten()
                         .map(|c| c.title)
@@ -413,6 +417,7 @@ async fn run_worker<E>(
                 let s = lock(&store);
                 for l in &loaded {
                     let _ = s.mark_classify_v2_retry(
+                        l.item.account_id,
                         l.item.msg_id,
                         l.item.chat_id,
                         &e.to_string(),
@@ -456,6 +461,7 @@ async fn run_worker<E>(
                         l.item.chat_id
                     );
                     let _ = s.mark_classify_v2_retry(
+                        l.item.account_id,
                         l.item.msg_id,
                         l.item.chat_id,
                         &e.to_string(),
@@ -774,7 +780,11 @@ struct ApplyClassifyV2<'a> {
 /// `Ok(false)` when validation failed and the row was retried.
 fn apply_classify_v2(store: &Store, input: ApplyClassifyV2<'_>) -> Result<bool, sqlite::Error> {
     if input.assignments.is_empty() {
-        store.mark_classify_v2_done(input.item.msg_id, input.item.chat_id)?;
+        store.mark_classify_v2_done(
+            input.item.account_id,
+            input.item.msg_id,
+            input.item.chat_id,
+        )?;
         return Ok(true);
     }
 
@@ -784,6 +794,7 @@ fn apply_classify_v2(store: &Store, input: ApplyClassifyV2<'_>) -> Result<bool,
             Ok(s) => s,
             Err(e) => {
                 store.mark_classify_v2_retry(
+                    input.item.account_id,
                     input.item.msg_id,
                     input.item.chat_id,
                     &e.to_string(),
@@ -837,6 +848,7 @@ fn apply_classify_v2(store: &Store, input: ApplyClassifyV2<'_>) -> Result<bool,
                 .insert_evidence_v2(&NewEvidenceV2 {
                     page_id: page_ref.id,
                     msg_id: input.item.msg_id,
+                    account_id: input.item.account_id,
                     chat_id: input.item.chat_id,
                     sender_id: input.sender_id,
                     ts: input.ts,
@@ -853,6 +865,7 @@ fn apply_classify_v2(store: &Store, input: ApplyClassifyV2<'_>) -> Result<bool,
         if let Some(hint) = needs_successor {
             if !any_succeeded {
                 store.mark_classify_v2_successor_needed(
+                    input.item.account_id,
                     input.item.msg_id,
                     input.item.chat_id,
                     hint,
@@ -860,7 +873,11 @@ fn apply_classify_v2(store: &Store, input: ApplyClassifyV2<'_>) -> Result<bool,
                 return Ok(true);
             }
         }
-        store.mark_classify_v2_done(input.item.msg_id, input.item.chat_id)?;
+        store.mark_classify_v2_done(
+            input.item.account_id,
+            input.item.msg_id,
+            input.item.chat_id,
+        )?;
         // Spec §6.3: trigger lives inside classify txn, idempotent via PK.
         for pid in &touched_pages {
             store.maybe_enqueue_rewrite(*pid)?;
@@ -1038,12 +1055,18 @@ mod tests {
             .unwrap();
         s.insert_messages_batch(&[MessageRow {
             message_id: 100,
+            account_id: 0,
             chat_id: 1,
             timestamp: 1_700_000_000,
             text_plain: "Bitcoin ETF approved by SEC today".into(),
             text_stripped: "BitcoinETFapprovedbySECtoday".into(),
             link: None,
             sender_id: 42,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         }])
         .unwrap();
         s.conn()
@@ -1054,6 +1077,7 @@ mod tests {
 
     fn fake_item() -> ClassifyV2Item {
         ClassifyV2Item {
+            account_id: 0,
             msg_id: 100,
             chat_id: 1,
             attempts: 0,
@@ -1184,6 +1208,7 @@ mod tests {
         s.insert_evidence_v2(&NewEvidenceV2 {
             page_id: p.id,
             msg_id: 1,
+            account_id: 0,
             chat_id: 1,
             sender_id: 0,
             ts: crate::wiki::norm::unix_now() - 100,
@@ -1269,6 +1294,7 @@ mod tests {
         s.insert_evidence_v2(&NewEvidenceV2 {
             page_id: 1,
             msg_id: 99,
+            account_id: 0,
             chat_id: 1,
             sender_id: 0,
             ts: now - 50,
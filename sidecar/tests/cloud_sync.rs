@@ -0,0 +1,236 @@
+//! Cloud sync against the in-process stand-in server: push, ack,
+//! retry after failures, and backfill of watermark gaps, with no
+//! network beyond a per-test Unix socket or loopback port.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use seoyu::cloud::client::{start_sync, SyncClient, SyncConfig};
+use seoyu::cloud::push::{Endpoint, PushStatus, Pusher, SyncError};
+use seoyu::cloud::stand_in::StandInServer;
+use seoyu::store::chat::ChatRow;
+use seoyu::store::message::{strip_whitespace, MessageRef, MessageRow};
+use seoyu::store::Store;
+
+fn unique_socket_path(tag: &str) -> PathBuf {
+    let pid = std::process::id();
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("seoyu-cloud-{tag}-{pid}-{nanos}.sock"))
+}
+
+fn insert(store: &Store, message_id: i64, text: &str) {
+    insert_in(store, 1, message_id, text);
+}
+
+fn insert_in(store: &Store, chat_id: i64, message_id: i64, text: &str) {
+    store
+        .insert_messages_batch(&[MessageRow {
+            message_id,
+            account_id: 0,
+            chat_id,
+            timestamp: 1_700_000_000 + message_id,
+            text_plain: text.to_string(),
+            text_stripped: strip_whitespace(text),
+            link: None,
+            sender_id: 7,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
+        }])
+        .unwrap();
+}
+
+fn fast_config() -> SyncConfig {
+    SyncConfig {
+        batch_size: 2,
+        idle_interval: Duration::from_millis(50),
+        base_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        timeout: Duration::from_secs(5),
+    }
+}
+
+#[tokio::test]
+async fn push_ack_and_retry_over_unix_socket() {
+    let socket = unique_socket_path("push");
+    let server = StandInServer::bind_unix(&socket).expect("bind stand-in");
+
+    let store = Store::open_in_memory().unwrap();
+    store.set_cloud_sync_enabled(true).unwrap();
+    store
+        .upsert_chat(&ChatRow {
+            account_id: 0,
+            chat_id: 1,
+            title: "공모주 방".into(),
+            chat_type: "channel".into(),
+            username: None,
+            access_hash: None,
+            is_excluded: false,
+        })
+        .unwrap();
+    insert(&store, 1, "청약 일정 공지");
+    insert(&store, 2, "환불일 안내");
+    insert(&store, 1, "청약 일정 변경");
+    let store = Arc::new(Mutex::new(store));
+    let endpoint = Endpoint::parse(server.endpoint()).unwrap();
+    let client = SyncClient::new(
+        Arc::clone(&store),
+        endpoint,
+        SyncConfig {
+            batch_size: 10,
+            ..fast_config()
+        },
+    );
+
+    // A failed request leaves every op queued with the error.
+    server.fail_next(1);
+    match client.push_once().await {
+        Err(SyncError::Http { status: 503, .. }) => {}
+        other => panic!("expected 503, got {other:?}"),
+    }
+    let stats = store.lock().unwrap().outbox_stats().unwrap();
+    assert_eq!((stats.pending, stats.failing), (4, 4));
+    assert!(stats.last_error.unwrap().contains("503"));
+
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.sent, report.acked, report.failed), (4, 4, 0));
+    assert_eq!(store.lock().unwrap().outbox_len().unwrap(), 0);
+    assert_eq!(server.chat_title(0, 1).as_deref(), Some("공모주 방"));
+    let edited = server.message(0, 1, 1).unwrap();
+    assert_eq!(edited.msg_version, 2);
+    assert_eq!(edited.text.as_deref(), Some("청약 일정 변경"));
+    // Everything acked, so nothing is a gap.
+    assert_eq!(store.lock().unwrap().requeue_outbox_gaps().unwrap(), 0);
+
+    // A per-op error keeps just that op for the next round.
+    store
+        .lock()
+        .unwrap()
+        .delete_messages(&[MessageRef {
+            account_id: 0,
+            chat_id: 1,
+            message_id: 2,
+        }])
+        .unwrap();
+    server.reject_message(1, 2);
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.acked, report.failed), (0, 1));
+    let left = store.lock().unwrap().peek_outbox(10).unwrap();
+    assert_eq!(left[0].attempts, 1);
+    assert_eq!(left[0].last_error.as_deref(), Some("rejected"));
+
+    server.accept_message(1, 2);
+    let report = client.push_once().await.unwrap();
+    assert_eq!(report.acked, 1);
+    assert_eq!(server.message(0, 1, 2).unwrap().text, None);
+    assert_eq!(client.push_once().await.unwrap().sent, 0);
+    assert_eq!(server.requests(), 4);
+}
+
+#[tokio::test]
+async fn rejected_ops_do_not_block_the_queue() {
+    let socket = unique_socket_path("reject");
+    let server = StandInServer::bind_unix(&socket).expect("bind stand-in");
+
+    let store = Store::open_in_memory().unwrap();
+    store.set_cloud_sync_enabled(true).unwrap();
+    for message_id in 1..=3 {
+        insert(&store, message_id, "청약 일정 공지");
+    }
+    insert_in(&store, 2, 4, "환불일 안내");
+    let store = Arc::new(Mutex::new(store));
+    let endpoint = Endpoint::parse(server.endpoint()).unwrap();
+    // More rejected ops than fit in one batch.
+    let client = SyncClient::new(Arc::clone(&store), endpoint, fast_config());
+    for message_id in 1..=3 {
+        server.reject_message(1, message_id);
+    }
+
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.sent, report.acked, report.failed), (2, 0, 2));
+    // The next round takes the other chat first.
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.sent, report.acked, report.failed), (2, 1, 1));
+    assert!(server.message(0, 2, 4).is_some());
+    assert_eq!(store.lock().unwrap().outbox_len().unwrap(), 3);
+
+    for message_id in 1..=3 {
+        server.accept_message(1, message_id);
+    }
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.sent, report.acked), (2, 2));
+    let report = client.push_once().await.unwrap();
+    assert_eq!((report.sent, report.acked), (1, 1));
+    assert_eq!(store.lock().unwrap().outbox_len().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn stand_in_answers_replays_stale_ops_and_new_protocols_over_tcp() {
+    let server = StandInServer::bind_tcp().await.expect("bind stand-in");
+    let pusher = Pusher::new(
+        Endpoint::parse(server.endpoint()).unwrap(),
+        Duration::from_secs(5),
+    );
+    let op = |id: &str, version: i64| {
+        format!(
+            r#"{{"protocol_version":1,"client_op_id":"{id}","op":"msg_upsert","account_id":0,"chat_id":1,"message_id":9,"msg_version":{version},"text":"v{version}"}}"#
+        )
+    };
+
+    let first = pusher.push(&[op("a", 2)]).await.unwrap();
+    assert_eq!(first[0].status, PushStatus::Applied { seq: 1 });
+    // Replaying an op returns its first result; an older version is
+    // stale.
+    let again = pusher.push(&[op("a", 2), op("b", 1)]).await.unwrap();
+    assert_eq!(again[0].status, PushStatus::Applied { seq: 1 });
+    assert_eq!(again[1].status, PushStatus::Stale { current_version: 2 });
+    assert_eq!(server.ops(), vec!["a".to_string()]);
+
+    let newer = op("c", 3).replace("\"protocol_version\":1", "\"protocol_version\":2");
+    assert!(matches!(
+        pusher.push(&[newer]).await,
+        Err(SyncError::UpgradeRequired)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn running_client_pushes_what_was_queued_before_sync() {
+    let socket = unique_socket_path("backfill");
+    let server = StandInServer::bind_unix(&socket).expect("bind stand-in");
+
+    let store = Store::open_in_memory().unwrap();
+    for id in 1..=5 {
+        insert(&store, id, &format!("예전 메시지 {id}"));
+    }
+    // Queued while sync was off, waiting for the client.
+    assert_eq!(store.outbox_len().unwrap(), 5);
+    store.set_cloud_sync_enabled(true).unwrap();
+    let store = Arc::new(Mutex::new(store));
+
+    // The first rounds fail; the client backs off and retries.
+    server.fail_next(2);
+    let handle = start_sync(
+        Arc::clone(&store),
+        Endpoint::parse(server.endpoint()).unwrap(),
+        fast_config(),
+    )
+    .expect("start sync");
+    for _ in 0..100 {
+        if server.message_count() == 5 && store.lock().unwrap().outbox_len().unwrap() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    handle.stop();
+    handle.join();
+
+    assert_eq!(server.message_count(), 5);
+    assert_eq!(store.lock().unwrap().outbox_len().unwrap(), 0);
+    assert!(server.requests() >= 5);
+}
@@ -1,6 +1,7 @@
 //! End-to-end smoke tests for the IPC server. Spins up a real
 //! `SidecarServer` on a per-test socket, connects, and verifies a
-//! ping → pong handshake plus an index + search round-trip.
+//! ping → pong handshake, an index + search round-trip, and one
+//! feature of the protocol per test after that.
 //!
 //! Each test uses a unique socket path under the process's temp dir
 //! so parallel cargo test runs don't collide.
@@ -43,6 +44,10 @@ async fn connect_and_call(socket: &std::path::Path, request: Value) -> Value {
     serde_json::from_slice(&frame).expect("decode response")
 }
 
+fn items_of(response: &Value) -> &Vec<Value> {
+    response["result"]["items"].as_array().expect("items array")
+}
+
 #[tokio::test]
 async fn ping_returns_pong() {
     let socket = unique_socket_path("ping");
@@ -76,6 +81,7 @@ async fn index_then_search_round_trip() {
         let store = Store::open(&db).expect("open store");
         store
             .upsert_chat(&seoyu::store::chat::ChatRow {
+                account_id: 0,
                 chat_id: 7,
                 title: "Test Chat".into(),
                 chat_type: "channel".into(),
@@ -140,3 +146,830 @@ async fn index_then_search_round_trip() {
     let _ = std::fs::remove_file(&socket);
     let _ = std::fs::remove_file(&db);
 }
+
+/// A server on a fresh socket and store whose chat 7 ("Test Chat", a
+/// channel) already exists, holding `messages`.
+struct TestServer {
+    socket: PathBuf,
+    db: PathBuf,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl TestServer {
+    async fn start(tag: &str, messages: Value) -> Self {
+        let socket = unique_socket_path(tag);
+        let db = unique_db_path(tag);
+        {
+            let store = Store::open(&db).expect("open store");
+            store
+                .upsert_chat(&seoyu::store::chat::ChatRow {
+                    account_id: 0,
+                    chat_id: 7,
+                    title: "Test Chat".into(),
+                    chat_type: "channel".into(),
+                    username: None,
+                    access_hash: None,
+                    is_excluded: false,
+                })
+                .expect("seed chat");
+        }
+        let store = Store::open(&db).expect("open store");
+        let (server, _events) =
+            SidecarServer::bind(&socket, SidecarState::new(store)).expect("bind");
+        let handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        let server = Self { socket, db, handle };
+        let index = server.index(messages).await;
+        assert!(index.get("error").is_none(), "index failed: {index}");
+        server
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Value {
+        connect_and_call(
+            &self.socket,
+            json!({ "id": 1, "method": method, "params": params }),
+        )
+        .await
+    }
+
+    async fn index(&self, messages: Value) -> Value {
+        self.call("index_messages_batch", json!({ "messages": messages }))
+            .await
+    }
+
+    async fn search(&self, params: Value) -> Value {
+        self.call("search", params).await
+    }
+
+    async fn stop(self) {
+        let _ = connect_and_call(&self.socket, json!({ "id": 99, "method": "shutdown" })).await;
+        let _ = self.handle.await;
+        let _ = std::fs::remove_file(&self.socket);
+        let _ = std::fs::remove_file(&self.db);
+    }
+}
+
+/// A message in chat 7 of account 0 with no sender.
+fn message(message_id: i64, timestamp: i64, text: &str) -> Value {
+    json!({
+        "chat_id": 7,
+        "message_id": message_id,
+        "sender_id": null,
+        "sender_name": null,
+        "timestamp": timestamp,
+        "text": text
+    })
+}
+
+fn ids_of(response: &Value) -> Vec<i64> {
+    items_of(response)
+        .iter()
+        .map(|i| i["message_id"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn search_filters_apply_over_ipc() {
+    let server = TestServer::start(
+        "filters",
+        json!([message(100, 1_700_000_000, "삼성전자 주가 상승")]),
+    )
+    .await;
+
+    let filtered = server
+        .search(json!({
+            "query": "삼성전자",
+            "filters": { "since": 1_700_000_050, "chat_types": ["channel"] }
+        }))
+        .await;
+    assert!(
+        items_of(&filtered).is_empty(),
+        "date filter should drop the match, got {filtered}"
+    );
+    let kept = server
+        .search(json!({
+            "query": "삼성전자",
+            "filters": { "until": 1_700_000_050, "chat_types": ["channel"] }
+        }))
+        .await;
+    assert_eq!(ids_of(&kept), vec![100]);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn search_facets_over_ipc() {
+    let server = TestServer::start(
+        "facets",
+        json!([message(100, 1_700_000_000, "삼성전자 주가 상승")]),
+    )
+    .await;
+
+    let plain = server.search(json!({ "query": "삼성전자" })).await;
+    assert!(plain["result"]["facets"].is_null());
+    let faceted = server
+        .search(json!({ "query": "삼성전자", "facets": true }))
+        .await;
+    let facets = &faceted["result"]["facets"];
+    assert_eq!(facets["total"], 1);
+    assert_eq!(facets["by_chat"][0]["chat_id"], 7);
+    assert_eq!(facets["by_chat"][0]["chat_title"], "Test Chat");
+    assert_eq!(facets["by_day"][0]["count"], 1);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn search_snippets_over_ipc() {
+    let server = TestServer::start(
+        "snippet",
+        json!([message(100, 1_700_000_000, "삼성전자 주가 상승")]),
+    )
+    .await;
+
+    let whole = server.search(json!({ "query": "삼성전자" })).await;
+    assert_eq!(items_of(&whole)[0]["is_snippet"], false);
+    let snipped = server
+        .search(json!({ "query": "삼성전자", "snippet": { "max_len": 4 } }))
+        .await;
+    let hit = &items_of(&snipped)[0];
+    assert_eq!(hit["is_snippet"], true);
+    assert_eq!(hit["text"], "삼성전자…");
+    assert_eq!(hit["highlights"][0]["start"], 0);
+    assert_eq!(hit["highlights"][0]["end"], 12);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn message_context_over_ipc() {
+    let server = TestServer::start(
+        "context",
+        json!([
+            message(100, 1_700_000_000, "삼성전자 주가 상승"),
+            message(101, 1_700_000_100, "apple unrelated"),
+        ]),
+    )
+    .await;
+
+    let context = server
+        .call(
+            "message_context",
+            json!({ "chat_id": 7, "message_id": 101, "before": 3, "after": 3 }),
+        )
+        .await;
+    let rows = context["result"].as_array().expect("context array");
+    let ids: Vec<i64> = rows
+        .iter()
+        .map(|r| r["message_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![100, 101]);
+    assert_eq!(rows[0]["chat_title"], "Test Chat");
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn suggest_completes_from_search_history() {
+    let server = TestServer::start(
+        "suggest",
+        json!([message(100, 1_700_000_000, "삼성전자 주가 상승")]),
+    )
+    .await;
+
+    let search = server.search(json!({ "query": "삼성전자" })).await;
+    assert_eq!(ids_of(&search), vec![100]);
+    let suggest = server.call("suggest", json!({ "prefix": "ㅅㅏㅁ" })).await;
+    let suggestions = suggest["result"].as_array().expect("suggestions");
+    assert_eq!(suggestions[0]["text"], "삼성전자");
+    assert_eq!(suggestions[0]["kind"], "history");
+
+    // With history off, searches still run but are not recorded.
+    let off = server
+        .call("set_search_history_enabled", json!({ "enabled": false }))
+        .await;
+    assert_eq!(off["result"], false);
+    let search = server.search(json!({ "query": "주가" })).await;
+    assert_eq!(ids_of(&search), vec![100]);
+    let recent = server.call("suggest", json!({ "prefix": "" })).await;
+    let recent = recent["result"].as_array().expect("suggestions");
+    assert!(recent.iter().all(|s| s["text"] != "주가"));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn entity_filters_and_top_entities_over_ipc() {
+    let server = TestServer::start(
+        "entities",
+        json!([
+            message(100, 1_700_000_000, "삼성전자 주가 상승"),
+            message(101, 1_700_000_100, "apple #Airdrop https://x.com/a"),
+        ]),
+    )
+    .await;
+
+    let tagged = server.search(json!({ "query": "tag:airdrop" })).await;
+    assert_eq!(ids_of(&tagged), vec![101]);
+    let top = server
+        .call("top_entities", json!({ "kind": "link", "chat_id": 7 }))
+        .await;
+    assert_eq!(top["result"][0]["value"], "x.com");
+    assert_eq!(top["result"][0]["count"], 1);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn media_type_filter_over_ipc() {
+    let server = TestServer::start(
+        "media",
+        json!([{
+            "chat_id": 7,
+            "message_id": 102,
+            "sender_id": null,
+            "sender_name": null,
+            "timestamp": 1_700_000_200,
+            "text": "",
+            "media": {
+                "kind": "document",
+                "file_name": "q3_report.pdf",
+                "mime_type": "application/pdf",
+                "size_bytes": 52_000
+            }
+        }]),
+    )
+    .await;
+
+    let documents = server
+        .search(json!({ "query": "report type:document" }))
+        .await;
+    assert_eq!(ids_of(&documents), vec![102]);
+    let photos = server.search(json!({ "query": "report type:photo" })).await;
+    assert!(items_of(&photos).is_empty());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn reply_chains_and_threads_over_ipc() {
+    let server = TestServer::start(
+        "threads",
+        json!([
+            message(102, 1_700_000_200, "apple 공지"),
+            {
+                "chat_id": 7,
+                "message_id": 103,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_300,
+                "text": "apple 실적 스레드",
+                "reply_to_message_id": 102,
+                "thread_id": 102
+            },
+            {
+                "chat_id": 7,
+                "message_id": 104,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_400,
+                "text": "apple 답글",
+                "reply_to_message_id": 103,
+                "thread_id": 102
+            },
+            message(105, 1_700_000_500, "apple 다른 이야기"),
+        ]),
+    )
+    .await;
+
+    let chain = server
+        .call("reply_chain", json!({ "chat_id": 7, "message_id": 104 }))
+        .await;
+    let chain_ids: Vec<i64> = chain["result"]
+        .as_array()
+        .expect("chain")
+        .iter()
+        .map(|m| m["message_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(chain_ids, vec![102, 103, 104]);
+    let in_thread = server
+        .search(json!({
+            "query": "apple",
+            "scope": { "kind": "thread", "chat_id": 7, "thread_id": 102 }
+        }))
+        .await;
+    assert_eq!(items_of(&in_thread).len(), 3);
+    let page = server
+        .call(
+            "thread_messages",
+            json!({ "chat_id": 7, "thread_id": 102, "after_message_id": 102 }),
+        )
+        .await;
+    assert_eq!(page["result"].as_array().expect("thread").len(), 2);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn from_filter_matches_sender_names_over_ipc() {
+    let server = TestServer::start(
+        "from",
+        json!([
+            {
+                "chat_id": 7,
+                "message_id": 103,
+                "sender_id": 55,
+                "sender_name": "김철수",
+                "sender_username": "cheolsu",
+                "timestamp": 1_700_000_300,
+                "text": "apple 실적"
+            },
+            message(104, 1_700_000_400, "apple 답글"),
+        ]),
+    )
+    .await;
+
+    let from = server.search(json!({ "query": "apple from:철수" })).await;
+    let items = items_of(&from);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["message_id"], 103);
+    assert_eq!(items[0]["sender_name"], "김철수");
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn accounts_stay_apart_over_ipc() {
+    let server = TestServer::start(
+        "accounts",
+        json!([
+            message(100, 1_700_000_000, "삼성전자 주가 상승"),
+            // A second signed-in account mirroring the same chat.
+            {
+                "account_id": 2,
+                "chat_id": 7,
+                "message_id": 100,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_000,
+                "text": "삼성전자 목표가"
+            },
+        ]),
+    )
+    .await;
+
+    let both = server.search(json!({ "query": "삼성전자" })).await;
+    let accounts: Vec<i64> = items_of(&both)
+        .iter()
+        .map(|i| i["account_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(accounts.len(), 2);
+    assert!(accounts.contains(&0) && accounts.contains(&2));
+    let one = server
+        .search(json!({ "query": "삼성전자", "filters": { "account_ids": [2] } }))
+        .await;
+    let items = items_of(&one);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["text"], "삼성전자 목표가");
+    let context = server
+        .call(
+            "message_context",
+            json!({ "account_id": 2, "chat_id": 7, "message_id": 100 }),
+        )
+        .await;
+    assert_eq!(context["result"].as_array().expect("context").len(), 1);
+    // A chat scope names its account.
+    let scoped = server
+        .search(json!({
+            "query": "삼성전자",
+            "scope": { "kind": "chat", "account_id": 2, "chat_id": 7 }
+        }))
+        .await;
+    assert_eq!(items_of(&scoped)[0]["text"], "삼성전자 목표가");
+    assert_eq!(items_of(&scoped).len(), 1);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn edits_keep_searchable_revisions_over_ipc() {
+    let server = TestServer::start(
+        "revisions",
+        json!([message(100, 1_700_000_000, "삼성전자 목표가")]),
+    )
+    .await;
+
+    // An edit keeps the old text as revision 1, searchable on request.
+    let edit = server
+        .index(json!([message(100, 1_700_000_000, "목표가 하향")]))
+        .await;
+    assert_eq!(edit["result"]["updated"], 1);
+    let revisions = server
+        .call(
+            "message_revisions",
+            json!({ "chat_id": 7, "message_id": 100 }),
+        )
+        .await;
+    let revisions = revisions["result"].as_array().expect("revisions");
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0]["msg_version"], 1);
+    assert_eq!(revisions[0]["text_plain"], "삼성전자 목표가");
+    let current = server.search(json!({ "query": "삼성전자" })).await;
+    assert!(items_of(&current).is_empty());
+    let old = server
+        .search(json!({
+            "query": "삼성전자",
+            "filters": { "include_revisions": true }
+        }))
+        .await;
+    let items = items_of(&old);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["text"], "목표가 하향");
+    assert_eq!(items[0]["matched_revision"], 1);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn delete_and_undelete_over_ipc() {
+    let server = TestServer::start(
+        "delete",
+        json!([message(100, 1_700_000_000, "목표가 하향")]),
+    )
+    .await;
+
+    // Deleting hides the message from search; undo brings it back
+    // while the tombstone is kept.
+    let delete = server
+        .call("delete_message", json!({ "chat_id": 7, "message_id": 100 }))
+        .await;
+    assert!(delete.get("error").is_none(), "delete failed: {delete}");
+    let gone = server.search(json!({ "query": "하향" })).await;
+    assert!(items_of(&gone).is_empty());
+    let undo = server
+        .call(
+            "undelete_message",
+            json!({ "chat_id": 7, "message_id": 100 }),
+        )
+        .await;
+    assert_eq!(undo["result"]["restored"], true);
+    let back = server.search(json!({ "query": "하향" })).await;
+    assert_eq!(ids_of(&back), vec![100]);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn saved_search_matches_are_pushed_as_events() {
+    let socket = unique_socket_path("saved");
+    let db = unique_db_path("saved");
+    let saved_id = Store::open(&db)
+        .expect("open store")
+        .create_saved_search("Airdrops", "airdrop snapshot", None)
+        .expect("save search");
+    let store = Store::open(&db).expect("open store");
+    let (server, _events) = SidecarServer::bind(&socket, SidecarState::new(store)).expect("bind");
+    let server_handle = tokio::spawn(server.run());
+
+    // One connection for both frames: the event follows the response
+    // on the same socket.
+    let mut stream = tokio::net::UnixStream::connect(&socket)
+        .await
+        .expect("connect");
+    let request = json!({
+        "id": 20,
+        "method": "index_messages_batch",
+        "params": {
+            "messages": [{
+                "chat_id": 3,
+                "message_id": 1,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_000,
+                "text": "airdrop snapshot at block 123"
+            }]
+        }
+    });
+    let body = serde_json::to_vec(&request).expect("encode request");
+    codec::write_frame(&mut stream, &body).await.expect("write");
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        let frame = codec::read_frame(&mut stream)
+            .await
+            .expect("read")
+            .expect("frame");
+        frames.push(serde_json::from_slice::<Value>(&frame).expect("decode"));
+    }
+    assert_eq!(frames[0]["id"], 20);
+    assert_eq!(frames[0]["result"]["inserted"], 1);
+    assert_eq!(frames[1]["event"], "saved_search_matches");
+    let matches = &frames[1]["payload"]["matches"];
+    assert_eq!(matches[0]["saved_search_id"], saved_id);
+    assert_eq!(matches[0]["name"], "Airdrops");
+    assert_eq!(matches[0]["new_matches"], 1);
+    drop(stream);
+
+    let _ = connect_and_call(&socket, json!({ "id": 99, "method": "shutdown" })).await;
+    let _ = server_handle.await;
+    let _ = std::fs::remove_file(&socket);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn reconcile_reports_gaps_and_requests_backfill() {
+    let socket = unique_socket_path("recon");
+    let db = unique_db_path("recon");
+    let store = Store::open(&db).expect("open store");
+    let (server, _events) = SidecarServer::bind(&socket, SidecarState::new(store)).expect("bind");
+    let server_handle = tokio::spawn(server.run());
+
+    let messages: Vec<Value> = [1, 2, 4]
+        .iter()
+        .map(|id| {
+            json!({
+                "account_id": 1,
+                "chat_id": 9,
+                "message_id": id,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_000 + id,
+                "text": format!("공지 {id}")
+            })
+        })
+        .collect();
+    let index = connect_and_call(
+        &socket,
+        json!({
+            "id": 1,
+            "method": "index_messages_batch",
+            "params": { "messages": messages }
+        }),
+    )
+    .await;
+    assert_eq!(index["result"]["inserted"], 3);
+
+    let mut stream = tokio::net::UnixStream::connect(&socket)
+        .await
+        .expect("connect");
+    let request = json!({
+        "id": 2,
+        "method": "reconcile_chat",
+        "params": {
+            "account_id": 1,
+            "chat_id": 9,
+            "mode": "ranges",
+            "ranges": [{ "from": 1, "to": 6 }],
+            "full": true
+        }
+    });
+    let body = serde_json::to_vec(&request).expect("encode request");
+    codec::write_frame(&mut stream, &body).await.expect("write");
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        let frame = codec::read_frame(&mut stream)
+            .await
+            .expect("read")
+            .expect("frame");
+        frames.push(serde_json::from_slice::<Value>(&frame).expect("decode"));
+    }
+    let report = &frames[0]["result"];
+    assert_eq!(
+        report["missing"],
+        json!([{ "from": 3, "to": 3 }, { "from": 5, "to": 6 }])
+    );
+    assert_eq!(report["max_msg_id"], 6);
+    assert!(report["last_full_diff"].as_i64().unwrap() > 0);
+    assert_eq!(frames[1]["event"], "backfill_requested");
+    assert_eq!(frames[1]["payload"]["account_id"], 1);
+    assert_eq!(frames[1]["payload"]["ranges"], report["missing"]);
+    drop(stream);
+
+    let _ = connect_and_call(&socket, json!({ "id": 99, "method": "shutdown" })).await;
+    let _ = server_handle.await;
+    let _ = std::fs::remove_file(&socket);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn sync_state_updates_push_backfill_progress() {
+    let socket = unique_socket_path("sync");
+    let db = unique_db_path("sync");
+    let store = Store::open(&db).expect("open store");
+    let (server, _events) = SidecarServer::bind(&socket, SidecarState::new(store)).expect("bind");
+    let server_handle = tokio::spawn(server.run());
+
+    let index = connect_and_call(
+        &socket,
+        json!({
+            "id": 1,
+            "method": "index_messages_batch",
+            "params": {
+                "messages": [{
+                    "chat_id": 4,
+                    "message_id": 200,
+                    "sender_id": null,
+                    "sender_name": null,
+                    "timestamp": 1_700_000_000,
+                    "text": "백필 시작"
+                }]
+            }
+        }),
+    )
+    .await;
+    assert_eq!(index["result"]["inserted"], 1);
+    // One connection throughout: each update answers and then pushes
+    // a progress event.
+    let mut stream = tokio::net::UnixStream::connect(&socket)
+        .await
+        .expect("connect");
+    let mut call = async |request: Value, frames: usize| {
+        let body = serde_json::to_vec(&request).expect("encode request");
+        codec::write_frame(&mut stream, &body).await.expect("write");
+        let mut out = Vec::new();
+        for _ in 0..frames {
+            let frame = codec::read_frame(&mut stream)
+                .await
+                .expect("read")
+                .expect("frame");
+            out.push(serde_json::from_slice::<Value>(&frame).expect("decode"));
+        }
+        out
+    };
+
+    let none = call(
+        json!({ "id": 2, "method": "get_sync_state", "params": { "chat_id": 4 } }),
+        1,
+    )
+    .await;
+    assert!(none[0]["result"].is_null(), "unexpected: {}", none[0]);
+    let last = call(
+        json!({
+            "id": 3,
+            "method": "update_last_message_id",
+            "params": { "chat_id": 4, "last_message_id": 200, "last_sync_at": "2026-01-01T00:00:00Z" }
+        }),
+        2,
+    )
+    .await;
+    assert_eq!(last[0]["result"]["progress"], 0.0);
+    assert_eq!(last[1]["event"], "backfill_progress");
+
+    let frames = call(
+        json!({
+            "id": 4,
+            "method": "update_oldest_message_id",
+            "params": { "chat_id": 4, "oldest_message_id": 51 }
+        }),
+        2,
+    )
+    .await;
+    assert_eq!(frames[0]["id"], 4);
+    assert_eq!(frames[0]["result"]["progress"], 0.75);
+    assert_eq!(frames[0]["result"]["indexed_messages"], 1);
+    assert_eq!(frames[1]["event"], "backfill_progress");
+    assert_eq!(frames[1]["payload"]["chat_id"], 4);
+    assert_eq!(frames[1]["payload"]["progress"], 0.75);
+
+    let state = call(
+        json!({ "id": 5, "method": "get_sync_state", "params": { "chat_id": 4 } }),
+        1,
+    )
+    .await;
+    assert_eq!(state[0]["result"]["oldest_message_id"], 51);
+    assert_eq!(state[0]["result"]["last_sync_at"], "2026-01-01T00:00:00Z");
+    let _ = call(
+        json!({ "id": 6, "method": "mark_initial_done", "params": { "chat_id": 4 } }),
+        2,
+    )
+    .await;
+    let all = call(
+        json!({ "id": 7, "method": "backfill_progress", "params": {} }),
+        1,
+    )
+    .await;
+    assert_eq!(all[0]["result"][0]["initial_done"], true);
+    assert_eq!(all[0]["result"][0]["progress"], 1.0);
+    drop(stream);
+
+    let _ = connect_and_call(&socket, json!({ "id": 99, "method": "shutdown" })).await;
+    let _ = server_handle.await;
+    let _ = std::fs::remove_file(&socket);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn chats_are_managed_over_ipc() {
+    let socket = unique_socket_path("chats");
+    let db = unique_db_path("chats");
+    let store = Store::open(&db).expect("open store");
+    let (server, _events) = SidecarServer::bind(&socket, SidecarState::new(store)).expect("bind");
+    let server_handle = tokio::spawn(server.run());
+
+    // Indexing ahead of the chat's details leaves an untitled stub.
+    let messages: Vec<Value> = [7, 8]
+        .iter()
+        .map(|id| {
+            json!({
+                "chat_id": 30,
+                "message_id": id,
+                "sender_id": null,
+                "sender_name": null,
+                "timestamp": 1_700_000_000 + id,
+                "text": "상장 첫날"
+            })
+        })
+        .collect();
+    let index = connect_and_call(
+        &socket,
+        json!({
+            "id": 1,
+            "method": "index_messages_batch",
+            "params": { "messages": messages }
+        }),
+    )
+    .await;
+    assert_eq!(index["result"]["inserted"], 2);
+    let listed = connect_and_call(
+        &socket,
+        json!({ "id": 2, "method": "list_chats", "params": {} }),
+    )
+    .await;
+    let stub = &listed["result"][0];
+    assert_eq!(stub["chat_id"], 30);
+    assert_eq!(stub["title"], "");
+    assert_eq!(stub["chat_type"], "dm");
+    assert_eq!(stub["message_count"], 2);
+    assert_eq!(stub["last_message_at"], 1_700_000_008);
+    assert!(stub["last_indexed_at"].as_i64().unwrap() > 0);
+
+    let upserted = connect_and_call(
+        &socket,
+        json!({
+            "id": 3,
+            "method": "upsert_chat",
+            "params": {
+                "chat_id": 30,
+                "title": "공모주 알림",
+                "chat_type": "channel",
+                "username": "ipo_alerts"
+            }
+        }),
+    )
+    .await;
+    assert_eq!(upserted["result"]["title"], "공모주 알림");
+    assert_eq!(upserted["result"]["is_excluded"], false);
+    let _ = connect_and_call(
+        &socket,
+        json!({
+            "id": 4,
+            "method": "upsert_chat",
+            "params": { "chat_id": 31, "title": "잡담", "chat_type": "group" }
+        }),
+    )
+    .await;
+
+    let excluded = connect_and_call(
+        &socket,
+        json!({
+            "id": 5,
+            "method": "set_chat_excluded",
+            "params": { "chat_id": 30, "excluded": true }
+        }),
+    )
+    .await;
+    assert_eq!(excluded["result"]["is_excluded"], true);
+    let active = connect_and_call(
+        &socket,
+        json!({ "id": 6, "method": "list_chats", "params": {} }),
+    )
+    .await;
+    assert_eq!(active["result"].as_array().unwrap().len(), 1);
+    assert_eq!(active["result"][0]["chat_id"], 31);
+    assert_eq!(active["result"][0]["message_count"], 0);
+    let all = connect_and_call(
+        &socket,
+        json!({ "id": 7, "method": "list_chats", "params": { "include_excluded": true } }),
+    )
+    .await;
+    assert_eq!(all["result"].as_array().unwrap().len(), 2);
+
+    let fetched = connect_and_call(
+        &socket,
+        json!({ "id": 8, "method": "get_chat", "params": { "chat_id": 30 } }),
+    )
+    .await;
+    assert_eq!(fetched["result"]["username"], "ipo_alerts");
+    assert_eq!(fetched["result"]["is_excluded"], true);
+    let unknown = connect_and_call(
+        &socket,
+        json!({ "id": 9, "method": "get_chat", "params": { "chat_id": 404 } }),
+    )
+    .await;
+    assert!(unknown["result"].is_null(), "unexpected: {unknown}");
+
+    let _ = connect_and_call(&socket, json!({ "id": 99, "method": "shutdown" })).await;
+    let _ = server_handle.await;
+    let _ = std::fs::remove_file(&socket);
+    let _ = std::fs::remove_file(&db);
+}
@@ -5,7 +5,13 @@
 //! the FFI types and in the wiring that forwards to the core
 //! modules.
 
-use seoyu::uniffi_api::{ChatInfo, IndexedMessage, MessageRef, SearchScope, Seoyu};
+use std::sync::{Arc, Mutex};
+
+use seoyu::uniffi_api::{
+    ChatInfo, EntityKind, IdRange, IndexedMessage, MediaKind, MessageMedia, MessageRef,
+    ReconBucket, ReconMessage, SavedSearchObserver, SearchFilters, SearchOptions, SearchScope,
+    Seoyu, SeoyuError, SuggestionKind,
+};
 
 fn tmp_db(tag: &str) -> String {
     let pid = std::process::id();
@@ -34,6 +40,7 @@ fn index_then_korean_search_round_trip() {
 
     seoyu
         .upsert_chat(ChatInfo {
+            account_id: 0,
             chat_id: 42,
             title: "Test".into(),
             chat_type: "channel".into(),
@@ -46,20 +53,32 @@ fn index_then_korean_search_round_trip() {
     let outcome = seoyu
         .index_messages(vec![
             IndexedMessage {
+                account_id: 0,
                 chat_id: 42,
                 message_id: 1,
                 timestamp: 1_700_000_000,
                 text: "삼성전자 실적 발표".into(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
             IndexedMessage {
+                account_id: 0,
                 chat_id: 42,
                 message_id: 2,
                 timestamp: 1_700_000_100,
                 text: "apple unrelated".into(),
                 link: None,
                 sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
             },
         ])
         .expect("index");
@@ -75,6 +94,332 @@ fn index_then_korean_search_round_trip() {
         "expected 삼성전자 row via partial match, got {ids:?}"
     );
 
+    // The search above is remembered and completes from half-typed jamo.
+    let suggestions = seoyu.suggest("ㅅㅏㅁ".into(), 0).expect("suggest");
+    let pairs: Vec<(&str, SuggestionKind)> = suggestions
+        .iter()
+        .map(|s| (s.text.as_str(), s.kind))
+        .collect();
+    assert_eq!(pairs, vec![("삼성", SuggestionKind::History)]);
+    seoyu.set_search_history_enabled(false).expect("opt out");
+    seoyu
+        .search("apple".into(), SearchScope::All, 30, None)
+        .expect("search");
+    let recent = seoyu.recent_searches(10).expect("recent");
+    assert_eq!(recent.len(), 1);
+    seoyu.clear_search_history().expect("clear");
+    assert!(seoyu.recent_searches(10).expect("recent").is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn entity_filters_and_top_entities() {
+    let path = tmp_db("entities");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let msg = |message_id, text: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 5,
+        message_id,
+        timestamp: 1_000 + message_id,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![
+            msg(1, "#airdrop 스냅샷 https://docs.example.com/a"),
+            msg(2, "#airdrop 마감 $BTC"),
+            msg(3, "airdrop 관련 없음"),
+        ])
+        .expect("index");
+
+    let ids = |query: &str, filters: SearchFilters| -> Vec<i64> {
+        seoyu
+            .search(query.into(), SearchScope::Filtered { filters }, 30, None)
+            .expect("search")
+            .items
+            .iter()
+            .map(|h| h.message_id)
+            .collect()
+    };
+    assert_eq!(ids("tag:airdrop", SearchFilters::default()), vec![2, 1]);
+    assert_eq!(ids("airdrop has:link", SearchFilters::default()), vec![1]);
+    let by_domain = SearchFilters {
+        domains: vec!["example.com".into()],
+        ..Default::default()
+    };
+    assert_eq!(ids("", by_domain), vec![1]);
+    let by_kind = SearchFilters {
+        has: vec![EntityKind::Cashtag],
+        ..Default::default()
+    };
+    assert_eq!(ids("tag:airdrop", by_kind), vec![2]);
+
+    let top = seoyu
+        .top_entities(Some(EntityKind::Hashtag), 0, Some(5), None, None, 0)
+        .expect("top");
+    assert_eq!(top.len(), 1);
+    assert_eq!((top[0].value.as_str(), top[0].count), ("airdrop", 2));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn media_metadata_round_trips_and_filters() {
+    let path = tmp_db("media");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let photo = MessageMedia {
+        kind: Some(MediaKind::Photo),
+        file_name: None,
+        mime_type: Some("image/jpeg".into()),
+        size_bytes: Some(120_000),
+        caption: Some("신제품 발표회 현장".into()),
+        forward_from_name: Some("테크 뉴스".into()),
+        forward_from_chat_id: Some(-1001),
+        forward_from_message_id: Some(42),
+    };
+    seoyu
+        .index_messages(vec![
+            IndexedMessage {
+                account_id: 0,
+                chat_id: 5,
+                message_id: 1,
+                timestamp: 1_000,
+                text: String::new(),
+                link: None,
+                sender_id: 0,
+                media: Some(photo.clone()),
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            },
+            IndexedMessage {
+                account_id: 0,
+                chat_id: 5,
+                message_id: 2,
+                timestamp: 1_001,
+                text: "발표회 언제예요".into(),
+                link: None,
+                sender_id: 0,
+                media: None,
+                reply_to_message_id: None,
+                thread_id: None,
+                sender_name: None,
+                sender_username: None,
+            },
+        ])
+        .expect("index");
+
+    let stored = seoyu
+        .message_media(0, 5, 1)
+        .expect("media")
+        .expect("photo row");
+    assert_eq!(stored.kind, Some(MediaKind::Photo));
+    assert_eq!(stored.caption, photo.caption);
+    assert_eq!(stored.size_bytes, Some(120_000));
+    assert_eq!(stored.forward_from_message_id, Some(42));
+    assert!(seoyu.message_media(0, 5, 2).expect("media").is_none());
+
+    let ids = |query: &str| -> Vec<i64> {
+        seoyu
+            .search(query.into(), SearchScope::All, 30, None)
+            .expect("search")
+            .items
+            .iter()
+            .map(|h| h.message_id)
+            .collect()
+    };
+    assert_eq!(ids("발표회"), vec![2, 1]);
+    assert_eq!(ids("발표회 has:photo"), vec![1]);
+    assert_eq!(ids("type:video"), Vec::<i64>::new());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn reply_chains_threads_and_thread_scope() {
+    let path = tmp_db("threads");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let msg = |message_id, reply_to, thread_id, text: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 9,
+        message_id,
+        timestamp: 1_000 + message_id,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: reply_to,
+        thread_id,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![
+            msg(1, None, None, "배당 토픽"),
+            msg(2, Some(1), Some(1), "배당 언제"),
+            msg(3, Some(2), Some(1), "다음 달 배당"),
+            msg(4, None, None, "배당 무관"),
+        ])
+        .expect("index");
+
+    let ids = |hits: Vec<seoyu::uniffi_api::SearchHit>| -> Vec<i64> {
+        hits.iter().map(|h| h.message_id).collect()
+    };
+    assert_eq!(
+        ids(seoyu.reply_chain(0, 9, 3).expect("chain")),
+        vec![1, 2, 3]
+    );
+    assert_eq!(
+        ids(seoyu.thread_messages(0, 9, 1, Some(1), 0).expect("thread")),
+        vec![2, 3]
+    );
+    let page = seoyu
+        .search(
+            "배당".into(),
+            SearchScope::Thread {
+                account_id: 0,
+                chat_id: 9,
+                thread_id: 1,
+            },
+            30,
+            None,
+        )
+        .expect("search");
+    let mut found = ids(page.items);
+    found.sort();
+    assert_eq!(found, vec![1, 2, 3]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn sender_names_reach_hits_and_from_filter() {
+    let path = tmp_db("senders");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let msg = |message_id, sender_id, name: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 3,
+        message_id,
+        timestamp: 1_000 + message_id,
+        text: "상장 공지".into(),
+        link: None,
+        sender_id,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: Some(name.into()),
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![msg(1, 10, "이지은"), msg(2, 11, "박서준")])
+        .expect("index");
+
+    let page = seoyu
+        .search("상장 from:지은".into(), SearchScope::All, 30, None)
+        .expect("search");
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].sender_name.as_deref(), Some("이지은"));
+
+    let filters = SearchFilters {
+        from: vec!["박서".into()],
+        ..Default::default()
+    };
+    let page = seoyu
+        .search("".into(), SearchScope::Filtered { filters }, 30, None)
+        .expect("search");
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].message_id, 2);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn filtered_scope_narrows_by_sender_date_and_chat_type() {
+    let path = tmp_db("filters");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+
+    for (chat_id, chat_type) in [(1, "channel"), (2, "dm")] {
+        seoyu
+            .upsert_chat(ChatInfo {
+                account_id: 0,
+                chat_id,
+                title: format!("Chat {chat_id}"),
+                chat_type: chat_type.into(),
+                username: None,
+                access_hash: None,
+                is_excluded: false,
+            })
+            .expect("upsert");
+    }
+    let msg = |chat_id, message_id, timestamp, sender_id| IndexedMessage {
+        account_id: 0,
+        chat_id,
+        message_id,
+        timestamp,
+        text: "bitcoin etf flows".into(),
+        link: None,
+        sender_id,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![
+            msg(1, 1, 1_000, 7),
+            msg(1, 2, 2_000, 7),
+            msg(1, 3, 2_000, 8),
+            msg(2, 4, 2_000, 7),
+        ])
+        .expect("index");
+
+    let scope = SearchScope::Filtered {
+        filters: SearchFilters {
+            since: Some(1_500),
+            sender_ids: vec![7],
+            chat_types: vec!["channel".into()],
+            ..Default::default()
+        },
+    };
+    let page = seoyu
+        .search("bitcoin".into(), scope, 30, None)
+        .expect("search");
+    let ids: Vec<i64> = page.items.iter().map(|h| h.message_id).collect();
+    assert_eq!(ids, vec![2]);
+    assert!(page.facets.is_none());
+
+    let page = seoyu
+        .search_with_options(
+            "bitcoin".into(),
+            SearchScope::All,
+            1,
+            None,
+            SearchOptions {
+                include_facets: true,
+                ..Default::default()
+            },
+        )
+        .expect("search");
+    assert_eq!(page.items.len(), 1);
+    let facets = page.facets.expect("facets requested");
+    assert_eq!(facets.total, 4);
+    let chats: Vec<(i64, u64)> = facets
+        .by_chat
+        .iter()
+        .map(|c| (c.chat_id, c.count))
+        .collect();
+    assert_eq!(chats, vec![(1, 3), (2, 1)]);
+    assert_eq!(facets.by_day.len(), 1);
+
     let _ = std::fs::remove_file(&path);
 }
 
@@ -85,6 +430,7 @@ fn index_update_and_delete_round_trip() {
 
     seoyu
         .upsert_chat(ChatInfo {
+            account_id: 0,
             chat_id: 77,
             title: "Edits".into(),
             chat_type: "channel".into(),
@@ -96,28 +442,44 @@ fn index_update_and_delete_round_trip() {
 
     let first = seoyu
         .index_messages(vec![IndexedMessage {
+            account_id: 0,
             chat_id: 77,
             message_id: 1,
             timestamp: 1_700_000_000,
             text: "old keyword".into(),
             link: None,
             sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         }])
         .expect("insert");
     assert_eq!((first.inserted, first.updated), (1, 0));
 
     let second = seoyu
         .index_messages(vec![IndexedMessage {
+            account_id: 0,
             chat_id: 77,
             message_id: 1,
             timestamp: 1_700_000_001,
             text: "new keyword".into(),
             link: None,
             sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
         }])
         .expect("update");
     assert_eq!((second.inserted, second.updated), (0, 1));
 
+    let context = seoyu.message_context(0, 77, 1, 5, 5).expect("context");
+    assert_eq!(context.len(), 1);
+    assert_eq!(context[0].text, "new keyword");
+
     assert!(seoyu
         .search("old".into(), SearchScope::All, 30, None)
         .expect("old search")
@@ -135,6 +497,7 @@ fn index_update_and_delete_round_trip() {
     assert_eq!(
         seoyu
             .delete_messages(vec![MessageRef {
+                account_id: 0,
                 chat_id: 77,
                 message_id: 1,
             }])
@@ -146,6 +509,476 @@ fn index_update_and_delete_round_trip() {
         .expect("deleted search")
         .items
         .is_empty());
+    assert!(seoyu
+        .message_context(0, 77, 1, 5, 5)
+        .expect("deleted context")
+        .is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[derive(Default)]
+struct RecordingObserver {
+    calls: Mutex<Vec<(i64, String, u64)>>,
+}
+
+impl SavedSearchObserver for RecordingObserver {
+    fn on_new_matches(&self, saved_search_id: i64, name: String, new_matches: u64) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((saved_search_id, name, new_matches));
+    }
+}
+
+#[test]
+fn saved_search_alerts_observer_on_new_matches() {
+    let path = tmp_db("saved");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let observer = Arc::new(RecordingObserver::default());
+    seoyu.set_saved_search_observer(Some(observer.clone()));
+
+    assert!(matches!(
+        seoyu.create_saved_search("empty".into(), "   ".into(), 0, None),
+        Err(SeoyuError::InvalidArgument(_))
+    ));
+    let id = seoyu
+        .create_saved_search("Listings".into(), "상장 공지".into(), 0, None)
+        .expect("create");
+
+    let message = |message_id: i64, text: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 5,
+        message_id,
+        timestamp: 1_700_000_000 + message_id,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![
+            message(1, "바이낸스 상장 공지"),
+            message(2, "unrelated"),
+            message(3, "업비트 신규 상장 공지"),
+        ])
+        .expect("index");
+    assert_eq!(
+        *observer.calls.lock().unwrap(),
+        vec![(id, "Listings".to_string(), 2)]
+    );
+
+    let listed = seoyu.list_saved_searches().expect("list");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].unseen_count, 2);
+    let hits = seoyu.saved_search_hits(id, 10).expect("hits");
+    assert_eq!(
+        hits.iter().map(|h| h.message_id).collect::<Vec<_>>(),
+        vec![3, 1]
+    );
+    seoyu.mark_saved_search_seen(id).expect("seen");
+    assert_eq!(
+        seoyu.list_saved_searches().expect("list")[0].unseen_count,
+        0
+    );
+
+    assert!(seoyu.delete_saved_search(id).expect("delete"));
+    seoyu
+        .index_messages(vec![message(4, "상장 공지 또")])
+        .expect("index");
+    assert_eq!(observer.calls.lock().unwrap().len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn accounts_index_and_search_side_by_side() {
+    let path = tmp_db("accounts");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+
+    let message = |account_id: i64, text: &str| IndexedMessage {
+        account_id,
+        chat_id: 5,
+        message_id: 1,
+        timestamp: 1_700_000_000,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    let outcome = seoyu
+        .index_messages(vec![
+            message(0, "개인 계정 공지"),
+            message(1, "업무 계정 공지"),
+        ])
+        .expect("index");
+    assert_eq!((outcome.inserted, outcome.updated), (2, 0));
+
+    let accounts = |scope: SearchScope| -> Vec<i64> {
+        seoyu
+            .search("공지".into(), scope, 30, None)
+            .expect("search")
+            .items
+            .iter()
+            .map(|h| h.account_id)
+            .collect()
+    };
+    assert_eq!(accounts(SearchScope::All), vec![0, 1]);
+    assert_eq!(
+        accounts(SearchScope::Filtered {
+            filters: SearchFilters {
+                account_ids: vec![1],
+                ..Default::default()
+            },
+        }),
+        vec![1]
+    );
+
+    assert_eq!(
+        seoyu
+            .delete_messages(vec![MessageRef {
+                account_id: 1,
+                chat_id: 5,
+                message_id: 1,
+            }])
+            .expect("delete"),
+        1
+    );
+    assert_eq!(accounts(SearchScope::All), vec![0]);
+    assert!(seoyu
+        .message_context(1, 5, 1, 2, 2)
+        .expect("context")
+        .is_empty());
+    assert_eq!(
+        seoyu.message_context(0, 5, 1, 2, 2).expect("context")[0].text,
+        "개인 계정 공지"
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn edits_keep_revisions_and_search_them_on_request() {
+    let path = tmp_db("revisions");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+
+    let message = |text: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 5,
+        message_id: 1,
+        timestamp: 1_700_000_000,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![message("회의 장소 변경 공지")])
+        .expect("index");
+    let outcome = seoyu
+        .index_messages(vec![message("회의 취소")])
+        .expect("edit");
+    assert_eq!((outcome.inserted, outcome.updated), (0, 1));
+
+    let revisions = seoyu.message_revisions(0, 5, 1).expect("revisions");
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].msg_version, 1);
+    assert_eq!(revisions[0].text_plain, "회의 장소 변경 공지");
+
+    let hits = |include_revisions: bool| {
+        seoyu
+            .search_with_options(
+                "장소 변경".into(),
+                SearchScope::All,
+                30,
+                None,
+                SearchOptions {
+                    include_revisions,
+                    ..Default::default()
+                },
+            )
+            .expect("search")
+            .items
+    };
+    assert!(hits(false).is_empty());
+    let items = hits(true);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].text, "회의 취소");
+    assert_eq!(items[0].matched_revision, Some(1));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn deleted_messages_can_be_restored_until_purged() {
+    let path = tmp_db("tombstones");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    assert_eq!(seoyu.tombstone_retention_days().expect("retention"), 30);
+
+    seoyu
+        .index_messages(vec![IndexedMessage {
+            account_id: 0,
+            chat_id: 5,
+            message_id: 1,
+            timestamp: 1_700_000_000,
+            text: "배당 기준일 안내".into(),
+            link: None,
+            sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
+        }])
+        .expect("index");
+    let refs = || {
+        vec![MessageRef {
+            account_id: 0,
+            chat_id: 5,
+            message_id: 1,
+        }]
+    };
+    let hits = || {
+        seoyu
+            .search("배당".into(), SearchScope::All, 30, None)
+            .expect("search")
+            .items
+            .len()
+    };
+
+    assert_eq!(seoyu.delete_messages(refs()).expect("delete"), 1);
+    assert_eq!(hits(), 0);
+    assert_eq!(seoyu.purge_deleted_messages().expect("purge"), 0);
+    assert_eq!(seoyu.undelete_messages(refs()).expect("undo"), 1);
+    assert_eq!(hits(), 1);
+
+    seoyu
+        .set_tombstone_retention_days(0)
+        .expect("set retention");
+    seoyu.delete_messages(refs()).expect("delete");
+    assert_eq!(seoyu.purge_deleted_messages().expect("purge"), 1);
+    assert_eq!(seoyu.undelete_messages(refs()).expect("undo"), 0);
+    assert_eq!(hits(), 0);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn cloud_sync_settings_and_status() {
+    let path = tmp_db("cloud");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+
+    let status = seoyu.cloud_sync_status().expect("status");
+    assert!(!status.enabled && !status.running);
+    assert!(matches!(
+        seoyu.start_cloud_sync(),
+        Err(SeoyuError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        seoyu.configure_cloud_sync(true, None),
+        Err(SeoyuError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        seoyu.configure_cloud_sync(true, Some("ftp://cloud".into())),
+        Err(SeoyuError::InvalidArgument(_))
+    ));
+
+    seoyu
+        .configure_cloud_sync(true, Some("http://127.0.0.1:9/v1/wiki/jobs".into()))
+        .expect("configure");
+    seoyu
+        .index_messages(vec![IndexedMessage {
+            account_id: 0,
+            chat_id: 3,
+            message_id: 1,
+            timestamp: 1_700_000_000,
+            text: "동기화 대기".into(),
+            link: None,
+            sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
+        }])
+        .expect("index");
+    let status = seoyu.cloud_sync_status().expect("status");
+    assert!(status.enabled);
+    assert_eq!(
+        status.endpoint.as_deref(),
+        Some("http://127.0.0.1:9/v1/wiki/jobs")
+    );
+    assert_eq!(status.pending_ops, 1);
+
+    seoyu.start_cloud_sync().expect("start");
+    assert!(seoyu.cloud_sync_status().expect("status").running);
+    seoyu.configure_cloud_sync(false, None).expect("turn off");
+    let status = seoyu.cloud_sync_status().expect("status");
+    assert!(!status.running && !status.enabled);
+    assert_eq!(status.pending_ops, 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn reconcile_by_digest_finds_missed_edits() {
+    let path = tmp_db("recon");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    let message = |message_id: i64, text: &str| IndexedMessage {
+        account_id: 0,
+        chat_id: 8,
+        message_id,
+        timestamp: 1_700_000_000 + message_id,
+        text: text.into(),
+        link: None,
+        sender_id: 0,
+        media: None,
+        reply_to_message_id: None,
+        thread_id: None,
+        sender_name: None,
+        sender_username: None,
+    };
+    seoyu
+        .index_messages(vec![message(1, "상장 공지"), message(3, "청약 마감")])
+        .expect("index");
+
+    // The shell holds an edit of 3 the index never saw.
+    let shell = |ids: &[(i64, &str)]| {
+        ids.iter()
+            .map(|(id, text)| ReconMessage {
+                message_id: *id,
+                text: (*text).into(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let buckets = vec![
+        ReconBucket {
+            from: 1,
+            to: 2,
+            count: 1,
+            digest: seoyu.recon_bucket_digest(shell(&[(1, "상장 공지")])),
+        },
+        ReconBucket {
+            from: 3,
+            to: 4,
+            count: 1,
+            digest: seoyu.recon_bucket_digest(shell(&[(3, "청약 마감 연장")])),
+        },
+    ];
+    let report = seoyu
+        .reconcile_chat_digest(0, 8, buckets, true)
+        .expect("reconcile");
+    assert!(report.missing.is_empty());
+    assert_eq!(report.backfill, vec![IdRange { from: 3, to: 4 }]);
+    assert_eq!(report.max_msg_id, 4);
+
+    let report = seoyu
+        .reconcile_chat_ranges(0, 8, vec![IdRange { from: 1, to: 3 }], false)
+        .expect("reconcile");
+    assert_eq!(report.missing, vec![IdRange { from: 2, to: 2 }]);
+    assert!(report.last_full_diff > 0);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn sync_state_round_trip_reports_backfill_progress() {
+    let path = tmp_db("sync");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    seoyu
+        .upsert_chat(ChatInfo {
+            account_id: 2,
+            chat_id: 6,
+            title: "백필".into(),
+            chat_type: "channel".into(),
+            username: None,
+            access_hash: None,
+            is_excluded: false,
+        })
+        .expect("upsert");
+
+    assert!(seoyu.get_sync_state(2, 6).expect("get").is_none());
+    seoyu
+        .update_last_message_id(2, 6, 1000, "2026-01-01T00:00:00Z".into())
+        .expect("last");
+    let progress = seoyu.update_oldest_message_id(2, 6, 501).expect("oldest");
+    assert_eq!(progress.progress, 0.5);
+    let state = seoyu.get_sync_state(2, 6).expect("get").expect("state");
+    assert_eq!(state.oldest_message_id, Some(501));
+    assert!(!state.initial_done);
+
+    let done = seoyu.mark_initial_done(2, 6).expect("done");
+    assert!(done.initial_done);
+    assert_eq!(done.progress, 1.0);
+    assert_eq!(seoyu.backfill_progress(Some(2)).expect("list").len(), 1);
+    assert!(seoyu.backfill_progress(Some(0)).expect("list").is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn chats_are_listed_with_counts_and_can_be_excluded() {
+    let path = tmp_db("chats");
+    let seoyu = Seoyu::new(path.clone()).expect("open");
+    seoyu
+        .upsert_chat(ChatInfo {
+            account_id: 0,
+            chat_id: 5,
+            title: "공모주".into(),
+            chat_type: "channel".into(),
+            username: Some("ipo".into()),
+            access_hash: None,
+            is_excluded: false,
+        })
+        .expect("upsert");
+    seoyu
+        .index_messages(vec![IndexedMessage {
+            account_id: 0,
+            chat_id: 5,
+            message_id: 1,
+            timestamp: 1_700_000_000,
+            text: "청약 일정".into(),
+            link: None,
+            sender_id: 0,
+            media: None,
+            reply_to_message_id: None,
+            thread_id: None,
+            sender_name: None,
+            sender_username: None,
+        }])
+        .expect("index");
+
+    let chats = seoyu.list_chats(None, false).expect("list");
+    assert_eq!(chats.len(), 1);
+    assert_eq!(chats[0].chat.title, "공모주");
+    assert_eq!(chats[0].message_count, 1);
+    assert_eq!(chats[0].last_message_at, Some(1_700_000_000));
+    assert!(chats[0].last_indexed_at.is_some());
+
+    let excluded = seoyu
+        .set_chat_excluded(0, 5, true)
+        .expect("exclude")
+        .expect("chat");
+    assert!(excluded.is_excluded);
+    assert!(seoyu.list_chats(None, false).expect("list").is_empty());
+    assert_eq!(seoyu.list_chats(Some(0), true).expect("list").len(), 1);
+    assert!(seoyu.get_chat(0, 5).expect("get").unwrap().is_excluded);
+    assert!(seoyu
+        .set_chat_excluded(0, 6, true)
+        .expect("exclude")
+        .is_none());
 
     let _ = std::fs::remove_file(&path);
 }